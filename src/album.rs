@@ -0,0 +1,369 @@
+//! Albums (media groups).
+//!
+//! `AlbumBuilder` makes validated `sendMessageAlbum` requests: 2-10 items that are either photos and
+//! videos, or only documents, or only audios, each with its own caption. `AlbumAggregator` collects
+//! incoming messages sharing a `media_album_id` and delivers them as one album once no item arrived
+//! for a short window, because TDLib sends every item of an album as a separate `updateNewMessage`.
+//!
+//! ```rust
+//! use rtdlib::album::AlbumBuilder;
+//! # use rtdlib::types::*;
+//! let file = |path: &str| InputFile::local(InputFileLocal::builder().path(path).build());
+//! let request = AlbumBuilder::new(-1001234567890)
+//!   .photo(file("/tmp/1.jpg"), FormattedText::builder().text("First").build())
+//!   .video(file("/tmp/2.mp4"), FormattedText::builder().text("Second").build())
+//!   .build()
+//!   .unwrap();
+//! assert_eq!(request.input_message_contents().len(), 2);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Minimum number of items in an album
+pub const MIN_ALBUM_ITEMS: usize = 2;
+/// Maximum number of items in an album
+pub const MAX_ALBUM_ITEMS: usize = 10;
+/// Default number of seconds to wait for more items of an incoming album
+pub const DEFAULT_ALBUM_WINDOW: i64 = 1;
+
+/// Which items can be grouped together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlbumKind {
+  /// Photos and videos, which can be mixed
+  Media,
+  /// Documents only
+  Document,
+  /// Audios only
+  Audio,
+}
+
+impl AlbumKind {
+  /// Kind of an album item, `None` if the content can't be sent in an album
+  pub fn of(content: &InputMessageContent) -> Option<AlbumKind> {
+    match content {
+      InputMessageContent::InputMessagePhoto(_) | InputMessageContent::InputMessageVideo(_) => Some(AlbumKind::Media),
+      InputMessageContent::InputMessageDocument(_) => Some(AlbumKind::Document),
+      InputMessageContent::InputMessageAudio(_) => Some(AlbumKind::Audio),
+      _ => None,
+    }
+  }
+
+  /// Kind of a received album item
+  pub fn of_message(content: &MessageContent) -> Option<AlbumKind> {
+    match content {
+      MessageContent::MessagePhoto(_) | MessageContent::MessageVideo(_) => Some(AlbumKind::Media),
+      MessageContent::MessageDocument(_) => Some(AlbumKind::Document),
+      MessageContent::MessageAudio(_) => Some(AlbumKind::Audio),
+      _ => None,
+    }
+  }
+}
+
+/// Builds `sendMessageAlbum` requests
+#[derive(Debug, Clone)]
+pub struct AlbumBuilder {
+  chat_id: i64,
+  message_thread_id: i64,
+  reply_to_message_id: i64,
+  options: Option<MessageSendOptions>,
+  items: Vec<InputMessageContent>,
+}
+
+impl AlbumBuilder {
+  pub fn new(chat_id: i64) -> Self {
+    Self { chat_id, message_thread_id: 0, reply_to_message_id: 0, options: None, items: vec![] }
+  }
+
+  pub fn message_thread_id(&mut self, message_thread_id: i64) -> &mut Self {
+    self.message_thread_id = message_thread_id;
+    self
+  }
+
+  pub fn reply_to(&mut self, reply_to_message_id: i64) -> &mut Self {
+    self.reply_to_message_id = reply_to_message_id;
+    self
+  }
+
+  pub fn options<T: AsRef<MessageSendOptions>>(&mut self, options: T) -> &mut Self {
+    self.options = Some(options.as_ref().clone());
+    self
+  }
+
+  /// Adds an item built elsewhere, with its caption already set
+  pub fn item<T: AsRef<InputMessageContent>>(&mut self, content: T) -> &mut Self {
+    self.items.push(content.as_ref().clone());
+    self
+  }
+
+  pub fn photo<F: AsRef<InputFile>, C: AsRef<FormattedText>>(&mut self, photo: F, caption: C) -> &mut Self {
+    self.item(InputMessageContent::input_message_photo(InputMessagePhoto::builder().photo(photo).caption(caption).build()))
+  }
+
+  pub fn video<F: AsRef<InputFile>, C: AsRef<FormattedText>>(&mut self, video: F, caption: C) -> &mut Self {
+    self.item(InputMessageContent::input_message_video(InputMessageVideo::builder().video(video).caption(caption).build()))
+  }
+
+  pub fn document<F: AsRef<InputFile>, C: AsRef<FormattedText>>(&mut self, document: F, caption: C) -> &mut Self {
+    self.item(InputMessageContent::input_message_document(InputMessageDocument::builder().document(document).caption(caption).build()))
+  }
+
+  pub fn audio<F: AsRef<InputFile>, C: AsRef<FormattedText>>(&mut self, audio: F, caption: C) -> &mut Self {
+    self.item(InputMessageContent::input_message_audio(InputMessageAudio::builder().audio(audio).caption(caption).build()))
+  }
+
+  /// Kind of the album, checking that all items can be grouped together
+  pub fn kind(&self) -> RTDResult<AlbumKind> {
+    if self.items.len() < MIN_ALBUM_ITEMS || self.items.len() > MAX_ALBUM_ITEMS {
+      return Err(RTDError::custom(format!("album must have {}-{} items", MIN_ALBUM_ITEMS, MAX_ALBUM_ITEMS)));
+    }
+    let mut kind = None;
+    for (index, item) in self.items.iter().enumerate() {
+      let item_kind = AlbumKind::of(item)
+        .ok_or_else(|| RTDError::custom(format!("album item {} must be a photo, video, document or audio", index)))?;
+      match kind {
+        Some(kind) if kind != item_kind => {
+          return Err(RTDError::custom(format!("album item {} can't be grouped with {:?} items", index, kind)));
+        }
+        _ => kind = Some(item_kind),
+      }
+    }
+    Ok(kind.unwrap_or(AlbumKind::Media))
+  }
+
+  pub fn build(&self) -> RTDResult<SendMessageAlbum> {
+    self.kind()?;
+    let mut request = SendMessageAlbum::builder();
+    request
+      .chat_id(self.chat_id)
+      .message_thread_id(self.message_thread_id)
+      .reply_to_message_id(self.reply_to_message_id)
+      .input_message_contents(self.items.clone());
+    if let Some(options) = &self.options {
+      request.options(options);
+    }
+    Ok(request.build())
+  }
+}
+
+/// Messages of one incoming album, ordered by message identifier
+#[derive(Debug, Clone)]
+pub struct Album {
+  chat_id: i64,
+  media_album_id: i64,
+  messages: Vec<Message>,
+}
+
+impl Album {
+  pub fn chat_id(&self) -> i64 { self.chat_id }
+
+  /// Zero for a message that isn't part of an album
+  pub fn media_album_id(&self) -> i64 { self.media_album_id }
+
+  pub fn messages(&self) -> &Vec<Message> { &self.messages }
+
+  pub fn message_ids(&self) -> Vec<i64> { self.messages.iter().map(|message| message.id()).collect() }
+
+  /// Caption of the album: the only non-empty item caption, if there is exactly one
+  pub fn caption(&self) -> Option<&FormattedText> {
+    let mut captions = self.messages.iter().filter_map(|message| caption(message.content())).filter(|caption| !caption.text().is_empty());
+    match (captions.next(), captions.next()) {
+      (Some(caption), None) => Some(caption),
+      _ => None,
+    }
+  }
+}
+
+fn caption(content: &MessageContent) -> Option<&FormattedText> {
+  match content {
+    MessageContent::MessagePhoto(photo) => Some(photo.caption()),
+    MessageContent::MessageVideo(video) => Some(video.caption()),
+    MessageContent::MessageDocument(document) => Some(document.caption()),
+    MessageContent::MessageAudio(audio) => Some(audio.caption()),
+    _ => None,
+  }
+}
+
+#[derive(Debug)]
+struct Pending {
+  deadline: i64,
+  messages: Vec<Message>,
+}
+
+/// Groups incoming messages into albums
+#[derive(Debug)]
+pub struct AlbumAggregator {
+  window: i64,
+  pending: HashMap<(i64, i64), Pending>,
+}
+
+impl Default for AlbumAggregator {
+  fn default() -> Self { Self::new(DEFAULT_ALBUM_WINDOW) }
+}
+
+impl AlbumAggregator {
+  /// Waits `window` seconds after the last item of an album for more items
+  pub fn new(window: i64) -> Self {
+    Self { window, pending: HashMap::new() }
+  }
+
+  /// Number of albums still waiting for items
+  pub fn len(&self) -> usize { self.pending.len() }
+
+  pub fn is_empty(&self) -> bool { self.pending.is_empty() }
+
+  /// Adds an incoming message. Returns an album when it is complete: right away for a message
+  /// outside of an album, or when the album has the maximum number of items
+  pub fn on_message(&mut self, message: &Message, now: i64) -> Option<Album> {
+    let media_album_id = message.media_album_id();
+    if media_album_id == 0 {
+      return Some(Album { chat_id: message.chat_id(), media_album_id, messages: vec![message.clone()] });
+    }
+    let key = (message.chat_id(), media_album_id);
+    let pending = self.pending.entry(key).or_insert_with(|| Pending { deadline: now, messages: vec![] });
+    pending.deadline = now + self.window;
+    match pending.messages.iter_mut().find(|item| item.id() == message.id()) {
+      Some(item) => *item = message.clone(),
+      None => pending.messages.push(message.clone()),
+    }
+    if pending.messages.len() < MAX_ALBUM_ITEMS {
+      return None;
+    }
+    self.pending.remove(&key).map(|pending| album(key, pending))
+  }
+
+  /// Replaces a buffered message with its new version, e.g. after `updateMessageContent`
+  pub fn on_message_changed(&mut self, message: &Message) {
+    if let Some(pending) = self.pending.get_mut(&(message.chat_id(), message.media_album_id())) {
+      if let Some(item) = pending.messages.iter_mut().find(|item| item.id() == message.id()) {
+        *item = message.clone();
+      }
+    }
+  }
+
+  /// Drops buffered messages deleted before their album was delivered
+  pub fn on_delete_messages<U: AsRef<UpdateDeleteMessages>>(&mut self, update: U) {
+    let update = update.as_ref();
+    if !update.is_permanent() {
+      return;
+    }
+    for pending in self.pending.values_mut() {
+      pending.messages.retain(|message| message.chat_id() != update.chat_id() || !update.message_ids().contains(&message.id()));
+    }
+    self.pending.retain(|_, pending| !pending.messages.is_empty());
+  }
+
+  /// When `flush` should be called next
+  pub fn next_flush_at(&self) -> Option<i64> {
+    self.pending.values().map(|pending| pending.deadline).min()
+  }
+
+  /// Albums that didn't receive new items during the window
+  pub fn flush(&mut self, now: i64) -> Vec<Album> {
+    let keys: Vec<(i64, i64)> = self.pending.iter()
+      .filter(|(_, pending)| pending.deadline <= now)
+      .map(|(key, _)| *key)
+      .collect();
+    let mut albums: Vec<Album> = keys.into_iter()
+      .filter_map(|key| self.pending.remove(&key).map(|pending| album(key, pending)))
+      .collect();
+    albums.sort_by_key(|album| album.messages.first().map(|message| (message.date(), message.id())));
+    albums
+  }
+
+  /// All buffered albums, regardless of the window
+  pub fn flush_all(&mut self) -> Vec<Album> {
+    self.flush(i64::MAX)
+  }
+}
+
+fn album((chat_id, media_album_id): (i64, i64), pending: Pending) -> Album {
+  let mut messages = pending.messages;
+  messages.sort_by_key(|message| message.id());
+  Album { chat_id, media_album_id, messages }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::album::*;
+
+  fn file(path: &str) -> InputFile {
+    InputFile::local(InputFileLocal::builder().path(path).build())
+  }
+
+  fn text(text: &str) -> FormattedText {
+    FormattedText::builder().text(text).build()
+  }
+
+  fn photo_message(chat_id: i64, id: i64, media_album_id: i64, caption: &str) -> Message {
+    Message::builder()
+      .chat_id(chat_id)
+      .id(id)
+      .media_album_id(media_album_id)
+      .sender_id(MessageSender::user(MessageSenderUser::builder().user_id(1).build()))
+      .content(MessageContent::message_photo(MessagePhoto::builder().caption(text(caption)).build()))
+      .build()
+  }
+
+  #[test]
+  fn test_album_builder() {
+    assert!(AlbumBuilder::new(1).photo(file("a"), text("a")).build().is_err());
+    assert!(AlbumBuilder::new(1).photo(file("a"), text("a")).document(file("b"), text("b")).build().is_err());
+    assert!(AlbumBuilder::new(1).audio(file("a"), text("a")).document(file("b"), text("b")).build().is_err());
+    let mut too_many = AlbumBuilder::new(1);
+    for _ in 0..=MAX_ALBUM_ITEMS {
+      too_many.photo(file("a"), text(""));
+    }
+    assert!(too_many.build().is_err());
+
+    let mut builder = AlbumBuilder::new(5);
+    builder.reply_to(10).document(file("a"), text("first")).document(file("b"), text("second"));
+    assert_eq!(builder.kind().unwrap(), AlbumKind::Document);
+    let request = builder.build().unwrap();
+    assert_eq!(request.chat_id(), 5);
+    assert_eq!(request.reply_to_message_id(), 10);
+    let captions: Vec<&String> = request.input_message_contents().iter()
+      .filter_map(|content| content.as_input_message_document())
+      .map(|document| document.caption().text())
+      .collect();
+    assert_eq!(captions, vec!["first", "second"]);
+  }
+
+  #[test]
+  fn test_aggregator() {
+    let mut aggregator = AlbumAggregator::new(2);
+    assert_eq!(aggregator.on_message(&photo_message(1, 5, 0, ""), 100).unwrap().message_ids(), vec![5]);
+
+    assert!(aggregator.on_message(&photo_message(1, 12, 77, ""), 100).is_none());
+    assert!(aggregator.on_message(&photo_message(1, 11, 77, "caption"), 101).is_none());
+    assert!(aggregator.on_message(&photo_message(2, 20, 77, ""), 101).is_none());
+    assert_eq!(aggregator.len(), 2);
+    assert_eq!(aggregator.next_flush_at(), Some(103));
+    assert!(aggregator.flush(102).is_empty());
+
+    let albums = aggregator.flush(103);
+    assert_eq!(albums.len(), 2);
+    assert_eq!(albums[0].chat_id(), 1);
+    assert_eq!(albums[0].message_ids(), vec![11, 12]);
+    assert_eq!(albums[0].caption().map(|caption| caption.text().as_str()), Some("caption"));
+    assert!(aggregator.is_empty());
+  }
+
+  #[test]
+  fn test_aggregator_full_and_deleted() {
+    let mut aggregator = AlbumAggregator::default();
+    for id in 1..MAX_ALBUM_ITEMS as i64 {
+      assert!(aggregator.on_message(&photo_message(1, id, 9, ""), 100).is_none());
+    }
+    let album = aggregator.on_message(&photo_message(1, 10, 9, ""), 100).unwrap();
+    assert_eq!(album.messages().len(), MAX_ALBUM_ITEMS);
+    assert!(album.caption().is_none());
+
+    aggregator.on_message(&photo_message(1, 30, 8, ""), 100);
+    let deleted = UpdateDeleteMessages::builder().chat_id(1).message_ids(vec![30]).is_permanent(true).build();
+    aggregator.on_delete_messages(&deleted);
+    assert!(aggregator.flush_all().is_empty());
+  }
+}
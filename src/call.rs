@@ -0,0 +1,367 @@
+//! Private call state tracking and signaling bridge.
+//!
+//! `CallController` follows every call through `updateCall`, rejects state changes TDLib never
+//! produces, and drives a `CallMediaBackend` which does the actual audio/video work.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::encoding::*;
+use crate::errors::*;
+use crate::types::*;
+
+/// State of a call without the state details
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallPhase {
+  Pending,
+  ExchangingKeys,
+  Ready,
+  HangingUp,
+  Discarded,
+  Error,
+}
+
+impl CallPhase {
+  pub fn of<S: AsRef<CallState>>(state: S) -> Option<CallPhase> {
+    match state.as_ref() {
+      CallState::Pending(_) => Some(CallPhase::Pending),
+      CallState::ExchangingKeys(_) => Some(CallPhase::ExchangingKeys),
+      CallState::Ready(_) => Some(CallPhase::Ready),
+      CallState::HangingUp(_) => Some(CallPhase::HangingUp),
+      CallState::Discarded(_) => Some(CallPhase::Discarded),
+      CallState::Error(_) => Some(CallPhase::Error),
+      _ => None,
+    }
+  }
+
+  /// Whether the call is over
+  pub fn is_final(&self) -> bool {
+    matches!(self, CallPhase::Discarded | CallPhase::Error)
+  }
+
+  /// Whether TDLib may change call state from this phase to `next`
+  pub fn can_transition_to(&self, next: CallPhase) -> bool {
+    use CallPhase::*;
+    match (self, next) {
+      (_, Error) => !self.is_final(),
+      // pending state is repeated when `is_created` or `is_received` changes, ready when servers change
+      (Pending, Pending) | (Ready, Ready) => true,
+      (Pending, ExchangingKeys) | (ExchangingKeys, Ready) => true,
+      (Pending, HangingUp) | (ExchangingKeys, HangingUp) | (Ready, HangingUp) => true,
+      (Pending, Discarded) | (ExchangingKeys, Discarded) | (Ready, Discarded) | (HangingUp, Discarded) => true,
+      _ => false,
+    }
+  }
+}
+
+impl fmt::Display for CallPhase {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match self {
+      CallPhase::Pending => "pending",
+      CallPhase::ExchangingKeys => "exchanging keys",
+      CallPhase::Ready => "ready",
+      CallPhase::HangingUp => "hanging up",
+      CallPhase::Discarded => "discarded",
+      CallPhase::Error => "error",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// Media backend driven by `CallController`, e.g. a tgcalls binding
+pub trait CallMediaBackend {
+  /// tgcalls versions supported by the backend, used in `CallProtocol`
+  fn library_versions(&self) -> Vec<String>;
+
+  /// Start media for a call that became ready. `ready` contains the encryption key, emojis,
+  /// servers and the negotiated protocol
+  fn start(&mut self, call: &Call, ready: &CallStateReady) -> RTDResult<()>;
+
+  /// Signaling data received from the other party
+  fn receive_signaling_data(&mut self, call_id: i64, data: &[u8]) -> RTDResult<()>;
+
+  /// Signaling data to send to the other party, collected by `CallController::outgoing_signaling_data`
+  fn take_signaling_data(&mut self, _call_id: i64) -> Vec<Vec<u8>> { vec![] }
+
+  /// Stop media of a finished call
+  fn stop(&mut self, call_id: i64);
+}
+
+/// A call tracked by `CallController`
+#[derive(Debug, Clone)]
+pub struct TrackedCall {
+  call: Call,
+  phase: CallPhase,
+  history: Vec<CallPhase>,
+  media_started: bool,
+}
+
+impl TrackedCall {
+  pub fn call(&self) -> &Call { &self.call }
+
+  pub fn phase(&self) -> CallPhase { self.phase }
+
+  /// All phases the call went through, including the current one
+  pub fn history(&self) -> &Vec<CallPhase> { &self.history }
+
+  pub fn ready(&self) -> Option<&CallStateReady> { self.call.state().as_ready() }
+
+  /// Emojis to compare with the other party, available when the call is ready
+  pub fn emojis(&self) -> Option<&Vec<String>> { self.ready().map(|ready| ready.emojis()) }
+
+  /// Call encryption key, available when the call is ready
+  pub fn encryption_key(&self) -> Option<&String> { self.ready().map(|ready| ready.encryption_key()) }
+
+  pub fn servers(&self) -> Option<&Vec<CallServer>> { self.ready().map(|ready| ready.servers()) }
+
+  pub fn is_media_started(&self) -> bool { self.media_started }
+}
+
+/// Tracks private calls and bridges them to a `CallMediaBackend`.
+///
+/// Requests returned by the controller must be sent to TDLib by the caller.
+#[derive(Debug)]
+pub struct CallController<B: CallMediaBackend> {
+  backend: B,
+  calls: HashMap<i64, TrackedCall>,
+  min_layer: i64,
+  max_layer: i64,
+}
+
+impl<B: CallMediaBackend> CallController<B> {
+  pub fn new(backend: B) -> Self {
+    Self { backend, calls: HashMap::new(), min_layer: 65, max_layer: 92 }
+  }
+
+  /// Supported API layers of `CallProtocol`, default is 65..=92
+  pub fn layers(&mut self, min_layer: i64, max_layer: i64) -> &mut Self {
+    self.min_layer = min_layer;
+    self.max_layer = max_layer;
+    self
+  }
+
+  pub fn backend(&self) -> &B { &self.backend }
+
+  pub fn backend_mut(&mut self) -> &mut B { &mut self.backend }
+
+  pub fn call(&self, call_id: i64) -> Option<&TrackedCall> { self.calls.get(&call_id) }
+
+  /// Calls not finished yet
+  pub fn active_calls(&self) -> Vec<&TrackedCall> {
+    self.calls.values().filter(|call| !call.phase.is_final()).collect()
+  }
+
+  pub fn protocol(&self) -> CallProtocol {
+    CallProtocol::builder()
+      .udp_p2p(true)
+      .udp_reflector(true)
+      .min_layer(self.min_layer)
+      .max_layer(self.max_layer)
+      .library_versions(self.backend.library_versions())
+      .build()
+  }
+
+  pub fn create_call(&self, user_id: i64, is_video: bool) -> CreateCall {
+    CreateCall::builder()
+      .user_id(user_id)
+      .protocol(self.protocol())
+      .is_video(is_video)
+      .build()
+  }
+
+  /// Accept an incoming pending call
+  pub fn accept_call(&self, call_id: i64) -> RTDResult<AcceptCall> {
+    let tracked = self.tracked(call_id)?;
+    if tracked.call.is_outgoing() || tracked.phase != CallPhase::Pending {
+      return Err(RTDError::custom(format!("call {} can't be accepted", call_id)));
+    }
+    Ok(AcceptCall::builder().call_id(call_id).protocol(self.protocol()).build())
+  }
+
+  /// Hang up or decline a call, `duration` is in seconds
+  pub fn discard_call(&self, call_id: i64, is_disconnected: bool, duration: i64) -> RTDResult<DiscardCall> {
+    let tracked = self.tracked(call_id)?;
+    if tracked.phase.is_final() || tracked.phase == CallPhase::HangingUp {
+      return Err(RTDError::custom(format!("call {} is already {}", call_id, tracked.phase)));
+    }
+    Ok(DiscardCall::builder()
+      .call_id(call_id)
+      .is_disconnected(is_disconnected)
+      .duration(duration)
+      .is_video(tracked.call.is_video())
+      .build())
+  }
+
+  /// Handle `updateCall`, returns the new call phase.
+  ///
+  /// An illegal state change is rejected and the call keeps its previous state.
+  pub fn on_update_call<U: AsRef<UpdateCall>>(&mut self, update: U) -> RTDResult<CallPhase> {
+    let call = update.as_ref().call();
+    let phase = CallPhase::of(call.state())
+      .ok_or_else(|| RTDError::custom(format!("call {} has unknown state", call.id())))?;
+    let media_started = match self.calls.get(&call.id()) {
+      Some(tracked) => {
+        if !tracked.phase.can_transition_to(phase) {
+          return Err(RTDError::custom(format!("illegal call {} transition from {} to {}", call.id(), tracked.phase, phase)));
+        }
+        tracked.media_started
+      }
+      None => false,
+    };
+
+    let mut tracked = self.calls.remove(&call.id()).unwrap_or_else(|| TrackedCall {
+      call: call.clone(),
+      phase,
+      history: vec![],
+      media_started,
+    });
+    tracked.call = call.clone();
+    tracked.phase = phase;
+    if tracked.history.last() != Some(&phase) { tracked.history.push(phase); }
+
+    let result = match call.state() {
+      CallState::Ready(ready) if !tracked.media_started => {
+        let result = self.backend.start(call, ready);
+        tracked.media_started = result.is_ok();
+        result
+      }
+      _ if phase.is_final() || phase == CallPhase::HangingUp => {
+        if tracked.media_started {
+          self.backend.stop(call.id());
+          tracked.media_started = false;
+        }
+        Ok(())
+      }
+      _ => Ok(()),
+    };
+    self.calls.insert(call.id(), tracked);
+    result.map(|_| phase)
+  }
+
+  /// Handle `updateNewCallSignalingData`, the data is passed to the backend
+  pub fn on_signaling_data<U: AsRef<UpdateNewCallSignalingData>>(&mut self, update: U) -> RTDResult<()> {
+    let update = update.as_ref();
+    let tracked = self.tracked(update.call_id())?;
+    if !tracked.media_started {
+      return Err(RTDError::custom(format!("signaling data for call {} which is {}", update.call_id(), tracked.phase)));
+    }
+    let data = decode_base64(update.data())
+      .ok_or_else(|| RTDError::custom(format!("invalid signaling data for call {}", update.call_id())))?;
+    self.backend.receive_signaling_data(update.call_id(), &data)
+  }
+
+  /// `SendCallSignalingData` requests for data produced by the backend
+  pub fn outgoing_signaling_data(&mut self) -> Vec<SendCallSignalingData> {
+    let mut call_ids = self.calls.values()
+      .filter(|tracked| tracked.media_started)
+      .map(|tracked| tracked.call.id())
+      .collect::<Vec<i64>>();
+    call_ids.sort_unstable();
+    let mut requests = vec![];
+    for call_id in call_ids {
+      for data in self.backend.take_signaling_data(call_id) {
+        requests.push(SendCallSignalingData::builder().call_id(call_id).data(encode_base64(&data)).build());
+      }
+    }
+    requests
+  }
+
+  /// Forget finished calls
+  pub fn remove_finished(&mut self) {
+    self.calls.retain(|_, tracked| !tracked.phase.is_final());
+  }
+
+  fn tracked(&self, call_id: i64) -> RTDResult<&TrackedCall> {
+    self.calls.get(&call_id).ok_or_else(|| RTDError::custom(format!("unknown call {}", call_id)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use crate::call::*;
+
+  #[derive(Debug, Default)]
+  struct DummyBackend {
+    started: Vec<(i64, String)>,
+    stopped: Vec<i64>,
+    received: Vec<Vec<u8>>,
+    outgoing: HashMap<i64, Vec<Vec<u8>>>,
+  }
+
+  impl CallMediaBackend for DummyBackend {
+    fn library_versions(&self) -> Vec<String> { vec!["3.0.0".to_string()] }
+
+    fn start(&mut self, call: &Call, ready: &CallStateReady) -> RTDResult<()> {
+      self.started.push((call.id(), ready.encryption_key().clone()));
+      Ok(())
+    }
+
+    fn receive_signaling_data(&mut self, _call_id: i64, data: &[u8]) -> RTDResult<()> {
+      self.received.push(data.to_vec());
+      Ok(())
+    }
+
+    fn take_signaling_data(&mut self, call_id: i64) -> Vec<Vec<u8>> {
+      self.outgoing.remove(&call_id).unwrap_or_default()
+    }
+
+    fn stop(&mut self, call_id: i64) { self.stopped.push(call_id); }
+  }
+
+  fn update(state: CallState) -> UpdateCall {
+    UpdateCall::builder()
+      .call(Call::builder().id(7).user_id(42).state(state).build())
+      .build()
+  }
+
+  fn pending() -> CallState { CallState::pending(CallStatePending::builder().build()) }
+
+  fn ready() -> CallState {
+    CallState::ready(CallStateReady::builder()
+      .encryption_key("a2V5")
+      .emojis(vec!["🐱".to_string(), "🚀".to_string(), "🍕".to_string(), "🌵".to_string()])
+      .build())
+  }
+
+  #[test]
+  fn test_call_lifecycle() {
+    let mut controller = CallController::new(DummyBackend::default());
+    assert_eq!(controller.on_update_call(update(pending())).unwrap(), CallPhase::Pending);
+    assert!(controller.accept_call(7).is_ok());
+    controller.on_update_call(update(CallState::exchanging_keys(CallStateExchangingKeys::builder().build()))).unwrap();
+    controller.on_update_call(update(ready())).unwrap();
+
+    let tracked = controller.call(7).unwrap();
+    assert_eq!(tracked.emojis().unwrap().len(), 4);
+    assert_eq!(controller.backend().started, vec![(7, "a2V5".to_string())]);
+
+    controller.on_signaling_data(UpdateNewCallSignalingData::builder().call_id(7).data("aGVsbG8=").build()).unwrap();
+    assert_eq!(controller.backend().received, vec![b"hello".to_vec()]);
+    controller.backend_mut().outgoing.insert(7, vec![b"hi".to_vec()]);
+    let outgoing = controller.outgoing_signaling_data();
+    assert_eq!(outgoing[0].data(), "aGk=");
+
+    controller.on_update_call(update(CallState::hanging_up(CallStateHangingUp::builder().build()))).unwrap();
+    assert_eq!(controller.backend().stopped, vec![7]);
+    assert!(controller.discard_call(7, false, 10).is_err());
+    controller.on_update_call(update(CallState::discarded(CallStateDiscarded::builder().build()))).unwrap();
+    assert_eq!(controller.call(7).unwrap().history(), &vec![
+      CallPhase::Pending, CallPhase::ExchangingKeys, CallPhase::Ready, CallPhase::HangingUp, CallPhase::Discarded,
+    ]);
+    controller.remove_finished();
+    assert!(controller.call(7).is_none());
+  }
+
+  #[test]
+  fn test_illegal_transition() {
+    let mut controller = CallController::new(DummyBackend::default());
+    controller.on_update_call(update(pending())).unwrap();
+    controller.on_update_call(update(CallState::discarded(CallStateDiscarded::builder().build()))).unwrap();
+    assert!(controller.on_update_call(update(ready())).is_err());
+    assert_eq!(controller.call(7).unwrap().phase(), CallPhase::Discarded);
+    assert!(controller.backend().started.is_empty());
+    assert!(controller.on_signaling_data(UpdateNewCallSignalingData::builder().call_id(7).data("aGk=").build()).is_err());
+  }
+}
@@ -0,0 +1,387 @@
+//! Callback query router with typed callback data.
+//!
+//! Payloads are serialized with serde into the callback data of a button, together with a route
+//! name and a version, and optionally signed with a truncated HMAC-SHA256 so users can't forge
+//! them. The whole data must fit into 64 bytes, so payloads use a positional binary encoding
+//! (bincode with variable length integers): field names aren't stored and fields must only be
+//! added at the end of a payload with a new route version.
+//!
+//! ```rust
+//! use rtdlib::callback::{CallbackAnswer, CallbackRouter};
+//! use rtdlib::keyboard::InlineKeyboard;
+//!
+//! #[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+//! struct Vote { poll: i64, yes: bool }
+//!
+//! let mut router = CallbackRouter::new();
+//! router.secret("bot secret").route("vote", 1, |_context, vote: Vote| {
+//!   Ok(CallbackAnswer::text(if vote.yes { "Voted yes" } else { "Voted no" }))
+//! }).unwrap();
+//! let markup = InlineKeyboard::builder()
+//!   .callback("Yes", router.encode("vote", &Vote { poll: 1, yes: true }).unwrap())
+//!   .build()
+//!   .unwrap();
+//! ```
+
+use std::collections::HashMap;
+
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::crypto::*;
+use crate::encoding::*;
+use crate::errors::*;
+use crate::keyboard::MAX_CALLBACK_DATA_LENGTH;
+use crate::types::*;
+
+/// Size of the signature appended to signed callback data, in bytes
+pub const SIGNATURE_LENGTH: usize = 8;
+
+fn payload_options() -> impl Options {
+  bincode::DefaultOptions::new().with_limit(MAX_CALLBACK_DATA_LENGTH as u64)
+}
+
+/// Where a callback query comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallbackContext {
+  query_id: i64,
+  sender_user_id: i64,
+  chat_id: Option<i64>,
+  message_id: Option<i64>,
+  inline_message_id: Option<String>,
+  chat_instance: i64,
+  password: Option<String>,
+}
+
+impl CallbackContext {
+  pub fn query_id(&self) -> i64 { self.query_id }
+
+  pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
+
+  /// Chat of the message with the button, `None` for inline messages
+  pub fn chat_id(&self) -> Option<i64> { self.chat_id }
+
+  /// Message with the button, `None` for inline messages
+  pub fn message_id(&self) -> Option<i64> { self.message_id }
+
+  /// Inline message with the button
+  pub fn inline_message_id(&self) -> Option<&String> { self.inline_message_id.as_ref() }
+
+  pub fn chat_instance(&self) -> i64 { self.chat_instance }
+
+  /// Password entered for a callback button requiring password
+  pub fn password(&self) -> Option<&String> { self.password.as_ref() }
+}
+
+/// Answer to a callback query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallbackAnswer {
+  text: String,
+  show_alert: bool,
+  url: String,
+  cache_time: Option<i64>,
+}
+
+impl CallbackAnswer {
+  /// Answer without notification
+  pub fn empty() -> Self { Self::default() }
+
+  /// Answer with a toast notification
+  pub fn text<S: AsRef<str>>(text: S) -> Self {
+    Self { text: text.as_ref().to_string(), ..Default::default() }
+  }
+
+  /// Answer with an alert
+  pub fn alert<S: AsRef<str>>(text: S) -> Self {
+    Self { text: text.as_ref().to_string(), show_alert: true, ..Default::default() }
+  }
+
+  /// Answer opening an URL, e.g. a game
+  pub fn url<S: AsRef<str>>(url: S) -> Self {
+    Self { url: url.as_ref().to_string(), ..Default::default() }
+  }
+
+  /// Time during which the answer can be cached, in seconds; router default is used otherwise
+  pub fn cache_time(mut self, cache_time: i64) -> Self {
+    self.cache_time = Some(cache_time);
+    self
+  }
+}
+
+/// `None` if the payload can't be decoded as the route type
+type Handler = Box<dyn FnMut(&CallbackContext, &[u8]) -> Option<RTDResult<CallbackAnswer>>>;
+type GameHandler = Box<dyn FnMut(&CallbackContext, &str) -> RTDResult<CallbackAnswer>>;
+type ErrorMapper = Box<dyn Fn(&RTDError) -> String>;
+
+struct Route {
+  version: u32,
+  handler: Handler,
+}
+
+/// Routes callback queries to handlers of typed payloads and answers them
+pub struct CallbackRouter {
+  secret: Option<Vec<u8>>,
+  routes: HashMap<String, Route>,
+  game: Option<GameHandler>,
+  cache_time: i64,
+  invalid_text: String,
+  error_mapper: ErrorMapper,
+}
+
+impl Default for CallbackRouter {
+  fn default() -> Self { CallbackRouter::new() }
+}
+
+impl CallbackRouter {
+  pub fn new() -> Self {
+    Self {
+      secret: None,
+      routes: HashMap::new(),
+      game: None,
+      cache_time: 0,
+      invalid_text: "This button is no longer valid".to_string(),
+      error_mapper: Box::new(|_| "Something went wrong, please try again later".to_string()),
+    }
+  }
+
+  /// Sign callback data with this secret and reject data with invalid signatures
+  pub fn secret<S: AsRef<[u8]>>(&mut self, secret: S) -> &mut Self {
+    self.secret = Some(secret.as_ref().to_vec());
+    self
+  }
+
+  /// Default cache time of answers, in seconds
+  pub fn cache_time(&mut self, cache_time: i64) -> &mut Self {
+    self.cache_time = cache_time;
+    self
+  }
+
+  /// Alert shown for unknown, outdated or forged callback data
+  pub fn invalid_text<S: AsRef<str>>(&mut self, invalid_text: S) -> &mut Self {
+    self.invalid_text = invalid_text.as_ref().to_string();
+    self
+  }
+
+  /// Map handler errors to alert text
+  pub fn on_error<F: Fn(&RTDError) -> String + 'static>(&mut self, mapper: F) -> &mut Self {
+    self.error_mapper = Box::new(mapper);
+    self
+  }
+
+  /// Register a handler of `route` payloads. Data encoded with another version is rejected.
+  /// Route names must be non-empty and can't contain `:`
+  pub fn route<P, F>(&mut self, route: &str, version: u32, mut handler: F) -> RTDResult<&mut Self>
+    where P: DeserializeOwned, F: FnMut(&CallbackContext, P) -> RTDResult<CallbackAnswer> + 'static {
+    if route.is_empty() || route.contains(':') {
+      return Err(RTDError::custom(format!("invalid callback route name {}", route)));
+    }
+    let handler: Handler = Box::new(move |context, payload| {
+      payload_options().deserialize(payload).ok().map(|payload| handler(context, payload))
+    });
+    self.routes.insert(route.to_string(), Route { version, handler });
+    Ok(self)
+  }
+
+  /// Register a handler of game callback buttons, it receives the game short name
+  pub fn game<F: FnMut(&CallbackContext, &str) -> RTDResult<CallbackAnswer> + 'static>(&mut self, handler: F) -> &mut Self {
+    self.game = Some(Box::new(handler));
+    self
+  }
+
+  /// Encode payload into callback data of a registered route
+  pub fn encode<P: Serialize>(&self, route: &str, payload: &P) -> RTDResult<Vec<u8>> {
+    let version = self.routes.get(route)
+      .map(|route| route.version)
+      .ok_or_else(|| RTDError::custom(format!("unknown callback route {}", route)))?;
+    let mut data = format!("{}:{}:", route, version).into_bytes();
+    let payload = payload_options().serialize(payload)
+      .map_err(|e| RTDError::custom(format!("callback payload of route {} can't be encoded: {}", route, e)))?;
+    data.extend_from_slice(&payload);
+    if let Some(secret) = &self.secret {
+      let signature = hmac_sha256(secret, &data);
+      data.extend_from_slice(&signature[..SIGNATURE_LENGTH]);
+    }
+    if data.len() > MAX_CALLBACK_DATA_LENGTH {
+      return Err(RTDError::custom(format!(
+        "callback data of route {} is {} bytes, at most {} bytes allowed", route, data.len(), MAX_CALLBACK_DATA_LENGTH
+      )));
+    }
+    Ok(data)
+  }
+
+  /// Split callback data into route, version and payload, verifying the signature
+  fn decode<'a>(&self, data: &'a [u8]) -> RTDResult<(&'a str, u32, &'a [u8])> {
+    let data = match &self.secret {
+      Some(secret) => {
+        if data.len() < SIGNATURE_LENGTH { return Err(RTDError::custom("callback data isn't signed".to_string())) }
+        let (data, signature) = data.split_at(data.len() - SIGNATURE_LENGTH);
+        if !constant_time_eq(&hmac_sha256(secret, data)[..SIGNATURE_LENGTH], signature) {
+          return Err(RTDError::custom("invalid callback data signature".to_string()));
+        }
+        data
+      }
+      None => data,
+    };
+    let mut parts = data.splitn(3, |byte| *byte == b':');
+    let text = |part: Option<&'a [u8]>| part.and_then(|part| std::str::from_utf8(part).ok());
+    match (text(parts.next()), text(parts.next()).and_then(|v| v.parse::<u32>().ok()), parts.next()) {
+      (Some(route), Some(version), Some(payload)) => Ok((route, version, payload)),
+      _ => Err(RTDError::custom("malformed callback data".to_string())),
+    }
+  }
+
+  fn dispatch(&mut self, context: &CallbackContext, payload: &CallbackQueryPayload) -> CallbackAnswer {
+    let result = match payload {
+      CallbackQueryPayload::Game(game) => match self.game.as_mut() {
+        Some(handler) => handler(context, game.game_short_name()),
+        None => Ok(CallbackAnswer::empty()),
+      },
+      CallbackQueryPayload::Data(data) => return self.dispatch_data(context, data.data()),
+      CallbackQueryPayload::DataWithPassword(data) => return self.dispatch_data(context, data.data()),
+      _ => return CallbackAnswer::alert(&self.invalid_text),
+    };
+    result.unwrap_or_else(|e| CallbackAnswer::alert((self.error_mapper)(&e)))
+  }
+
+  fn dispatch_data(&mut self, context: &CallbackContext, data: &str) -> CallbackAnswer {
+    let data = match decode_base64(data) {
+      Some(data) => data,
+      None => return CallbackAnswer::alert(&self.invalid_text),
+    };
+    let (name, version, payload) = match self.decode(&data) {
+      Ok(decoded) => decoded,
+      Err(_) => return CallbackAnswer::alert(&self.invalid_text),
+    };
+    let route = match self.routes.get_mut(name) {
+      Some(route) if route.version == version => route,
+      _ => return CallbackAnswer::alert(&self.invalid_text),
+    };
+    match (route.handler)(context, payload) {
+      Some(Ok(answer)) => answer,
+      Some(Err(e)) => CallbackAnswer::alert((self.error_mapper)(&e)),
+      // payload doesn't match the route type anymore
+      None => CallbackAnswer::alert(&self.invalid_text),
+    }
+  }
+
+  fn answer(&self, query_id: i64, answer: CallbackAnswer) -> AnswerCallbackQuery {
+    AnswerCallbackQuery::builder()
+      .callback_query_id(query_id)
+      .text(answer.text)
+      .show_alert(answer.show_alert)
+      .url(answer.url)
+      .cache_time(answer.cache_time.unwrap_or(self.cache_time))
+      .build()
+  }
+
+  /// Handle `updateNewCallbackQuery`, returns the answer to send
+  pub fn handle<U: AsRef<UpdateNewCallbackQuery>>(&mut self, update: U) -> AnswerCallbackQuery {
+    let update = update.as_ref();
+    let context = CallbackContext {
+      query_id: update.id(),
+      sender_user_id: update.sender_user_id(),
+      chat_id: Some(update.chat_id()),
+      message_id: Some(update.message_id()),
+      inline_message_id: None,
+      chat_instance: update.chat_instance(),
+      password: update.payload().as_data_with_password().map(|data| data.password().clone()),
+    };
+    let answer = self.dispatch(&context, update.payload());
+    self.answer(context.query_id, answer)
+  }
+
+  /// Handle `updateNewInlineCallbackQuery`, returns the answer to send
+  pub fn handle_inline<U: AsRef<UpdateNewInlineCallbackQuery>>(&mut self, update: U) -> AnswerCallbackQuery {
+    let update = update.as_ref();
+    let context = CallbackContext {
+      query_id: update.id(),
+      sender_user_id: update.sender_user_id(),
+      chat_id: None,
+      message_id: None,
+      inline_message_id: Some(update.inline_message_id().clone()),
+      chat_instance: update.chat_instance(),
+      password: update.payload().as_data_with_password().map(|data| data.password().clone()),
+    };
+    let answer = self.dispatch(&context, update.payload());
+    self.answer(context.query_id, answer)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::RefCell;
+  use std::rc::Rc;
+
+  use crate::callback::*;
+
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  struct Page { list: String, page: u32 }
+
+  fn query(data: &[u8]) -> UpdateNewCallbackQuery {
+    let payload = CallbackQueryPayloadData::builder().data(encode_base64(data)).build();
+    UpdateNewCallbackQuery::builder()
+      .id(99)
+      .sender_user_id(5)
+      .chat_id(10)
+      .message_id(20)
+      .payload(CallbackQueryPayload::data(payload))
+      .build()
+  }
+
+  #[test]
+  fn test_route_signed_payload() {
+    let pages = Rc::new(RefCell::new(vec![]));
+    let received = pages.clone();
+    let mut router = CallbackRouter::new();
+    router.secret("secret").cache_time(5).route("page", 2, move |context, page: Page| {
+      assert_eq!(context.chat_id(), Some(10));
+      received.borrow_mut().push(page);
+      Ok(CallbackAnswer::text("ok"))
+    }).unwrap();
+
+    let data = router.encode("page", &Page { list: "users".to_string(), page: 3 }).unwrap();
+    // 7 bytes of route and version, 7 bytes of payload and the signature
+    assert_eq!(data.len(), 7 + 7 + SIGNATURE_LENGTH);
+    let answer = router.handle(query(&data));
+    assert_eq!(answer.callback_query_id(), 99);
+    assert_eq!(answer.text(), "ok");
+    assert_eq!(answer.cache_time(), 5);
+    assert_eq!(pages.borrow()[0], Page { list: "users".to_string(), page: 3 });
+
+    // forged data is rejected
+    let mut forged = data.clone();
+    forged[10] ^= 1;
+    let answer = router.handle(query(&forged));
+    assert!(answer.show_alert());
+    assert_eq!(answer.text(), "This button is no longer valid");
+    assert_eq!(pages.borrow().len(), 1);
+  }
+
+  #[test]
+  fn test_version_and_errors() {
+    let mut old = CallbackRouter::new();
+    old.route("page", 1, |_, _: Page| Ok(CallbackAnswer::empty())).unwrap();
+    let data = old.encode("page", &Page { list: "a".to_string(), page: 1 }).unwrap();
+
+    let mut router = CallbackRouter::new();
+    router.route("page", 2, |_, _: Page| Err(RTDError::custom("boom".to_string()))).unwrap();
+    assert!(router.route("admin:page", 1, |_, _: Page| Ok(CallbackAnswer::empty())).is_err());
+    assert!(router.route("", 1, |_, _: Page| Ok(CallbackAnswer::empty())).is_err());
+    router.on_error(|e| format!("Error: {}", e));
+    assert_eq!(router.handle(query(&data)).text(), "This button is no longer valid");
+    let data = router.encode("page", &Page { list: "a".to_string(), page: 1 }).unwrap();
+    let answer = router.handle(query(&data));
+    assert!(answer.show_alert());
+    assert_eq!(answer.text(), "Error: boom");
+
+    assert!(router.encode("page", &Page { list: "x".repeat(64), page: 1 }).is_err());
+    assert!(router.encode("missing", &1).is_err());
+
+    // a payload of another type is outdated
+    let mut numbers = CallbackRouter::new();
+    numbers.route("page", 2, |_, _: u8| Ok(CallbackAnswer::empty())).unwrap();
+    let data = numbers.encode("page", &200u8).unwrap();
+    assert_eq!(router.handle(query(&data)).text(), "This button is no longer valid");
+  }
+}
@@ -0,0 +1,592 @@
+//! Bot command framework.
+//!
+//! `CommandRouter` recognizes `/command@botname arguments` messages by their bot command entity,
+//! parses arguments into typed values and calls the registered handler. The same registrations
+//! are used to keep the command menu shown by Telegram in sync, one `setCommands` request per
+//! command scope and language.
+//!
+//! ```rust
+//! use rtdlib::command::{CommandRouter, CommandScope, CommandSpec};
+//!
+//! let mut router: CommandRouter<String> = CommandRouter::new();
+//! router.username("example_bot");
+//! router
+//!   .command(CommandSpec::new("start", "Start the bot").localized("de", "Bot starten"), |_context, _: ()| {
+//!     Ok("Hello".to_string())
+//!   }).unwrap()
+//!   .command(CommandSpec::new("ban", "Ban a user").usage("<user_id> [reason]").scope(CommandScope::AllChatAdministrators),
+//!     |_context, (user_id, reason): (i64, Option<String>)| {
+//!       Ok(format!("Banned {}: {}", user_id, reason.unwrap_or_default()))
+//!     }).unwrap();
+//! // requests to send so the menu matches the registered commands
+//! let requests = router.sync_menu();
+//! ```
+
+use std::collections::HashMap;
+
+use crate::encoding::utf16_range;
+use crate::errors::*;
+use crate::types::*;
+
+/// Maximum length of a command name
+pub const MAX_COMMAND_LENGTH: usize = 32;
+/// Maximum length of a command description
+pub const MAX_DESCRIPTION_LENGTH: usize = 256;
+/// Maximum number of commands in a menu
+pub const MAX_MENU_COMMANDS: usize = 100;
+
+/// A bot command found at the beginning of a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCommand {
+  name: String,
+  bot_username: Option<String>,
+  args: String,
+}
+
+impl ParsedCommand {
+  /// Parse a command starting the text, using its bot command entity
+  pub fn parse<T: AsRef<FormattedText>>(text: T) -> Option<ParsedCommand> {
+    let text = text.as_ref();
+    let entity = text.entities().iter().find(|entity| entity.offset() == 0 && entity.type_().is_bot_command())?;
+    let range = utf16_range(text.text(), entity.offset(), entity.length())?;
+    let command = text.text()[range.clone()].strip_prefix('/')?;
+    let (name, bot_username) = match command.split_once('@') {
+      Some((name, bot_username)) => (name, Some(bot_username.to_string())),
+      None => (command, None),
+    };
+    if name.is_empty() { return None }
+    Some(ParsedCommand {
+      name: name.to_lowercase(),
+      bot_username,
+      args: text.text()[range.end..].trim().to_string(),
+    })
+  }
+
+  /// Command name in lowercase, without slash
+  pub fn name(&self) -> &String { &self.name }
+
+  /// Bot the command is addressed to, as in `/start@botname`
+  pub fn bot_username(&self) -> Option<&String> { self.bot_username.as_ref() }
+
+  /// Text following the command
+  pub fn args(&self) -> &String { &self.args }
+
+  /// Whether the command is for the bot with `username`, commands without a bot username are
+  /// for every bot, and any command is for a bot with unknown username
+  pub fn is_for(&self, username: Option<&str>) -> bool {
+    match (username, &self.bot_username) {
+      (Some(username), Some(addressed)) => username.eq_ignore_ascii_case(addressed),
+      _ => true,
+    }
+  }
+}
+
+/// Split command arguments on whitespace; double quotes group words and `\` escapes the next character
+pub fn split_args(args: &str) -> Vec<String> {
+  let mut words = vec![];
+  let mut word: Option<String> = None;
+  let mut quoted = false;
+  let mut chars = args.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+      '"' => {
+        quoted = !quoted;
+        word.get_or_insert_with(String::new);
+      }
+      c if c.is_whitespace() && !quoted => words.extend(word.take()),
+      c => word.get_or_insert_with(String::new).push(c),
+    }
+  }
+  words.extend(word);
+  words
+}
+
+/// Arguments of a command handler
+///
+/// Tuples parse one word per element, trailing `Option` elements may be missing.
+pub trait CommandArgs: Sized {
+  fn parse_args(args: &str) -> RTDResult<Self>;
+}
+
+impl CommandArgs for () {
+  fn parse_args(_args: &str) -> RTDResult<Self> { Ok(()) }
+}
+
+/// The whole argument text
+impl CommandArgs for String {
+  fn parse_args(args: &str) -> RTDResult<Self> { Ok(args.to_string()) }
+}
+
+impl CommandArgs for Vec<String> {
+  fn parse_args(args: &str) -> RTDResult<Self> { Ok(split_args(args)) }
+}
+
+/// A single word of a tuple of arguments
+pub trait CommandArg: Sized {
+  fn parse_arg(word: Option<&String>) -> RTDResult<Self>;
+}
+
+macro_rules! from_str_arg {
+  ($($type:ty),+) => {
+    $(
+      impl CommandArg for $type {
+        fn parse_arg(word: Option<&String>) -> RTDResult<Self> {
+          let word = word.ok_or_else(|| RTDError::custom("missing argument".to_string()))?;
+          word.parse().map_err(|_| RTDError::custom(format!("invalid argument {}", word)))
+        }
+      }
+    )+
+  };
+}
+
+from_str_arg!(String, char, bool, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl<T: CommandArg> CommandArg for Option<T> {
+  fn parse_arg(word: Option<&String>) -> RTDResult<Self> {
+    word.map(|word| T::parse_arg(Some(word))).transpose()
+  }
+}
+
+macro_rules! tuple_args {
+  ($count:expr; $($name:ident),+) => {
+    impl<$($name: CommandArg),+> CommandArgs for ($($name,)+) {
+      fn parse_args(args: &str) -> RTDResult<Self> {
+        let words = split_args(args);
+        if words.len() > $count { return Err(RTDError::custom("too many arguments".to_string())) }
+        let mut words = words.iter();
+        Ok(($($name::parse_arg(words.next())?,)+))
+      }
+    }
+  };
+}
+
+tuple_args!(1; A);
+tuple_args!(2; A, B);
+tuple_args!(3; A, B, C);
+tuple_args!(4; A, B, C, D);
+
+/// Scope of a command menu, see `BotCommandScope`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CommandScope {
+  Default,
+  AllPrivateChats,
+  AllGroupChats,
+  AllChatAdministrators,
+  Chat(i64),
+  ChatAdministrators(i64),
+  /// Chat and user identifiers
+  ChatMember(i64, i64),
+}
+
+impl CommandScope {
+  pub fn of<S: AsRef<BotCommandScope>>(scope: S) -> Option<CommandScope> {
+    match scope.as_ref() {
+      BotCommandScope::Default(_) => Some(CommandScope::Default),
+      BotCommandScope::AllPrivateChats(_) => Some(CommandScope::AllPrivateChats),
+      BotCommandScope::AllGroupChats(_) => Some(CommandScope::AllGroupChats),
+      BotCommandScope::AllChatAdministrators(_) => Some(CommandScope::AllChatAdministrators),
+      BotCommandScope::Chat(scope) => Some(CommandScope::Chat(scope.chat_id())),
+      BotCommandScope::ChatAdministrators(scope) => Some(CommandScope::ChatAdministrators(scope.chat_id())),
+      BotCommandScope::ChatMember(scope) => Some(CommandScope::ChatMember(scope.chat_id(), scope.user_id())),
+      _ => None,
+    }
+  }
+
+  pub fn to_bot_command_scope(&self) -> BotCommandScope {
+    match *self {
+      CommandScope::Default => BotCommandScope::default(BotCommandScopeDefault::builder().build()),
+      CommandScope::AllPrivateChats => BotCommandScope::all_private_chats(BotCommandScopeAllPrivateChats::builder().build()),
+      CommandScope::AllGroupChats => BotCommandScope::all_group_chats(BotCommandScopeAllGroupChats::builder().build()),
+      CommandScope::AllChatAdministrators => BotCommandScope::all_chat_administrators(BotCommandScopeAllChatAdministrators::builder().build()),
+      CommandScope::Chat(chat_id) => BotCommandScope::chat(BotCommandScopeChat::builder().chat_id(chat_id).build()),
+      CommandScope::ChatAdministrators(chat_id) => {
+        BotCommandScope::chat_administrators(BotCommandScopeChatAdministrators::builder().chat_id(chat_id).build())
+      }
+      CommandScope::ChatMember(chat_id, user_id) => {
+        BotCommandScope::chat_member(BotCommandScopeChatMember::builder().chat_id(chat_id).user_id(user_id).build())
+      }
+    }
+  }
+
+  /// Whether commands of this scope are shown in `other` scope too.
+  ///
+  /// Telegram shows the commands of the most specific scope only, so commands of broader scopes are
+  /// repeated in narrower ones. Private chats have positive identifiers.
+  pub fn covers(&self, other: &CommandScope) -> bool {
+    use CommandScope::*;
+    match (*self, *other) {
+      (a, b) if a == b => true,
+      (Default, _) => true,
+      (AllPrivateChats, Chat(chat_id)) => chat_id > 0,
+      (AllGroupChats, Chat(chat_id)) | (AllGroupChats, ChatMember(chat_id, _)) => chat_id < 0,
+      (AllGroupChats, AllChatAdministrators) | (AllGroupChats, ChatAdministrators(_)) => true,
+      (AllChatAdministrators, ChatAdministrators(_)) => true,
+      (Chat(chat_id), ChatAdministrators(other)) | (Chat(chat_id), ChatMember(other, _)) => chat_id == other,
+      _ => false,
+    }
+  }
+}
+
+/// Name, descriptions and menu placement of a command
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSpec {
+  name: String,
+  description: String,
+  descriptions: HashMap<String, String>,
+  usage: String,
+  scopes: Vec<CommandScope>,
+  hidden: bool,
+}
+
+impl CommandSpec {
+  pub fn new<S: AsRef<str>, D: AsRef<str>>(name: S, description: D) -> Self {
+    Self {
+      name: name.as_ref().trim_start_matches('/').to_lowercase(),
+      description: description.as_ref().to_string(),
+      descriptions: HashMap::new(),
+      usage: String::new(),
+      scopes: vec![],
+      hidden: false,
+    }
+  }
+
+  /// Description for users with this language code
+  pub fn localized<L: AsRef<str>, D: AsRef<str>>(mut self, language_code: L, description: D) -> Self {
+    self.descriptions.insert(language_code.as_ref().to_string(), description.as_ref().to_string());
+    self
+  }
+
+  /// Arguments shown in errors, e.g. `<user_id> [reason]`
+  pub fn usage<S: AsRef<str>>(mut self, usage: S) -> Self {
+    self.usage = usage.as_ref().to_string();
+    self
+  }
+
+  /// Show the command in this scope, `CommandScope::Default` if no scope is added.
+  ///
+  /// Scopes only affect the menu, the handler is called for the command in any chat.
+  pub fn scope(mut self, scope: CommandScope) -> Self {
+    self.scopes.push(scope);
+    self
+  }
+
+  /// Handle the command without showing it in the menu
+  pub fn hidden(mut self) -> Self {
+    self.hidden = true;
+    self
+  }
+
+  pub fn name(&self) -> &String { &self.name }
+
+  pub fn description(&self) -> &String { &self.description }
+
+  /// Description for a language code, the default one if not localized
+  pub fn description_for(&self, language_code: &str) -> &String {
+    self.descriptions.get(language_code).unwrap_or(&self.description)
+  }
+
+  fn in_scope(&self, scope: &CommandScope) -> bool {
+    if self.scopes.is_empty() { return true }
+    self.scopes.iter().any(|own| own.covers(scope))
+  }
+
+  fn validate(&self) -> RTDResult<()> {
+    let valid_name = !self.name.is_empty()
+      && self.name.len() <= MAX_COMMAND_LENGTH
+      && self.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid_name {
+      return Err(RTDError::custom(format!("invalid command name {}", self.name)));
+    }
+    let descriptions = std::iter::once(&self.description).chain(self.descriptions.values());
+    for description in descriptions {
+      let length = description.chars().count();
+      if !self.hidden && (length == 0 || length > MAX_DESCRIPTION_LENGTH) {
+        return Err(RTDError::custom(format!("description of command {} must be 1-{} characters", self.name, MAX_DESCRIPTION_LENGTH)));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Where a command was sent
+#[derive(Debug, Clone)]
+pub struct CommandContext {
+  chat_id: i64,
+  message_id: i64,
+  message_thread_id: i64,
+  sender_id: MessageSender,
+  command: ParsedCommand,
+}
+
+impl CommandContext {
+  pub fn chat_id(&self) -> i64 { self.chat_id }
+
+  pub fn message_id(&self) -> i64 { self.message_id }
+
+  pub fn message_thread_id(&self) -> i64 { self.message_thread_id }
+
+  pub fn sender_id(&self) -> &MessageSender { &self.sender_id }
+
+  pub fn command(&self) -> &ParsedCommand { &self.command }
+}
+
+/// Commands of one menu
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandMenu {
+  scope: CommandScope,
+  language_code: String,
+  commands: Vec<(String, String)>,
+}
+
+impl CommandMenu {
+  pub fn scope(&self) -> CommandScope { self.scope }
+
+  /// Language code of the menu, empty for users without a dedicated menu
+  pub fn language_code(&self) -> &String { &self.language_code }
+
+  pub fn commands(&self) -> Vec<BotCommand> {
+    self.commands.iter()
+      .map(|(command, description)| BotCommand::builder().command(command).description(description).build())
+      .collect()
+  }
+
+  pub fn set_commands(&self) -> SetCommands {
+    SetCommands::builder()
+      .scope(self.scope.to_bot_command_scope())
+      .language_code(&self.language_code)
+      .commands(self.commands())
+      .build()
+  }
+}
+
+/// Request keeping the command menu in sync
+#[derive(Debug, Clone)]
+pub enum MenuRequest {
+  Set(SetCommands),
+  Delete(DeleteCommands),
+}
+
+type Handler<R> = Box<dyn FnMut(&CommandContext) -> RTDResult<R>>;
+
+struct Command<R> {
+  spec: CommandSpec,
+  handler: Handler<R>,
+}
+
+/// Dispatches bot commands to typed handlers and computes the command menus
+pub struct CommandRouter<R> {
+  username: Option<String>,
+  commands: Vec<Command<R>>,
+  synced: HashMap<(CommandScope, String), Vec<(String, String)>>,
+}
+
+impl<R> Default for CommandRouter<R> {
+  fn default() -> Self { CommandRouter::new() }
+}
+
+impl<R> CommandRouter<R> {
+  pub fn new() -> Self {
+    Self { username: None, commands: vec![], synced: HashMap::new() }
+  }
+
+  /// Username of the bot, commands addressed to other bots are ignored
+  pub fn username<S: AsRef<str>>(&mut self, username: S) -> &mut Self {
+    self.username = Some(username.as_ref().trim_start_matches('@').to_string());
+    self
+  }
+
+  /// Register a command handler, replacing a previous handler of the same command
+  pub fn command<A, F>(&mut self, spec: CommandSpec, mut handler: F) -> RTDResult<&mut Self>
+    where A: CommandArgs, F: FnMut(&CommandContext, A) -> RTDResult<R> + 'static {
+    spec.validate()?;
+    let usage = format!("/{} {}", spec.name, spec.usage).trim_end().to_string();
+    let handler: Handler<R> = Box::new(move |context| {
+      let args = A::parse_args(context.command.args())
+        .map_err(|e| RTDError::custom(format!("{}, usage: {}", e, usage)))?;
+      handler(context, args)
+    });
+    match self.commands.iter_mut().find(|command| command.spec.name == spec.name) {
+      Some(command) => *command = Command { spec, handler },
+      None => self.commands.push(Command { spec, handler }),
+    }
+    Ok(self)
+  }
+
+  pub fn specs(&self) -> Vec<&CommandSpec> {
+    self.commands.iter().map(|command| &command.spec).collect()
+  }
+
+  /// Handle a message, `None` if it isn't a known command for this bot
+  pub fn handle<M: AsRef<Message>>(&mut self, message: M) -> Option<RTDResult<R>> {
+    let message = message.as_ref();
+    if message.is_outgoing() { return None }
+    let command = ParsedCommand::parse(message.content().as_message_text()?.text())?;
+    if !command.is_for(self.username.as_deref()) { return None }
+    let handler = &mut self.commands.iter_mut().find(|registered| registered.spec.name == command.name)?.handler;
+    let context = CommandContext {
+      chat_id: message.chat_id(),
+      message_id: message.id(),
+      message_thread_id: message.message_thread_id(),
+      sender_id: message.sender_id().clone(),
+      command,
+    };
+    Some(handler(&context))
+  }
+
+  /// Handle `updateNewMessage`
+  pub fn handle_update<U: AsRef<UpdateNewMessage>>(&mut self, update: U) -> Option<RTDResult<R>> {
+    self.handle(update.as_ref().message())
+  }
+
+  /// Menus of every scope and language the registered commands are shown in
+  pub fn menus(&self) -> RTDResult<Vec<CommandMenu>> {
+    let visible = self.commands.iter().map(|command| &command.spec).filter(|spec| !spec.hidden).collect::<Vec<_>>();
+    let mut scopes = visible.iter()
+      .flat_map(|spec| if spec.scopes.is_empty() { vec![CommandScope::Default] } else { spec.scopes.clone() })
+      .collect::<Vec<_>>();
+    scopes.sort();
+    scopes.dedup();
+
+    let mut menus = vec![];
+    for scope in scopes {
+      let specs = visible.iter().filter(|spec| spec.in_scope(&scope)).collect::<Vec<_>>();
+      if specs.len() > MAX_MENU_COMMANDS {
+        return Err(RTDError::custom(format!("{} commands in scope {:?}, at most {} allowed", specs.len(), scope, MAX_MENU_COMMANDS)));
+      }
+      let mut language_codes = specs.iter().flat_map(|spec| spec.descriptions.keys()).cloned().collect::<Vec<_>>();
+      language_codes.sort();
+      language_codes.dedup();
+      language_codes.insert(0, String::new());
+      for language_code in language_codes {
+        let commands = specs.iter()
+          .map(|spec| (spec.name.clone(), spec.description_for(&language_code).clone()))
+          .collect();
+        menus.push(CommandMenu { scope, language_code, commands });
+      }
+    }
+    Ok(menus)
+  }
+
+  /// Requests to send so the menus match the registered commands, only changed menus are set
+  /// and menus no longer needed are deleted.
+  ///
+  /// The menus are considered synced once the requests are returned.
+  pub fn sync_menu(&mut self) -> RTDResult<Vec<MenuRequest>> {
+    let menus = self.menus()?;
+    let mut requests = vec![];
+    let mut synced = HashMap::new();
+    for menu in menus {
+      let key = (menu.scope, menu.language_code.clone());
+      if self.synced.get(&key) != Some(&menu.commands) {
+        requests.push(MenuRequest::Set(menu.set_commands()));
+      }
+      synced.insert(key, menu.commands);
+    }
+    let mut removed = self.synced.keys().filter(|key| !synced.contains_key(*key)).collect::<Vec<_>>();
+    removed.sort();
+    for (scope, language_code) in removed {
+      requests.push(MenuRequest::Delete(DeleteCommands::builder()
+        .scope(scope.to_bot_command_scope())
+        .language_code(language_code)
+        .build()));
+    }
+    self.synced = synced;
+    Ok(requests)
+  }
+
+  /// Forget what was synced, e.g. when the menus were changed by another client
+  pub fn reset_menu(&mut self) {
+    self.synced.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::*;
+
+  fn text(text: &str, command_length: i64) -> FormattedText {
+    FormattedText::builder()
+      .text(text)
+      .entities(vec![TextEntity::builder()
+        .offset(0)
+        .length(command_length)
+        .type_(TextEntityType::bot_command(TextEntityTypeBotCommand::builder().build()))
+        .build()])
+      .build()
+  }
+
+  fn message(text: FormattedText) -> Message {
+    Message::builder()
+      .id(1)
+      .chat_id(-100)
+      .sender_id(MessageSender::user(MessageSenderUser::builder().user_id(5).build()))
+      .content(MessageContent::message_text(MessageText::builder().text(text).build()))
+      .build()
+  }
+
+  #[test]
+  fn test_parse() {
+    let command = ParsedCommand::parse(text("/Start@example_bot  ref 42", 18)).unwrap();
+    assert_eq!(command.name(), "start");
+    assert_eq!(command.bot_username(), Some(&"example_bot".to_string()));
+    assert_eq!(command.args(), "ref 42");
+    assert_eq!(ParsedCommand::parse(FormattedText::builder().text("/start").build()), None);
+    assert_eq!(split_args(r#"a "b c" d\ e """#), vec!["a", "b c", "d e", ""]);
+
+    assert_eq!(<(i64, Option<String>)>::parse_args("7").unwrap(), (7, None));
+    assert_eq!(<(i64, Option<String>)>::parse_args("7 spam").unwrap(), (7, Some("spam".to_string())));
+    assert!(<(i64,)>::parse_args("x").is_err());
+    assert!(<(i64,)>::parse_args("1 2").is_err());
+  }
+
+  #[test]
+  fn test_dispatch() {
+    let mut router: CommandRouter<String> = CommandRouter::new();
+    router.username("example_bot");
+    router.command(CommandSpec::new("ban", "Ban a user").usage("<user_id>"), |context, (user_id,): (i64,)| {
+      Ok(format!("{} banned {}", context.chat_id(), user_id))
+    }).unwrap();
+
+    assert_eq!(router.handle(message(text("/ban 7", 4))).unwrap().unwrap(), "-100 banned 7");
+    assert_eq!(router.handle(message(text("/ban@Example_Bot 8", 16))).unwrap().unwrap(), "-100 banned 8");
+    assert!(router.handle(message(text("/ban@other_bot 8", 14))).is_none());
+    assert!(router.handle(message(text("/kick 8", 5))).is_none());
+    let error = router.handle(message(text("/ban", 4))).unwrap().unwrap_err();
+    assert!(error.to_string().contains("usage: /ban <user_id>"));
+    assert!(router.command(CommandSpec::new("Bad-Name", "x"), |_, _: ()| Ok(String::new())).is_err());
+  }
+
+  #[test]
+  fn test_sync_menu() {
+    let mut router: CommandRouter<()> = CommandRouter::new();
+    router
+      .command(CommandSpec::new("start", "Start").localized("de", "Starten"), |_, _: ()| Ok(())).unwrap()
+      .command(CommandSpec::new("ban", "Ban").scope(CommandScope::AllChatAdministrators), |_, _: ()| Ok(())).unwrap()
+      .command(CommandSpec::new("debug", "").hidden(), |_, _: ()| Ok(())).unwrap();
+
+    let menus = router.menus().unwrap();
+    let summary = menus.iter()
+      .map(|menu| (menu.scope(), menu.language_code().as_str(), menu.commands.iter().map(|c| c.1.as_str()).collect::<Vec<_>>()))
+      .collect::<Vec<_>>();
+    assert_eq!(summary, vec![
+      (CommandScope::Default, "", vec!["Start"]),
+      (CommandScope::Default, "de", vec!["Starten"]),
+      (CommandScope::AllChatAdministrators, "", vec!["Start", "Ban"]),
+      (CommandScope::AllChatAdministrators, "de", vec!["Starten", "Ban"]),
+    ]);
+    assert_eq!(router.sync_menu().unwrap().len(), 4);
+    assert!(router.sync_menu().unwrap().is_empty());
+
+    router.command(CommandSpec::new("start", "Start the bot"), |_, _: ()| Ok(())).unwrap();
+    let requests = router.sync_menu().unwrap();
+    assert_eq!(requests.len(), 4);
+    match (&requests[0], &requests[3]) {
+      (MenuRequest::Set(set), MenuRequest::Delete(delete)) => {
+        assert_eq!(set.commands()[0].description(), "Start the bot");
+        assert_eq!(delete.language_code(), "de");
+      }
+      _ => panic!("unexpected requests {:?}", requests),
+    }
+  }
+}
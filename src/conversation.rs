@@ -0,0 +1,497 @@
+//! Multi-step conversations with users.
+//!
+//! A conversation is keyed by chat, user and message thread and holds a typed state, e.g. an enum
+//! with one variant per question. Messages and callback queries of a user in a conversation are
+//! passed to the handler with the current state, and the handler chooses the next one. States are
+//! serialized into a `ConversationStore`, so flows survive restarts with `FileStore`.
+//!
+//! ```rust
+//! use rtdlib::conversation::{ConversationEngine, ConversationKey, MemoryStore, Step};
+//!
+//! #[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+//! enum Signup { AskName, AskPhone { name: String } }
+//!
+//! let mut engine: ConversationEngine<Signup, String> = ConversationEngine::new(MemoryStore::new(), |_key, state, input| {
+//!   Ok(match state {
+//!     Signup::AskName => match input.text() {
+//!       Some(name) => Step::next(Signup::AskPhone { name: name.to_string() }).reply("Share your phone number"),
+//!       None => Step::stay().reply("What is your name?"),
+//!     },
+//!     Signup::AskPhone { name } => match input.contact() {
+//!       Some(contact) => Step::finish().reply(format!("Thanks {}, {}", name, contact.phone_number())),
+//!       None => Step::stay().reply("Please use the button below"),
+//!     },
+//!   })
+//! });
+//! engine.timeout(600).cancel_command("cancel");
+//! engine.start(&ConversationKey::new(1, 1, 0), Signup::AskName, 0).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::command::ParsedCommand;
+use crate::encoding::decode_base64;
+use crate::errors::*;
+use crate::files;
+use crate::types::*;
+
+/// Identifies a conversation: a user in a chat and message thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ConversationKey {
+  chat_id: i64,
+  user_id: i64,
+  message_thread_id: i64,
+}
+
+impl ConversationKey {
+  pub fn new(chat_id: i64, user_id: i64, message_thread_id: i64) -> Self {
+    Self { chat_id, user_id, message_thread_id }
+  }
+
+  /// Key of a message sent by a user, `None` for messages sent on behalf of chats
+  pub fn of_message<M: AsRef<Message>>(message: M) -> Option<ConversationKey> {
+    let message = message.as_ref();
+    match message.sender_id() {
+      MessageSender::User(user) => Some(ConversationKey::new(message.chat_id(), user.user_id(), message.message_thread_id())),
+      _ => None,
+    }
+  }
+
+  pub fn chat_id(&self) -> i64 { self.chat_id }
+
+  pub fn user_id(&self) -> i64 { self.user_id }
+
+  pub fn message_thread_id(&self) -> i64 { self.message_thread_id }
+}
+
+/// Serialized conversation state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredState {
+  state: serde_json::Value,
+  /// Unix time of the last step
+  updated_at: i64,
+}
+
+impl StoredState {
+  pub fn state(&self) -> &serde_json::Value { &self.state }
+
+  pub fn updated_at(&self) -> i64 { self.updated_at }
+}
+
+/// Persistence of conversation states
+pub trait ConversationStore {
+  fn load(&mut self, key: &ConversationKey) -> RTDResult<Option<StoredState>>;
+
+  fn save(&mut self, key: &ConversationKey, state: StoredState) -> RTDResult<()>;
+
+  fn remove(&mut self, key: &ConversationKey) -> RTDResult<()>;
+
+  /// Every stored conversation, used to find expired ones
+  fn all(&mut self) -> RTDResult<Vec<(ConversationKey, StoredState)>>;
+}
+
+/// Keeps conversations in memory, they are lost on restart
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+  states: HashMap<ConversationKey, StoredState>,
+}
+
+impl MemoryStore {
+  pub fn new() -> Self { Self::default() }
+}
+
+impl ConversationStore for MemoryStore {
+  fn load(&mut self, key: &ConversationKey) -> RTDResult<Option<StoredState>> { Ok(self.states.get(key).cloned()) }
+
+  fn save(&mut self, key: &ConversationKey, state: StoredState) -> RTDResult<()> {
+    self.states.insert(*key, state);
+    Ok(())
+  }
+
+  fn remove(&mut self, key: &ConversationKey) -> RTDResult<()> {
+    self.states.remove(key);
+    Ok(())
+  }
+
+  fn all(&mut self) -> RTDResult<Vec<(ConversationKey, StoredState)>> {
+    Ok(self.states.iter().map(|(key, state)| (*key, state.clone())).collect())
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+  key: ConversationKey,
+  #[serde(flatten)]
+  state: StoredState,
+}
+
+/// Keeps conversations in a json file, rewritten after every change
+#[derive(Debug, Clone)]
+pub struct FileStore {
+  path: PathBuf,
+  memory: MemoryStore,
+}
+
+impl FileStore {
+  /// Open the store, loading conversations saved in the file if it exists
+  pub fn open<P: AsRef<Path>>(path: P) -> RTDResult<Self> {
+    let path = path.as_ref().to_path_buf();
+    let mut memory = MemoryStore::new();
+    if path.exists() {
+      let entries: Vec<FileEntry> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+      memory.states.extend(entries.into_iter().map(|entry| (entry.key, entry.state)));
+    }
+    Ok(Self { path, memory })
+  }
+
+  fn flush(&self) -> RTDResult<()> {
+    let mut entries = self.memory.states.iter()
+      .map(|(key, state)| FileEntry { key: *key, state: state.clone() })
+      .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.key);
+    files::write_atomic(&self.path, serde_json::to_string(&entries)?)?;
+    Ok(())
+  }
+}
+
+impl ConversationStore for FileStore {
+  fn load(&mut self, key: &ConversationKey) -> RTDResult<Option<StoredState>> { self.memory.load(key) }
+
+  fn save(&mut self, key: &ConversationKey, state: StoredState) -> RTDResult<()> {
+    self.memory.save(key, state)?;
+    self.flush()
+  }
+
+  fn remove(&mut self, key: &ConversationKey) -> RTDResult<()> {
+    if self.memory.states.remove(key).is_some() { self.flush()?; }
+    Ok(())
+  }
+
+  fn all(&mut self) -> RTDResult<Vec<(ConversationKey, StoredState)>> { self.memory.all() }
+}
+
+/// A message or callback query received in a conversation
+#[derive(Debug, Clone)]
+pub enum ConversationInput {
+  Message(Box<Message>),
+  /// Decoded data of a callback button pressed under a message of the chat
+  Callback { message_id: i64, data: Vec<u8> },
+}
+
+impl ConversationInput {
+  /// Text of a text message
+  pub fn text(&self) -> Option<&str> {
+    match self {
+      ConversationInput::Message(message) => message.content().as_message_text().map(|text| text.text().text().as_str()),
+      ConversationInput::Callback { .. } => None,
+    }
+  }
+
+  /// Shared contact, e.g. after a `KeyboardButtonTypeRequestPhoneNumber` button
+  pub fn contact(&self) -> Option<&Contact> {
+    match self {
+      ConversationInput::Message(message) => message.content().as_message_contact().map(|contact| contact.contact()),
+      ConversationInput::Callback { .. } => None,
+    }
+  }
+
+  pub fn message(&self) -> Option<&Message> {
+    match self {
+      ConversationInput::Message(message) => Some(message),
+      ConversationInput::Callback { .. } => None,
+    }
+  }
+
+  pub fn callback_data(&self) -> Option<&[u8]> {
+    match self {
+      ConversationInput::Message(_) => None,
+      ConversationInput::Callback { data, .. } => Some(data),
+    }
+  }
+}
+
+enum Transition<S> {
+  Stay,
+  Next(S),
+  Finish,
+}
+
+/// Result of a conversation handler: the next state and an optional reply
+pub struct Step<S, R> {
+  transition: Transition<S>,
+  reply: Option<R>,
+}
+
+impl<S, R> Step<S, R> {
+  /// Keep the current state, e.g. to ask again after an invalid answer
+  pub fn stay() -> Self { Self { transition: Transition::Stay, reply: None } }
+
+  /// Move to another state
+  pub fn next(state: S) -> Self { Self { transition: Transition::Next(state), reply: None } }
+
+  /// End the conversation
+  pub fn finish() -> Self { Self { transition: Transition::Finish, reply: None } }
+
+  pub fn reply<T: Into<R>>(mut self, reply: T) -> Self {
+    self.reply = Some(reply.into());
+    self
+  }
+}
+
+/// What `ConversationEngine` did with an input
+#[derive(Debug, Clone, PartialEq)]
+pub enum Handled<S, R> {
+  /// There is no conversation with the user, the input should be handled elsewhere
+  Ignored,
+  /// The handler moved or kept the state
+  Continued(Option<R>),
+  /// The handler ended the conversation
+  Finished(Option<R>),
+  /// The user sent a cancel command, with the state the conversation was in
+  Cancelled(S),
+  /// The conversation timed out before this input, which isn't passed to the handler
+  Expired(S),
+}
+
+type Handler<S, R> = Box<dyn FnMut(&ConversationKey, S, &ConversationInput) -> RTDResult<Step<S, R>>>;
+
+/// Runs conversations with a handler of typed states
+pub struct ConversationEngine<S, R> {
+  store: Box<dyn ConversationStore>,
+  handler: Handler<S, R>,
+  timeout: Option<i64>,
+  username: Option<String>,
+  cancel_commands: Vec<String>,
+}
+
+impl<S: Serialize + DeserializeOwned, R> ConversationEngine<S, R> {
+  pub fn new<T, F>(store: T, handler: F) -> Self
+    where T: ConversationStore + 'static, F: FnMut(&ConversationKey, S, &ConversationInput) -> RTDResult<Step<S, R>> + 'static {
+    Self { store: Box::new(store), handler: Box::new(handler), timeout: None, username: None, cancel_commands: vec![] }
+  }
+
+  /// Seconds of inactivity after which a conversation expires
+  pub fn timeout(&mut self, timeout: i64) -> &mut Self {
+    self.timeout = Some(timeout);
+    self
+  }
+
+  /// Username of the bot, cancel commands addressed to other bots are ignored
+  pub fn username<U: AsRef<str>>(&mut self, username: U) -> &mut Self {
+    self.username = Some(username.as_ref().trim_start_matches('@').to_string());
+    self
+  }
+
+  /// Bot command cancelling the conversation, e.g. `cancel` for `/cancel`
+  pub fn cancel_command<C: AsRef<str>>(&mut self, command: C) -> &mut Self {
+    self.cancel_commands.push(command.as_ref().trim_start_matches('/').to_lowercase());
+    self
+  }
+
+  /// Start a conversation, replacing the current one of the key
+  pub fn start(&mut self, key: &ConversationKey, state: S, now: i64) -> RTDResult<()> {
+    self.store.save(key, StoredState { state: serde_json::to_value(state)?, updated_at: now })
+  }
+
+  /// Current state of a conversation, expired ones included
+  pub fn state(&mut self, key: &ConversationKey) -> RTDResult<Option<S>> {
+    self.store.load(key)?.map(|stored| Ok(serde_json::from_value(stored.state)?)).transpose()
+  }
+
+  /// End a conversation, returning its state
+  pub fn cancel(&mut self, key: &ConversationKey) -> RTDResult<Option<S>> {
+    let state = self.state(key)?;
+    self.store.remove(key)?;
+    Ok(state)
+  }
+
+  fn is_expired(&self, stored: &StoredState, now: i64) -> bool {
+    self.timeout.is_some_and(|timeout| stored.updated_at + timeout <= now)
+  }
+
+  /// Pass an input to the conversation of the key
+  pub fn on_input(&mut self, key: &ConversationKey, input: ConversationInput, now: i64) -> RTDResult<Handled<S, R>> {
+    let stored = match self.store.load(key)? {
+      Some(stored) => stored,
+      None => return Ok(Handled::Ignored),
+    };
+    let expired = self.is_expired(&stored, now);
+    let state: S = serde_json::from_value(stored.state.clone())?;
+    if expired {
+      self.store.remove(key)?;
+      return Ok(Handled::Expired(state));
+    }
+
+    let command = input.message()
+      .and_then(|message| message.content().as_message_text())
+      .and_then(|text| ParsedCommand::parse(text.text()));
+    let username = self.username.as_deref();
+    if command.is_some_and(|command| command.is_for(username) && self.cancel_commands.contains(command.name())) {
+      self.store.remove(key)?;
+      return Ok(Handled::Cancelled(state));
+    }
+
+    let step = (self.handler)(key, state, &input)?;
+    match step.transition {
+      Transition::Stay => {
+        self.store.save(key, StoredState { state: stored.state, updated_at: now })?;
+        Ok(Handled::Continued(step.reply))
+      }
+      Transition::Next(state) => {
+        self.start(key, state, now)?;
+        Ok(Handled::Continued(step.reply))
+      }
+      Transition::Finish => {
+        self.store.remove(key)?;
+        Ok(Handled::Finished(step.reply))
+      }
+    }
+  }
+
+  /// Handle an incoming message
+  pub fn on_message<M: AsRef<Message>>(&mut self, message: M, now: i64) -> RTDResult<Handled<S, R>> {
+    let message = message.as_ref();
+    match ConversationKey::of_message(message) {
+      Some(key) if !message.is_outgoing() => self.on_input(&key, ConversationInput::Message(Box::new(message.clone())), now),
+      _ => Ok(Handled::Ignored),
+    }
+  }
+
+  /// Handle `updateNewCallbackQuery` with data, in the conversation of `message_thread_id`
+  /// since callback queries don't tell the thread of the message
+  pub fn on_callback_query<U: AsRef<UpdateNewCallbackQuery>>(&mut self, update: U, message_thread_id: i64, now: i64) -> RTDResult<Handled<S, R>> {
+    let update = update.as_ref();
+    let data = match update.payload() {
+      CallbackQueryPayload::Data(data) => data.data(),
+      CallbackQueryPayload::DataWithPassword(data) => data.data(),
+      _ => return Ok(Handled::Ignored),
+    };
+    let data = decode_base64(data)
+      .ok_or_else(|| RTDError::custom("callback data isn't valid base64".to_string()))?;
+    let key = ConversationKey::new(update.chat_id(), update.sender_user_id(), message_thread_id);
+    self.on_input(&key, ConversationInput::Callback { message_id: update.message_id(), data }, now)
+  }
+
+  /// Remove conversations which timed out, returning them to notify users
+  pub fn expire(&mut self, now: i64) -> RTDResult<Vec<(ConversationKey, S)>> {
+    let mut expired = vec![];
+    for (key, stored) in self.store.all()? {
+      if self.is_expired(&stored, now) {
+        self.store.remove(&key)?;
+        expired.push((key, serde_json::from_value(stored.state)?));
+      }
+    }
+    expired.sort_by_key(|(key, _)| *key);
+    Ok(expired)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::conversation::*;
+
+  #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+  enum Signup { AskName, AskPhone { name: String }, Confirm { name: String, phone: String } }
+
+  fn engine<T: ConversationStore + 'static>(store: T) -> ConversationEngine<Signup, String> {
+    let mut engine = ConversationEngine::new(store, |_key: &ConversationKey, state, input: &ConversationInput| {
+      Ok(match state {
+        Signup::AskName => match input.text() {
+          Some(name) => Step::next(Signup::AskPhone { name: name.to_string() }).reply("phone?"),
+          None => Step::stay().reply("name?"),
+        },
+        Signup::AskPhone { name } => match input.contact() {
+          Some(contact) => Step::next(Signup::Confirm { name, phone: contact.phone_number().clone() }).reply("ok?"),
+          None => Step::stay().reply("use the button"),
+        },
+        Signup::Confirm { name, .. } => match input.callback_data() {
+          Some(b"yes") => Step::finish().reply(format!("welcome {}", name)),
+          _ => Step::next(Signup::AskName).reply("name?"),
+        },
+      })
+    });
+    engine.timeout(60).username("@example_bot").cancel_command("/cancel");
+    engine
+  }
+
+  fn message(content: MessageContent) -> Message {
+    Message::builder()
+      .chat_id(10)
+      .message_thread_id(0)
+      .sender_id(MessageSender::user(MessageSenderUser::builder().user_id(5).build()))
+      .content(content)
+      .build()
+  }
+
+  fn text(text: &str) -> Message {
+    let mut entities = vec![];
+    if text.starts_with('/') {
+      entities.push(TextEntity::builder()
+        .length(text.encode_utf16().count() as i64)
+        .type_(TextEntityType::bot_command(TextEntityTypeBotCommand::builder().build()))
+        .build());
+    }
+    let text = FormattedText::builder().text(text).entities(entities).build();
+    message(MessageContent::message_text(MessageText::builder().text(text).build()))
+  }
+
+  #[test]
+  fn test_flow() {
+    let key = ConversationKey::new(10, 5, 0);
+    let mut engine = engine(MemoryStore::new());
+    assert_eq!(engine.on_message(text("hi"), 0).unwrap(), Handled::Ignored);
+
+    engine.start(&key, Signup::AskName, 0).unwrap();
+    assert_eq!(engine.on_message(text("Ann"), 10).unwrap(), Handled::Continued(Some("phone?".to_string())));
+    assert_eq!(engine.on_message(text("123"), 20).unwrap(), Handled::Continued(Some("use the button".to_string())));
+    let contact = MessageContact::builder().contact(Contact::builder().phone_number("+100").build()).build();
+    engine.on_message(message(MessageContent::message_contact(contact)), 30).unwrap();
+    assert_eq!(engine.state(&key).unwrap(), Some(Signup::Confirm { name: "Ann".to_string(), phone: "+100".to_string() }));
+
+    let handled = engine.on_input(&key, ConversationInput::Callback { message_id: 1, data: b"yes".to_vec() }, 40).unwrap();
+    assert_eq!(handled, Handled::Finished(Some("welcome Ann".to_string())));
+    assert_eq!(engine.state(&key).unwrap(), None);
+  }
+
+  #[test]
+  fn test_cancel_and_timeout() {
+    let key = ConversationKey::new(10, 5, 0);
+    let mut engine = engine(MemoryStore::new());
+    engine.start(&key, Signup::AskName, 0).unwrap();
+    assert_eq!(engine.on_message(text("/cancel"), 10).unwrap(), Handled::Cancelled(Signup::AskName));
+    // a cancel command of another bot in the group is just input
+    engine.start(&key, Signup::AskName, 0).unwrap();
+    assert!(!matches!(engine.on_message(text("/cancel@other_bot"), 10).unwrap(), Handled::Cancelled(_)));
+    let cancelled = engine.on_message(text("/cancel@Example_Bot"), 10).unwrap();
+    assert_eq!(cancelled, Handled::Cancelled(Signup::AskPhone { name: "/cancel@other_bot".to_string() }));
+
+    engine.start(&key, Signup::AskName, 0).unwrap();
+    engine.on_message(text("Ann"), 50).unwrap();
+    assert_eq!(engine.expire(100).unwrap(), vec![]);
+    assert_eq!(engine.on_message(text("+100"), 110).unwrap(), Handled::Expired(Signup::AskPhone { name: "Ann".to_string() }));
+
+    engine.start(&ConversationKey::new(11, 5, 0), Signup::AskName, 0).unwrap();
+    assert_eq!(engine.expire(60).unwrap(), vec![(ConversationKey::new(11, 5, 0), Signup::AskName)]);
+  }
+
+  #[test]
+  fn test_file_store() {
+    let path = std::env::temp_dir().join(format!("rtdlib-conversations-{}.json", std::process::id()));
+    let key = ConversationKey::new(10, 5, 0);
+    {
+      let mut engine = engine(FileStore::open(&path).unwrap());
+      engine.start(&key, Signup::AskName, 0).unwrap();
+      engine.on_message(text("Ann"), 10).unwrap();
+    }
+    let mut engine = engine(FileStore::open(&path).unwrap());
+    assert_eq!(engine.state(&key).unwrap(), Some(Signup::AskPhone { name: "Ann".to_string() }));
+    engine.cancel(&key).unwrap();
+    assert!(FileStore::open(&path).unwrap().all().unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+  }
+}
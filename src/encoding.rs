@@ -0,0 +1,105 @@
+//! Hex and base64 helpers, TDLib `bytes` are base64 encoded in json, and text entity offsets.
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+  let mut out = String::with_capacity(bytes.len() * 2);
+  for b in bytes {
+    out.push(HEX[(b >> 4) as usize] as char);
+    out.push(HEX[(b & 0x0f) as usize] as char);
+  }
+  out
+}
+
+pub(crate) fn decode_hex(text: &str) -> Option<Vec<u8>> {
+  if !text.len().is_multiple_of(2) { return None }
+  let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+  text.as_bytes()
+    .chunks(2)
+    .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+    .collect()
+}
+
+/// Encode standard base64 with padding
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+  let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+  for chunk in bytes.chunks(3) {
+    let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, b)| buffer | (*b as u32) << (16 - 8 * i));
+    for i in 0..4 {
+      if i <= chunk.len() {
+        out.push(BASE64[(buffer >> (18 - 6 * i) & 0x3f) as usize] as char);
+      } else {
+        out.push('=');
+      }
+    }
+  }
+  out
+}
+
+/// Decode standard or url safe base64, padding is optional
+pub(crate) fn decode_base64(text: &str) -> Option<Vec<u8>> {
+  let value = |c: u8| match c {
+    b'A'..=b'Z' => Some(c - b'A'),
+    b'a'..=b'z' => Some(c - b'a' + 26),
+    b'0'..=b'9' => Some(c - b'0' + 52),
+    b'+' | b'-' => Some(62),
+    b'/' | b'_' => Some(63),
+    _ => None,
+  };
+  let text = text.trim_end_matches('=');
+  if text.len() % 4 == 1 { return None }
+  let mut out = Vec::with_capacity(text.len() * 3 / 4);
+  let mut buffer = 0u32;
+  let mut bits = 0;
+  for c in text.bytes() {
+    buffer = buffer << 6 | value(c)? as u32;
+    bits += 6;
+    if bits >= 8 {
+      bits -= 8;
+      out.push((buffer >> bits) as u8);
+    }
+  }
+  Some(out)
+}
+
+/// Convert an entity offset and length in UTF-16 code units, as used by TDLib, to a byte range of `text`
+pub(crate) fn utf16_range(text: &str, offset: i64, length: i64) -> Option<std::ops::Range<usize>> {
+  if offset < 0 || length < 0 { return None }
+  let (offset, end) = (offset as usize, (offset + length) as usize);
+  let mut units = 0;
+  let mut start = None;
+  for (index, c) in text.char_indices() {
+    if units == offset { start = Some(index); }
+    if units == end { return start.map(|start| start..index) }
+    units += c.len_utf16();
+  }
+  if units == offset { start = Some(text.len()); }
+  if units == end { start.map(|start| start..text.len()) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::encoding::*;
+
+  #[test]
+  fn test_base64() {
+    for text in ["", "f", "fo", "foo", "foob", "fooba", "foobar"].iter() {
+      let encoded = encode_base64(text.as_bytes());
+      assert_eq!(decode_base64(&encoded).unwrap(), text.as_bytes());
+    }
+    assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+    assert_eq!(encode_base64(b"fooba"), "Zm9vYmE=");
+    assert_eq!(decode_hex(&encode_hex(&[0, 15, 255])).unwrap(), vec![0, 15, 255]);
+  }
+
+  #[test]
+  fn test_utf16_range() {
+    let text = "😀 /start@bot";
+    let range = utf16_range(text, 3, 6).unwrap();
+    assert_eq!(&text[range], "/start");
+    assert_eq!(&text[utf16_range(text, 3, 10).unwrap()], "/start@bot");
+    assert_eq!(utf16_range(text, 1, 2), None);
+    assert_eq!(utf16_range(text, 3, 11), None);
+  }
+}
@@ -0,0 +1,496 @@
+//! Chat event log (admin log) auditing.
+//!
+//! `EventLogAuditor` pages through `getChatEventLog` from the latest event back to its watermark,
+//! the last event of the previous sync, and converts every `ChatEvent` into an `AuditRecord` with
+//! the actor, the target and the changed fields. The watermark only moves once a sync reached
+//! it, so an interrupted sync is restarted from the latest event without losing older ones.
+//!
+//! ```rust
+//! use rtdlib::event_log::{EventLogAuditor, write_json_lines};
+//! # use rtdlib::types::*;
+//! let mut auditor = EventLogAuditor::new(-1001234567890);
+//! let mut records = vec![];
+//! while let Some(request) = auditor.next_request() {
+//!   // send the request and pass the `ChatEvents` received in response
+//!   # let events = ChatEvents::builder().build();
+//!   records.extend(auditor.on_events(&events).unwrap());
+//! }
+//! write_json_lines(&mut std::io::stdout(), &records).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::errors::*;
+use crate::files;
+use crate::types::*;
+
+/// Last event of a chat already audited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watermark {
+  pub chat_id: i64,
+  #[serde(with = "crate::types::int64")]
+  pub event_id: i64,
+  pub date: i64,
+}
+
+/// Watermarks of audited chats kept in a json file, rewritten after every change
+#[derive(Debug, Clone)]
+pub struct WatermarkStore {
+  path: PathBuf,
+  watermarks: HashMap<i64, Watermark>,
+}
+
+impl WatermarkStore {
+  pub fn open<P: AsRef<Path>>(path: P) -> RTDResult<Self> {
+    let path = path.as_ref().to_path_buf();
+    let mut watermarks = HashMap::new();
+    if path.exists() {
+      let saved: Vec<Watermark> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+      watermarks.extend(saved.into_iter().map(|watermark| (watermark.chat_id, watermark)));
+    }
+    Ok(Self { path, watermarks })
+  }
+
+  pub fn get(&self, chat_id: i64) -> Option<Watermark> { self.watermarks.get(&chat_id).copied() }
+
+  pub fn save(&mut self, watermark: Watermark) -> RTDResult<()> {
+    if self.watermarks.insert(watermark.chat_id, watermark) == Some(watermark) { return Ok(()) }
+    let mut saved = self.watermarks.values().copied().collect::<Vec<_>>();
+    saved.sort_by_key(|watermark| watermark.chat_id);
+    files::write_atomic(&self.path, serde_json::to_string(&saved)?)?;
+    Ok(())
+  }
+}
+
+/// Kind of an audited action, one per `ChatEventLogFilters` flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditCategory {
+  MessageEdits,
+  MessageDeletions,
+  MessagePins,
+  MemberJoins,
+  MemberLeaves,
+  MemberInvites,
+  MemberPromotions,
+  MemberRestrictions,
+  InfoChanges,
+  SettingChanges,
+  InviteLinkChanges,
+  VideoChatChanges,
+}
+
+impl AuditCategory {
+  pub fn of(action: &ChatEventAction) -> Option<AuditCategory> {
+    use ChatEventAction::*;
+    Some(match action {
+      ChatEventMessageEdited(_) | ChatEventPollStopped(_) => AuditCategory::MessageEdits,
+      ChatEventMessageDeleted(_) => AuditCategory::MessageDeletions,
+      ChatEventMessagePinned(_) | ChatEventMessageUnpinned(_) => AuditCategory::MessagePins,
+      ChatEventMemberJoined(_) | ChatEventMemberJoinedByInviteLink(_) | ChatEventMemberJoinedByRequest(_) => AuditCategory::MemberJoins,
+      ChatEventMemberLeft(_) => AuditCategory::MemberLeaves,
+      ChatEventMemberInvited(_) => AuditCategory::MemberInvites,
+      ChatEventMemberPromoted(_) => AuditCategory::MemberPromotions,
+      ChatEventMemberRestricted(_) => AuditCategory::MemberRestrictions,
+      ChatEventDescriptionChanged(_) | ChatEventLinkedChatChanged(_) | ChatEventLocationChanged(_)
+      | ChatEventPhotoChanged(_) | ChatEventStickerSetChanged(_) | ChatEventTitleChanged(_)
+      | ChatEventUsernameChanged(_) => AuditCategory::InfoChanges,
+      ChatEventHasProtectedContentToggled(_) | ChatEventInvitesToggled(_) | ChatEventIsAllHistoryAvailableToggled(_)
+      | ChatEventMessageTtlChanged(_) | ChatEventPermissionsChanged(_) | ChatEventSignMessagesToggled(_)
+      | ChatEventSlowModeDelayChanged(_) => AuditCategory::SettingChanges,
+      ChatEventInviteLinkDeleted(_) | ChatEventInviteLinkEdited(_) | ChatEventInviteLinkRevoked(_) => AuditCategory::InviteLinkChanges,
+      ChatEventVideoChatCreated(_) | ChatEventVideoChatEnded(_) | ChatEventVideoChatMuteNewParticipantsToggled(_)
+      | ChatEventVideoChatParticipantIsMutedToggled(_) | ChatEventVideoChatParticipantVolumeLevelChanged(_) => AuditCategory::VideoChatChanges,
+      _ => return None,
+    })
+  }
+}
+
+/// Who performed an action or what it was performed on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditSubject {
+  User { id: i64 },
+  Chat { id: i64 },
+  Message { id: i64 },
+  InviteLink { link: String },
+  VideoChat { id: i64 },
+}
+
+impl AuditSubject {
+  pub fn of_sender(sender: &MessageSender) -> Option<AuditSubject> {
+    match sender {
+      MessageSender::User(user) => Some(AuditSubject::User { id: user.user_id() }),
+      MessageSender::Chat(chat) => Some(AuditSubject::Chat { id: chat.chat_id() }),
+      _ => None,
+    }
+  }
+
+  /// What an action was performed on, `None` for changes of the chat itself
+  pub fn target_of(action: &ChatEventAction, actor: &MessageSender) -> Option<AuditSubject> {
+    use ChatEventAction::*;
+    let link = |link: &ChatInviteLink| AuditSubject::InviteLink { link: link.invite_link().clone() };
+    match action {
+      ChatEventMessageEdited(a) => Some(AuditSubject::Message { id: a.new_message().id() }),
+      ChatEventMessageDeleted(a) => Some(AuditSubject::Message { id: a.message().id() }),
+      ChatEventMessagePinned(a) => Some(AuditSubject::Message { id: a.message().id() }),
+      ChatEventMessageUnpinned(a) => Some(AuditSubject::Message { id: a.message().id() }),
+      ChatEventPollStopped(a) => Some(AuditSubject::Message { id: a.message().id() }),
+      ChatEventMemberJoined(_) | ChatEventMemberJoinedByInviteLink(_) | ChatEventMemberJoinedByRequest(_)
+      | ChatEventMemberLeft(_) => AuditSubject::of_sender(actor),
+      ChatEventMemberInvited(a) => Some(AuditSubject::User { id: a.user_id() }),
+      ChatEventMemberPromoted(a) => Some(AuditSubject::User { id: a.user_id() }),
+      ChatEventMemberRestricted(a) => AuditSubject::of_sender(a.member_id()),
+      ChatEventInviteLinkDeleted(a) => Some(link(a.invite_link())),
+      ChatEventInviteLinkEdited(a) => Some(link(a.new_invite_link())),
+      ChatEventInviteLinkRevoked(a) => Some(link(a.invite_link())),
+      ChatEventVideoChatCreated(a) => Some(AuditSubject::VideoChat { id: a.group_call_id() }),
+      ChatEventVideoChatEnded(a) => Some(AuditSubject::VideoChat { id: a.group_call_id() }),
+      ChatEventVideoChatParticipantIsMutedToggled(a) => AuditSubject::of_sender(a.participant_id()),
+      ChatEventVideoChatParticipantVolumeLevelChanged(a) => AuditSubject::of_sender(a.participant_id()),
+      _ => None,
+    }
+  }
+}
+
+/// A field changed by an action, nested fields are joined with dots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditChange {
+  pub field: String,
+  pub before: Value,
+  pub after: Value,
+}
+
+/// Normalized chat event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+  #[serde(with = "crate::types::int64")]
+  pub event_id: i64,
+  pub chat_id: i64,
+  pub date: i64,
+  /// Action name, e.g. `member_promoted` for `chatEventMemberPromoted`
+  pub action: String,
+  pub category: Option<AuditCategory>,
+  pub actor: Option<AuditSubject>,
+  pub target: Option<AuditSubject>,
+  pub changes: Vec<AuditChange>,
+  /// Fields of the action which aren't changes, e.g. the deleted message
+  pub details: Map<String, Value>,
+}
+
+impl AuditRecord {
+  pub fn new(chat_id: i64, event: &ChatEvent) -> RTDResult<AuditRecord> {
+    let action = event.action();
+    let mut fields = match strip_extra(serde_json::to_value(action)?) {
+      Value::Object(fields) => fields,
+      _ => Map::new(),
+    };
+    let name = fields.remove("@type").and_then(|name| name.as_str().map(action_name)).unwrap_or_default();
+
+    let mut changes = vec![];
+    let mut details = Map::new();
+    let mut keys = fields.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+      if key.starts_with("new_") { continue }
+      let value = fields[&key].clone();
+      if let Some(field) = key.strip_prefix("old_") {
+        let after = fields.get(&format!("new_{}", field)).cloned().unwrap_or(Value::Null);
+        diff(field.to_string(), &value, &after, &mut changes);
+      } else if let Value::Bool(after) = value {
+        // toggles only hold the new value
+        changes.push(AuditChange { field: key, before: Value::Bool(!after), after: Value::Bool(after) });
+      } else {
+        details.insert(key, value);
+      }
+    }
+
+    Ok(AuditRecord {
+      event_id: event.id(),
+      chat_id,
+      date: event.date(),
+      action: name,
+      category: AuditCategory::of(action),
+      actor: AuditSubject::of_sender(event.member_id()),
+      target: AuditSubject::target_of(action, event.member_id()),
+      changes,
+      details,
+    })
+  }
+
+  pub fn to_json_line(&self) -> RTDResult<String> { Ok(serde_json::to_string(self)?) }
+}
+
+/// `chatEventMemberPromoted` to `member_promoted`
+fn action_name(td_name: &str) -> String {
+  let mut name = String::new();
+  for c in td_name.trim_start_matches("chatEvent").chars() {
+    if c.is_ascii_uppercase() {
+      if !name.is_empty() { name.push('_'); }
+      name.push(c.to_ascii_lowercase());
+    } else {
+      name.push(c);
+    }
+  }
+  name
+}
+
+/// Remove the random `@extra` of builders from every object
+fn strip_extra(value: Value) -> Value {
+  match value {
+    Value::Object(fields) => Value::Object(
+      fields.into_iter().filter(|(key, _)| key != "@extra").map(|(key, value)| (key, strip_extra(value))).collect()
+    ),
+    Value::Array(values) => Value::Array(values.into_iter().map(strip_extra).collect()),
+    value => value,
+  }
+}
+
+/// Changed leaves of two values, objects of the same type are compared field by field
+fn diff(field: String, before: &Value, after: &Value, changes: &mut Vec<AuditChange>) {
+  if before == after { return }
+  match (before, after) {
+    (Value::Object(old), Value::Object(new)) if old.get("@type") == new.get("@type") => {
+      let mut keys = old.keys().chain(new.keys()).filter(|key| *key != "@type").cloned().collect::<Vec<_>>();
+      keys.sort();
+      keys.dedup();
+      for key in keys {
+        let (old, new) = (old.get(&key).unwrap_or(&Value::Null), new.get(&key).unwrap_or(&Value::Null));
+        diff(format!("{}.{}", field, key), old, new, changes);
+      }
+    }
+    _ => changes.push(AuditChange { field, before: before.clone(), after: after.clone() }),
+  }
+}
+
+/// Write records as JSON Lines, one json object per line
+pub fn write_json_lines<W: Write>(writer: &mut W, records: &[AuditRecord]) -> RTDResult<()> {
+  for record in records {
+    writer.write_all(record.to_json_line()?.as_bytes())?;
+    writer.write_all(b"\n")?;
+  }
+  writer.flush()?;
+  Ok(())
+}
+
+fn all_events() -> ChatEventLogFilters {
+  ChatEventLogFilters::builder()
+    .message_edits(true)
+    .message_deletions(true)
+    .message_pins(true)
+    .member_joins(true)
+    .member_leaves(true)
+    .member_invites(true)
+    .member_promotions(true)
+    .member_restrictions(true)
+    .info_changes(true)
+    .setting_changes(true)
+    .invite_link_changes(true)
+    .video_chat_changes(true)
+    .build()
+}
+
+/// Pages through the event log of a chat down to its watermark
+#[derive(Debug, Clone)]
+pub struct EventLogAuditor {
+  chat_id: i64,
+  query: String,
+  filters: ChatEventLogFilters,
+  user_ids: Vec<i64>,
+  page_size: i64,
+  watermark: Watermark,
+  /// `from_event_id` of the next page, `None` once the sync is done
+  cursor: Option<i64>,
+  /// Latest event of the running sync
+  latest: Option<Watermark>,
+}
+
+impl EventLogAuditor {
+  /// Audit the whole event log of a chat
+  pub fn new(chat_id: i64) -> Self {
+    Self {
+      chat_id,
+      query: String::new(),
+      filters: all_events(),
+      user_ids: vec![],
+      page_size: 100,
+      watermark: Watermark { chat_id, ..Default::default() },
+      cursor: Some(0),
+      latest: None,
+    }
+  }
+
+  /// Only audit events after a watermark, e.g. from `WatermarkStore::get`
+  pub fn since(&mut self, watermark: Watermark) -> &mut Self {
+    self.watermark = Watermark { chat_id: self.chat_id, ..watermark };
+    self
+  }
+
+  pub fn query<S: AsRef<str>>(&mut self, query: S) -> &mut Self {
+    self.query = query.as_ref().to_string();
+    self
+  }
+
+  pub fn filters<F: AsRef<ChatEventLogFilters>>(&mut self, filters: F) -> &mut Self {
+    self.filters = filters.as_ref().clone();
+    self
+  }
+
+  pub fn user_ids(&mut self, user_ids: Vec<i64>) -> &mut Self {
+    self.user_ids = user_ids;
+    self
+  }
+
+  /// Events requested per page, up to 100
+  pub fn page_size(&mut self, page_size: i64) -> &mut Self {
+    self.page_size = page_size.clamp(1, 100);
+    self
+  }
+
+  pub fn chat_id(&self) -> i64 { self.chat_id }
+
+  /// Last event audited by a finished sync
+  pub fn watermark(&self) -> Watermark { self.watermark }
+
+  pub fn is_done(&self) -> bool { self.cursor.is_none() }
+
+  /// Request of the next page, `None` once the sync reached the watermark
+  pub fn next_request(&self) -> Option<GetChatEventLog> {
+    let from_event_id = self.cursor?;
+    Some(GetChatEventLog::builder()
+      .chat_id(self.chat_id)
+      .query(&self.query)
+      .from_event_id(from_event_id)
+      .limit(self.page_size)
+      .filters(&self.filters)
+      .user_ids(self.user_ids.clone())
+      .build())
+  }
+
+  /// Audit a page received for `next_request`, records are returned oldest first. If an event
+  /// can't be audited the sync doesn't move, so the watermark never passes unaudited events
+  pub fn on_events(&mut self, events: &ChatEvents) -> RTDResult<Vec<AuditRecord>> {
+    if self.cursor.is_none() { return Ok(vec![]) }
+    let mut fresh = events.events().iter()
+      .filter(|event| event.id() > self.watermark.event_id)
+      .collect::<Vec<_>>();
+    fresh.sort_by_key(|event| event.id());
+    let records = fresh.iter().map(|event| AuditRecord::new(self.chat_id, event)).collect::<RTDResult<Vec<_>>>()?;
+
+    if let Some(event) = fresh.last() {
+      if self.latest.is_none_or(|latest| event.id() > latest.event_id) {
+        self.latest = Some(Watermark { chat_id: self.chat_id, event_id: event.id(), date: event.date() });
+      }
+    }
+    let reached_watermark = fresh.len() < events.events().len();
+    self.cursor = match fresh.first() {
+      Some(oldest) if !reached_watermark => Some(oldest.id()),
+      _ => None,
+    };
+    if self.cursor.is_none() {
+      if let Some(latest) = self.latest.take() { self.watermark = latest; }
+    }
+    Ok(records)
+  }
+
+  /// Start syncing events newer than the watermark
+  pub fn restart(&mut self) {
+    self.cursor = Some(0);
+    self.latest = None;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::event_log::*;
+
+  fn user(user_id: i64) -> MessageSender {
+    MessageSender::user(MessageSenderUser::builder().user_id(user_id).build())
+  }
+
+  fn event(id: i64, action: ChatEventAction) -> ChatEvent {
+    ChatEvent::builder().id(id).date(1_600_000_000 + id).member_id(user(7)).action(action).build()
+  }
+
+  fn title(id: i64) -> ChatEvent {
+    let action = ChatEventTitleChanged::builder().old_title(format!("v{}", id - 1)).new_title(format!("v{}", id)).build();
+    event(id, ChatEventAction::chat_event_title_changed(action))
+  }
+
+  fn page(events: Vec<ChatEvent>) -> ChatEvents {
+    ChatEvents::builder().events(events).build()
+  }
+
+  #[test]
+  fn test_record() {
+    let permissions = ChatEventPermissionsChanged::builder()
+      .old_permissions(ChatPermissions::builder().can_send_messages(true).can_send_polls(true).build())
+      .new_permissions(ChatPermissions::builder().can_send_messages(true).build())
+      .build();
+    let record = AuditRecord::new(-100, &event(5, ChatEventAction::chat_event_permissions_changed(permissions))).unwrap();
+    assert_eq!(record.action, "permissions_changed");
+    assert_eq!(record.category, Some(AuditCategory::SettingChanges));
+    assert_eq!(record.actor, Some(AuditSubject::User { id: 7 }));
+    assert_eq!(record.target, None);
+    assert_eq!(record.changes, vec![AuditChange { field: "permissions.can_send_polls".to_string(), before: Value::Bool(true), after: Value::Bool(false) }]);
+
+    let restricted = ChatEventMemberRestricted::builder()
+      .member_id(user(9))
+      .old_status(ChatMemberStatus::member(ChatMemberStatusMember::builder().build()))
+      .new_status(ChatMemberStatus::banned(ChatMemberStatusBanned::builder().banned_until_date(10).build()))
+      .build();
+    let record = AuditRecord::new(-100, &event(6, ChatEventAction::chat_event_member_restricted(restricted))).unwrap();
+    assert_eq!(record.target, Some(AuditSubject::User { id: 9 }));
+    assert_eq!(record.changes.len(), 1);
+    assert_eq!(record.changes[0].field, "status");
+    assert_eq!(record.changes[0].after["banned_until_date"], 10);
+    assert_eq!(record.details["member_id"]["user_id"], 9);
+
+    let toggled = ChatEventSignMessagesToggled::builder().sign_messages(true).build();
+    let line = AuditRecord::new(-100, &event(7, ChatEventAction::chat_event_sign_messages_toggled(toggled))).unwrap().to_json_line().unwrap();
+    assert!(line.starts_with(r#"{"event_id":7,"chat_id":-100,"date":1600000007,"action":"sign_messages_toggled","category":"setting_changes","actor":{"type":"user","id":7},"target":null,"changes":[{"field":"sign_messages","before":false,"after":true}]"#));
+  }
+
+  #[test]
+  fn test_paging_and_watermark() {
+    let mut auditor = EventLogAuditor::new(-100);
+    auditor.page_size(2);
+    assert_eq!(auditor.next_request().unwrap().from_event_id(), 0);
+    let records = auditor.on_events(&page(vec![title(12), title(11)])).unwrap();
+    assert_eq!(records.iter().map(|record| record.event_id).collect::<Vec<_>>(), vec![11, 12]);
+    assert_eq!(auditor.next_request().unwrap().from_event_id(), 11);
+    // the watermark only moves once the sync is done
+    assert_eq!(auditor.watermark().event_id, 0);
+    assert_eq!(auditor.on_events(&page(vec![title(10)])).unwrap().len(), 1);
+    assert_eq!(auditor.on_events(&page(vec![])).unwrap().len(), 0);
+    assert!(auditor.is_done());
+    assert_eq!(auditor.watermark(), Watermark { chat_id: -100, event_id: 12, date: 1_600_000_012 });
+
+    auditor.restart();
+    let records = auditor.on_events(&page(vec![title(14), title(13), title(12)])).unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(auditor.next_request().is_none());
+    assert_eq!(auditor.watermark().event_id, 14);
+
+    let path = std::env::temp_dir().join(format!("rtdlib-watermarks-{}.json", std::process::id()));
+    let mut store = WatermarkStore::open(&path).unwrap();
+    store.save(auditor.watermark()).unwrap();
+    let store = WatermarkStore::open(&path).unwrap();
+    let mut resumed = EventLogAuditor::new(-100);
+    resumed.since(store.get(-100).unwrap());
+    assert_eq!(resumed.on_events(&page(vec![title(15), title(14)])).unwrap().len(), 1);
+    assert_eq!(resumed.watermark().event_id, 15);
+    std::fs::remove_file(&path).unwrap();
+
+    let mut out = vec![];
+    write_json_lines(&mut out, &records).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.lines().count(), 2);
+    assert!(out.lines().all(|line| serde_json::from_str::<AuditRecord>(line).is_ok()));
+  }
+}
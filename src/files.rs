@@ -0,0 +1,40 @@
+//! File helpers shared by the file backed stores.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `path` with `.tmp` appended to the whole file name, so `a.json` and `a.bin` don't share it
+fn temporary_path(path: &Path) -> PathBuf {
+  let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+  name.push(".tmp");
+  path.with_file_name(name)
+}
+
+/// Write a temporary file next to `path` and rename it over `path`, so a crash can't leave a
+/// truncated file behind
+pub(crate) fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+  let path = path.as_ref();
+  let temporary = temporary_path(path);
+  fs::write(&temporary, contents)?;
+  fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::files::*;
+
+  #[test]
+  fn test_write_atomic() {
+    assert_eq!(temporary_path(Path::new("/data/store.json")), Path::new("/data/store.json.tmp"));
+    assert_eq!(temporary_path(Path::new("store")), Path::new("store.tmp"));
+
+    let path = std::env::temp_dir().join(format!("rtdlib-files-{}.json", std::process::id()));
+    write_atomic(&path, "first").unwrap();
+    write_atomic(&path, "second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+    assert!(!temporary_path(&path).exists());
+    fs::remove_file(&path).unwrap();
+  }
+}
@@ -0,0 +1,343 @@
+//! Live group call (video chat) participant tracking.
+//!
+//! `GroupCallTracker` is fed with `updateGroupCall` and `updateGroupCallParticipant` and keeps the
+//! participant list of every group call in TDLib order, reporting what changed as events.
+
+use std::collections::HashMap;
+
+use crate::types::*;
+
+/// Identifier of a group call participant, either a user or a chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ParticipantId {
+  User(i64),
+  Chat(i64),
+}
+
+impl ParticipantId {
+  pub fn of<S: AsRef<MessageSender>>(sender: S) -> Option<ParticipantId> {
+    match sender.as_ref() {
+      MessageSender::User(user) => Some(ParticipantId::User(user.user_id())),
+      MessageSender::Chat(chat) => Some(ParticipantId::Chat(chat.chat_id())),
+      _ => None,
+    }
+  }
+}
+
+/// What happened in a group call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupCallEventKind {
+  /// The group call became active
+  Started,
+  /// The group call information changed
+  Updated,
+  /// The group call is no longer active
+  Ended,
+  Joined(ParticipantId),
+  Left(ParticipantId),
+  Muted(ParticipantId),
+  Unmuted(ParticipantId),
+  HandRaised(ParticipantId),
+  HandLowered(ParticipantId),
+  StartedSpeaking(ParticipantId),
+  StoppedSpeaking(ParticipantId),
+  VideoStarted(ParticipantId),
+  VideoStopped(ParticipantId),
+  ScreenSharingStarted(ParticipantId),
+  ScreenSharingStopped(ParticipantId),
+}
+
+/// A change of a group call reported by `GroupCallTracker`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupCallEvent {
+  group_call_id: i64,
+  kind: GroupCallEventKind,
+}
+
+impl GroupCallEvent {
+  pub fn group_call_id(&self) -> i64 { self.group_call_id }
+
+  pub fn kind(&self) -> &GroupCallEventKind { &self.kind }
+}
+
+/// Synchronization source identifiers of a video channel
+pub fn video_source_ids(video_info: &GroupCallParticipantVideoInfo) -> Vec<i64> {
+  video_info.source_groups().iter().flat_map(|group| group.source_ids().iter().copied()).collect()
+}
+
+/// A group call with its known participants
+#[derive(Debug, Clone, Default)]
+pub struct TrackedGroupCall {
+  group_call: Option<GroupCall>,
+  participants: HashMap<ParticipantId, GroupCallParticipant>,
+}
+
+impl TrackedGroupCall {
+  /// Latest group call information, `None` if only participant updates were received
+  pub fn group_call(&self) -> Option<&GroupCall> { self.group_call.as_ref() }
+
+  pub fn participant(&self, participant_id: ParticipantId) -> Option<&GroupCallParticipant> {
+    self.participants.get(&participant_id)
+  }
+
+  /// Participants ordered as in the Telegram participant list
+  pub fn participants(&self) -> Vec<&GroupCallParticipant> {
+    let mut participants = self.participants.values().collect::<Vec<_>>();
+    participants.sort_by(|a, b| b.order().cmp(a.order()));
+    participants
+  }
+
+  pub fn speaking(&self) -> Vec<&GroupCallParticipant> {
+    self.participants().into_iter().filter(|p| p.is_speaking()).collect()
+  }
+
+  /// Participants waiting to be allowed to speak
+  pub fn raised_hands(&self) -> Vec<&GroupCallParticipant> {
+    self.participants().into_iter().filter(|p| p.is_hand_raised()).collect()
+  }
+
+  pub fn muted(&self) -> Vec<&GroupCallParticipant> {
+    self.participants().into_iter().filter(|p| p.is_muted_for_all_users()).collect()
+  }
+
+  pub fn recent_speakers(&self) -> Vec<ParticipantId> {
+    self.group_call.iter()
+      .flat_map(|call| call.recent_speakers().iter())
+      .filter_map(|speaker| ParticipantId::of(speaker.participant_id()))
+      .collect()
+  }
+
+  /// Find the participant owning an audio or video synchronization source
+  pub fn participant_by_source(&self, source_id: i64) -> Option<&GroupCallParticipant> {
+    self.participants.values().find(|p| {
+      p.audio_source_id() == source_id
+        || p.screen_sharing_audio_source_id() == source_id
+        || p.video_info().iter().chain(p.screen_sharing_video_info().iter()).any(|info| video_source_ids(info).contains(&source_id))
+    })
+  }
+}
+
+/// Tracks participants of group calls
+#[derive(Debug, Clone, Default)]
+pub struct GroupCallTracker {
+  calls: HashMap<i64, TrackedGroupCall>,
+}
+
+impl GroupCallTracker {
+  pub fn new() -> Self { Self::default() }
+
+  pub fn group_call(&self, group_call_id: i64) -> Option<&TrackedGroupCall> { self.calls.get(&group_call_id) }
+
+  /// Handle `updateGroupCall`
+  pub fn on_update_group_call<U: AsRef<UpdateGroupCall>>(&mut self, update: U) -> Vec<GroupCallEvent> {
+    let group_call = update.as_ref().group_call();
+    let group_call_id = group_call.id();
+    let tracked = self.calls.entry(group_call_id).or_default();
+    let was_active = tracked.group_call.as_ref().map(|call| call.is_active());
+    tracked.group_call = Some(group_call.clone());
+
+    let kind = match (was_active, group_call.is_active()) {
+      (Some(true), false) => GroupCallEventKind::Ended,
+      (Some(false), true) | (None, true) => GroupCallEventKind::Started,
+      _ => GroupCallEventKind::Updated,
+    };
+    let mut events = vec![];
+    if kind == GroupCallEventKind::Ended {
+      let mut left = tracked.participants.drain().map(|(id, participant)| (participant.order().clone(), id)).collect::<Vec<_>>();
+      left.sort_by(|a, b| b.0.cmp(&a.0));
+      events.extend(left.into_iter().map(|(_, id)| GroupCallEvent { group_call_id, kind: GroupCallEventKind::Left(id) }));
+    }
+    events.push(GroupCallEvent { group_call_id, kind });
+    events
+  }
+
+  /// Handle `updateGroupCallParticipant`
+  pub fn on_update_participant<U: AsRef<UpdateGroupCallParticipant>>(&mut self, update: U) -> Vec<GroupCallEvent> {
+    let group_call_id = update.as_ref().group_call_id();
+    let participant = update.as_ref().participant();
+    let id = match ParticipantId::of(participant.participant_id()) {
+      Some(id) => id,
+      None => return vec![],
+    };
+    let tracked = self.calls.entry(group_call_id).or_default();
+    let event = |kind| GroupCallEvent { group_call_id, kind };
+
+    // empty order means the participant must be removed from the list
+    if participant.order().is_empty() {
+      return match tracked.participants.remove(&id) {
+        Some(_) => vec![event(GroupCallEventKind::Left(id))],
+        None => vec![],
+      };
+    }
+
+    let previous = tracked.participants.insert(id, participant.clone());
+    let previous = match previous {
+      Some(previous) => previous,
+      None => {
+        let mut events = vec![event(GroupCallEventKind::Joined(id))];
+        if participant.is_hand_raised() { events.push(event(GroupCallEventKind::HandRaised(id))); }
+        if participant.video_info().is_some() { events.push(event(GroupCallEventKind::VideoStarted(id))); }
+        if participant.screen_sharing_video_info().is_some() { events.push(event(GroupCallEventKind::ScreenSharingStarted(id))); }
+        return events;
+      }
+    };
+
+    let changes = [
+      (previous.is_muted_for_all_users(), participant.is_muted_for_all_users(), GroupCallEventKind::Muted(id), GroupCallEventKind::Unmuted(id)),
+      (previous.is_hand_raised(), participant.is_hand_raised(), GroupCallEventKind::HandRaised(id), GroupCallEventKind::HandLowered(id)),
+      (previous.is_speaking(), participant.is_speaking(), GroupCallEventKind::StartedSpeaking(id), GroupCallEventKind::StoppedSpeaking(id)),
+      (previous.video_info().is_some(), participant.video_info().is_some(), GroupCallEventKind::VideoStarted(id), GroupCallEventKind::VideoStopped(id)),
+      (
+        previous.screen_sharing_video_info().is_some(),
+        participant.screen_sharing_video_info().is_some(),
+        GroupCallEventKind::ScreenSharingStarted(id),
+        GroupCallEventKind::ScreenSharingStopped(id),
+      ),
+    ];
+    changes.iter()
+      .filter(|(before, after, _, _)| before != after)
+      .map(|(_, after, on, off)| event(if *after { on.clone() } else { off.clone() }))
+      .collect()
+  }
+
+  /// Forget a group call, e.g. after leaving it
+  pub fn remove(&mut self, group_call_id: i64) -> Option<TrackedGroupCall> {
+    self.calls.remove(&group_call_id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::group_call::*;
+
+  fn user(user_id: i64) -> MessageSender {
+    MessageSender::user(MessageSenderUser::builder().user_id(user_id).build())
+  }
+
+  fn participant(user_id: i64, order: &str) -> RTDGroupCallParticipantBuilder {
+    let mut builder = GroupCallParticipant::builder();
+    builder.participant_id(user(user_id)).audio_source_id(user_id * 100).order(order);
+    builder
+  }
+
+  fn changed(participant: &mut RTDGroupCallParticipantBuilder) -> UpdateGroupCallParticipant {
+    UpdateGroupCallParticipant::builder().group_call_id(1).participant(participant.build()).build()
+  }
+
+  fn call(is_active: bool) -> UpdateGroupCall {
+    UpdateGroupCall::builder().group_call(GroupCall::builder().id(1).is_active(is_active).build()).build()
+  }
+
+  fn kinds(events: Vec<GroupCallEvent>) -> Vec<GroupCallEventKind> {
+    events.into_iter().map(|event| event.kind).collect()
+  }
+
+  fn update(user_id: i64, order: &str, muted: bool) -> UpdateGroupCallParticipant {
+    UpdateGroupCallParticipant::builder()
+      .group_call_id(1)
+      .participant(GroupCallParticipant::builder()
+        .participant_id(user(user_id))
+        .audio_source_id(user_id * 100)
+        .order(order)
+        .is_muted_for_all_users(muted)
+        .build())
+      .build()
+  }
+
+  #[test]
+  fn test_participants() {
+    let mut tracker = GroupCallTracker::new();
+    let events = tracker.on_update_group_call(UpdateGroupCall::builder()
+      .group_call(GroupCall::builder().id(1).is_active(true).build())
+      .build());
+    assert_eq!(events[0].kind(), &GroupCallEventKind::Started);
+
+    assert_eq!(tracker.on_update_participant(update(10, "100", false))[0].kind(), &GroupCallEventKind::Joined(ParticipantId::User(10)));
+    tracker.on_update_participant(update(20, "300", false));
+    tracker.on_update_participant(update(30, "200", false));
+    let order = tracker.group_call(1).unwrap().participants().iter()
+      .map(|p| ParticipantId::of(p.participant_id()).unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(order, vec![ParticipantId::User(20), ParticipantId::User(30), ParticipantId::User(10)]);
+
+    let events = tracker.on_update_participant(update(10, "100", true));
+    assert_eq!(events, vec![GroupCallEvent { group_call_id: 1, kind: GroupCallEventKind::Muted(ParticipantId::User(10)) }]);
+    assert_eq!(tracker.group_call(1).unwrap().muted().len(), 1);
+    assert_eq!(ParticipantId::of(tracker.group_call(1).unwrap().participant_by_source(3000).unwrap().participant_id()), Some(ParticipantId::User(30)));
+
+    let events = tracker.on_update_participant(update(30, "", false));
+    assert_eq!(events[0].kind(), &GroupCallEventKind::Left(ParticipantId::User(30)));
+    assert_eq!(tracker.group_call(1).unwrap().participants().len(), 2);
+
+    let events = tracker.on_update_group_call(UpdateGroupCall::builder()
+      .group_call(GroupCall::builder().id(1).is_active(false).build())
+      .build());
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[2].kind(), &GroupCallEventKind::Ended);
+  }
+
+  #[test]
+  fn test_participant_changes() {
+    use GroupCallEventKind::*;
+    let mut tracker = GroupCallTracker::new();
+    let video = GroupCallParticipantVideoInfo::builder()
+      .source_groups(vec![GroupCallVideoSourceGroup::builder().semantics("SIM").source_ids(vec![501, 502]).build()])
+      .endpoint_id("endpoint")
+      .build();
+    let id = ParticipantId::User(5);
+    assert_eq!(kinds(tracker.on_update_participant(changed(participant(5, "100").is_hand_raised(true).video_info(video.clone())))), vec![
+      Joined(id), HandRaised(id), VideoStarted(id),
+    ]);
+    assert_eq!(tracker.group_call(1).unwrap().raised_hands().len(), 1);
+    assert_eq!(tracker.group_call(1).unwrap().participant_by_source(502).unwrap().audio_source_id(), 500);
+
+    assert_eq!(kinds(tracker.on_update_participant(changed(participant(5, "100").is_speaking(true)))), vec![
+      HandLowered(id), StartedSpeaking(id), VideoStopped(id),
+    ]);
+    assert!(tracker.group_call(1).unwrap().participant_by_source(502).is_none());
+    assert_eq!(tracker.group_call(1).unwrap().speaking().len(), 1);
+    // a new order only moves the participant
+    assert!(tracker.on_update_participant(changed(participant(5, "900").is_speaking(true))).is_empty());
+    tracker.on_update_participant(changed(&mut participant(6, "500")));
+    let order = tracker.group_call(1).unwrap().participants().iter().map(|p| p.audio_source_id()).collect::<Vec<_>>();
+    assert_eq!(order, vec![500, 600]);
+
+    let chat = GroupCallParticipant::builder()
+      .participant_id(MessageSender::chat(MessageSenderChat::builder().chat_id(-100).build()))
+      .order("300")
+      .build();
+    let update = UpdateGroupCallParticipant::builder().group_call_id(1).participant(chat).build();
+    assert_eq!(kinds(tracker.on_update_participant(update)), vec![Joined(ParticipantId::Chat(-100))]);
+    assert_eq!(tracker.group_call(1).unwrap().participants().len(), 3);
+  }
+
+  #[test]
+  fn test_leave_and_end() {
+    use GroupCallEventKind::*;
+    let mut tracker = GroupCallTracker::new();
+    // participants can be known before the call itself
+    tracker.on_update_participant(update(10, "100", false));
+    tracker.on_update_participant(update(20, "200", false));
+    assert!(tracker.group_call(1).unwrap().group_call().is_none());
+    assert_eq!(kinds(tracker.on_update_group_call(call(true))), vec![Started]);
+    assert_eq!(kinds(tracker.on_update_group_call(call(true))), vec![Updated]);
+
+    // a participant that isn't listed can't leave
+    assert!(tracker.on_update_participant(update(30, "", false)).is_empty());
+    assert_eq!(kinds(tracker.on_update_participant(update(10, "", false))), vec![Left(ParticipantId::User(10))]);
+    assert!(tracker.group_call(1).unwrap().participant(ParticipantId::User(10)).is_none());
+
+    let speakers = vec![GroupCallRecentSpeaker::builder().participant_id(user(20)).is_speaking(true).build()];
+    let active = GroupCall::builder().id(1).is_active(true).recent_speakers(speakers).build();
+    tracker.on_update_group_call(UpdateGroupCall::builder().group_call(active).build());
+    assert_eq!(tracker.group_call(1).unwrap().recent_speakers(), vec![ParticipantId::User(20)]);
+
+    // everybody left when the call ended
+    tracker.on_update_participant(update(30, "300", false));
+    assert_eq!(kinds(tracker.on_update_group_call(call(false))), vec![Left(ParticipantId::User(30)), Left(ParticipantId::User(20)), Ended]);
+    assert!(tracker.group_call(1).unwrap().participants().is_empty());
+    assert_eq!(kinds(tracker.on_update_group_call(call(false))), vec![Updated]);
+    assert!(tracker.remove(1).is_some());
+    assert!(tracker.group_call(1).is_none());
+  }
+}
@@ -0,0 +1,423 @@
+//! Inline query framework for bots.
+//!
+//! An `InlineQueryHandler` only returns the results of a page, `InlineQueryDispatcher` validates
+//! them, removes duplicates, computes `next_offset` and builds the `AnswerInlineQuery` request.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Maximum number of results of an inline query answer
+pub const MAX_INLINE_RESULTS: usize = 50;
+/// Maximum size of an inline query result identifier, in bytes
+pub const MAX_RESULT_ID_LENGTH: usize = 64;
+/// Minimum time the identifiers sent for a query are remembered, in seconds
+pub const MIN_SENT_IDS_TTL: i64 = 60;
+
+const COMMON_CONTENTS: [&str; 5] = ["inputMessageText", "inputMessageInvoice", "inputMessageLocation", "inputMessageVenue", "inputMessageContact"];
+
+/// An incoming inline query
+#[derive(Debug, Clone)]
+pub struct InlineQuery {
+  id: i64,
+  sender_user_id: i64,
+  user_location: Option<Location>,
+  chat_type: Option<ChatType>,
+  query: String,
+  offset: String,
+}
+
+impl InlineQuery {
+  pub fn id(&self) -> i64 { self.id }
+
+  pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
+
+  pub fn user_location(&self) -> Option<&Location> { self.user_location.as_ref() }
+
+  /// Type of the chat the query was sent from, if known
+  pub fn chat_type(&self) -> Option<&ChatType> { self.chat_type.as_ref() }
+
+  pub fn query(&self) -> &String { &self.query }
+
+  /// Raw offset of the query, empty for the first page
+  pub fn offset(&self) -> &String { &self.offset }
+}
+
+impl From<&UpdateNewInlineQuery> for InlineQuery {
+  fn from(update: &UpdateNewInlineQuery) -> Self {
+    InlineQuery {
+      id: update.id(),
+      sender_user_id: update.sender_user_id(),
+      user_location: update.user_location().clone(),
+      chat_type: update.chat_type().clone(),
+      query: update.query().clone(),
+      offset: update.offset().clone(),
+    }
+  }
+}
+
+/// Page of results requested from `InlineQueryHandler`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlinePage {
+  offset: usize,
+  limit: usize,
+}
+
+impl InlinePage {
+  /// Index of the first result of the page
+  pub fn offset(&self) -> usize { self.offset }
+
+  /// Maximum number of results to return; returning more tells that there is a next page
+  pub fn limit(&self) -> usize { self.limit }
+}
+
+/// An inline result chosen by a user
+#[derive(Debug, Clone)]
+pub struct ChosenInlineResult {
+  sender_user_id: i64,
+  user_location: Option<Location>,
+  query: String,
+  result_id: String,
+  inline_message_id: String,
+}
+
+impl ChosenInlineResult {
+  pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
+
+  pub fn user_location(&self) -> Option<&Location> { self.user_location.as_ref() }
+
+  pub fn query(&self) -> &String { &self.query }
+
+  pub fn result_id(&self) -> &String { &self.result_id }
+
+  /// Identifier of the sent inline message, if known
+  pub fn inline_message_id(&self) -> &String { &self.inline_message_id }
+}
+
+/// Bot logic answering inline queries
+pub trait InlineQueryHandler {
+  /// Results of the requested page. Return up to `page.limit() + 1` results, an extra result
+  /// means there is a next page and is not sent
+  fn results(&mut self, query: &InlineQuery, page: InlinePage) -> RTDResult<Vec<InputInlineQueryResult>>;
+
+  /// Time the results may be cached by Telegram, in seconds
+  fn cache_time(&self, _query: &InlineQuery) -> i64 { 300 }
+
+  /// Whether the results are specific to the user who sent the query
+  fn is_personal(&self, _query: &InlineQuery) -> bool { false }
+
+  /// Text and start parameter of the button opening a private chat with the bot
+  fn switch_pm(&self, _query: &InlineQuery) -> Option<(String, String)> { None }
+
+  /// Called on `updateNewChosenInlineResult`
+  fn on_chosen(&mut self, _result: &ChosenInlineResult) {}
+}
+
+/// Identifier of an inline query result
+pub fn result_id(result: &InputInlineQueryResult) -> Option<&String> {
+  match result {
+    InputInlineQueryResult::Animation(t) => Some(t.id()),
+    InputInlineQueryResult::Article(t) => Some(t.id()),
+    InputInlineQueryResult::Audio(t) => Some(t.id()),
+    InputInlineQueryResult::Contact(t) => Some(t.id()),
+    InputInlineQueryResult::Document(t) => Some(t.id()),
+    InputInlineQueryResult::Game(t) => Some(t.id()),
+    InputInlineQueryResult::Location(t) => Some(t.id()),
+    InputInlineQueryResult::Photo(t) => Some(t.id()),
+    InputInlineQueryResult::Sticker(t) => Some(t.id()),
+    InputInlineQueryResult::Venue(t) => Some(t.id()),
+    InputInlineQueryResult::Video(t) => Some(t.id()),
+    InputInlineQueryResult::VoiceNote(t) => Some(t.id()),
+    _ => None,
+  }
+}
+
+fn require(id: &str, fields: &[(&str, &str)]) -> RTDResult<()> {
+  match fields.iter().find(|(_, value)| value.is_empty()) {
+    Some((name, _)) => Err(RTDError::custom(format!("inline query result {} has empty {}", id, name))),
+    None => Ok(()),
+  }
+}
+
+fn check_content(id: &str, content: &InputMessageContent, specific: Option<&str>) -> RTDResult<()> {
+  let name = content.td_name();
+  if COMMON_CONTENTS.contains(&name) || specific == Some(name) { return Ok(()) }
+  Err(RTDError::custom(format!("inline query result {} can't have content {}", id, name)))
+}
+
+fn check_mime_type(id: &str, mime_type: &str, allowed: &[&str]) -> RTDResult<()> {
+  if allowed.contains(&mime_type) { return Ok(()) }
+  Err(RTDError::custom(format!("inline query result {} has unsupported mime type {}", id, mime_type)))
+}
+
+/// Check fields required by TDLib for the result type
+pub fn validate_result(result: &InputInlineQueryResult) -> RTDResult<()> {
+  let id = result_id(result).ok_or_else(|| RTDError::custom("unknown inline query result".to_string()))?;
+  if id.is_empty() || id.len() > MAX_RESULT_ID_LENGTH {
+    return Err(RTDError::custom(format!("inline query result id must be 1-{} bytes: {}", MAX_RESULT_ID_LENGTH, id)));
+  }
+  let (reply_markup, content, specific) = match result {
+    InputInlineQueryResult::Animation(t) => {
+      require(id, &[("title", t.title()), ("video_url", t.video_url())])?;
+      check_mime_type(id, t.video_mime_type(), &["image/gif", "video/mp4"])?;
+      if !t.thumbnail_mime_type().is_empty() {
+        check_mime_type(id, t.thumbnail_mime_type(), &["image/jpeg", "image/gif", "video/mp4"])?;
+      }
+      (t.reply_markup(), t.input_message_content(), Some("inputMessageAnimation"))
+    }
+    InputInlineQueryResult::Article(t) => {
+      require(id, &[("title", t.title())])?;
+      (t.reply_markup(), t.input_message_content(), None)
+    }
+    InputInlineQueryResult::Audio(t) => {
+      require(id, &[("title", t.title()), ("audio_url", t.audio_url())])?;
+      (t.reply_markup(), t.input_message_content(), Some("inputMessageAudio"))
+    }
+    InputInlineQueryResult::Contact(t) => {
+      require(id, &[("phone_number", t.contact().phone_number()), ("first_name", t.contact().first_name())])?;
+      (t.reply_markup(), t.input_message_content(), None)
+    }
+    InputInlineQueryResult::Document(t) => {
+      require(id, &[("title", t.title()), ("document_url", t.document_url())])?;
+      check_mime_type(id, t.mime_type(), &["application/pdf", "application/zip"])?;
+      (t.reply_markup(), t.input_message_content(), Some("inputMessageDocument"))
+    }
+    InputInlineQueryResult::Game(t) => {
+      require(id, &[("game_short_name", t.game_short_name())])?;
+      if !t.reply_markup()._is_default() && !t.reply_markup().is_inline_keyboard() {
+        return Err(RTDError::custom(format!("inline query result {} reply markup must be an inline keyboard", id)));
+      }
+      return Ok(());
+    }
+    InputInlineQueryResult::Location(t) => {
+      require(id, &[("title", t.title())])?;
+      (t.reply_markup(), t.input_message_content(), None)
+    }
+    InputInlineQueryResult::Photo(t) => {
+      require(id, &[("photo_url", t.photo_url()), ("thumbnail_url", t.thumbnail_url())])?;
+      (t.reply_markup(), t.input_message_content(), Some("inputMessagePhoto"))
+    }
+    InputInlineQueryResult::Sticker(t) => {
+      require(id, &[("sticker_url", t.sticker_url())])?;
+      (t.reply_markup(), t.input_message_content(), Some("inputMessageSticker"))
+    }
+    InputInlineQueryResult::Venue(t) => {
+      require(id, &[("title", t.venue().title()), ("address", t.venue().address())])?;
+      (t.reply_markup(), t.input_message_content(), None)
+    }
+    InputInlineQueryResult::Video(t) => {
+      require(id, &[("title", t.title()), ("video_url", t.video_url()), ("thumbnail_url", t.thumbnail_url())])?;
+      check_mime_type(id, t.mime_type(), &["text/html", "video/mp4"])?;
+      (t.reply_markup(), t.input_message_content(), Some("inputMessageVideo"))
+    }
+    InputInlineQueryResult::VoiceNote(t) => {
+      require(id, &[("title", t.title()), ("voice_note_url", t.voice_note_url())])?;
+      (t.reply_markup(), t.input_message_content(), Some("inputMessageVoiceNote"))
+    }
+    _ => return Err(RTDError::custom(format!("unsupported inline query result {}", id))),
+  };
+  if !reply_markup._is_default() && !reply_markup.is_inline_keyboard() {
+    return Err(RTDError::custom(format!("inline query result {} reply markup must be an inline keyboard", id)));
+  }
+  // media results without content send the media itself
+  if content._is_default() {
+    return match specific {
+      Some(_) => Ok(()),
+      None => Err(RTDError::custom(format!("inline query result {} has no input message content", id))),
+    };
+  }
+  check_content(id, content, specific)
+}
+
+/// Answers inline queries using an `InlineQueryHandler`
+#[derive(Debug)]
+pub struct InlineQueryDispatcher<H: InlineQueryHandler> {
+  handler: H,
+  page_size: usize,
+  sent_ids: HashMap<(i64, String), (i64, HashSet<String>)>,
+  chosen: HashMap<String, u64>,
+}
+
+impl<H: InlineQueryHandler> InlineQueryDispatcher<H> {
+  pub fn new(handler: H) -> Self {
+    Self { handler, page_size: MAX_INLINE_RESULTS, sent_ids: HashMap::new(), chosen: HashMap::new() }
+  }
+
+  /// Number of results per answer, at most 50
+  pub fn page_size(&mut self, page_size: usize) -> &mut Self {
+    self.page_size = page_size.clamp(1, MAX_INLINE_RESULTS);
+    self
+  }
+
+  pub fn handler(&self) -> &H { &self.handler }
+
+  pub fn handler_mut(&mut self) -> &mut H { &mut self.handler }
+
+  /// How many times a result was chosen
+  pub fn chosen_count<S: AsRef<str>>(&self, result_id: S) -> u64 {
+    self.chosen.get(result_id.as_ref()).copied().unwrap_or(0)
+  }
+
+  /// Handle `updateNewInlineQuery`, returns the answer to send
+  pub fn handle<U: AsRef<UpdateNewInlineQuery>>(&mut self, update: U, now: i64) -> RTDResult<AnswerInlineQuery> {
+    let query = InlineQuery::from(update.as_ref());
+    let offset = match query.offset.as_str() {
+      "" => 0,
+      offset => offset.parse::<usize>().map_err(|_| RTDError::custom(format!("invalid inline query offset {}", offset)))?,
+    };
+    let page = InlinePage { offset, limit: self.page_size };
+    let mut results = self.handler.results(&query, page)?;
+    let has_more = results.len() > self.page_size;
+    results.truncate(self.page_size);
+    for result in &results {
+      validate_result(result)?;
+    }
+
+    // result identifiers must be unique across all pages of a query. Telegram shares the cached
+    // pages of non-personal answers between users, so only those are unique within the page
+    let is_personal = self.handler.is_personal(&query);
+    let cache_time = self.handler.cache_time(&query);
+    self.sent_ids.retain(|_, (expires_at, _)| *expires_at > now);
+    let key = (query.sender_user_id, query.query.clone());
+    if offset == 0 || !is_personal { self.sent_ids.remove(&key); }
+    let (expires_at, sent_ids) = self.sent_ids.entry(key.clone()).or_default();
+    *expires_at = now + cache_time.max(MIN_SENT_IDS_TTL);
+    let mut unique = Vec::with_capacity(results.len());
+    for result in results {
+      if let Some(id) = result_id(&result) {
+        if sent_ids.insert(id.clone()) { unique.push(result); }
+      }
+    }
+    if !has_more || !is_personal { self.sent_ids.remove(&key); }
+
+    let next_offset = if has_more { (offset + self.page_size).to_string() } else { String::new() };
+    let (switch_pm_text, switch_pm_parameter) = self.handler.switch_pm(&query).unwrap_or_default();
+    Ok(AnswerInlineQuery::builder()
+      .inline_query_id(query.id)
+      .is_personal(is_personal)
+      .results(unique)
+      .cache_time(cache_time)
+      .next_offset(next_offset)
+      .switch_pm_text(switch_pm_text)
+      .switch_pm_parameter(switch_pm_parameter)
+      .build())
+  }
+
+  /// Handle `updateNewChosenInlineResult`
+  pub fn on_chosen_result<U: AsRef<UpdateNewChosenInlineResult>>(&mut self, update: U) -> ChosenInlineResult {
+    let update = update.as_ref();
+    let chosen = ChosenInlineResult {
+      sender_user_id: update.sender_user_id(),
+      user_location: update.user_location().clone(),
+      query: update.query().clone(),
+      result_id: update.result_id().clone(),
+      inline_message_id: update.inline_message_id().clone(),
+    };
+    *self.chosen.entry(chosen.result_id.clone()).or_insert(0) += 1;
+    self.handler.on_chosen(&chosen);
+    chosen
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::inline::*;
+
+  #[derive(Debug, Default)]
+  struct Numbers { chosen: Vec<String>, shared: bool, invalid: Option<usize> }
+
+  fn article(id: &str) -> InputInlineQueryResult {
+    let text = InputMessageText::builder().text(FormattedText::builder().text(id).build()).build();
+    InputInlineQueryResult::article(InputInlineQueryResultArticle::builder()
+      .id(id)
+      .title(format!("Number {}", id))
+      .input_message_content(InputMessageContent::input_message_text(text))
+      .build())
+  }
+
+  impl InlineQueryHandler for Numbers {
+    fn results(&mut self, query: &InlineQuery, page: InlinePage) -> RTDResult<Vec<InputInlineQueryResult>> {
+      let total = query.query().parse::<usize>().unwrap_or(0);
+      // the last result of every page is repeated in the next one
+      Ok((page.offset().saturating_sub(1)..total).take(page.limit() + 1)
+        .map(|i| if Some(i) == self.invalid { article("") } else { article(&i.to_string()) })
+        .collect())
+    }
+
+    fn is_personal(&self, _query: &InlineQuery) -> bool { !self.shared }
+
+    fn on_chosen(&mut self, result: &ChosenInlineResult) { self.chosen.push(result.result_id().clone()); }
+  }
+
+  const NOW: i64 = 1_650_000_000;
+
+  fn query(text: &str, offset: &str) -> UpdateNewInlineQuery {
+    UpdateNewInlineQuery::builder().id(1).sender_user_id(7).query(text).offset(offset).build()
+  }
+
+  #[test]
+  fn test_pagination() {
+    let mut dispatcher = InlineQueryDispatcher::new(Numbers::default());
+    dispatcher.page_size(10);
+    let first = dispatcher.handle(query("25", ""), NOW).unwrap();
+    assert_eq!(first.results().len(), 10);
+    assert_eq!(first.next_offset(), "10");
+    assert!(first.is_personal());
+    assert_eq!(first.cache_time(), 300);
+
+    let second = dispatcher.handle(query("25", "10"), NOW + 1).unwrap();
+    // "9" was already sent on the first page
+    assert_eq!(result_id(&second.results()[0]).unwrap(), "10");
+    assert_eq!(second.results().len(), 9);
+    let last = dispatcher.handle(query("25", "20"), NOW + 2).unwrap();
+    assert_eq!(result_id(&last.results()[0]).unwrap(), "19");
+    assert_eq!(last.results().len(), 6);
+    assert_eq!(last.next_offset(), "");
+
+    dispatcher.on_chosen_result(UpdateNewChosenInlineResult::builder().sender_user_id(7).result_id("3").build());
+    assert_eq!(dispatcher.chosen_count("3"), 1);
+    assert_eq!(dispatcher.handler().chosen, vec!["3".to_string()]);
+  }
+
+  #[test]
+  fn test_sent_ids_expire() {
+    let mut dispatcher = InlineQueryDispatcher::new(Numbers::default());
+    dispatcher.page_size(10);
+    dispatcher.handle(query("25", ""), NOW).unwrap();
+    dispatcher.handle(query("30", ""), NOW + 299).unwrap();
+    assert_eq!(dispatcher.sent_ids.len(), 2);
+    // the user stopped paging the first query
+    dispatcher.handle(query("40", ""), NOW + 301).unwrap();
+    assert_eq!(dispatcher.sent_ids.len(), 2);
+    assert!(!dispatcher.sent_ids.contains_key(&(7, "25".to_string())));
+
+    let mut shared = InlineQueryDispatcher::new(Numbers { shared: true, ..Default::default() });
+    shared.page_size(10);
+    shared.handle(query("25", ""), NOW).unwrap();
+    let second = shared.handle(query("25", "10"), NOW).unwrap();
+    assert_eq!(result_id(&second.results()[0]).unwrap(), "9");
+    assert!(shared.sent_ids.is_empty());
+
+    // an invalid result fails the page without recording the ids before it
+    let mut invalid = InlineQueryDispatcher::new(Numbers { invalid: Some(5), ..Default::default() });
+    invalid.page_size(10);
+    assert!(invalid.handle(query("25", ""), NOW).is_err());
+    assert!(invalid.sent_ids.is_empty());
+  }
+
+  #[test]
+  fn test_validate_result() {
+    assert!(validate_result(&article("1")).is_ok());
+    let no_content = InputInlineQueryResult::article(InputInlineQueryResultArticle::builder().id("1").title("t").build());
+    assert!(validate_result(&no_content).is_err());
+    let photo = InputInlineQueryResultPhoto::builder().id("p").photo_url("https://example.com/p.jpg").build();
+    assert!(validate_result(&InputInlineQueryResult::photo(photo)).is_err());
+    let document = InputInlineQueryResultDocument::builder()
+      .id("d").title("Doc").document_url("https://example.com/d.doc").mime_type("application/msword")
+      .build();
+    assert!(validate_result(&InputInlineQueryResult::document(document)).is_err());
+    let game = InputInlineQueryResultGame::builder().id("g").game_short_name("tetris").build();
+    assert!(validate_result(&InputInlineQueryResult::game(game)).is_ok());
+  }
+}
@@ -0,0 +1,755 @@
+//! Instant View rendering to HTML, Markdown and plain text.
+//!
+//! Links to anchors of the page are rendered as `#anchor` fragments, links to anchors missing
+//! from the page fall back to their url. Media files are referenced with the url returned by
+//! the media resolver, by default the local path of downloaded files; media which can't be
+//! resolved is left out, keeping its caption.
+//! Links and embedded frames of the page are kept only for http, https, tg, mailto and tel
+//! urls and anchors, and url frames are sandboxed.
+//!
+//! ```rust
+//! use rtdlib::instant_view::InstantViewRenderer;
+//! # use rtdlib::types::*;
+//! # let view = WebPageInstantView::builder().build();
+//! let mut renderer = InstantViewRenderer::new();
+//! renderer.media_url(|file| Some(format!("media/{}", file.remote().unique_id())));
+//! let html = renderer.to_html(&view);
+//! let markdown = renderer.to_markdown(&view);
+//! ```
+
+use std::collections::HashSet;
+
+use crate::errors::*;
+use crate::statistics::{escape_html, format_date};
+use crate::types::*;
+
+type MediaResolver = Box<dyn Fn(&File) -> Option<String>>;
+
+/// Renders `WebPageInstantView` pages
+pub struct InstantViewRenderer {
+  media_url: MediaResolver,
+}
+
+impl Default for InstantViewRenderer {
+  fn default() -> Self { InstantViewRenderer::new() }
+}
+
+impl InstantViewRenderer {
+  pub fn new() -> Self {
+    Self {
+      media_url: Box::new(|file| {
+        let local = file.local();
+        if local.is_downloading_completed() && !local.path().is_empty() { Some(local.path().clone()) } else { None }
+      }),
+    }
+  }
+
+  /// Url used for a media file, `None` leaves the media out
+  pub fn media_url<F: Fn(&File) -> Option<String> + 'static>(&mut self, resolver: F) -> &mut Self {
+    self.media_url = Box::new(resolver);
+    self
+  }
+
+  pub fn to_html(&self, view: &WebPageInstantView) -> String {
+    let html = Html { media_url: &self.media_url, anchors: anchors(view.page_blocks()) };
+    let body = view.page_blocks().iter().map(|block| html.block(block)).collect::<String>();
+    let dir = if view.is_rtl() { " dir=\"rtl\"" } else { "" };
+    format!("<article{}>{}</article>", dir, body)
+  }
+
+  pub fn to_markdown(&self, view: &WebPageInstantView) -> String {
+    let markdown = Markdown { media_url: &self.media_url, anchors: anchors(view.page_blocks()), plain: false };
+    markdown.blocks(view.page_blocks()).join("\n\n")
+  }
+
+  /// Text without markup, links are followed by their url
+  pub fn to_text(&self, view: &WebPageInstantView) -> String {
+    let text = Markdown { media_url: &self.media_url, anchors: anchors(view.page_blocks()), plain: true };
+    text.blocks(view.page_blocks()).join("\n\n")
+  }
+}
+
+/// Parse an Instant View from json and render it as HTML with the default renderer
+pub fn instant_view_to_html<S: AsRef<str>>(json: S) -> RTDResult<String> {
+  Ok(InstantViewRenderer::new().to_html(&WebPageInstantView::from_json(json)?))
+}
+
+/// Text of a rich text without formatting
+pub fn plain_text(text: &RichText) -> String {
+  match text {
+    RichText::Plain(plain) => plain.text().clone(),
+    RichText::RichTexts(texts) => texts.texts().iter().map(plain_text).collect(),
+    RichText::Anchor(_) | RichText::Icon(_) => String::new(),
+    _ => inner_text(text).map(plain_text).unwrap_or_default(),
+  }
+}
+
+/// Text wrapped by a formatting rich text
+fn inner_text(text: &RichText) -> Option<&RichText> {
+  Some(match text {
+    RichText::AnchorLink(t) => t.text(),
+    RichText::Bold(t) => t.text(),
+    RichText::EmailAddress(t) => t.text(),
+    RichText::Fixed(t) => t.text(),
+    RichText::Italic(t) => t.text(),
+    RichText::Marked(t) => t.text(),
+    RichText::PhoneNumber(t) => t.text(),
+    RichText::Reference(t) => t.text(),
+    RichText::Strikethrough(t) => t.text(),
+    RichText::Subscript(t) => t.text(),
+    RichText::Superscript(t) => t.text(),
+    RichText::Underline(t) => t.text(),
+    RichText::Url(t) => t.text(),
+    _ => return None,
+  })
+}
+
+/// Nested blocks and rich texts of a block
+fn block_children(block: &PageBlock) -> (Vec<&PageBlock>, Vec<&RichText>) {
+  fn caption(caption: &PageBlockCaption) -> Vec<&RichText> { vec![caption.text(), caption.credit()] }
+  match block {
+    PageBlock::Animation(b) => (vec![], caption(b.caption())),
+    PageBlock::Audio(b) => (vec![], caption(b.caption())),
+    PageBlock::AuthorDate(b) => (vec![], vec![b.author()]),
+    PageBlock::BlockQuote(b) => (vec![], vec![b.text(), b.credit()]),
+    PageBlock::Collage(b) => (b.page_blocks().iter().collect(), caption(b.caption())),
+    PageBlock::Cover(b) => (vec![b.cover()], vec![]),
+    PageBlock::Details(b) => (b.page_blocks().iter().collect(), vec![b.header()]),
+    PageBlock::Embedded(b) => (vec![], caption(b.caption())),
+    PageBlock::EmbeddedPost(b) => (b.page_blocks().iter().collect(), caption(b.caption())),
+    PageBlock::Footer(b) => (vec![], vec![b.footer()]),
+    PageBlock::Header(b) => (vec![], vec![b.header()]),
+    PageBlock::Kicker(b) => (vec![], vec![b.kicker()]),
+    PageBlock::List(b) => (b.items().iter().flat_map(|item| item.page_blocks().iter()).collect(), vec![]),
+    PageBlock::Map(b) => (vec![], caption(b.caption())),
+    PageBlock::Paragraph(b) => (vec![], vec![b.text()]),
+    PageBlock::Photo(b) => (vec![], caption(b.caption())),
+    PageBlock::Preformatted(b) => (vec![], vec![b.text()]),
+    PageBlock::PullQuote(b) => (vec![], vec![b.text(), b.credit()]),
+    PageBlock::RelatedArticles(b) => (vec![], vec![b.header()]),
+    PageBlock::Slideshow(b) => (b.page_blocks().iter().collect(), caption(b.caption())),
+    PageBlock::Subheader(b) => (vec![], vec![b.subheader()]),
+    PageBlock::Subtitle(b) => (vec![], vec![b.subtitle()]),
+    PageBlock::Table(b) => (vec![], std::iter::once(b.caption()).chain(b.cells().iter().flatten().filter_map(|cell| cell.text().as_ref())).collect()),
+    PageBlock::Title(b) => (vec![], vec![b.title()]),
+    PageBlock::Video(b) => (vec![], caption(b.caption())),
+    PageBlock::VoiceNote(b) => (vec![], caption(b.caption())),
+    _ => (vec![], vec![]),
+  }
+}
+
+/// Names of every anchor of the page
+fn anchors(blocks: &[PageBlock]) -> HashSet<String> {
+  fn text_anchors(text: &RichText, anchors: &mut HashSet<String>) {
+    match text {
+      RichText::Anchor(anchor) => { anchors.insert(anchor.name().clone()); }
+      RichText::RichTexts(texts) => texts.texts().iter().for_each(|text| text_anchors(text, anchors)),
+      _ => if let Some(inner) = inner_text(text) { text_anchors(inner, anchors) },
+    }
+  }
+  fn block_anchors(block: &PageBlock, anchors: &mut HashSet<String>) {
+    if let PageBlock::Anchor(anchor) = block { anchors.insert(anchor.name().clone()); }
+    let (blocks, texts) = block_children(block);
+    blocks.into_iter().for_each(|block| block_anchors(block, anchors));
+    texts.into_iter().for_each(|text| text_anchors(text, anchors));
+  }
+  let mut names = HashSet::new();
+  blocks.iter().for_each(|block| block_anchors(block, &mut names));
+  names
+}
+
+fn anchor_href(anchors: &HashSet<String>, anchor_name: &str, url: &str) -> String {
+  if anchors.contains(anchor_name) || url.is_empty() { format!("#{}", anchor_name) } else { url.to_string() }
+}
+
+/// Schemes allowed in links and frames taken from the page
+const SAFE_SCHEMES: [&str; 5] = ["http", "https", "tg", "mailto", "tel"];
+
+/// The url if it is an `#anchor`, a relative url or has a safe scheme, dropping `javascript:`,
+/// `data:` and other urls which could run code in the rendered page
+fn safe_url(url: &str) -> Option<&str> {
+  let scheme_end = url.find([':', '/', '?', '#']);
+  match scheme_end {
+    Some(end) if url[end..].starts_with(':') => {
+      let scheme = &url[..end];
+      if SAFE_SCHEMES.iter().any(|safe| safe.eq_ignore_ascii_case(scheme)) { Some(url) } else { None }
+    }
+    _ => Some(url),
+  }
+}
+
+fn largest_photo(photo: &Option<Photo>) -> Option<&File> {
+  photo.as_ref()?.sizes().iter().max_by_key(|size| size.width() * size.height()).map(|size| size.photo())
+}
+
+fn map_url(location: &Location, zoom: i64) -> String {
+  let (latitude, longitude) = (location.latitude(), location.longitude());
+  format!("https://www.openstreetmap.org/?mlat={}&mlon={}#map={}/{}/{}", latitude, longitude, zoom, latitude, longitude)
+}
+
+struct Html<'a> {
+  media_url: &'a MediaResolver,
+  anchors: HashSet<String>,
+}
+
+impl<'a> Html<'a> {
+  fn rich(&self, text: &RichText) -> String {
+    let wrap = |tag: &str, inner: &RichText| format!("<{}>{}</{}>", tag, self.rich(inner), tag);
+    let link = |href: &str, inner: &RichText| match safe_url(href) {
+      Some(href) => format!("<a href=\"{}\">{}</a>", escape_html(href), self.rich(inner)),
+      None => self.rich(inner),
+    };
+    match text {
+      RichText::Plain(t) => escape_html(t.text()),
+      RichText::Bold(t) => wrap("b", t.text()),
+      RichText::Italic(t) => wrap("i", t.text()),
+      RichText::Underline(t) => wrap("u", t.text()),
+      RichText::Strikethrough(t) => wrap("s", t.text()),
+      RichText::Fixed(t) => wrap("code", t.text()),
+      RichText::Marked(t) => wrap("mark", t.text()),
+      RichText::Subscript(t) => wrap("sub", t.text()),
+      RichText::Superscript(t) => wrap("sup", t.text()),
+      RichText::Url(t) => link(t.url(), t.text()),
+      RichText::EmailAddress(t) => link(&format!("mailto:{}", t.email_address()), t.text()),
+      RichText::PhoneNumber(t) => link(&format!("tel:{}", t.phone_number()), t.text()),
+      RichText::Anchor(t) => format!("<a id=\"{}\"></a>", escape_html(t.name())),
+      RichText::AnchorLink(t) => link(&anchor_href(&self.anchors, t.anchor_name(), t.url()), t.text()),
+      RichText::Reference(t) => match safe_url(&anchor_href(&self.anchors, t.anchor_name(), t.url())) {
+        Some(href) => format!("<a class=\"reference\" href=\"{}\">{}</a>", escape_html(href), self.rich(t.text())),
+        None => self.rich(t.text()),
+      },
+      RichText::Icon(t) => match (self.media_url)(t.document().document()) {
+        Some(url) => format!("<img src=\"{}\" width=\"{}\" height=\"{}\" alt=\"\">", escape_html(url), t.width(), t.height()),
+        None => String::new(),
+      },
+      RichText::RichTexts(t) => t.texts().iter().map(|text| self.rich(text)).collect(),
+      _ => String::new(),
+    }
+  }
+
+  fn caption(&self, caption: &PageBlockCaption) -> String {
+    let (text, credit) = (self.rich(caption.text()), self.rich(caption.credit()));
+    if text.is_empty() && credit.is_empty() { return String::new() }
+    let credit = if credit.is_empty() { credit } else { format!("<cite>{}</cite>", credit) };
+    format!("<figcaption>{}{}</figcaption>", text, credit)
+  }
+
+  fn figure(&self, media: Option<String>, caption: &PageBlockCaption) -> String {
+    format!("<figure>{}{}</figure>", media.unwrap_or_default(), self.caption(caption))
+  }
+
+  fn media(&self, tag: &str, file: Option<&File>, attributes: &str) -> Option<String> {
+    let url = (self.media_url)(file?)?;
+    Some(format!("<{} src=\"{}\"{}></{}>", tag, escape_html(url), attributes, tag))
+  }
+
+  fn block(&self, block: &PageBlock) -> String {
+    let tagged = |tag: &str, text: &RichText| format!("<{}>{}</{}>", tag, self.rich(text), tag);
+    match block {
+      PageBlock::Title(b) => tagged("h1", b.title()),
+      PageBlock::Subtitle(b) => format!("<p class=\"subtitle\">{}</p>", self.rich(b.subtitle())),
+      PageBlock::Kicker(b) => format!("<p class=\"kicker\">{}</p>", self.rich(b.kicker())),
+      PageBlock::Header(b) => tagged("h2", b.header()),
+      PageBlock::Subheader(b) => tagged("h3", b.subheader()),
+      PageBlock::Paragraph(b) => tagged("p", b.text()),
+      PageBlock::Footer(b) => tagged("footer", b.footer()),
+      PageBlock::Divider(_) => "<hr>".to_string(),
+      PageBlock::Anchor(b) => format!("<a id=\"{}\"></a>", escape_html(b.name())),
+      PageBlock::AuthorDate(b) => {
+        let author = self.rich(b.author());
+        let date = if b.publish_date() > 0 {
+          let date = format_date(b.publish_date());
+          format!("<time datetime=\"{}\">{}</time>", date, date)
+        } else {
+          String::new()
+        };
+        let separator = if !author.is_empty() && !date.is_empty() { " · " } else { "" };
+        format!("<address>{}{}{}</address>", author, separator, date)
+      }
+      PageBlock::Preformatted(b) => {
+        let class = if b.language().is_empty() { String::new() } else { format!(" class=\"language-{}\"", escape_html(b.language())) };
+        format!("<pre><code{}>{}</code></pre>", class, self.rich(b.text()))
+      }
+      PageBlock::BlockQuote(b) => self.quote("blockquote", b.text(), b.credit()),
+      PageBlock::PullQuote(b) => self.quote("aside", b.text(), b.credit()),
+      PageBlock::List(b) => {
+        let ordered = b.items().iter().all(|item| item.label().trim_end_matches('.').parse::<u64>().is_ok());
+        let items = b.items().iter()
+          .map(|item| format!("<li>{}</li>", item.page_blocks().iter().map(|block| self.block(block)).collect::<String>()))
+          .collect::<String>();
+        if ordered { format!("<ol>{}</ol>", items) } else { format!("<ul>{}</ul>", items) }
+      }
+      PageBlock::Photo(b) => {
+        let image = largest_photo(b.photo())
+          .and_then(|file| (self.media_url)(file))
+          .map(|url| format!("<img src=\"{}\" alt=\"{}\">", escape_html(url), escape_html(plain_text(b.caption().text()))));
+        let image = match image {
+          Some(image) if !b.url().is_empty() && safe_url(b.url()).is_some() => Some(format!("<a href=\"{}\">{}</a>", escape_html(b.url()), image)),
+          image => image,
+        };
+        self.figure(image, b.caption())
+      }
+      PageBlock::Animation(b) => {
+        let attributes = if b.need_autoplay() { " autoplay loop muted" } else { " controls loop" };
+        self.figure(self.media("video", b.animation().as_ref().map(|a| a.animation()), attributes), b.caption())
+      }
+      PageBlock::Video(b) => {
+        let attributes = format!(" controls{}{}", if b.need_autoplay() { " autoplay muted" } else { "" }, if b.is_looped() { " loop" } else { "" });
+        self.figure(self.media("video", b.video().as_ref().map(|v| v.video()), &attributes), b.caption())
+      }
+      PageBlock::Audio(b) => self.figure(self.media("audio", b.audio().as_ref().map(|a| a.audio()), " controls"), b.caption()),
+      PageBlock::VoiceNote(b) => self.figure(self.media("audio", b.voice_note().as_ref().map(|v| v.voice()), " controls"), b.caption()),
+      PageBlock::Cover(b) => self.block(b.cover()),
+      PageBlock::Embedded(b) => {
+        let size = format!(" width=\"{}\" height=\"{}\"", b.width(), b.height());
+        let frame = if !b.html().is_empty() {
+          Some(format!("<iframe sandbox=\"allow-scripts\" srcdoc=\"{}\"{}></iframe>", escape_html(b.html()), size))
+        } else if !b.url().is_empty() && safe_url(b.url()).is_some() {
+          Some(format!("<iframe sandbox=\"allow-scripts allow-popups\" src=\"{}\"{}></iframe>", escape_html(b.url()), size))
+        } else {
+          None
+        };
+        self.figure(frame, b.caption())
+      }
+      PageBlock::EmbeddedPost(b) => {
+        let date = if b.date() > 0 { format!(" <time>{}</time>", format_date(b.date())) } else { String::new() };
+        format!(
+          "<blockquote class=\"embedded-post\" cite=\"{}\"><p><b>{}</b>{}</p>{}{}</blockquote>",
+          escape_html(safe_url(b.url()).unwrap_or_default()), escape_html(b.author()), date,
+          b.page_blocks().iter().map(|block| self.block(block)).collect::<String>(),
+          self.caption(b.caption()),
+        )
+      }
+      PageBlock::Collage(b) => self.gallery("collage", b.page_blocks(), b.caption()),
+      PageBlock::Slideshow(b) => self.gallery("slideshow", b.page_blocks(), b.caption()),
+      PageBlock::ChatLink(b) => format!(
+        "<p class=\"chat-link\"><a href=\"https://t.me/{}\">{}</a></p>", escape_html(b.username()), escape_html(b.title())
+      ),
+      PageBlock::Table(b) => self.table(b),
+      PageBlock::Details(b) => format!(
+        "<details{}><summary>{}</summary>{}</details>",
+        if b.is_open() { " open" } else { "" }, self.rich(b.header()),
+        b.page_blocks().iter().map(|block| self.block(block)).collect::<String>()
+      ),
+      PageBlock::RelatedArticles(b) => {
+        let articles = b.articles().iter().map(|article| {
+          let description = if article.description().is_empty() { String::new() } else { format!(" — {}", escape_html(article.description())) };
+          let title = if article.title().is_empty() { article.url() } else { article.title() };
+          match safe_url(article.url()) {
+            Some(url) => format!("<li><a href=\"{}\">{}</a>{}</li>", escape_html(url), escape_html(title), description),
+            None => format!("<li>{}{}</li>", escape_html(title), description),
+          }
+        }).collect::<String>();
+        format!("<section class=\"related-articles\"><h3>{}</h3><ul>{}</ul></section>", self.rich(b.header()), articles)
+      }
+      PageBlock::Map(b) => {
+        let link = format!("<a href=\"{}\">Map</a>", escape_html(map_url(b.location(), b.zoom())));
+        self.figure(Some(link), b.caption())
+      }
+      _ => String::new(),
+    }
+  }
+
+  fn quote(&self, tag: &str, text: &RichText, credit: &RichText) -> String {
+    let credit = self.rich(credit);
+    let credit = if credit.is_empty() { credit } else { format!("<cite>{}</cite>", credit) };
+    format!("<{}><p>{}</p>{}</{}>", tag, self.rich(text), credit, tag)
+  }
+
+  fn gallery(&self, class: &str, blocks: &[PageBlock], caption: &PageBlockCaption) -> String {
+    format!(
+      "<figure class=\"{}\">{}{}</figure>",
+      class, blocks.iter().map(|block| self.block(block)).collect::<String>(), self.caption(caption)
+    )
+  }
+
+  fn table(&self, table: &PageBlockTable) -> String {
+    let mut class = vec![];
+    if table.is_bordered() { class.push("bordered"); }
+    if table.is_striped() { class.push("striped"); }
+    let class = if class.is_empty() { String::new() } else { format!(" class=\"{}\"", class.join(" ")) };
+    let caption = self.rich(table.caption());
+    let caption = if caption.is_empty() { caption } else { format!("<caption>{}</caption>", caption) };
+    let rows = table.cells().iter().map(|row| {
+      let cells = row.iter().map(|cell| {
+        let tag = if cell.is_header() { "th" } else { "td" };
+        let mut attributes = String::new();
+        if cell.colspan() > 1 { attributes.push_str(&format!(" colspan=\"{}\"", cell.colspan())); }
+        if cell.rowspan() > 1 { attributes.push_str(&format!(" rowspan=\"{}\"", cell.rowspan())); }
+        let align = match cell.align() {
+          PageBlockHorizontalAlignment::Center(_) => "center",
+          PageBlockHorizontalAlignment::Right(_) => "right",
+          _ => "",
+        };
+        let valign = match cell.valign() {
+          PageBlockVerticalAlignment::Middle(_) => "middle",
+          PageBlockVerticalAlignment::Bottom(_) => "bottom",
+          _ => "",
+        };
+        let style = [("text-align", align), ("vertical-align", valign)].iter()
+          .filter(|(_, value)| !value.is_empty())
+          .map(|(name, value)| format!("{}:{}", name, value))
+          .collect::<Vec<_>>();
+        if !style.is_empty() { attributes.push_str(&format!(" style=\"{}\"", style.join(";"))); }
+        let text = cell.text().as_ref().map(|text| self.rich(text)).unwrap_or_default();
+        format!("<{}{}>{}</{}>", tag, attributes, text, tag)
+      }).collect::<String>();
+      format!("<tr>{}</tr>", cells)
+    }).collect::<String>();
+    format!("<table{}>{}{}</table>", class, caption, rows)
+  }
+}
+
+/// Renders Markdown, or plain text without markup
+struct Markdown<'a> {
+  media_url: &'a MediaResolver,
+  anchors: HashSet<String>,
+  plain: bool,
+}
+
+fn escape_markdown(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' | '|' | '~') { escaped.push('\\'); }
+    escaped.push(c);
+  }
+  escaped
+}
+
+impl<'a> Markdown<'a> {
+  fn rich(&self, text: &RichText) -> String {
+    let wrap = |marker: &str, inner: &RichText| {
+      let inner = self.rich(inner);
+      if self.plain || inner.trim().is_empty() { inner } else { format!("{}{}{}", marker, inner, marker) }
+    };
+    let link = |href: &str, inner: &RichText| {
+      let inner = self.rich(inner);
+      match self.plain {
+        true if href.starts_with('#') || plain_text(text) == href || href.is_empty() => inner,
+        true => format!("{} ({})", inner, href),
+        false => match safe_url(href) {
+          Some(href) => format!("[{}]({})", inner, href.replace(' ', "%20").replace(')', "%29")),
+          None => inner,
+        },
+      }
+    };
+    match text {
+      RichText::Plain(t) => if self.plain { t.text().clone() } else { escape_markdown(t.text()) },
+      RichText::Bold(t) => wrap("**", t.text()),
+      RichText::Italic(t) => wrap("_", t.text()),
+      RichText::Strikethrough(t) => wrap("~~", t.text()),
+      RichText::Fixed(t) => match self.plain {
+        true => plain_text(t.text()),
+        false => format!("`{}`", plain_text(t.text()).replace('`', "'")),
+      },
+      RichText::Url(t) => link(t.url(), t.text()),
+      RichText::EmailAddress(t) => link(&format!("mailto:{}", t.email_address()), t.text()),
+      RichText::PhoneNumber(t) => link(&format!("tel:{}", t.phone_number()), t.text()),
+      RichText::Anchor(t) if !self.plain => format!("<a id=\"{}\"></a>", escape_html(t.name())),
+      RichText::AnchorLink(t) => link(&anchor_href(&self.anchors, t.anchor_name(), t.url()), t.text()),
+      RichText::Reference(t) => link(&anchor_href(&self.anchors, t.anchor_name(), t.url()), t.text()),
+      RichText::RichTexts(t) => t.texts().iter().map(|text| self.rich(text)).collect(),
+      _ => inner_text(text).map(|inner| self.rich(inner)).unwrap_or_default(),
+    }
+  }
+
+  fn caption(&self, caption: &PageBlockCaption) -> Option<String> {
+    let (text, credit) = (self.rich(caption.text()), self.rich(caption.credit()));
+    match (text.is_empty(), credit.is_empty()) {
+      (true, true) => None,
+      (false, true) => Some(text),
+      (true, false) => Some(credit),
+      (false, false) => Some(format!("{} — {}", text, credit)),
+    }
+  }
+
+  /// Media link followed by the caption
+  fn media(&self, label: &str, file: Option<&File>, caption: &PageBlockCaption, image: bool) -> Vec<String> {
+    let url = file.and_then(|file| (self.media_url)(file));
+    let caption = self.caption(caption);
+    let mut out = vec![];
+    match (url, self.plain) {
+      (Some(url), true) => out.push(format!("[{}: {}]", label, url)),
+      (Some(url), false) => {
+        let alt = caption.clone().unwrap_or_else(|| label.to_string()).replace(']', "\\]");
+        out.push(format!("{}[{}]({})", if image { "!" } else { "" }, alt, url.replace(' ', "%20")));
+      }
+      (None, _) => {}
+    }
+    out.extend(caption.map(|caption| if self.plain { caption } else { format!("_{}_", caption) }));
+    out
+  }
+
+  fn quote(&self, text: &RichText, credit: &RichText) -> Vec<String> {
+    let mut lines = self.rich(text);
+    let credit = self.rich(credit);
+    if !credit.is_empty() { lines.push_str(&format!("\n— {}", credit)); }
+    if self.plain { return vec![lines] }
+    vec![lines.lines().map(|line| format!("> {}", line)).collect::<Vec<_>>().join("\n")]
+  }
+
+  fn heading(&self, level: usize, text: &RichText) -> Vec<String> {
+    let text = self.rich(text);
+    if text.is_empty() { return vec![] }
+    if self.plain { vec![text] } else { vec![format!("{} {}", "#".repeat(level), text.replace('\n', " "))] }
+  }
+
+  fn blocks(&self, blocks: &[PageBlock]) -> Vec<String> {
+    blocks.iter().flat_map(|block| self.block(block)).filter(|chunk| !chunk.is_empty()).collect()
+  }
+
+  fn block(&self, block: &PageBlock) -> Vec<String> {
+    match block {
+      PageBlock::Title(b) => self.heading(1, b.title()),
+      PageBlock::Subtitle(b) => self.heading(2, b.subtitle()),
+      PageBlock::Header(b) => self.heading(2, b.header()),
+      PageBlock::Subheader(b) => self.heading(3, b.subheader()),
+      PageBlock::Kicker(b) => vec![self.rich(b.kicker())],
+      PageBlock::Paragraph(b) => vec![self.rich(b.text())],
+      PageBlock::Footer(b) => vec![self.rich(b.footer())],
+      PageBlock::Divider(_) => vec![if self.plain { "* * *" } else { "---" }.to_string()],
+      PageBlock::Anchor(b) if !self.plain => vec![format!("<a id=\"{}\"></a>", escape_html(b.name()))],
+      PageBlock::AuthorDate(b) => {
+        let author = self.rich(b.author());
+        let date = if b.publish_date() > 0 { format_date(b.publish_date()) } else { String::new() };
+        let line = [author, date].iter().filter(|part| !part.is_empty()).cloned().collect::<Vec<_>>().join(" · ");
+        vec![if self.plain || line.is_empty() { line } else { format!("_{}_", line) }]
+      }
+      PageBlock::Preformatted(b) => {
+        let code = plain_text(b.text());
+        vec![if self.plain { code } else { format!("```{}\n{}\n```", b.language(), code) }]
+      }
+      PageBlock::BlockQuote(b) => self.quote(b.text(), b.credit()),
+      PageBlock::PullQuote(b) => self.quote(b.text(), b.credit()),
+      PageBlock::List(b) => {
+        let ordered = b.items().iter().all(|item| item.label().trim_end_matches('.').parse::<u64>().is_ok());
+        let items = b.items().iter().enumerate().map(|(index, item)| {
+          let marker = match (ordered, self.plain) {
+            (true, _) => format!("{}.", index + 1),
+            (false, false) => "-".to_string(),
+            (false, true) => if item.label().is_empty() { "•".to_string() } else { item.label().clone() },
+          };
+          let indent = " ".repeat(marker.chars().count() + 1);
+          let body = self.blocks(item.page_blocks()).join("\n\n");
+          let body = body.lines().enumerate()
+            .map(|(line, text)| if line == 0 || text.is_empty() { text.to_string() } else { format!("{}{}", indent, text) })
+            .collect::<Vec<_>>()
+            .join("\n");
+          format!("{} {}", marker, body)
+        }).collect::<Vec<_>>();
+        vec![items.join("\n")]
+      }
+      PageBlock::Photo(b) => {
+        let mut out = self.media("Photo", largest_photo(b.photo()), b.caption(), true);
+        if !b.url().is_empty() && (self.plain || safe_url(b.url()).is_some()) {
+          out.push(if self.plain { b.url().clone() } else { format!("<{}>", b.url()) });
+        }
+        out
+      }
+      PageBlock::Animation(b) => self.media("Animation", b.animation().as_ref().map(|a| a.animation()), b.caption(), false),
+      PageBlock::Video(b) => self.media("Video", b.video().as_ref().map(|v| v.video()), b.caption(), false),
+      PageBlock::Audio(b) => self.media("Audio", b.audio().as_ref().map(|a| a.audio()), b.caption(), false),
+      PageBlock::VoiceNote(b) => self.media("Voice note", b.voice_note().as_ref().map(|v| v.voice()), b.caption(), false),
+      PageBlock::Cover(b) => self.block(b.cover()),
+      PageBlock::Embedded(b) => {
+        let mut out = vec![];
+        if !b.url().is_empty() && (self.plain || safe_url(b.url()).is_some()) {
+          out.push(if self.plain { b.url().clone() } else { format!("[Embedded content]({})", b.url()) });
+        }
+        out.extend(self.caption(b.caption()));
+        out
+      }
+      PageBlock::EmbeddedPost(b) => {
+        let mut header = b.author().clone();
+        if b.date() > 0 { header.push_str(&format!(", {}", format_date(b.date()))); }
+        let mut body = vec![header];
+        body.extend(self.blocks(b.page_blocks()));
+        body.extend(self.caption(b.caption()));
+        if !b.url().is_empty() { body.push(b.url().clone()); }
+        let body = body.join("\n\n");
+        if self.plain { return vec![body] }
+        vec![body.lines().map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) }).collect::<Vec<_>>().join("\n")]
+      }
+      PageBlock::Collage(b) => self.gallery(b.page_blocks(), b.caption()),
+      PageBlock::Slideshow(b) => self.gallery(b.page_blocks(), b.caption()),
+      PageBlock::ChatLink(b) => {
+        let url = format!("https://t.me/{}", b.username());
+        vec![if self.plain { format!("{} ({})", b.title(), url) } else { format!("[{}]({})", escape_markdown(b.title()), url) }]
+      }
+      PageBlock::Table(b) => self.table(b),
+      PageBlock::Details(b) => {
+        let mut out = self.heading(4, b.header());
+        out.extend(self.blocks(b.page_blocks()));
+        out
+      }
+      PageBlock::RelatedArticles(b) => {
+        let mut out = self.heading(3, b.header());
+        let articles = b.articles().iter().map(|article| {
+          let title = if article.title().is_empty() { article.url() } else { article.title() };
+          let description = if article.description().is_empty() { String::new() } else { format!(" — {}", article.description()) };
+          match self.plain {
+            true => format!("• {} ({}){}", title, article.url(), description),
+            false if safe_url(article.url()).is_none() => format!("- {}{}", escape_markdown(title), escape_markdown(&description)),
+            false => format!("- [{}]({}){}", escape_markdown(title), article.url(), escape_markdown(&description)),
+          }
+        }).collect::<Vec<_>>();
+        if !articles.is_empty() { out.push(articles.join("\n")); }
+        out
+      }
+      PageBlock::Map(b) => {
+        let url = map_url(b.location(), b.zoom());
+        let mut out = vec![if self.plain { format!("[Map: {}]", url) } else { format!("[Map]({})", url) }];
+        out.extend(self.caption(b.caption()));
+        out
+      }
+      _ => vec![],
+    }
+  }
+
+  fn gallery(&self, blocks: &[PageBlock], caption: &PageBlockCaption) -> Vec<String> {
+    let mut out = self.blocks(blocks);
+    out.extend(self.caption(caption));
+    out
+  }
+
+  fn table(&self, table: &PageBlockTable) -> Vec<String> {
+    let mut out = vec![];
+    let caption = self.rich(table.caption());
+    if !caption.is_empty() { out.push(caption); }
+    let rows = table.cells().iter()
+      .map(|row| row.iter().map(|cell| {
+        let text = cell.text().as_ref().map(|text| self.rich(text)).unwrap_or_default().replace('\n', " ");
+        if self.plain { text } else { text.replace('|', "\\|") }
+      }).collect::<Vec<_>>())
+      .filter(|row| !row.is_empty())
+      .collect::<Vec<_>>();
+    if rows.is_empty() { return out }
+    if self.plain {
+      out.push(rows.iter().map(|row| row.join(" | ")).collect::<Vec<_>>().join("\n"));
+      return out;
+    }
+    let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let line = |row: &Vec<String>| {
+      let mut cells = row.clone();
+      cells.resize(columns, String::new());
+      format!("| {} |", cells.join(" | "))
+    };
+    let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    lines.extend(rows[1..].iter().map(line));
+    out.push(lines.join("\n"));
+    out
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::instant_view::*;
+
+  fn plain(text: &str) -> RichText {
+    RichText::plain(RichTextPlain::builder().text(text).build())
+  }
+
+  fn texts(texts: Vec<RichText>) -> RichText {
+    RichText::rich_texts(RichTexts::builder().texts(texts).build())
+  }
+
+  fn paragraph(text: RichText) -> PageBlock {
+    PageBlock::paragraph(PageBlockParagraph::builder().text(text).build())
+  }
+
+  fn view() -> WebPageInstantView {
+    let bold = RichText::bold(RichTextBold::builder().text(plain("Rust")).build());
+    let reference = RichText::reference(RichTextReference::builder()
+      .text(plain("[1]"))
+      .anchor_name("note1")
+      .url("https://example.com/article#note1")
+      .build());
+    let missing = RichText::anchor_link(RichTextAnchorLink::builder()
+      .text(plain("elsewhere"))
+      .anchor_name("missing")
+      .url("https://example.com/other#missing")
+      .build());
+    let photo = Photo::builder()
+      .sizes(vec![
+        PhotoSize::builder().width(90).height(60).photo(File::builder().id(1).build()).build(),
+        PhotoSize::builder().width(800).height(600).photo(File::builder().id(2).build()).build(),
+      ])
+      .build();
+    let cell = |text: &str, is_header: bool| PageBlockTableCell::builder().text(plain(text)).is_header(is_header).build();
+    WebPageInstantView::builder()
+      .page_blocks(vec![
+        PageBlock::title(PageBlockTitle::builder().title(plain("Hello <World>")).build()),
+        paragraph(texts(vec![bold, plain(" is fast"), reference, plain(", see "), missing])),
+        PageBlock::photo(PageBlockPhoto::builder()
+          .photo(photo)
+          .caption(PageBlockCaption::builder().text(plain("A crab")).credit(plain("Ferris")).build())
+          .build()),
+        PageBlock::list(PageBlockList::builder().items(vec![
+          PageBlockListItem::builder().label("1").page_blocks(vec![paragraph(plain("first"))]).build(),
+          PageBlockListItem::builder().label("2").page_blocks(vec![paragraph(plain("second"))]).build(),
+        ]).build()),
+        PageBlock::table(PageBlockTable::builder()
+          .cells(vec![vec![cell("Name", true), cell("Speed", true)], vec![cell("Rust", false), cell("fast", false)]])
+          .build()),
+        PageBlock::anchor(PageBlockAnchor::builder().name("note1").build()),
+        paragraph(plain("[1] The note")),
+      ])
+      .build()
+  }
+
+  fn renderer() -> InstantViewRenderer {
+    let mut renderer = InstantViewRenderer::new();
+    renderer.media_url(|file| Some(format!("media/{}.jpg", file.id())));
+    renderer
+  }
+
+  #[test]
+  fn test_html() {
+    let html = renderer().to_html(&view());
+    assert!(html.starts_with("<article><h1>Hello &lt;World&gt;</h1>"));
+    assert!(html.contains("<p><b>Rust</b> is fast<a class=\"reference\" href=\"#note1\">[1]</a>, see <a href=\"https://example.com/other#missing\">elsewhere</a></p>"));
+    assert!(html.contains("<figure><img src=\"media/2.jpg\" alt=\"A crab\"><figcaption>A crab<cite>Ferris</cite></figcaption></figure>"));
+    assert!(html.contains("<ol><li><p>first</p></li><li><p>second</p></li></ol>"));
+    assert!(html.contains("<tr><th>Name</th><th>Speed</th></tr><tr><td>Rust</td><td>fast</td></tr>"));
+    assert!(html.contains("<a id=\"note1\"></a>"));
+  }
+
+  #[test]
+  fn test_markdown_and_text() {
+    let markdown = renderer().to_markdown(&view());
+    assert_eq!(markdown, [
+      "# Hello \\<World\\>",
+      "**Rust** is fast[\\[1\\]](#note1), see [elsewhere](https://example.com/other#missing)",
+      "![A crab — Ferris](media/2.jpg)",
+      "_A crab — Ferris_",
+      "1. first\n2. second",
+      "| Name | Speed |\n| --- | --- |\n| Rust | fast |",
+      "<a id=\"note1\"></a>",
+      "\\[1\\] The note",
+    ].join("\n\n"));
+
+    let text = renderer().to_text(&view());
+    assert!(text.starts_with("Hello <World>\n\nRust is fast[1], see elsewhere (https://example.com/other#missing)\n\n[Photo: media/2.jpg]"));
+    assert!(text.contains("Name | Speed\nRust | fast"));
+
+    let json = r#"{"@type":"webPageInstantView","page_blocks":[{"@type":"pageBlockParagraph","text":{"@type":"richTextPlain","text":"Hi"}}],"view_count":1,"version":2,"is_rtl":true,"is_full":true,"feedback_link":{"@type":"internalLinkTypeUnknownDeepLink","link":""}}"#;
+    assert_eq!(instant_view_to_html(json).unwrap(), "<article dir=\"rtl\"><p>Hi</p></article>");
+  }
+
+  #[test]
+  fn test_unsafe_urls() {
+    let link = |url: &str| RichText::url(RichTextUrl::builder().text(plain("click")).url(url).build());
+    let embedded = |url: &str| PageBlock::embedded(PageBlockEmbedded::builder().url(url).width(300).height(200).build());
+    let view = WebPageInstantView::builder().page_blocks(vec![
+      paragraph(texts(vec![link("javascript:alert(1)"), link(" JavaScript:alert(1)"), link("data:text/html,x"), link("tg://resolve?domain=rust")])),
+      embedded("javascript:alert(1)"),
+      embedded("https://example.com/player"),
+    ]).build();
+    let html = renderer().to_html(&view);
+    assert!(!html.to_lowercase().contains("javascript") && !html.contains("data:"));
+    assert!(html.contains("<p>clickclickclick<a href=\"tg://resolve?domain=rust\">click</a></p>"));
+    assert!(html.contains("<iframe sandbox=\"allow-scripts allow-popups\" src=\"https://example.com/player\""));
+    assert_eq!(html.matches("<iframe").count(), 1);
+    assert!(!renderer().to_markdown(&view).contains("javascript"));
+  }
+}
@@ -0,0 +1,408 @@
+//! Keyboard DSL for `ReplyMarkup`.
+//!
+//! Buttons are added to the current row, `row()` starts a new one. Telegram limits are checked
+//! when the keyboard is built.
+//!
+//! ```rust
+//! use rtdlib::keyboard::InlineKeyboard;
+//!
+//! let markup = InlineKeyboard::builder()
+//!   .callback("Yes", "vote:yes")
+//!   .callback("No", "vote:no")
+//!   .row()
+//!   .url("Results", "https://example.com/results")
+//!   .build()
+//!   .unwrap();
+//! assert!(markup.is_inline_keyboard());
+//!
+//! let markup = rtdlib::keyboard!(inline;
+//!   ["Yes" => callback("vote:yes"), "No" => callback("vote:no")],
+//!   ["Results" => url("https://example.com/results")],
+//! ).unwrap();
+//! ```
+
+use crate::encoding::*;
+use crate::errors::*;
+use crate::types::*;
+
+/// Maximum size of callback data, in bytes
+pub const MAX_CALLBACK_DATA_LENGTH: usize = 64;
+/// Maximum number of buttons in a row of an inline keyboard
+pub const MAX_INLINE_BUTTONS_PER_ROW: usize = 8;
+/// Maximum number of buttons of an inline keyboard
+pub const MAX_INLINE_BUTTONS: usize = 100;
+/// Maximum number of buttons in a row of a reply keyboard
+pub const MAX_KEYBOARD_BUTTONS_PER_ROW: usize = 12;
+/// Maximum number of buttons of a reply keyboard
+pub const MAX_KEYBOARD_BUTTONS: usize = 300;
+/// Maximum length of input field placeholder, in characters
+pub const MAX_PLACEHOLDER_LENGTH: usize = 64;
+
+const URL_SCHEMES: [&str; 4] = ["http://", "https://", "tg://", "ton://"];
+
+fn check_url(url: &str, schemes: &[&str]) -> RTDResult<()> {
+  let lower = url.to_lowercase();
+  match schemes.iter().find(|scheme| lower.starts_with(*scheme)) {
+    Some(scheme) if url.len() > scheme.len() => Ok(()),
+    _ => Err(RTDError::custom(format!("unsupported button url: {}", url))),
+  }
+}
+
+fn check_placeholder(placeholder: &Option<String>) -> RTDResult<()> {
+  match placeholder {
+    Some(placeholder) if placeholder.chars().count() > MAX_PLACEHOLDER_LENGTH => Err(RTDError::custom(format!(
+      "input field placeholder is longer than {} characters", MAX_PLACEHOLDER_LENGTH
+    ))),
+    _ => Ok(()),
+  }
+}
+
+fn check_layout<T>(rows: &[Vec<T>], max_per_row: usize, max_total: usize) -> RTDResult<()> {
+  if rows.is_empty() {
+    return Err(RTDError::custom("keyboard has no buttons".to_string()));
+  }
+  if let Some(row) = rows.iter().position(|row| row.len() > max_per_row) {
+    return Err(RTDError::custom(format!("keyboard row {} has more than {} buttons", row + 1, max_per_row)));
+  }
+  if rows.iter().map(|row| row.len()).sum::<usize>() > max_total {
+    return Err(RTDError::custom(format!("keyboard has more than {} buttons", max_total)));
+  }
+  Ok(())
+}
+
+/// Rows of buttons, shared by both keyboard builders
+#[derive(Debug, Clone)]
+struct Rows<T> {
+  rows: Vec<Vec<T>>,
+  columns: Option<usize>,
+}
+
+impl<T: Clone> Rows<T> {
+  fn new() -> Self { Self { rows: vec![], columns: None } }
+
+  fn row(&mut self) {
+    if self.rows.last().is_some_and(|row| !row.is_empty()) { self.rows.push(vec![]); }
+  }
+
+  fn push(&mut self, button: T) {
+    let full = match (self.rows.last(), self.columns) {
+      (None, _) => true,
+      (Some(row), Some(columns)) => columns > 0 && row.len() >= columns,
+      (Some(_), None) => false,
+    };
+    if full { self.rows.push(vec![]); }
+    if let Some(row) = self.rows.last_mut() { row.push(button); }
+  }
+
+  fn rows(&self) -> Vec<Vec<T>> {
+    self.rows.iter().filter(|row| !row.is_empty()).cloned().collect()
+  }
+}
+
+/// Builder of `ReplyMarkupInlineKeyboard`
+#[derive(Debug, Clone)]
+pub struct InlineKeyboard {
+  rows: Rows<InlineKeyboardButton>,
+  error: Option<String>,
+}
+
+impl InlineKeyboard {
+  pub fn builder() -> Self { Self { rows: Rows::new(), error: None } }
+
+  /// Start a new row of buttons
+  pub fn row(&mut self) -> &mut Self {
+    self.rows.row();
+    self
+  }
+
+  /// Wrap buttons into rows of `columns` buttons automatically
+  pub fn columns(&mut self, columns: usize) -> &mut Self {
+    self.rows.columns = Some(columns);
+    self
+  }
+
+  /// Add a button of any type
+  pub fn button<S: AsRef<str>, T: AsRef<InlineKeyboardButtonType>>(&mut self, text: S, type_: T) -> &mut Self {
+    if text.as_ref().is_empty() { self.fail("inline keyboard button text is empty".to_string()); }
+    self.rows.push(InlineKeyboardButton::builder().text(text).type_(type_).build());
+    self
+  }
+
+  /// A button that sends `data` in a callback query, at most 64 bytes
+  pub fn callback<S: AsRef<str>, D: AsRef<[u8]>>(&mut self, text: S, data: D) -> &mut Self {
+    let data = data.as_ref();
+    if data.len() > MAX_CALLBACK_DATA_LENGTH {
+      self.fail(format!("callback data of button {} is longer than {} bytes", text.as_ref(), MAX_CALLBACK_DATA_LENGTH));
+    }
+    let type_ = InlineKeyboardButtonTypeCallback::builder().data(encode_base64(data)).build();
+    self.button(text, InlineKeyboardButtonType::callback(type_))
+  }
+
+  /// A callback button which asks for the 2-step verification password of the current user
+  pub fn callback_with_password<S: AsRef<str>, D: AsRef<[u8]>>(&mut self, text: S, data: D) -> &mut Self {
+    let data = data.as_ref();
+    if data.len() > MAX_CALLBACK_DATA_LENGTH {
+      self.fail(format!("callback data of button {} is longer than {} bytes", text.as_ref(), MAX_CALLBACK_DATA_LENGTH));
+    }
+    let type_ = InlineKeyboardButtonTypeCallbackWithPassword::builder().data(encode_base64(data)).build();
+    self.button(text, InlineKeyboardButtonType::callback_with_password(type_))
+  }
+
+  /// A button that opens `url`; http, https, tg and ton links are allowed
+  pub fn url<S: AsRef<str>, U: AsRef<str>>(&mut self, text: S, url: U) -> &mut Self {
+    if let Err(e) = check_url(url.as_ref(), &URL_SCHEMES) { self.fail(e.to_string()); }
+    let type_ = InlineKeyboardButtonTypeUrl::builder().url(url).build();
+    self.button(text, InlineKeyboardButtonType::url(type_))
+  }
+
+  /// A button that opens `url` and authorizes the user, only https links are allowed
+  pub fn login_url<S: AsRef<str>, U: AsRef<str>>(&mut self, text: S, url: U, id: i64) -> &mut Self {
+    if let Err(e) = check_url(url.as_ref(), &["https://"]) { self.fail(e.to_string()); }
+    let type_ = InlineKeyboardButtonTypeLoginUrl::builder().url(url).id(id).build();
+    self.button(text, InlineKeyboardButtonType::login_url(type_))
+  }
+
+  /// A button that switches to inline mode with `query` in another chat
+  pub fn switch_inline<S: AsRef<str>, Q: AsRef<str>>(&mut self, text: S, query: Q) -> &mut Self {
+    let type_ = InlineKeyboardButtonTypeSwitchInline::builder().query(query).in_current_chat(false).build();
+    self.button(text, InlineKeyboardButtonType::switch_inline(type_))
+  }
+
+  /// A button that switches to inline mode with `query` in the current chat
+  pub fn switch_inline_current_chat<S: AsRef<str>, Q: AsRef<str>>(&mut self, text: S, query: Q) -> &mut Self {
+    let type_ = InlineKeyboardButtonTypeSwitchInline::builder().query(query).in_current_chat(true).build();
+    self.button(text, InlineKeyboardButtonType::switch_inline(type_))
+  }
+
+  /// A button with a game, must be the first button of the keyboard
+  pub fn callback_game<S: AsRef<str>>(&mut self, text: S) -> &mut Self {
+    self.button(text, InlineKeyboardButtonType::callback_game(InlineKeyboardButtonTypeCallbackGame::builder().build()))
+  }
+
+  /// A button to buy something, must be the first button of the keyboard
+  pub fn buy<S: AsRef<str>>(&mut self, text: S) -> &mut Self {
+    self.button(text, InlineKeyboardButtonType::buy(InlineKeyboardButtonTypeBuy::builder().build()))
+  }
+
+  /// A button with a user reference
+  pub fn user<S: AsRef<str>>(&mut self, text: S, user_id: i64) -> &mut Self {
+    self.button(text, InlineKeyboardButtonType::user(InlineKeyboardButtonTypeUser::builder().user_id(user_id).build()))
+  }
+
+  fn fail(&mut self, error: String) {
+    if self.error.is_none() { self.error = Some(error); }
+  }
+
+  pub fn build(&self) -> RTDResult<ReplyMarkup> {
+    if let Some(error) = &self.error { return Err(RTDError::custom(error.clone())) }
+    let rows = self.rows.rows();
+    check_layout(&rows, MAX_INLINE_BUTTONS_PER_ROW, MAX_INLINE_BUTTONS)?;
+    let must_be_first = |button: &InlineKeyboardButton| button.type_().is_buy() || button.type_().is_callback_game();
+    let misplaced = rows.iter().flatten().skip(1).find(|button| must_be_first(button));
+    if let Some(button) = misplaced {
+      return Err(RTDError::custom(format!("{} button must be the first button of the keyboard", button.text())));
+    }
+    Ok(ReplyMarkup::inline_keyboard(ReplyMarkupInlineKeyboard::builder().rows(rows).build()))
+  }
+}
+
+/// Builder of `ReplyMarkupShowKeyboard`
+#[derive(Debug, Clone)]
+pub struct Keyboard {
+  rows: Rows<KeyboardButton>,
+  resize_keyboard: bool,
+  one_time: bool,
+  is_personal: bool,
+  input_field_placeholder: Option<String>,
+  error: Option<String>,
+}
+
+impl Keyboard {
+  pub fn builder() -> Self {
+    Self {
+      rows: Rows::new(),
+      resize_keyboard: false,
+      one_time: false,
+      is_personal: false,
+      input_field_placeholder: None,
+      error: None,
+    }
+  }
+
+  /// Start a new row of buttons
+  pub fn row(&mut self) -> &mut Self {
+    self.rows.row();
+    self
+  }
+
+  /// Wrap buttons into rows of `columns` buttons automatically
+  pub fn columns(&mut self, columns: usize) -> &mut Self {
+    self.rows.columns = Some(columns);
+    self
+  }
+
+  pub fn resize_keyboard(&mut self, resize_keyboard: bool) -> &mut Self {
+    self.resize_keyboard = resize_keyboard;
+    self
+  }
+
+  pub fn one_time(&mut self, one_time: bool) -> &mut Self {
+    self.one_time = one_time;
+    self
+  }
+
+  pub fn is_personal(&mut self, is_personal: bool) -> &mut Self {
+    self.is_personal = is_personal;
+    self
+  }
+
+  pub fn input_field_placeholder<S: AsRef<str>>(&mut self, input_field_placeholder: S) -> &mut Self {
+    self.input_field_placeholder = Some(input_field_placeholder.as_ref().to_string());
+    self
+  }
+
+  /// Add a button of any type
+  pub fn button<S: AsRef<str>, T: AsRef<KeyboardButtonType>>(&mut self, text: S, type_: T) -> &mut Self {
+    if text.as_ref().is_empty() && self.error.is_none() {
+      self.error = Some("keyboard button text is empty".to_string());
+    }
+    self.rows.push(KeyboardButton::builder().text(text).type_(type_).build());
+    self
+  }
+
+  /// A button that sends its text
+  pub fn text<S: AsRef<str>>(&mut self, text: S) -> &mut Self {
+    self.button(text, KeyboardButtonType::text(KeyboardButtonTypeText::builder().build()))
+  }
+
+  /// A button that sends the user's phone number, private chats only
+  pub fn request_phone_number<S: AsRef<str>>(&mut self, text: S) -> &mut Self {
+    self.button(text, KeyboardButtonType::request_phone_number(KeyboardButtonTypeRequestPhoneNumber::builder().build()))
+  }
+
+  /// A button that sends the user's location, private chats only
+  pub fn request_location<S: AsRef<str>>(&mut self, text: S) -> &mut Self {
+    self.button(text, KeyboardButtonType::request_location(KeyboardButtonTypeRequestLocation::builder().build()))
+  }
+
+  /// A button that allows the user to create and send a poll, private chats only
+  pub fn request_poll<S: AsRef<str>>(&mut self, text: S, force_regular: bool, force_quiz: bool) -> &mut Self {
+    if force_regular && force_quiz && self.error.is_none() {
+      self.error = Some(format!("poll button {} can't force both regular and quiz poll", text.as_ref()));
+    }
+    let type_ = KeyboardButtonTypeRequestPoll::builder().force_regular(force_regular).force_quiz(force_quiz).build();
+    self.button(text, KeyboardButtonType::request_poll(type_))
+  }
+
+  pub fn build(&self) -> RTDResult<ReplyMarkup> {
+    if let Some(error) = &self.error { return Err(RTDError::custom(error.clone())) }
+    let rows = self.rows.rows();
+    check_layout(&rows, MAX_KEYBOARD_BUTTONS_PER_ROW, MAX_KEYBOARD_BUTTONS)?;
+    check_placeholder(&self.input_field_placeholder)?;
+    Ok(ReplyMarkup::show_keyboard(ReplyMarkupShowKeyboard::builder()
+      .rows(rows)
+      .resize_keyboard(self.resize_keyboard)
+      .one_time(self.one_time)
+      .is_personal(self.is_personal)
+      .input_field_placeholder(self.input_field_placeholder.clone().unwrap_or_default())
+      .build()))
+  }
+}
+
+/// Force the user to reply to the message
+pub fn force_reply<S: AsRef<str>>(is_personal: bool, input_field_placeholder: S) -> RTDResult<ReplyMarkup> {
+  let placeholder = Some(input_field_placeholder.as_ref().to_string());
+  check_placeholder(&placeholder)?;
+  Ok(ReplyMarkup::force_reply(ReplyMarkupForceReply::builder()
+    .is_personal(is_personal)
+    .input_field_placeholder(input_field_placeholder)
+    .build()))
+}
+
+/// Remove the reply keyboard
+pub fn remove_keyboard(is_personal: bool) -> ReplyMarkup {
+  ReplyMarkup::remove_keyboard(ReplyMarkupRemoveKeyboard::builder().is_personal(is_personal).build())
+}
+
+/// Build a keyboard row by row, each button is `text => kind(args...)` where `kind` is a method of
+/// `InlineKeyboard` (`inline;`) or `Keyboard` (`reply;`). Returns `RTDResult<ReplyMarkup>`.
+///
+/// ```rust
+/// let markup = rtdlib::keyboard!(reply;
+///   ["Share phone" => request_phone_number(), "Share location" => request_location()],
+///   ["Cancel" => text()],
+/// ).unwrap();
+/// assert!(markup.is_show_keyboard());
+/// ```
+#[macro_export]
+macro_rules! keyboard {
+  (inline; $([$($text:expr => $kind:ident($($arg:expr),*)),* $(,)?]),* $(,)?) => {
+    $crate::keyboard::InlineKeyboard::builder()
+      $(.row() $(.$kind($text $(, $arg)*))*)*
+      .build()
+  };
+  (reply; $([$($text:expr => $kind:ident($($arg:expr),*)),* $(,)?]),* $(,)?) => {
+    $crate::keyboard::Keyboard::builder()
+      $(.row() $(.$kind($text $(, $arg)*))*)*
+      .build()
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::keyboard::*;
+
+  #[test]
+  fn test_inline_keyboard() {
+    let markup = crate::keyboard!(inline;
+      ["Yes" => callback("yes"), "No" => callback(b"no")],
+      ["Docs" => url("https://docs.rs"), "Share" => switch_inline("query")],
+    ).unwrap();
+    let rows = markup.as_inline_keyboard().unwrap().rows();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][0].type_().as_callback().unwrap().data(), "eWVz");
+    assert_eq!(rows[1][0].text(), "Docs");
+
+    let markup = InlineKeyboard::builder()
+      .columns(2)
+      .callback("1", "1").callback("2", "2").callback("3", "3")
+      .build()
+      .unwrap();
+    let sizes = markup.as_inline_keyboard().unwrap().rows().iter().map(|row| row.len()).collect::<Vec<_>>();
+    assert_eq!(sizes, vec![2, 1]);
+  }
+
+  #[test]
+  fn test_inline_keyboard_limits() {
+    assert!(InlineKeyboard::builder().callback("Long", [0u8; 65]).build().is_err());
+    assert!(InlineKeyboard::builder().callback("Max", [0u8; 64]).build().is_ok());
+    assert!(InlineKeyboard::builder().url("Bad", "javascript:alert(1)").build().is_err());
+    assert!(InlineKeyboard::builder().login_url("Login", "http://example.com", 1).build().is_err());
+    assert!(InlineKeyboard::builder().callback("A", "a").buy("Pay").build().is_err());
+    assert!(InlineKeyboard::builder().buy("Pay").callback("A", "a").build().is_ok());
+    assert!(InlineKeyboard::builder().build().is_err());
+
+    let mut builder = InlineKeyboard::builder();
+    (0..9).for_each(|i| { builder.callback(i.to_string(), "x"); });
+    assert!(builder.build().is_err());
+  }
+
+  #[test]
+  fn test_reply_keyboard() {
+    let markup = Keyboard::builder()
+      .request_phone_number("Share phone")
+      .row()
+      .text("Cancel")
+      .resize_keyboard(true)
+      .one_time(true)
+      .build()
+      .unwrap();
+    let keyboard = markup.as_show_keyboard().unwrap();
+    assert!(keyboard.rows()[0][0].type_().is_request_phone_number());
+    assert!(keyboard.one_time());
+
+    assert!(Keyboard::builder().text("A").input_field_placeholder("x".repeat(65)).build().is_err());
+    assert!(force_reply(true, "Your name").unwrap().is_force_reply());
+    assert!(remove_keyboard(false).is_remove_keyboard());
+  }
+}
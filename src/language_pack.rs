@@ -0,0 +1,413 @@
+//! Localization with TDLib language packs.
+//!
+//! `LanguagePack` holds the strings of a language pack loaded from `LanguagePackStrings`, keeps
+//! them up to date with `updateLanguagePackStrings` and falls back to a base pack, usually the
+//! English one, for missing and deleted strings. Pluralized strings are selected with the CLDR
+//! cardinal rules of the pack language, and `%1$s`, `%d` and `%@` placeholders are substituted.
+//!
+//! ```rust
+//! use rtdlib::language_pack::LanguagePack;
+//! # use rtdlib::types::*;
+//! # let strings = LanguagePackStrings::builder().build();
+//! let mut english = LanguagePack::new("en", "en");
+//! english.load_strings(&strings);
+//! let mut russian = LanguagePack::new("ru", "ru");
+//! russian.set_base(english);
+//! let text = russian.plural("Members", 21, &[] as &[&str]);
+//! let greeting = russian.format("HelloUser", &["Alice"]);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::*;
+use crate::files;
+use crate::types::*;
+
+/// CLDR plural category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralCategory {
+  Zero,
+  One,
+  Two,
+  Few,
+  Many,
+  Other,
+}
+
+/// CLDR cardinal plural category of an integer in a language, e.g. `ru` or `pt-br`
+pub fn plural_category(language_code: &str, count: i64) -> PluralCategory {
+  use PluralCategory::*;
+  let language = language_code.split(['-', '_']).next().unwrap_or("").to_lowercase();
+  let n = count.unsigned_abs();
+  let (n10, n100) = (n % 10, n % 100);
+  match language.as_str() {
+    "ja" | "ko" | "zh" | "vi" | "th" | "id" | "ms" | "lo" | "my" | "km" | "jv" | "bo" | "dz" | "yo" | "ig" => Other,
+    "fr" | "pt" | "hy" | "ff" | "kab" => if n <= 1 { One } else { Other },
+    "fa" | "hi" | "bn" | "am" | "zu" | "gu" | "kn" | "mr" | "as" => if n <= 1 { One } else { Other },
+    "ru" | "uk" | "be" => {
+      if n10 == 1 && n100 != 11 { One }
+      else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) { Few }
+      else { Many }
+    }
+    "hr" | "sr" | "bs" | "sh" => {
+      if n10 == 1 && n100 != 11 { One }
+      else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) { Few }
+      else { Other }
+    }
+    "pl" => {
+      if n == 1 { One }
+      else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) { Few }
+      else { Many }
+    }
+    "cs" | "sk" => match n { 1 => One, 2..=4 => Few, _ => Other },
+    "lt" => {
+      if n10 == 1 && !(11..=19).contains(&n100) { One }
+      else if n10 >= 2 && !(11..=19).contains(&n100) { Few }
+      else { Other }
+    }
+    "lv" => {
+      if n10 == 0 || (11..=19).contains(&n100) { Zero }
+      else if n10 == 1 { One }
+      else { Other }
+    }
+    "ro" | "mo" => {
+      if n == 1 { One }
+      else if n == 0 || (1..=19).contains(&n100) { Few }
+      else { Other }
+    }
+    "sl" => match n100 { 1 => One, 2 => Two, 3 | 4 => Few, _ => Other },
+    "he" | "iw" => match n { 1 => One, 2 => Two, _ => Other },
+    "ar" => match (n, n100) {
+      (0, _) => Zero,
+      (1, _) => One,
+      (2, _) => Two,
+      (_, 3..=10) => Few,
+      (_, 11..=99) => Many,
+      _ => Other,
+    },
+    "ga" => match n { 1 => One, 2 => Two, 3..=6 => Few, 7..=10 => Many, _ => Other },
+    "cy" => match n { 0 => Zero, 1 => One, 2 => Two, 3 => Few, 6 => Many, _ => Other },
+    "mt" => match (n, n100) {
+      (1, _) => One,
+      (2, _) => Two,
+      (0, _) | (_, 3..=10) => Few,
+      (_, 11..=19) => Many,
+      _ => Other,
+    },
+    "gd" => match n { 1 | 11 => One, 2 | 12 => Two, 3..=10 | 13..=19 => Few, _ => Other },
+    "br" => {
+      let excluded = |range: std::ops::RangeInclusive<u64>| !range.contains(&n100);
+      if n10 == 1 && excluded(11..=11) && excluded(71..=71) && excluded(91..=91) { One }
+      else if n10 == 2 && excluded(12..=12) && excluded(72..=72) && excluded(92..=92) { Two }
+      else if [3, 4, 9].contains(&n10) && excluded(10..=19) && excluded(70..=79) && excluded(90..=99) { Few }
+      else if n != 0 && n.is_multiple_of(1_000_000) { Many }
+      else { Other }
+    }
+    _ => if n == 1 { One } else { Other },
+  }
+}
+
+/// Substitute `%1$s`, `%2$d`, `%@` and sequential `%s`/`%d` placeholders, `%%` is a percent sign.
+/// Placeholders without an argument are kept.
+pub fn format_placeholders<A: Display>(template: &str, args: &[A]) -> String {
+  let mut out = String::with_capacity(template.len());
+  let mut next = 0;
+  let mut rest = template;
+  while let Some(position) = rest.find('%') {
+    out.push_str(&rest[..position]);
+    let placeholder = &rest[position + 1..];
+    if let Some(after) = placeholder.strip_prefix('%') {
+      out.push('%');
+      rest = after;
+      continue;
+    }
+    let digits = placeholder.bytes().take_while(u8::is_ascii_digit).count();
+    let (index, conversion) = match placeholder[digits..].strip_prefix('$') {
+      Some(conversion) if digits > 0 => (placeholder[..digits].parse::<usize>().ok().and_then(|i| i.checked_sub(1)), conversion),
+      _ if digits == 0 => (Some(next), placeholder),
+      _ => (None, placeholder),
+    };
+    match (index.and_then(|index| args.get(index)), conversion.chars().next()) {
+      (Some(arg), Some('s' | 'd' | '@' | 'i' | 'u')) => {
+        out.push_str(&arg.to_string());
+        if digits == 0 { next += 1; }
+        rest = &conversion[1..];
+      }
+      _ => {
+        out.push('%');
+        rest = placeholder;
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+/// A string of a language pack
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PackString {
+  Ordinary(String),
+  Pluralized {
+    zero: String,
+    one: String,
+    two: String,
+    few: String,
+    many: String,
+    other: String,
+  },
+}
+
+impl PackString {
+  /// `None` for deleted strings
+  pub fn of(value: &LanguagePackStringValue) -> Option<PackString> {
+    match value {
+      LanguagePackStringValue::Ordinary(value) => Some(PackString::Ordinary(value.value().clone())),
+      LanguagePackStringValue::Pluralized(value) => Some(PackString::Pluralized {
+        zero: value.zero_value().clone(),
+        one: value.one_value().clone(),
+        two: value.two_value().clone(),
+        few: value.few_value().clone(),
+        many: value.many_value().clone(),
+        other: value.other_value().clone(),
+      }),
+      _ => None,
+    }
+  }
+
+  /// Form of a category, pluralized strings fall back to `other` for empty forms
+  pub fn form(&self, category: PluralCategory) -> &str {
+    match self {
+      PackString::Ordinary(value) => value,
+      PackString::Pluralized { zero, one, two, few, many, other } => {
+        let form = match category {
+          PluralCategory::Zero => zero,
+          PluralCategory::One => one,
+          PluralCategory::Two => two,
+          PluralCategory::Few => few,
+          PluralCategory::Many => many,
+          PluralCategory::Other => other,
+        };
+        if form.is_empty() { other } else { form }
+      }
+    }
+  }
+}
+
+/// What `LanguagePack::on_update` did with an update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackUpdate {
+  /// The strings of this pack or of a base pack were changed
+  Applied,
+  /// The update has no strings, the pack must be reloaded with `reload_request`
+  ReloadRequired,
+  /// The update is for another language pack
+  Ignored,
+}
+
+/// Strings of a language pack with an optional base pack for missing strings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguagePack {
+  language_pack_id: String,
+  /// Language whose plural rules are used
+  plural_code: String,
+  strings: HashMap<String, PackString>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  base: Option<Box<LanguagePack>>,
+}
+
+impl LanguagePack {
+  /// An empty pack, `plural_code` is the `plural_code` of its `LanguagePackInfo`
+  pub fn new<I: AsRef<str>, P: AsRef<str>>(language_pack_id: I, plural_code: P) -> Self {
+    Self {
+      language_pack_id: language_pack_id.as_ref().to_string(),
+      plural_code: plural_code.as_ref().to_string(),
+      ..Default::default()
+    }
+  }
+
+  /// Pack used for strings missing from this one
+  pub fn set_base(&mut self, base: LanguagePack) -> &mut Self {
+    self.base = Some(Box::new(base));
+    self
+  }
+
+  pub fn base(&self) -> Option<&LanguagePack> { self.base.as_deref() }
+
+  pub fn language_pack_id(&self) -> &str { &self.language_pack_id }
+
+  pub fn len(&self) -> usize { self.strings.len() }
+
+  pub fn is_empty(&self) -> bool { self.strings.is_empty() }
+
+  /// Add or replace strings, deleted strings are removed so they fall back to the base pack
+  pub fn load_strings(&mut self, strings: &LanguagePackStrings) {
+    self.apply(strings.strings());
+  }
+
+  fn apply(&mut self, strings: &[LanguagePackString]) {
+    for string in strings {
+      match PackString::of(string.value()) {
+        Some(value) => { self.strings.insert(string.key().clone(), value); }
+        None => { self.strings.remove(string.key()); }
+      }
+    }
+  }
+
+  /// Apply `updateLanguagePackStrings` to this pack or to the base pack it is for
+  pub fn on_update(&mut self, update: &UpdateLanguagePackStrings) -> PackUpdate {
+    if update.language_pack_id() != &self.language_pack_id {
+      return self.base.as_mut().map_or(PackUpdate::Ignored, |base| base.on_update(update));
+    }
+    if update.strings().is_empty() { return PackUpdate::ReloadRequired }
+    self.apply(update.strings());
+    PackUpdate::Applied
+  }
+
+  /// Request of all the strings of the pack
+  pub fn reload_request(&self) -> GetLanguagePackStrings {
+    GetLanguagePackStrings::builder().language_pack_id(&self.language_pack_id).keys(vec![]).build()
+  }
+
+  /// The string and the pack holding it, looking into base packs
+  fn lookup(&self, key: &str) -> Option<(&PackString, &LanguagePack)> {
+    match self.strings.get(key) {
+      Some(value) => Some((value, self)),
+      None => self.base.as_ref()?.lookup(key),
+    }
+  }
+
+  pub fn string(&self, key: &str) -> Option<&PackString> { self.lookup(key).map(|(value, _)| value) }
+
+  /// Ordinary string, or the `other` form of a pluralized one
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.string(key).map(|value| value.form(PluralCategory::Other))
+  }
+
+  /// Substitute arguments in a string, the key is returned for missing strings
+  pub fn format<A: Display>(&self, key: &str, args: &[A]) -> String {
+    match self.get(key) {
+      Some(template) => format_placeholders(template, args),
+      None => key.to_string(),
+    }
+  }
+
+  /// Form of a pluralized string for `count` in the language of the pack holding it, `count` is
+  /// the first argument followed by `args`
+  pub fn plural<A: Display>(&self, key: &str, count: i64, args: &[A]) -> String {
+    let (value, pack) = match self.lookup(key) {
+      Some(found) => found,
+      None => return key.to_string(),
+    };
+    let template = value.form(plural_category(&pack.plural_code, count));
+    let args = std::iter::once(count.to_string()).chain(args.iter().map(|arg| arg.to_string())).collect::<Vec<_>>();
+    format_placeholders(template, &args)
+  }
+
+  pub fn from_json<S: AsRef<str>>(json: S) -> RTDResult<Self> { Ok(serde_json::from_str(json.as_ref())?) }
+
+  pub fn to_json(&self) -> RTDResult<String> { Ok(serde_json::to_string(self)?) }
+
+  /// Load a snapshot saved with `save`, including base packs
+  pub fn load<P: AsRef<Path>>(path: P) -> RTDResult<Self> {
+    Self::from_json(fs::read_to_string(path)?)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> RTDResult<()> {
+    files::write_atomic(path, self.to_json()?)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::language_pack::*;
+
+  fn ordinary(key: &str, value: &str) -> LanguagePackString {
+    let value = LanguagePackStringValueOrdinary::builder().value(value).build();
+    LanguagePackString::builder().key(key).value(LanguagePackStringValue::ordinary(value)).build()
+  }
+
+  fn pluralized(key: &str, one: &str, few: &str, many: &str, other: &str) -> LanguagePackString {
+    let value = LanguagePackStringValuePluralized::builder().one_value(one).few_value(few).many_value(many).other_value(other).build();
+    LanguagePackString::builder().key(key).value(LanguagePackStringValue::pluralized(value)).build()
+  }
+
+  fn strings(strings: Vec<LanguagePackString>) -> LanguagePackStrings {
+    LanguagePackStrings::builder().strings(strings).build()
+  }
+
+  #[test]
+  fn test_plural_category() {
+    use PluralCategory::*;
+    let categories = |code: &str, counts: &[i64]| counts.iter().map(|count| plural_category(code, *count)).collect::<Vec<_>>();
+    assert_eq!(categories("en", &[0, 1, 2, 11]), vec![Other, One, Other, Other]);
+    assert_eq!(categories("ru", &[1, 2, 5, 11, 21, 22, 112, 1001]), vec![One, Few, Many, Many, One, Few, Many, One]);
+    assert_eq!(categories("pl", &[1, 2, 5, 21, 22]), vec![One, Few, Many, Many, Few]);
+    assert_eq!(categories("ar", &[0, 1, 2, 3, 11, 100, 102]), vec![Zero, One, Two, Few, Many, Other, Other]);
+    assert_eq!(categories("pt-br", &[0, 1, 2]), vec![One, One, Other]);
+    assert_eq!(categories("ja", &[1]), vec![Other]);
+    assert_eq!(categories("cs", &[1, 3, 5]), vec![One, Few, Other]);
+    assert_eq!(categories("lv", &[0, 1, 11, 21]), vec![Zero, One, Zero, One]);
+    assert_eq!(categories("ro", &[0, 1, 2, 19, 20, 101, 119, 120]), vec![Few, One, Few, Few, Other, Few, Few, Other]);
+  }
+
+  #[test]
+  fn test_format_placeholders() {
+    assert_eq!(format_placeholders("%1$s invited %2$s", &["Alice", "Bob"]), "Alice invited Bob");
+    assert_eq!(format_placeholders("%2$s was invited by %1$s", &["Alice", "Bob"]), "Bob was invited by Alice");
+    assert_eq!(format_placeholders("%s and %d, %@", &["a", "2", "c"]), "a and 2, c");
+    assert_eq!(format_placeholders("100%% of %3$s", &["a"]), "100% of %3$s");
+    assert_eq!(format_placeholders("%", &["a"]), "%");
+    assert_eq!(format_placeholders("€%1$s", &[5]), "€5");
+  }
+
+  #[test]
+  fn test_pack_fallback_and_updates() {
+    let mut english = LanguagePack::new("en", "en");
+    english.load_strings(&strings(vec![
+      ordinary("Hello", "Hello, %1$s!"),
+      ordinary("Bye", "Bye"),
+      pluralized("Members", "%1$d member", "", "", "%1$d members"),
+    ]));
+    let mut russian = LanguagePack::new("ru", "ru");
+    russian.load_strings(&strings(vec![
+      ordinary("Hello", "Привет, %1$s!"),
+      pluralized("Members", "%1$d участник", "%1$d участника", "%1$d участников", "%1$d участника"),
+    ]));
+    russian.set_base(english);
+
+    assert_eq!(russian.format("Hello", &["Алиса"]), "Привет, Алиса!");
+    assert_eq!(russian.get("Bye"), Some("Bye"));
+    assert_eq!(russian.format("Missing", &[] as &[&str]), "Missing");
+    assert_eq!(russian.plural("Members", 21, &[] as &[&str]), "21 участник");
+    assert_eq!(russian.plural("Members", 3, &[] as &[&str]), "3 участника");
+    assert_eq!(russian.plural("Members", 11, &[] as &[&str]), "11 участников");
+
+    let deleted = LanguagePackString::builder()
+      .key("Members")
+      .value(LanguagePackStringValue::deleted(LanguagePackStringValueDeleted::builder().build()))
+      .build();
+    let update = |id: &str, strings: Vec<LanguagePackString>| {
+      UpdateLanguagePackStrings::builder().localization_target("android").language_pack_id(id).strings(strings).build()
+    };
+    assert_eq!(russian.on_update(&update("ru", vec![deleted])), PackUpdate::Applied);
+    // deleted strings fall back to the base pack and its plural rules
+    assert_eq!(russian.plural("Members", 21, &[] as &[&str]), "21 members");
+    assert_eq!(russian.on_update(&update("en", vec![ordinary("Bye", "Goodbye")])), PackUpdate::Applied);
+    assert_eq!(russian.get("Bye"), Some("Goodbye"));
+    assert_eq!(russian.on_update(&update("de", vec![ordinary("Bye", "Tschüss")])), PackUpdate::Ignored);
+    assert_eq!(russian.on_update(&update("ru", vec![])), PackUpdate::ReloadRequired);
+    assert_eq!(russian.reload_request().language_pack_id(), "ru");
+
+    let path = std::env::temp_dir().join(format!("rtdlib-language-pack-{}.json", std::process::id()));
+    russian.save(&path).unwrap();
+    let loaded = LanguagePack::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, russian);
+    assert_eq!(loaded.plural("Members", 1, &[] as &[&str]), "1 member");
+  }
+}
@@ -6,3 +6,30 @@ pub use rtdlib_sys::Tdlib;
 
 pub mod types;
 pub mod errors;
+pub mod statistics;
+pub mod proxy;
+pub mod usage;
+pub mod call;
+pub mod group_call;
+pub mod keyboard;
+pub mod callback;
+pub mod inline;
+pub mod command;
+pub mod conversation;
+pub mod payments;
+pub mod poll;
+pub mod instant_view;
+pub mod event_log;
+pub mod passport;
+pub mod language_pack;
+pub mod theme;
+pub mod secret_chat;
+pub mod sessions;
+pub mod sending;
+pub mod scheduled;
+pub mod album;
+pub mod notifications;
+
+mod crypto;
+mod encoding;
+mod files;
@@ -0,0 +1,569 @@
+//! Notification groups, like the notification area of a desktop client.
+//!
+//! `NotificationCenter` applies `updateActiveNotifications`, `updateNotificationGroup`,
+//! `updateNotification` and `updateHavePendingNotifications` to the list of active notification
+//! groups and reports which notifications to show, edit or hide. Notifications are rendered to a
+//! title and a body using the known chat titles and user names, and the notification settings of
+//! their chat: muted chats don't show notifications and message text is hidden when previews are
+//! disabled for the chat or its scope.
+//!
+//! ```rust
+//! use rtdlib::notifications::{NotificationCenter, NotificationEventKind};
+//! # use rtdlib::types::*;
+//! # let now = 1_650_000_000;
+//! # let update = UpdateNotificationGroup::builder().build();
+//! let mut center = NotificationCenter::new();
+//! for event in center.on_update_notification_group(&update, now) {
+//!   match event.kind() {
+//!     NotificationEventKind::Shown(notification) => println!("{}: {}", notification.title(), notification.body()),
+//!     NotificationEventKind::Edited(_) | NotificationEventKind::Hidden(_) => {}
+//!   }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::types::*;
+
+/// Types of chats sharing notification settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationScope {
+  /// Private and secret chats
+  Private,
+  /// Basic groups and supergroups
+  Group,
+  /// Channels
+  Channel,
+}
+
+impl NotificationScope {
+  pub fn of(scope: &NotificationSettingsScope) -> Option<NotificationScope> {
+    match scope {
+      NotificationSettingsScope::PrivateChats(_) => Some(NotificationScope::Private),
+      NotificationSettingsScope::GroupChats(_) => Some(NotificationScope::Group),
+      NotificationSettingsScope::ChannelChats(_) => Some(NotificationScope::Channel),
+      _ => None,
+    }
+  }
+
+  pub fn of_chat_type(type_: &ChatType) -> Option<NotificationScope> {
+    match type_ {
+      ChatType::Private(_) | ChatType::Secret(_) => Some(NotificationScope::Private),
+      ChatType::BasicGroup(_) => Some(NotificationScope::Group),
+      ChatType::Supergroup(supergroup) if supergroup.is_channel() => Some(NotificationScope::Channel),
+      ChatType::Supergroup(_) => Some(NotificationScope::Group),
+      _ => None,
+    }
+  }
+
+  pub fn settings_scope(&self) -> NotificationSettingsScope {
+    match self {
+      NotificationScope::Private => NotificationSettingsScope::private_chats(NotificationSettingsScopePrivateChats::builder().build()),
+      NotificationScope::Group => NotificationSettingsScope::group_chats(NotificationSettingsScopeGroupChats::builder().build()),
+      NotificationScope::Channel => NotificationSettingsScope::channel_chats(NotificationSettingsScopeChannelChats::builder().build()),
+    }
+  }
+}
+
+/// Short text describing a message, as shown in notifications and chat lists
+pub fn message_preview(content: &MessageContent) -> String {
+  let with_caption = |name: &str, caption: &FormattedText| {
+    if caption.text().is_empty() { name.to_string() } else { format!("{}, {}", name, caption.text()) }
+  };
+  match content {
+    MessageContent::MessageText(text) => text.text().text().clone(),
+    MessageContent::MessagePhoto(photo) => with_caption("Photo", photo.caption()),
+    MessageContent::MessageVideo(video) => with_caption("Video", video.caption()),
+    MessageContent::MessageAnimation(animation) => with_caption("GIF", animation.caption()),
+    MessageContent::MessageAudio(audio) => with_caption("Audio", audio.caption()),
+    MessageContent::MessageVoiceNote(voice_note) => with_caption("Voice message", voice_note.caption()),
+    MessageContent::MessageDocument(document) if document.caption().text().is_empty() => document.document().file_name().clone(),
+    MessageContent::MessageDocument(document) => with_caption("File", document.caption()),
+    MessageContent::MessageVideoNote(_) => "Video message".to_string(),
+    MessageContent::MessageSticker(sticker) => format!("{} Sticker", sticker.sticker().emoji()).trim_start().to_string(),
+    MessageContent::MessageDice(dice) => dice.emoji().clone(),
+    MessageContent::MessagePoll(poll) => format!("Poll: {}", poll.poll().question()),
+    MessageContent::MessageLocation(_) | MessageContent::MessageVenue(_) => "Location".to_string(),
+    MessageContent::MessageContact(_) => "Contact".to_string(),
+    MessageContent::MessageGame(_) => "Game".to_string(),
+    MessageContent::MessageInvoice(_) => "Invoice".to_string(),
+    MessageContent::MessageCall(_) => "Call".to_string(),
+    _ => "Message".to_string(),
+  }
+}
+
+/// Short text describing the content of a push notification
+pub fn push_message_preview(content: &PushMessageContent) -> String {
+  let with_caption = |name: &str, caption: &String| {
+    if caption.is_empty() { name.to_string() } else { format!("{}, {}", name, caption) }
+  };
+  match content {
+    PushMessageContent::Text(text) => text.text().clone(),
+    PushMessageContent::Photo(photo) => with_caption("Photo", photo.caption()),
+    PushMessageContent::Video(video) => with_caption("Video", video.caption()),
+    PushMessageContent::Animation(animation) => with_caption("GIF", animation.caption()),
+    PushMessageContent::Audio(_) => "Audio".to_string(),
+    PushMessageContent::VoiceNote(_) => "Voice message".to_string(),
+    PushMessageContent::VideoNote(_) => "Video message".to_string(),
+    PushMessageContent::Document(_) => "File".to_string(),
+    PushMessageContent::Sticker(sticker) => format!("{} Sticker", sticker.emoji()).trim_start().to_string(),
+    PushMessageContent::Poll(poll) => format!("Poll: {}", poll.question()),
+    PushMessageContent::Location(_) => "Location".to_string(),
+    PushMessageContent::Contact(contact) => format!("Contact: {}", contact.name()),
+    PushMessageContent::MediaAlbum(album) => format!("Album ({} items)", album.total_count()),
+    PushMessageContent::MessageForwards(forwards) => format!("{} forwarded messages", forwards.total_count()),
+    PushMessageContent::Game(_) => "Game".to_string(),
+    PushMessageContent::Invoice(_) => "Invoice".to_string(),
+    _ => "New message".to_string(),
+  }
+}
+
+/// A notification rendered to text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedNotification {
+  notification_id: i64,
+  chat_id: i64,
+  date: i64,
+  title: String,
+  body: String,
+  is_silent: bool,
+}
+
+impl RenderedNotification {
+  pub fn notification_id(&self) -> i64 { self.notification_id }
+
+  pub fn chat_id(&self) -> i64 { self.chat_id }
+
+  pub fn date(&self) -> i64 { self.date }
+
+  pub fn title(&self) -> &String { &self.title }
+
+  pub fn body(&self) -> &String { &self.body }
+
+  /// The notification must be shown without sound
+  pub fn is_silent(&self) -> bool { self.is_silent }
+}
+
+/// What to do with a notification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotificationEventKind {
+  /// A new notification to show
+  Shown(RenderedNotification),
+  /// A shown notification changed
+  Edited(RenderedNotification),
+  /// The notification with the identifier must be removed
+  Hidden(i64),
+}
+
+/// A change of the notification area reported by `NotificationCenter`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationEvent {
+  group_id: i64,
+  kind: NotificationEventKind,
+}
+
+impl NotificationEvent {
+  pub fn group_id(&self) -> i64 { self.group_id }
+
+  pub fn kind(&self) -> &NotificationEventKind { &self.kind }
+}
+
+/// An active notification group
+#[derive(Debug, Clone)]
+pub struct TrackedNotificationGroup {
+  id: i64,
+  type_: NotificationGroupType,
+  chat_id: i64,
+  notification_settings_chat_id: i64,
+  total_count: i64,
+  notifications: Vec<Notification>,
+}
+
+impl TrackedNotificationGroup {
+  pub fn id(&self) -> i64 { self.id }
+
+  pub fn type_(&self) -> &NotificationGroupType { &self.type_ }
+
+  pub fn chat_id(&self) -> i64 { self.chat_id }
+
+  /// Chat whose notification settings apply to the group
+  pub fn notification_settings_chat_id(&self) -> i64 { self.notification_settings_chat_id }
+
+  /// Number of unread notifications, can be bigger than the number of active notifications
+  pub fn total_count(&self) -> i64 { self.total_count }
+
+  /// Active notifications, sorted by identifier
+  pub fn notifications(&self) -> &Vec<Notification> { &self.notifications }
+}
+
+#[derive(Debug, Clone)]
+struct ChatInfo {
+  title: String,
+  scope: Option<NotificationScope>,
+  settings: Option<(ChatNotificationSettings, i64)>,
+}
+
+/// Keeps active notification groups and renders their notifications
+#[derive(Debug, Default)]
+pub struct NotificationCenter {
+  groups: HashMap<i64, TrackedNotificationGroup>,
+  chats: HashMap<i64, ChatInfo>,
+  users: HashMap<i64, String>,
+  scopes: HashMap<NotificationScope, (ScopeNotificationSettings, i64)>,
+  have_delayed_notifications: bool,
+  have_unreceived_notifications: bool,
+}
+
+impl NotificationCenter {
+  pub fn new() -> Self { Self::default() }
+
+  pub fn group(&self, group_id: i64) -> Option<&TrackedNotificationGroup> { self.groups.get(&group_id) }
+
+  /// Active groups, the group with the latest notification first
+  pub fn groups(&self) -> Vec<&TrackedNotificationGroup> {
+    let mut groups = self.groups.values().collect::<Vec<_>>();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.notifications.last().map(|notification| (notification.date(), notification.id()))));
+    groups
+  }
+
+  /// Number of unread notifications in all groups
+  pub fn total_count(&self) -> i64 { self.groups.values().map(|group| group.total_count).sum() }
+
+  /// More notification updates are expected soon
+  pub fn has_pending(&self) -> bool { self.have_delayed_notifications || self.have_unreceived_notifications }
+
+  /// `getScopeNotificationSettings` requests for the scopes without known settings
+  pub fn scope_settings_requests(&self) -> Vec<GetScopeNotificationSettings> {
+    [NotificationScope::Private, NotificationScope::Group, NotificationScope::Channel].iter()
+      .filter(|scope| !self.scopes.contains_key(scope))
+      .map(|scope| GetScopeNotificationSettings::builder().scope(scope.settings_scope()).build())
+      .collect()
+  }
+
+  pub fn on_chat(&mut self, chat: &Chat, now: i64) {
+    self.chats.insert(chat.id(), ChatInfo {
+      title: chat.title().clone(),
+      scope: NotificationScope::of_chat_type(chat.type_()),
+      settings: Some((chat.notification_settings().clone(), now)),
+    });
+  }
+
+  pub fn on_update_chat_title<U: AsRef<UpdateChatTitle>>(&mut self, update: U) {
+    let update = update.as_ref();
+    if let Some(chat) = self.chats.get_mut(&update.chat_id()) {
+      chat.title = update.title().clone();
+    }
+  }
+
+  pub fn on_update_chat_notification_settings<U: AsRef<UpdateChatNotificationSettings>>(&mut self, update: U, now: i64) {
+    let update = update.as_ref();
+    let chat = self.chats.entry(update.chat_id()).or_insert_with(|| ChatInfo { title: String::new(), scope: None, settings: None });
+    chat.settings = Some((update.notification_settings().clone(), now));
+  }
+
+  pub fn on_update_scope_notification_settings<U: AsRef<UpdateScopeNotificationSettings>>(&mut self, update: U, now: i64) {
+    let update = update.as_ref();
+    if let Some(scope) = NotificationScope::of(update.scope()) {
+      self.scopes.insert(scope, (update.notification_settings().clone(), now));
+    }
+  }
+
+  pub fn on_user(&mut self, user: &User) {
+    let name = format!("{} {}", user.first_name(), user.last_name()).trim().to_string();
+    self.users.insert(user.id(), name);
+  }
+
+  pub fn on_update_have_pending_notifications<U: AsRef<UpdateHavePendingNotifications>>(&mut self, update: U) {
+    let update = update.as_ref();
+    self.have_delayed_notifications = update.have_delayed_notifications();
+    self.have_unreceived_notifications = update.have_unreceived_notifications();
+  }
+
+  fn scope_settings(&self, chat_id: i64) -> Option<&(ScopeNotificationSettings, i64)> {
+    self.chats.get(&chat_id).and_then(|chat| chat.scope).and_then(|scope| self.scopes.get(&scope))
+  }
+
+  /// Whether notifications of the chat are muted, following the scope settings when the chat uses them
+  pub fn is_muted(&self, chat_id: i64, now: i64) -> bool {
+    let chat = self.chats.get(&chat_id).and_then(|chat| chat.settings.as_ref());
+    let muted_until = match chat {
+      Some((settings, received)) if !settings.use_default_mute_for() => received + settings.mute_for(),
+      _ => self.scope_settings(chat_id).map(|(settings, received)| received + settings.mute_for()).unwrap_or(0),
+    };
+    muted_until > now
+  }
+
+  /// Whether message text may be shown in notifications of the chat
+  pub fn show_preview(&self, chat_id: i64) -> bool {
+    match self.chats.get(&chat_id).and_then(|chat| chat.settings.as_ref()) {
+      Some((settings, _)) if !settings.use_default_show_preview() => settings.show_preview(),
+      _ => self.scope_settings(chat_id).map(|(settings, _)| settings.show_preview()).unwrap_or(true),
+    }
+  }
+
+  /// Whether mentions are notified even in a muted chat
+  fn notify_mentions(&self, chat_id: i64) -> bool {
+    match self.chats.get(&chat_id).and_then(|chat| chat.settings.as_ref()) {
+      Some((settings, _)) if !settings.use_default_disable_mention_notifications() => !settings.disable_mention_notifications(),
+      _ => self.scope_settings(chat_id).map(|(settings, _)| !settings.disable_mention_notifications()).unwrap_or(true),
+    }
+  }
+
+  fn chat_title(&self, chat_id: i64) -> String {
+    self.chats.get(&chat_id).map(|chat| chat.title.clone()).filter(|title| !title.is_empty()).unwrap_or_else(|| "Telegram".to_string())
+  }
+
+  fn sender_name(&self, sender: &MessageSender) -> Option<String> {
+    match sender {
+      MessageSender::User(user) => self.users.get(&user.user_id()).cloned(),
+      MessageSender::Chat(chat) => self.chats.get(&chat.chat_id()).map(|chat| chat.title.clone()),
+      _ => None,
+    }
+  }
+
+  /// Renders a notification of the group to text
+  pub fn render(&self, group: &TrackedNotificationGroup, notification: &Notification) -> RenderedNotification {
+    let settings_chat_id = group.notification_settings_chat_id;
+    let show_preview = self.show_preview(settings_chat_id);
+    let is_group = self.chats.get(&group.chat_id).and_then(|chat| chat.scope) == Some(NotificationScope::Group);
+    let body = match notification.type_() {
+      NotificationType::NewMessage(new_message) => {
+        let message = new_message.message();
+        let text = if show_preview { message_preview(message.content()) } else { "New message".to_string() };
+        match self.sender_name(message.sender_id()).filter(|_| is_group) {
+          Some(name) => format!("{}: {}", name, text),
+          None => text,
+        }
+      }
+      NotificationType::NewPushMessage(push) => {
+        let text = if show_preview { push_message_preview(push.content()) } else { "New message".to_string() };
+        if is_group && !push.sender_name().is_empty() { format!("{}: {}", push.sender_name(), text) } else { text }
+      }
+      NotificationType::NewSecretChat(_) => "New secret chat".to_string(),
+      NotificationType::NewCall(_) => "Incoming call".to_string(),
+      _ => "New notification".to_string(),
+    };
+    RenderedNotification {
+      notification_id: notification.id(),
+      chat_id: group.chat_id,
+      date: notification.date(),
+      title: self.chat_title(group.chat_id),
+      body,
+      is_silent: notification.is_silent(),
+    }
+  }
+
+  /// Whether new notifications of the group must be shown
+  fn is_shown(&self, group: &TrackedNotificationGroup, now: i64) -> bool {
+    let chat_id = group.notification_settings_chat_id;
+    match group.type_ {
+      NotificationGroupType::Calls(_) | NotificationGroupType::SecretChat(_) => true,
+      NotificationGroupType::Mentions(_) => !self.is_muted(chat_id, now) || self.notify_mentions(chat_id),
+      _ => !self.is_muted(chat_id, now),
+    }
+  }
+
+  fn shown(&self, group: &TrackedNotificationGroup, notification: &Notification, is_silent: bool) -> NotificationEvent {
+    let mut rendered = self.render(group, notification);
+    rendered.is_silent |= is_silent;
+    NotificationEvent { group_id: group.id, kind: NotificationEventKind::Shown(rendered) }
+  }
+
+  /// Replaces all groups with the active notifications received at startup
+  pub fn on_update_active_notifications<U: AsRef<UpdateActiveNotifications>>(&mut self, update: U, now: i64) -> Vec<NotificationEvent> {
+    let mut events = vec![];
+    let mut old = std::mem::take(&mut self.groups);
+    for group in update.as_ref().groups() {
+      let mut notifications = group.notifications().clone();
+      notifications.sort_by_key(|notification| notification.id());
+      let tracked = TrackedNotificationGroup {
+        id: group.id(),
+        type_: group.type_().clone(),
+        chat_id: group.chat_id(),
+        notification_settings_chat_id: group.chat_id(),
+        total_count: group.total_count(),
+        notifications,
+      };
+      let known = old.remove(&group.id()).map(|group| group.notifications).unwrap_or_default();
+      for notification in known.iter().filter(|known| !tracked.notifications.iter().any(|n| n.id() == known.id())) {
+        events.push(NotificationEvent { group_id: tracked.id, kind: NotificationEventKind::Hidden(notification.id()) });
+      }
+      if self.is_shown(&tracked, now) {
+        for notification in tracked.notifications.iter().filter(|n| !known.iter().any(|known| known.id() == n.id())) {
+          events.push(self.shown(&tracked, notification, false));
+        }
+      }
+      self.groups.insert(tracked.id, tracked);
+    }
+    for group in old.values() {
+      events.extend(group.notifications.iter().map(|notification| NotificationEvent { group_id: group.id, kind: NotificationEventKind::Hidden(notification.id()) }));
+    }
+    events
+  }
+
+  pub fn on_update_notification_group<U: AsRef<UpdateNotificationGroup>>(&mut self, update: U, now: i64) -> Vec<NotificationEvent> {
+    let update = update.as_ref();
+    let group_id = update.notification_group_id();
+    let mut group = self.groups.remove(&group_id).unwrap_or_else(|| TrackedNotificationGroup {
+      id: group_id,
+      type_: update.type_().clone(),
+      chat_id: update.chat_id(),
+      notification_settings_chat_id: update.notification_settings_chat_id(),
+      total_count: 0,
+      notifications: vec![],
+    });
+    group.type_ = update.type_().clone();
+    group.chat_id = update.chat_id();
+    group.notification_settings_chat_id = update.notification_settings_chat_id();
+    group.total_count = update.total_count();
+
+    let mut events = vec![];
+    let removed = update.removed_notification_ids();
+    group.notifications.retain(|notification| !removed.contains(&notification.id()));
+    events.extend(removed.iter().map(|id| NotificationEvent { group_id, kind: NotificationEventKind::Hidden(*id) }));
+
+    let is_shown = self.is_shown(&group, now);
+    for notification in update.added_notifications() {
+      group.notifications.retain(|known| known.id() != notification.id());
+      group.notifications.push(notification.clone());
+      if is_shown {
+        events.push(self.shown(&group, notification, update.is_silent()));
+      }
+    }
+    group.notifications.sort_by_key(|notification| notification.id());
+    if !group.notifications.is_empty() || group.total_count != 0 {
+      self.groups.insert(group_id, group);
+    }
+    events
+  }
+
+  pub fn on_update_notification<U: AsRef<UpdateNotification>>(&mut self, update: U) -> Option<NotificationEvent> {
+    let update = update.as_ref();
+    let notification = update.notification();
+    let group = self.groups.get_mut(&update.notification_group_id())?;
+    let known = group.notifications.iter_mut().find(|known| known.id() == notification.id())?;
+    *known = notification.clone();
+    let group = &self.groups[&update.notification_group_id()];
+    Some(NotificationEvent { group_id: group.id, kind: NotificationEventKind::Edited(self.render(group, notification)) })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::notifications::*;
+
+  const NOW: i64 = 1_650_000_000;
+
+  fn chat(id: i64, title: &str, type_: ChatType, settings: ChatNotificationSettings) -> Chat {
+    Chat::builder().id(id).title(title).type_(type_).notification_settings(settings).build()
+  }
+
+  fn defaults() -> ChatNotificationSettings {
+    ChatNotificationSettings::builder()
+      .use_default_mute_for(true)
+      .use_default_show_preview(true)
+      .use_default_disable_mention_notifications(true)
+      .build()
+  }
+
+  fn group_type() -> ChatType {
+    ChatType::supergroup(ChatTypeSupergroup::builder().supergroup_id(5).build())
+  }
+
+  fn message_notification(id: i64, chat_id: i64, user_id: i64, text: &str) -> Notification {
+    let message = Message::builder()
+      .id(id * 10)
+      .chat_id(chat_id)
+      .sender_id(MessageSender::user(MessageSenderUser::builder().user_id(user_id).build()))
+      .content(MessageContent::message_text(MessageText::builder().text(FormattedText::builder().text(text).build()).build()))
+      .build();
+    let type_ = NotificationType::new_message(NotificationTypeNewMessage::builder().message(message).build());
+    Notification::builder().id(id).date(NOW + id).type_(type_).build()
+  }
+
+  fn group_update(chat_id: i64, added: Vec<Notification>, removed: Vec<i64>, total_count: i64) -> UpdateNotificationGroup {
+    UpdateNotificationGroup::builder()
+      .notification_group_id(1)
+      .type_(NotificationGroupType::messages(NotificationGroupTypeMessages::builder().build()))
+      .chat_id(chat_id)
+      .notification_settings_chat_id(chat_id)
+      .total_count(total_count)
+      .added_notifications(added)
+      .removed_notification_ids(removed)
+      .build()
+  }
+
+  fn bodies(events: &[NotificationEvent]) -> Vec<String> {
+    events.iter().filter_map(|event| match event.kind() {
+      NotificationEventKind::Shown(notification) => Some(format!("{}|{}", notification.title(), notification.body())),
+      NotificationEventKind::Hidden(id) => Some(format!("-{}", id)),
+      NotificationEventKind::Edited(_) => None,
+    }).collect()
+  }
+
+  #[test]
+  fn test_group_updates() {
+    let mut center = NotificationCenter::new();
+    center.on_chat(&chat(-100, "Rustaceans", group_type(), defaults()), NOW);
+    center.on_user(&User::builder().id(7).first_name("Ferris").build());
+
+    let events = center.on_update_notification_group(group_update(-100, vec![message_notification(1, -100, 7, "hi"), message_notification(2, -100, 8, "yo")], vec![], 2), NOW);
+    assert_eq!(bodies(&events), vec!["Rustaceans|Ferris: hi", "Rustaceans|yo"]);
+    assert_eq!(center.total_count(), 2);
+
+    let edited = UpdateNotification::builder().notification_group_id(1).notification(message_notification(2, -100, 7, "edited")).build();
+    match center.on_update_notification(&edited).unwrap().kind() {
+      NotificationEventKind::Edited(notification) => assert_eq!(notification.body(), "Ferris: edited"),
+      kind => panic!("unexpected {:?}", kind),
+    }
+
+    let events = center.on_update_notification_group(group_update(-100, vec![], vec![1], 1), NOW);
+    assert_eq!(bodies(&events), vec!["-1"]);
+    assert_eq!(center.group(1).unwrap().notifications().iter().map(|n| n.id()).collect::<Vec<_>>(), vec![2]);
+    center.on_update_notification_group(group_update(-100, vec![], vec![2], 0), NOW);
+    assert!(center.group(1).is_none());
+
+    let active = UpdateActiveNotifications::builder().groups(vec![NotificationGroup::builder()
+      .id(1)
+      .type_(NotificationGroupType::messages(NotificationGroupTypeMessages::builder().build()))
+      .chat_id(-100)
+      .total_count(1)
+      .notifications(vec![message_notification(3, -100, 7, "again")])
+      .build()]).build();
+    assert_eq!(bodies(&center.on_update_active_notifications(&active, NOW)), vec!["Rustaceans|Ferris: again"]);
+  }
+
+  #[test]
+  fn test_settings() {
+    let mut center = NotificationCenter::new();
+    let private = ChatType::private(ChatTypePrivate::builder().user_id(7).build());
+    center.on_chat(&chat(7, "Ferris", private, defaults()), NOW);
+    let scope = UpdateScopeNotificationSettings::builder()
+      .scope(NotificationScope::Private.settings_scope())
+      .notification_settings(ScopeNotificationSettings::builder().mute_for(0).show_preview(false).build())
+      .build();
+    center.on_update_scope_notification_settings(&scope, NOW);
+    assert_eq!(center.scope_settings_requests().len(), 2);
+    assert!(!center.show_preview(7));
+
+    let events = center.on_update_notification_group(group_update(7, vec![message_notification(1, 7, 7, "secret")], vec![], 1), NOW);
+    assert_eq!(bodies(&events), vec!["Ferris|New message"]);
+
+    let muted = ChatNotificationSettings::builder().use_default_mute_for(false).mute_for(3600).use_default_show_preview(true).build();
+    center.on_update_chat_notification_settings(UpdateChatNotificationSettings::builder().chat_id(7).notification_settings(muted).build(), NOW);
+    assert!(center.is_muted(7, NOW + 3599));
+    assert!(!center.is_muted(7, NOW + 3600));
+    assert!(center.on_update_notification_group(group_update(7, vec![message_notification(2, 7, 7, "muted")], vec![], 2), NOW).is_empty());
+    assert_eq!(center.group(1).unwrap().notifications().len(), 2);
+  }
+
+  #[test]
+  fn test_previews() {
+    let photo = MessageContent::message_photo(MessagePhoto::builder().caption(FormattedText::builder().text("sunset").build()).build());
+    assert_eq!(message_preview(&photo), "Photo, sunset");
+    let poll = PushMessageContent::poll(PushMessageContentPoll::builder().question("Tabs or spaces?").build());
+    assert_eq!(push_message_preview(&poll), "Poll: Tabs or spaces?");
+    let sticker = PushMessageContent::sticker(PushMessageContentSticker::builder().emoji("🦀").build());
+    assert_eq!(push_message_preview(&sticker), "🦀 Sticker");
+  }
+}
@@ -0,0 +1,588 @@
+//! Telegram Passport decryption for bots.
+//!
+//! `MessagePassportDataReceived` holds the shared elements and credentials encrypted with the
+//! bot's public key. `PassportDecryptor` decrypts the credentials with the bot's RSA private key,
+//! then every element and file with its own AES-256-CBC secret, checking the hash of each.
+//! `validate_form` checks the decrypted elements against the required elements of the form and
+//! `PassportErrors` reports problems back to the user with `SetPassportElementErrors`.
+//!
+//! ```rust,no_run
+//! use rtdlib::passport::{PassportDecryptor, PassportValue};
+//! # use rtdlib::types::*;
+//! # let data = MessagePassportDataReceived::builder().build();
+//! let decryptor = PassportDecryptor::from_pem(std::fs::read_to_string("bot_key.pem").unwrap()).unwrap();
+//! let passport = decryptor.decrypt(&data).unwrap();
+//! for element in passport.elements {
+//!   if let PassportValue::PersonalDetails(details) = &element.value {
+//!     println!("{} {}", details.first_name, details.last_name);
+//!   }
+//!   if let Some(front_side) = &element.front_side {
+//!     // download the file, then
+//!     let image = front_side.decrypt(&std::fs::read(front_side.file.file().local().path()).unwrap()).unwrap();
+//!   }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::crypto::{aes256_cbc_decrypt, constant_time_eq, sha256, sha512, RsaPrivateKey};
+use crate::encoding::decode_base64;
+use crate::errors::*;
+use crate::types::*;
+
+/// Key of an element type in the credentials, e.g. `driver_license`
+pub fn element_type_key(element_type: &PassportElementType) -> Option<&'static str> {
+  Some(match element_type {
+    PassportElementType::PersonalDetails(_) => "personal_details",
+    PassportElementType::Passport(_) => "passport",
+    PassportElementType::DriverLicense(_) => "driver_license",
+    PassportElementType::IdentityCard(_) => "identity_card",
+    PassportElementType::InternalPassport(_) => "internal_passport",
+    PassportElementType::Address(_) => "address",
+    PassportElementType::UtilityBill(_) => "utility_bill",
+    PassportElementType::BankStatement(_) => "bank_statement",
+    PassportElementType::RentalAgreement(_) => "rental_agreement",
+    PassportElementType::PassportRegistration(_) => "passport_registration",
+    PassportElementType::TemporaryRegistration(_) => "temporary_registration",
+    PassportElementType::PhoneNumber(_) => "phone_number",
+    PassportElementType::EmailAddress(_) => "email_address",
+    _ => return None,
+  })
+}
+
+fn decode(name: &str, value: &str) -> RTDResult<Vec<u8>> {
+  decode_base64(value).ok_or_else(|| RTDError::custom(format!("invalid base64 in passport {}", name)))
+}
+
+/// Decrypt passport data with its secret and check its hash, removing the random padding
+fn decrypt_data(encrypted: &[u8], secret: &[u8], hash: &[u8]) -> RTDResult<Vec<u8>> {
+  let secret_hash = sha512(&[secret, hash].concat());
+  let mut key = [0u8; 32];
+  let mut iv = [0u8; 16];
+  key.copy_from_slice(&secret_hash[..32]);
+  iv.copy_from_slice(&secret_hash[32..48]);
+  let decrypted = aes256_cbc_decrypt(&key, &iv, encrypted)
+    .ok_or_else(|| RTDError::custom("passport data isn't a multiple of the AES block size".to_string()))?;
+  if !constant_time_eq(&sha256(&decrypted), hash) {
+    return Err(RTDError::custom("passport data hash mismatch".to_string()));
+  }
+  let padding = decrypted.first().copied().unwrap_or(0) as usize;
+  if padding < 32 || padding > decrypted.len() {
+    return Err(RTDError::custom(format!("invalid passport data padding of {} bytes", padding)));
+  }
+  Ok(decrypted[padding..].to_vec())
+}
+
+/// Secret of the json data of an element
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataCredentials {
+  /// Base64 SHA-256 of the decrypted data
+  pub data_hash: String,
+  pub secret: String,
+}
+
+impl DataCredentials {
+  pub fn decrypt(&self, encrypted: &[u8]) -> RTDResult<Vec<u8>> {
+    decrypt_data(encrypted, &decode("secret", &self.secret)?, &decode("data hash", &self.data_hash)?)
+  }
+}
+
+/// Secret of a file of an element
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileCredentials {
+  /// Base64 SHA-256 of the decrypted file
+  pub file_hash: String,
+  pub secret: String,
+}
+
+impl FileCredentials {
+  pub fn decrypt(&self, encrypted: &[u8]) -> RTDResult<Vec<u8>> {
+    decrypt_data(encrypted, &decode("secret", &self.secret)?, &decode("file hash", &self.file_hash)?)
+  }
+}
+
+/// Secrets of an element
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecureValue {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub data: Option<DataCredentials>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub front_side: Option<FileCredentials>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reverse_side: Option<FileCredentials>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub selfie: Option<FileCredentials>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub translation: Vec<FileCredentials>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub files: Vec<FileCredentials>,
+}
+
+/// Decrypted `EncryptedCredentials`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+  /// Secrets by element type key, see `element_type_key`
+  pub secure_data: HashMap<String, SecureValue>,
+  /// Nonce of the authorization form request, to be checked by the bot
+  pub nonce: String,
+}
+
+/// Files of an element with their secrets, in the order of the element
+fn files(name: &str, files: &[DatedFile], credentials: &[FileCredentials]) -> RTDResult<Vec<PassportFile>> {
+  if files.len() != credentials.len() {
+    return Err(RTDError::custom(format!("{} passport {} received with {} secrets", files.len(), name, credentials.len())));
+  }
+  Ok(files.iter().zip(credentials.iter())
+    .map(|(file, credentials)| PassportFile { file: file.clone(), credentials: credentials.clone() })
+    .collect())
+}
+
+impl Credentials {
+  pub fn value(&self, element_type: &PassportElementType) -> Option<&SecureValue> {
+    self.secure_data.get(element_type_key(element_type)?)
+  }
+
+  /// Decrypt the data of an element and pair its files with their secrets
+  pub fn decrypt_element(&self, element: &EncryptedPassportElement) -> RTDResult<DecryptedPassportElement> {
+    let element_type = element.type_();
+    let value = match element_type {
+      PassportElementType::PhoneNumber(_) => PassportValue::PhoneNumber(element.value().clone()),
+      PassportElementType::EmailAddress(_) => PassportValue::EmailAddress(element.value().clone()),
+      _ => {
+        let key = element_type_key(element_type)
+          .ok_or_else(|| RTDError::custom("unknown passport element type".to_string()))?;
+        let secure_value = self.secure_data.get(key)
+          .ok_or_else(|| RTDError::custom(format!("no passport credentials for {}", key)))?;
+        return self.decrypt_secure_element(element, secure_value);
+      }
+    };
+    Ok(DecryptedPassportElement { element_type: element_type.clone(), hash: element.hash().clone(), value, ..Default::default() })
+  }
+
+  fn decrypt_secure_element(&self, element: &EncryptedPassportElement, secure: &SecureValue) -> RTDResult<DecryptedPassportElement> {
+    let element_type = element.type_();
+    let json = match &secure.data {
+      Some(credentials) => Some(credentials.decrypt(&decode("element data", element.data())?)?),
+      None => None,
+    };
+    let parse = |name: &str| -> RTDResult<&[u8]> {
+      json.as_deref().ok_or_else(|| RTDError::custom(format!("no data secret for passport {}", name)))
+    };
+    let value = match element_type {
+      PassportElementType::PersonalDetails(_) => PassportValue::PersonalDetails(serde_json::from_slice(parse("personal details")?)?),
+      PassportElementType::Passport(_) | PassportElementType::DriverLicense(_) | PassportElementType::IdentityCard(_)
+      | PassportElementType::InternalPassport(_) => PassportValue::IdentityDocument(serde_json::from_slice(parse("identity document")?)?),
+      PassportElementType::Address(_) => PassportValue::Address(serde_json::from_slice(parse("address")?)?),
+      _ => PassportValue::Files,
+    };
+    let file = |name: &str, file: Option<&DatedFile>, credentials: &Option<FileCredentials>| -> RTDResult<Option<PassportFile>> {
+      match (file, credentials) {
+        (Some(file), Some(credentials)) => Ok(Some(PassportFile { file: file.clone(), credentials: credentials.clone() })),
+        (None, Some(_)) => Err(RTDError::custom(format!("passport {} secret received without the file", name))),
+        _ => Ok(None),
+      }
+    };
+    Ok(DecryptedPassportElement {
+      element_type: element_type.clone(),
+      hash: element.hash().clone(),
+      data_hash: secure.data.as_ref().map(|data| data.data_hash.clone()),
+      value,
+      front_side: file("front side", Some(element.front_side()), &secure.front_side)?,
+      reverse_side: file("reverse side", element.reverse_side().as_ref(), &secure.reverse_side)?,
+      selfie: file("selfie", element.selfie().as_ref(), &secure.selfie)?,
+      translation: files("translation", element.translation(), &secure.translation)?,
+      files: files("files", element.files(), &secure.files)?,
+    })
+  }
+}
+
+/// Personal details, dates are `DD.MM.YYYY`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PersonalDetails {
+  pub first_name: String,
+  pub last_name: String,
+  pub middle_name: String,
+  pub birth_date: String,
+  /// `male` or `female`
+  pub gender: String,
+  /// ISO 3166-1 alpha-2 country code
+  pub country_code: String,
+  pub residence_country_code: String,
+  /// Names in the language of the country of residence
+  pub first_name_native: String,
+  pub last_name_native: String,
+  pub middle_name_native: String,
+}
+
+/// Data of a passport, driver license, identity card or internal passport
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdentityDocument {
+  pub document_no: String,
+  /// `DD.MM.YYYY`, empty if the document doesn't expire
+  pub expiry_date: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResidentialAddress {
+  pub street_line1: String,
+  pub street_line2: String,
+  pub city: String,
+  pub state: String,
+  pub country_code: String,
+  pub post_code: String,
+}
+
+/// Decrypted value of an element
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PassportValue {
+  PersonalDetails(PersonalDetails),
+  IdentityDocument(IdentityDocument),
+  Address(ResidentialAddress),
+  PhoneNumber(String),
+  EmailAddress(String),
+  /// Documents made of files only, e.g. a utility bill
+  #[default]
+  Files,
+}
+
+/// An encrypted file of an element with its secret, to be decrypted once downloaded
+#[derive(Debug, Clone)]
+pub struct PassportFile {
+  pub file: DatedFile,
+  pub credentials: FileCredentials,
+}
+
+impl PassportFile {
+  pub fn decrypt(&self, encrypted: &[u8]) -> RTDResult<Vec<u8>> { self.credentials.decrypt(encrypted) }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecryptedPassportElement {
+  pub element_type: PassportElementType,
+  /// Base64 hash of the entire element, identifies it in `Unspecified` errors
+  pub hash: String,
+  /// Base64 hash of the decrypted data, identifies it in `DataField` errors
+  pub data_hash: Option<String>,
+  pub value: PassportValue,
+  pub front_side: Option<PassportFile>,
+  pub reverse_side: Option<PassportFile>,
+  pub selfie: Option<PassportFile>,
+  pub translation: Vec<PassportFile>,
+  pub files: Vec<PassportFile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecryptedPassport {
+  pub nonce: String,
+  pub elements: Vec<DecryptedPassportElement>,
+}
+
+/// Decrypts passport data with the bot's private key
+#[derive(Debug, Clone)]
+pub struct PassportDecryptor {
+  key: RsaPrivateKey,
+}
+
+impl PassportDecryptor {
+  /// Load a PKCS#1 `RSA PRIVATE KEY` or PKCS#8 `PRIVATE KEY` PEM
+  pub fn from_pem<S: AsRef<str>>(pem: S) -> RTDResult<Self> {
+    let key = RsaPrivateKey::from_pem(pem.as_ref())
+      .ok_or_else(|| RTDError::custom("invalid RSA private key".to_string()))?;
+    Ok(Self { key })
+  }
+
+  pub fn decrypt_credentials(&self, credentials: &EncryptedCredentials) -> RTDResult<Credentials> {
+    let secret = self.key.decrypt_oaep(&decode("credentials secret", credentials.secret())?)
+      .ok_or_else(|| RTDError::custom("passport credentials secret can't be decrypted with the bot key".to_string()))?;
+    let json = decrypt_data(&decode("credentials", credentials.data())?, &secret, &decode("credentials hash", credentials.hash())?)?;
+    Ok(serde_json::from_slice(&json)?)
+  }
+
+  pub fn decrypt(&self, data: &MessagePassportDataReceived) -> RTDResult<DecryptedPassport> {
+    let credentials = self.decrypt_credentials(data.credentials())?;
+    let elements = data.elements().iter()
+      .map(|element| credentials.decrypt_element(element))
+      .collect::<RTDResult<Vec<_>>>()?;
+    Ok(DecryptedPassport { nonce: credentials.nonce, elements })
+  }
+}
+
+/// Data fields of an element type which can be reported with `PassportErrors::data_field`
+pub fn data_fields(element_type: &PassportElementType) -> &'static [&'static str] {
+  match element_type {
+    PassportElementType::PersonalDetails(_) => &[
+      "first_name", "last_name", "middle_name", "birth_date", "gender", "country_code", "residence_country_code",
+      "first_name_native", "last_name_native", "middle_name_native",
+    ],
+    PassportElementType::Passport(_) | PassportElementType::DriverLicense(_) | PassportElementType::IdentityCard(_)
+    | PassportElementType::InternalPassport(_) => &["document_no", "expiry_date"],
+    PassportElementType::Address(_) => &["street_line1", "street_line2", "city", "state", "country_code", "post_code"],
+    _ => &[],
+  }
+}
+
+fn same_type(a: &PassportElementType, b: &PassportElementType) -> bool {
+  element_type_key(a).is_some() && element_type_key(a) == element_type_key(b)
+}
+
+/// What is wrong with a received element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+  /// None of the suitable elements was received
+  MissingElement,
+  /// A required data field is empty
+  MissingField(&'static str),
+  /// The name in the language of the country of residence is required
+  MissingNativeName,
+  MissingFrontSide,
+  MissingReverseSide,
+  MissingSelfie,
+  MissingTranslation,
+  MissingFiles,
+  /// Phone number or email address is empty
+  MissingValue,
+}
+
+impl IssueKind {
+  fn message(&self) -> String {
+    match self {
+      IssueKind::MissingElement => "The document is missing".to_string(),
+      IssueKind::MissingField(field) => format!("The {} is missing", field.replace('_', " ")),
+      IssueKind::MissingNativeName => "Your name in the language of your country of residence is missing".to_string(),
+      IssueKind::MissingFrontSide => "The front side of the document is missing".to_string(),
+      IssueKind::MissingReverseSide => "The reverse side of the document is missing".to_string(),
+      IssueKind::MissingSelfie => "A selfie with the document is missing".to_string(),
+      IssueKind::MissingTranslation => "A certified English translation of the document is missing".to_string(),
+      IssueKind::MissingFiles => "The scans of the document are missing".to_string(),
+      IssueKind::MissingValue => "The value is missing".to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct PassportIssue {
+  /// Index of the required element of the form
+  pub requirement: usize,
+  pub element_type: PassportElementType,
+  pub kind: IssueKind,
+}
+
+/// Result of `validate_form`
+#[derive(Debug, Clone, Default)]
+pub struct FormValidation {
+  /// Element accepted for each required element of the form, `None` if none is valid
+  pub accepted: Vec<Option<PassportElementType>>,
+  pub issues: Vec<PassportIssue>,
+}
+
+impl FormValidation {
+  pub fn is_valid(&self) -> bool { self.issues.is_empty() }
+}
+
+fn element_issues(element: &DecryptedPassportElement, suitable: &PassportSuitableElement) -> Vec<IssueKind> {
+  let mut issues = vec![];
+  let mut require = |present: bool, issue: IssueKind| if !present { issues.push(issue) };
+  let is_document;
+  match &element.value {
+    PassportValue::PersonalDetails(details) => {
+      is_document = false;
+      for (field, value) in [
+        ("first_name", &details.first_name), ("last_name", &details.last_name), ("birth_date", &details.birth_date),
+        ("gender", &details.gender), ("country_code", &details.country_code), ("residence_country_code", &details.residence_country_code),
+      ] {
+        require(!value.is_empty(), IssueKind::MissingField(field));
+      }
+      if suitable.is_native_name_required() {
+        require(!details.first_name_native.is_empty() && !details.last_name_native.is_empty(), IssueKind::MissingNativeName);
+      }
+    }
+    PassportValue::IdentityDocument(document) => {
+      is_document = true;
+      require(!document.document_no.is_empty(), IssueKind::MissingField("document_no"));
+      require(element.front_side.is_some(), IssueKind::MissingFrontSide);
+      let has_reverse_side = matches!(element.element_type, PassportElementType::DriverLicense(_) | PassportElementType::IdentityCard(_));
+      require(!has_reverse_side || element.reverse_side.is_some(), IssueKind::MissingReverseSide);
+    }
+    PassportValue::Address(address) => {
+      is_document = false;
+      for (field, value) in [
+        ("street_line1", &address.street_line1), ("city", &address.city), ("country_code", &address.country_code),
+        ("post_code", &address.post_code),
+      ] {
+        require(!value.is_empty(), IssueKind::MissingField(field));
+      }
+    }
+    PassportValue::PhoneNumber(value) | PassportValue::EmailAddress(value) => {
+      is_document = false;
+      require(!value.is_empty(), IssueKind::MissingValue);
+    }
+    PassportValue::Files => {
+      is_document = true;
+      require(!element.files.is_empty(), IssueKind::MissingFiles);
+    }
+  }
+  if is_document {
+    require(!suitable.is_selfie_required() || element.selfie.is_some(), IssueKind::MissingSelfie);
+    require(!suitable.is_translation_required() || !element.translation.is_empty(), IssueKind::MissingTranslation);
+  }
+  issues
+}
+
+/// Check received elements against the required elements of an authorization form, each one is
+/// satisfied by any of its suitable elements
+pub fn validate_form(required: &[PassportRequiredElement], passport: &DecryptedPassport) -> FormValidation {
+  let mut validation = FormValidation::default();
+  for (requirement, required) in required.iter().enumerate() {
+    let mut first_issues = None;
+    let mut accepted = None;
+    for suitable in required.suitable_elements() {
+      let element = match passport.elements.iter().find(|element| same_type(&element.element_type, suitable.type_())) {
+        Some(element) => element,
+        None => continue,
+      };
+      let issues = element_issues(element, suitable);
+      if issues.is_empty() {
+        accepted = Some(element.element_type.clone());
+        break;
+      }
+      first_issues.get_or_insert((element.element_type.clone(), issues));
+    }
+    if accepted.is_none() {
+      let (element_type, kinds) = first_issues.unwrap_or_else(|| {
+        let element_type = required.suitable_elements().first().map(|suitable| suitable.type_().clone()).unwrap_or_default();
+        (element_type, vec![IssueKind::MissingElement])
+      });
+      validation.issues.extend(kinds.into_iter().map(|kind| PassportIssue { requirement, element_type: element_type.clone(), kind }));
+    }
+    validation.accepted.push(accepted);
+  }
+  validation
+}
+
+/// Builds `SetPassportElementErrors` with the hashes identifying the faulty data and files
+#[derive(Debug, Clone)]
+pub struct PassportErrors {
+  user_id: i64,
+  errors: Vec<InputPassportElementError>,
+  invalid: Vec<String>,
+}
+
+impl PassportErrors {
+  pub fn new(user_id: i64) -> Self {
+    Self { user_id, errors: vec![], invalid: vec![] }
+  }
+
+  fn push(&mut self, element: &DecryptedPassportElement, message: &str, source: Option<InputPassportElementErrorSource>, what: &str) -> &mut Self {
+    match source {
+      Some(source) => self.errors.push(InputPassportElementError::builder()
+        .type_(&element.element_type)
+        .message(message)
+        .source(source)
+        .build()),
+      None => self.invalid.push(format!("{} of {}", what, element_type_key(&element.element_type).unwrap_or("unknown element"))),
+    }
+    self
+  }
+
+  /// The element as a whole, e.g. a wrong phone number
+  pub fn unspecified<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, message: S) -> &mut Self {
+    let source = InputPassportElementErrorSource::unspecified(InputPassportElementErrorSourceUnspecified::builder().element_hash(&element.hash).build());
+    self.push(element, message.as_ref(), Some(source), "hash")
+  }
+
+  /// A field of the element data, see `data_fields`
+  pub fn data_field<F: AsRef<str>, S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, field_name: F, message: S) -> &mut Self {
+    let field_name = field_name.as_ref();
+    let source = element.data_hash.as_ref()
+      .filter(|_| data_fields(&element.element_type).contains(&field_name))
+      .map(|data_hash| InputPassportElementErrorSource::data_field(InputPassportElementErrorSourceDataField::builder()
+        .field_name(field_name)
+        .data_hash(data_hash)
+        .build()));
+    self.push(element, message.as_ref(), source, &format!("data field {}", field_name))
+  }
+
+  pub fn front_side<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, message: S) -> &mut Self {
+    let source = element.front_side.as_ref().map(|file| InputPassportElementErrorSource::front_side(
+      InputPassportElementErrorSourceFrontSide::builder().file_hash(&file.credentials.file_hash).build()
+    ));
+    self.push(element, message.as_ref(), source, "front side")
+  }
+
+  pub fn reverse_side<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, message: S) -> &mut Self {
+    let source = element.reverse_side.as_ref().map(|file| InputPassportElementErrorSource::reverse_side(
+      InputPassportElementErrorSourceReverseSide::builder().file_hash(&file.credentials.file_hash).build()
+    ));
+    self.push(element, message.as_ref(), source, "reverse side")
+  }
+
+  pub fn selfie<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, message: S) -> &mut Self {
+    let source = element.selfie.as_ref().map(|file| InputPassportElementErrorSource::selfie(
+      InputPassportElementErrorSourceSelfie::builder().file_hash(&file.credentials.file_hash).build()
+    ));
+    self.push(element, message.as_ref(), source, "selfie")
+  }
+
+  pub fn translation_file<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, index: usize, message: S) -> &mut Self {
+    let source = element.translation.get(index).map(|file| InputPassportElementErrorSource::translation_file(
+      InputPassportElementErrorSourceTranslationFile::builder().file_hash(&file.credentials.file_hash).build()
+    ));
+    self.push(element, message.as_ref(), source, &format!("translation file {}", index))
+  }
+
+  /// The translation as a whole, e.g. when a page is missing
+  pub fn translation_files<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, message: S) -> &mut Self {
+    let hashes = element.translation.iter().map(|file| file.credentials.file_hash.clone()).collect::<Vec<_>>();
+    let source = Some(hashes).filter(|hashes| !hashes.is_empty()).map(|hashes| InputPassportElementErrorSource::translation_files(
+      InputPassportElementErrorSourceTranslationFiles::builder().file_hashes(hashes).build()
+    ));
+    self.push(element, message.as_ref(), source, "translation files")
+  }
+
+  pub fn file<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, index: usize, message: S) -> &mut Self {
+    let source = element.files.get(index).map(|file| InputPassportElementErrorSource::file(
+      InputPassportElementErrorSourceFile::builder().file_hash(&file.credentials.file_hash).build()
+    ));
+    self.push(element, message.as_ref(), source, &format!("file {}", index))
+  }
+
+  /// The files as a whole, e.g. when a page is missing
+  pub fn files<S: AsRef<str>>(&mut self, element: &DecryptedPassportElement, message: S) -> &mut Self {
+    let hashes = element.files.iter().map(|file| file.credentials.file_hash.clone()).collect::<Vec<_>>();
+    let source = Some(hashes).filter(|hashes| !hashes.is_empty()).map(|hashes| InputPassportElementErrorSource::files(
+      InputPassportElementErrorSourceFiles::builder().file_hashes(hashes).build()
+    ));
+    self.push(element, message.as_ref(), source, "files")
+  }
+
+  /// Report an issue found by `validate_form` on the closest source
+  pub fn issue(&mut self, element: &DecryptedPassportElement, issue: &IssueKind) -> &mut Self {
+    let message = issue.message();
+    match issue {
+      IssueKind::MissingField(field) => self.data_field(element, field, message),
+      IssueKind::MissingNativeName => self.data_field(element, "first_name_native", message),
+      // missing files have no hash, the whole element has to be sent again
+      _ => self.unspecified(element, message),
+    }
+  }
+
+  /// Report every issue of a validation whose element was received
+  pub fn validation(&mut self, validation: &FormValidation, passport: &DecryptedPassport) -> &mut Self {
+    for issue in &validation.issues {
+      let element = passport.elements.iter().find(|element| same_type(&element.element_type, &issue.element_type));
+      if let Some(element) = element { self.issue(element, &issue.kind); }
+    }
+    self
+  }
+
+  /// Fails if an error refers to data or a file the element doesn't have
+  pub fn build(&self) -> RTDResult<SetPassportElementErrors> {
+    if !self.invalid.is_empty() {
+      return Err(RTDError::custom(format!("no {} to report a passport error on", self.invalid.join(", "))));
+    }
+    Ok(SetPassportElementErrors::builder().user_id(self.user_id).errors(self.errors.clone()).build())
+  }
+}
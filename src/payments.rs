@@ -0,0 +1,646 @@
+//! Payments: amounts, invoices, shipping and pre-checkout queries.
+//!
+//! Telegram counts prices in the smallest units of the currency, e.g. cents for `USD` and yens
+//! for `JPY`. `Amount` keeps the currency with its ISO 4217 exponent so prices can be parsed and
+//! shown in a human readable way. `PaymentDispatcher` answers shipping and pre-checkout queries with
+//! handlers, answering with an error if a handler fails, doesn't answer in time or isn't set.
+//!
+//! ```rust
+//! use rtdlib::payments::{Amount, Currency, InvoiceMessage};
+//!
+//! let usd = Currency::new("USD").unwrap();
+//! let content = InvoiceMessage::builder(usd)
+//!   .title("Rust book")
+//!   .description("The Rust Programming Language, paperback")
+//!   .payload("order:42")
+//!   .provider_token("provider token")
+//!   .price("Book", Amount::parse("39.95", usd).unwrap())
+//!   .price("Gift wrapping", Amount::parse("2", usd).unwrap())
+//!   .max_tip(Amount::parse("10", usd).unwrap())
+//!   .suggested_tips(vec![Amount::parse("1", usd).unwrap(), Amount::parse("5", usd).unwrap()])
+//!   .build()
+//!   .unwrap();
+//! ```
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::encoding::*;
+use crate::errors::*;
+use crate::types::*;
+
+/// Maximum number of suggested tip amounts
+pub const MAX_SUGGESTED_TIPS: usize = 4;
+/// Maximum size of an invoice payload, in bytes
+pub const MAX_PAYLOAD_LENGTH: usize = 128;
+/// Time Telegram waits for an answer to a shipping or pre-checkout query
+pub const QUERY_ANSWER_LIMIT: Duration = Duration::from_secs(10);
+
+/// An ISO 4217 currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+  code: [u8; 3],
+  exponent: u32,
+}
+
+impl Currency {
+  /// Currency with a three letter code, e.g. `EUR`
+  pub fn new<S: AsRef<str>>(code: S) -> RTDResult<Currency> {
+    let code = code.as_ref().to_ascii_uppercase();
+    if code.len() != 3 || !code.bytes().all(|c| c.is_ascii_uppercase()) {
+      return Err(RTDError::custom(format!("invalid currency code {}", code)));
+    }
+    let exponent = match code.as_str() {
+      "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "UYI" | "VND"
+      | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+      "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+      "CLF" | "UYW" => 4,
+      _ => 2,
+    };
+    let bytes = code.as_bytes();
+    Ok(Currency { code: [bytes[0], bytes[1], bytes[2]], exponent })
+  }
+
+  pub fn code(&self) -> &str { std::str::from_utf8(&self.code).unwrap_or_default() }
+
+  /// Number of digits after the decimal separator
+  pub fn exponent(&self) -> u32 { self.exponent }
+}
+
+impl fmt::Display for Currency {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.code()) }
+}
+
+/// An amount of money in the smallest units of its currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Amount {
+  currency: Currency,
+  value: i64,
+}
+
+impl Amount {
+  /// Amount in the smallest units, e.g. `1050` for 10.50 USD
+  pub fn new(value: i64, currency: Currency) -> Amount { Amount { currency, value } }
+
+  /// Parse a decimal amount like `10.5`, more digits than the currency exponent are refused
+  pub fn parse<S: AsRef<str>>(text: S, currency: Currency) -> RTDResult<Amount> {
+    let text = text.as_ref().trim();
+    let invalid = || RTDError::custom(format!("invalid {} amount {}", currency, text));
+    let (negative, digits) = match text.strip_prefix('-') {
+      Some(digits) => (true, digits),
+      None => (false, text),
+    };
+    let (units, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let all_digits = |s: &str| s.bytes().all(|c| c.is_ascii_digit());
+    if units.is_empty() || !all_digits(units) || !all_digits(fraction) || fraction.len() > currency.exponent as usize {
+      return Err(invalid());
+    }
+    let scale = 10i64.pow(currency.exponent);
+    let fraction = format!("{:0<width$}", fraction, width = currency.exponent as usize);
+    let value = units.parse::<i64>().ok()
+      .and_then(|units| units.checked_mul(scale))
+      .and_then(|value| value.checked_add(fraction.parse::<i64>().unwrap_or(0)))
+      .ok_or_else(invalid)?;
+    Ok(Amount { currency, value: if negative { -value } else { value } })
+  }
+
+  pub fn currency(&self) -> Currency { self.currency }
+
+  /// Value in the smallest units of the currency
+  pub fn value(&self) -> i64 { self.value }
+
+  /// Decimal representation without currency, e.g. `10.50`
+  pub fn to_decimal_string(&self) -> String {
+    let exponent = self.currency.exponent;
+    let sign = if self.value < 0 { "-" } else { "" };
+    let value = self.value.unsigned_abs();
+    if exponent == 0 { return format!("{}{}", sign, value) }
+    let scale = 10u64.pow(exponent);
+    format!("{}{}.{:0width$}", sign, value / scale, value % scale, width = exponent as usize)
+  }
+
+  /// Sum of amounts of the same currency
+  pub fn checked_add(&self, other: Amount) -> RTDResult<Amount> {
+    if self.currency != other.currency {
+      return Err(RTDError::custom(format!("can't add {} to {}", other.currency, self.currency)));
+    }
+    let value = self.value.checked_add(other.value).ok_or_else(|| RTDError::custom("amount overflow".to_string()))?;
+    Ok(Amount { currency: self.currency, value })
+  }
+}
+
+impl fmt::Display for Amount {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {}", self.to_decimal_string(), self.currency)
+  }
+}
+
+pub fn price_part<S: AsRef<str>>(label: S, amount: Amount) -> LabeledPricePart {
+  LabeledPricePart::builder().label(label.as_ref()).amount(amount.value()).build()
+}
+
+/// Total of price parts in a currency
+pub fn total<'a, I: IntoIterator<Item = &'a LabeledPricePart>>(parts: I, currency: Currency) -> RTDResult<Amount> {
+  parts.into_iter().try_fold(Amount::new(0, currency), |sum, part| sum.checked_add(Amount::new(part.amount(), currency)))
+}
+
+/// A shipping option with prices in a currency
+pub fn shipping_option<I: AsRef<str>, T: AsRef<str>>(id: I, title: T, prices: Vec<(&str, Amount)>) -> ShippingOption {
+  ShippingOption::builder()
+    .id(id.as_ref())
+    .title(title.as_ref())
+    .price_parts(prices.into_iter().map(|(label, amount)| price_part(label, amount)).collect())
+    .build()
+}
+
+/// Builds `InputMessageInvoice` content
+pub struct InvoiceMessage;
+
+impl InvoiceMessage {
+  pub fn builder(currency: Currency) -> InvoiceMessageBuilder {
+    InvoiceMessageBuilder {
+      currency,
+      prices: vec![],
+      max_tip: None,
+      suggested_tips: vec![],
+      invoice: Invoice::builder(),
+      content: InputMessageInvoice::builder(),
+    }
+  }
+}
+
+pub struct InvoiceMessageBuilder {
+  currency: Currency,
+  prices: Vec<(String, Amount)>,
+  max_tip: Option<Amount>,
+  suggested_tips: Vec<Amount>,
+  invoice: RTDInvoiceBuilder,
+  content: RTDInputMessageInvoiceBuilder,
+}
+
+impl InvoiceMessageBuilder {
+  /// Product name, 1-32 characters
+  pub fn title<S: AsRef<str>>(&mut self, title: S) -> &mut Self {
+    self.content.title(title.as_ref());
+    self
+  }
+
+  /// Product description, 1-255 characters
+  pub fn description<S: AsRef<str>>(&mut self, description: S) -> &mut Self {
+    self.content.description(description.as_ref());
+    self
+  }
+
+  pub fn photo<S: AsRef<str>>(&mut self, url: S, size: i64, width: i64, height: i64) -> &mut Self {
+    self.content
+      .photo_url(url.as_ref())
+      .photo_size(size)
+      .photo_width(width)
+      .photo_height(height);
+    self
+  }
+
+  /// Bot defined data of the invoice, 1-128 bytes, it is sent back in shipping and pre-checkout queries
+  pub fn payload<P: AsRef<[u8]>>(&mut self, payload: P) -> &mut Self {
+    self.content.payload(encode_base64(payload.as_ref()));
+    self
+  }
+
+  pub fn provider_token<S: AsRef<str>>(&mut self, provider_token: S) -> &mut Self {
+    self.content.provider_token(provider_token.as_ref());
+    self
+  }
+
+  /// JSON-serialized data for the payment provider
+  pub fn provider_data<S: AsRef<str>>(&mut self, provider_data: S) -> &mut Self {
+    self.content.provider_data(provider_data.as_ref());
+    self
+  }
+
+  /// Start parameter of the deep link used when the invoice is forwarded
+  pub fn start_parameter<S: AsRef<str>>(&mut self, start_parameter: S) -> &mut Self {
+    self.content.start_parameter(start_parameter.as_ref());
+    self
+  }
+
+  pub fn price<S: AsRef<str>>(&mut self, label: S, amount: Amount) -> &mut Self {
+    self.prices.push((label.as_ref().to_string(), amount));
+    self
+  }
+
+  /// Maximum tip the user can add
+  pub fn max_tip(&mut self, max_tip: Amount) -> &mut Self {
+    self.max_tip = Some(max_tip);
+    self
+  }
+
+  /// Tip amounts suggested to the user, at most 4 in increasing order
+  pub fn suggested_tips(&mut self, tips: Vec<Amount>) -> &mut Self {
+    self.suggested_tips = tips;
+    self
+  }
+
+  /// Invoice of the test payment environment
+  pub fn test(&mut self, is_test: bool) -> &mut Self {
+    self.invoice.is_test(is_test);
+    self
+  }
+
+  pub fn need_name(&mut self, need_name: bool) -> &mut Self {
+    self.invoice.need_name(need_name);
+    self
+  }
+
+  /// Require the phone number, `send_to_provider` also passes it to the payment provider
+  pub fn need_phone_number(&mut self, need_phone_number: bool, send_to_provider: bool) -> &mut Self {
+    self.invoice
+      .need_phone_number(need_phone_number)
+      .send_phone_number_to_provider(send_to_provider);
+    self
+  }
+
+  /// Require the email address, `send_to_provider` also passes it to the payment provider
+  pub fn need_email_address(&mut self, need_email_address: bool, send_to_provider: bool) -> &mut Self {
+    self.invoice
+      .need_email_address(need_email_address)
+      .send_email_address_to_provider(send_to_provider);
+    self
+  }
+
+  pub fn need_shipping_address(&mut self, need_shipping_address: bool) -> &mut Self {
+    self.invoice.need_shipping_address(need_shipping_address);
+    self
+  }
+
+  /// The price depends on the shipping address, shipping queries are sent to the bot
+  pub fn flexible(&mut self, is_flexible: bool) -> &mut Self {
+    self.invoice.is_flexible(is_flexible);
+    self
+  }
+
+  fn check_currency(&self, amount: &Amount) -> RTDResult<()> {
+    if amount.currency != self.currency {
+      return Err(RTDError::custom(format!("amount {} isn't in invoice currency {}", amount, self.currency)));
+    }
+    Ok(())
+  }
+
+  fn validate(&self) -> RTDResult<()> {
+    let (invoice, content) = (self.invoice.build(), self.content.build());
+    let title = content.title().chars().count();
+    if title == 0 || title > 32 { return Err(RTDError::custom("invoice title must be 1-32 characters".to_string())) }
+    let description = content.description().chars().count();
+    if description == 0 || description > 255 {
+      return Err(RTDError::custom("invoice description must be 1-255 characters".to_string()));
+    }
+    let payload = decode_base64(content.payload()).map(|payload| payload.len()).unwrap_or(0);
+    if payload == 0 || payload > MAX_PAYLOAD_LENGTH {
+      return Err(RTDError::custom(format!("invoice payload must be 1-{} bytes", MAX_PAYLOAD_LENGTH)));
+    }
+
+    if self.prices.is_empty() { return Err(RTDError::custom("invoice has no prices".to_string())) }
+    let mut sum = Amount::new(0, self.currency);
+    for (_, amount) in &self.prices {
+      self.check_currency(amount)?;
+      sum = sum.checked_add(*amount)?;
+    }
+    if sum.value <= 0 { return Err(RTDError::custom(format!("invoice total {} must be positive", sum))) }
+
+    let max_tip = self.max_tip.unwrap_or(Amount::new(0, self.currency));
+    self.check_currency(&max_tip)?;
+    if max_tip.value < 0 { return Err(RTDError::custom("max tip amount can't be negative".to_string())) }
+    if self.suggested_tips.len() > MAX_SUGGESTED_TIPS {
+      return Err(RTDError::custom(format!("at most {} suggested tips allowed", MAX_SUGGESTED_TIPS)));
+    }
+    let mut previous = 0;
+    for tip in &self.suggested_tips {
+      self.check_currency(tip)?;
+      if tip.value <= previous {
+        return Err(RTDError::custom("suggested tips must be positive and strictly increasing".to_string()));
+      }
+      if tip.value > max_tip.value {
+        return Err(RTDError::custom(format!("suggested tip {} exceeds max tip {}", tip, max_tip)));
+      }
+      previous = tip.value;
+    }
+    if invoice.is_flexible() && !invoice.need_shipping_address() {
+      return Err(RTDError::custom("flexible invoice must need the shipping address".to_string()));
+    }
+    Ok(())
+  }
+
+  pub fn build(&mut self) -> RTDResult<InputMessageContent> {
+    self.validate()?;
+    let invoice = self.invoice
+      .currency(self.currency.code())
+      .price_parts(self.prices.iter().map(|(label, amount)| price_part(label, *amount)).collect())
+      .max_tip_amount(self.max_tip.map(|tip| tip.value).unwrap_or(0))
+      .suggested_tip_amounts(self.suggested_tips.iter().map(|tip| tip.value).collect())
+      .build();
+    Ok(InputMessageContent::input_message_invoice(self.content.invoice(invoice).build()))
+  }
+}
+
+/// A shipping query with its decoded invoice payload
+#[derive(Debug, Clone)]
+pub struct ShippingQuery {
+  id: i64,
+  sender_user_id: i64,
+  payload: Vec<u8>,
+  shipping_address: Address,
+}
+
+impl ShippingQuery {
+  pub fn new<U: AsRef<UpdateNewShippingQuery>>(update: U) -> RTDResult<ShippingQuery> {
+    let update = update.as_ref();
+    Ok(ShippingQuery {
+      id: update.id(),
+      sender_user_id: update.sender_user_id(),
+      payload: decode_payload(update.invoice_payload())?,
+      shipping_address: update.shipping_address().clone(),
+    })
+  }
+
+  pub fn id(&self) -> i64 { self.id }
+
+  pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
+
+  pub fn payload(&self) -> &Vec<u8> { &self.payload }
+
+  pub fn shipping_address(&self) -> &Address { &self.shipping_address }
+}
+
+/// A pre-checkout query with its decoded invoice payload and total amount
+#[derive(Debug, Clone)]
+pub struct PreCheckoutQuery {
+  id: i64,
+  sender_user_id: i64,
+  total: Amount,
+  payload: Vec<u8>,
+  shipping_option_id: Option<String>,
+  order_info: Option<OrderInfo>,
+}
+
+impl PreCheckoutQuery {
+  pub fn new<U: AsRef<UpdateNewPreCheckoutQuery>>(update: U) -> RTDResult<PreCheckoutQuery> {
+    let update = update.as_ref();
+    let shipping_option_id = update.shipping_option_id();
+    Ok(PreCheckoutQuery {
+      id: update.id(),
+      sender_user_id: update.sender_user_id(),
+      total: Amount::new(update.total_amount(), Currency::new(update.currency())?),
+      payload: decode_payload(update.invoice_payload())?,
+      shipping_option_id: if shipping_option_id.is_empty() { None } else { Some(shipping_option_id.clone()) },
+      order_info: update.order_info().clone(),
+    })
+  }
+
+  pub fn id(&self) -> i64 { self.id }
+
+  pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
+
+  /// Total price including tip and shipping
+  pub fn total(&self) -> Amount { self.total }
+
+  pub fn payload(&self) -> &Vec<u8> { &self.payload }
+
+  pub fn shipping_option_id(&self) -> Option<&String> { self.shipping_option_id.as_ref() }
+
+  pub fn order_info(&self) -> Option<&OrderInfo> { self.order_info.as_ref() }
+}
+
+fn decode_payload(payload: &str) -> RTDResult<Vec<u8>> {
+  decode_base64(payload).ok_or_else(|| RTDError::custom("invoice payload isn't valid base64".to_string()))
+}
+
+/// Decision of a payment handler
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict<T> {
+  Accept(T),
+  /// Refuse with an error message shown to the user
+  Reject(String),
+}
+
+/// Provides shipping options for a shipping address
+pub trait ShippingQueryHandler {
+  /// Decide before `deadline`, a later decision is replaced with the timeout error
+  fn shipping_options(&mut self, query: &ShippingQuery, deadline: Instant) -> RTDResult<Verdict<Vec<ShippingOption>>>;
+}
+
+impl<F: FnMut(&ShippingQuery, Instant) -> RTDResult<Verdict<Vec<ShippingOption>>>> ShippingQueryHandler for F {
+  fn shipping_options(&mut self, query: &ShippingQuery, deadline: Instant) -> RTDResult<Verdict<Vec<ShippingOption>>> {
+    self(query, deadline)
+  }
+}
+
+/// Confirms an order is still available before the payment
+pub trait PreCheckoutQueryHandler {
+  /// Decide before `deadline`, a later decision is rejected with the timeout error, so the order
+  /// must not be committed after it
+  fn pre_checkout(&mut self, query: &PreCheckoutQuery, deadline: Instant) -> RTDResult<Verdict<()>>;
+}
+
+impl<F: FnMut(&PreCheckoutQuery, Instant) -> RTDResult<Verdict<()>>> PreCheckoutQueryHandler for F {
+  fn pre_checkout(&mut self, query: &PreCheckoutQuery, deadline: Instant) -> RTDResult<Verdict<()>> { self(query, deadline) }
+}
+
+type ErrorMapper = Box<dyn Fn(&RTDError) -> String>;
+
+/// Answers shipping and pre-checkout queries with handlers
+///
+/// Handlers run on the calling thread and get the deadline of their decision. A decision made
+/// after the deadline is replaced with the timeout error, because Telegram has given up on the
+/// query by then. Queries without a handler are rejected.
+pub struct PaymentDispatcher {
+  shipping: Option<Box<dyn ShippingQueryHandler>>,
+  pre_checkout: Option<Box<dyn PreCheckoutQueryHandler>>,
+  timeout: Duration,
+  timeout_text: String,
+  error_mapper: ErrorMapper,
+}
+
+impl Default for PaymentDispatcher {
+  fn default() -> Self { PaymentDispatcher::new() }
+}
+
+impl PaymentDispatcher {
+  pub fn new() -> Self {
+    Self {
+      shipping: None,
+      pre_checkout: None,
+      timeout: Duration::from_secs(8),
+      timeout_text: "The shop didn't respond in time, please try again".to_string(),
+      error_mapper: Box::new(|_| "Payment can't be processed right now, please try again later".to_string()),
+    }
+  }
+
+  pub fn shipping<H: ShippingQueryHandler + 'static>(&mut self, handler: H) -> &mut Self {
+    self.shipping = Some(Box::new(handler));
+    self
+  }
+
+  pub fn pre_checkout<H: PreCheckoutQueryHandler + 'static>(&mut self, handler: H) -> &mut Self {
+    self.pre_checkout = Some(Box::new(handler));
+    self
+  }
+
+  /// Time given to handlers, default is 8 seconds to answer within Telegram's limit
+  pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+    self.timeout = timeout.min(QUERY_ANSWER_LIMIT);
+    self
+  }
+
+  /// Error shown when a handler doesn't decide in time
+  pub fn timeout_text<S: AsRef<str>>(&mut self, timeout_text: S) -> &mut Self {
+    self.timeout_text = timeout_text.as_ref().to_string();
+    self
+  }
+
+  /// Map handler errors to the error shown to the user
+  pub fn on_error<F: Fn(&RTDError) -> String + 'static>(&mut self, mapper: F) -> &mut Self {
+    self.error_mapper = Box::new(mapper);
+    self
+  }
+
+  fn verdict<T>(&self, result: RTDResult<Verdict<T>>, deadline: Instant) -> Result<T, String> {
+    if Instant::now() > deadline {
+      return Err(self.timeout_text.clone());
+    }
+    match result {
+      Ok(Verdict::Accept(value)) => Ok(value),
+      Ok(Verdict::Reject(error)) => Err(error),
+      Err(e) => Err((self.error_mapper)(&e)),
+    }
+  }
+
+  /// Handle `updateNewShippingQuery`, returns the answer to send
+  pub fn on_shipping_query<U: AsRef<UpdateNewShippingQuery>>(&mut self, update: U) -> AnswerShippingQuery {
+    let update = update.as_ref();
+    let deadline = Instant::now() + self.timeout;
+    let result = match (self.shipping.as_mut(), ShippingQuery::new(update)) {
+      (Some(handler), Ok(query)) => {
+        let result = handler.shipping_options(&query, deadline);
+        self.verdict(result, deadline)
+      }
+      (None, _) => Err("Shipping isn't available".to_string()),
+      (_, Err(e)) => Err((self.error_mapper)(&e)),
+    };
+    let result = result.and_then(|options| {
+      if options.is_empty() { Err("Shipping to this address isn't available".to_string()) } else { Ok(options) }
+    });
+    match result {
+      Ok(options) => AnswerShippingQuery::builder().shipping_query_id(update.id()).shipping_options(options).build(),
+      Err(error) => AnswerShippingQuery::builder().shipping_query_id(update.id()).error_message(error).build(),
+    }
+  }
+
+  /// Handle `updateNewPreCheckoutQuery`, returns the answer to send
+  pub fn on_pre_checkout_query<U: AsRef<UpdateNewPreCheckoutQuery>>(&mut self, update: U) -> AnswerPreCheckoutQuery {
+    let update = update.as_ref();
+    let deadline = Instant::now() + self.timeout;
+    let result = match (self.pre_checkout.as_mut(), PreCheckoutQuery::new(update)) {
+      (Some(handler), Ok(query)) => {
+        let result = handler.pre_checkout(&query, deadline);
+        self.verdict(result, deadline)
+      }
+      (None, _) => Err("Payments aren't available".to_string()),
+      (_, Err(e)) => Err((self.error_mapper)(&e)),
+    };
+    AnswerPreCheckoutQuery::builder()
+      .pre_checkout_query_id(update.id())
+      .error_message(result.err().unwrap_or_default())
+      .build()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::payments::*;
+
+  #[test]
+  fn test_amount() {
+    let usd = Currency::new("usd").unwrap();
+    let jpy = Currency::new("JPY").unwrap();
+    let kwd = Currency::new("KWD").unwrap();
+    assert_eq!(Amount::parse("10.5", usd).unwrap().value(), 1050);
+    assert_eq!(Amount::parse("-0.05", usd).unwrap().to_decimal_string(), "-0.05");
+    assert_eq!(Amount::parse("1500", jpy).unwrap().value(), 1500);
+    assert!(Amount::parse("15.5", jpy).is_err());
+    assert!(Amount::parse("1.234", usd).is_err());
+    assert!(Amount::parse(".5", usd).is_err());
+    assert_eq!(Amount::new(12345, kwd).to_string(), "12.345 KWD");
+    assert_eq!(Amount::new(7, usd).to_string(), "0.07 USD");
+    assert!(Amount::new(1, usd).checked_add(Amount::new(1, jpy)).is_err());
+    assert!(Currency::new("US").is_err());
+  }
+
+  #[test]
+  fn test_invoice() {
+    let usd = Currency::new("USD").unwrap();
+    let usd = |value| Amount::new(value, usd);
+    let mut builder = InvoiceMessage::builder(usd(0).currency());
+    builder.title("Book").description("A book").payload("order:1").price("Book", usd(1000)).max_tip(usd(500));
+
+    builder.suggested_tips(vec![usd(200), usd(100)]);
+    assert!(builder.build().is_err());
+    builder.suggested_tips(vec![usd(100), usd(600)]);
+    assert!(builder.build().is_err());
+    builder.suggested_tips(vec![usd(100), usd(200), usd(300), usd(400), usd(500)]);
+    assert!(builder.build().is_err());
+    builder.suggested_tips(vec![usd(100), usd(500)]);
+    let content = builder.build().unwrap();
+    let invoice = content.as_input_message_invoice().unwrap();
+    assert_eq!(invoice.invoice().currency(), "USD");
+    assert_eq!(invoice.invoice().suggested_tip_amounts(), &vec![100, 500]);
+    assert_eq!(decode_base64(invoice.payload()).unwrap(), b"order:1");
+
+    builder.price("Discount", usd(-1500));
+    assert!(builder.build().is_err());
+    let mut flexible = InvoiceMessage::builder(usd(0).currency());
+    flexible.title("Book").description("A book").payload("1").price("Book", usd(1000)).flexible(true);
+    assert!(flexible.build().is_err());
+  }
+
+  #[test]
+  fn test_dispatcher() {
+    let usd = Currency::new("USD").unwrap();
+    let mut dispatcher = PaymentDispatcher::new();
+    let pre_checkout = |payload: &[u8]| UpdateNewPreCheckoutQuery::builder()
+      .id(2)
+      .currency("USD")
+      .total_amount(1500)
+      .invoice_payload(encode_base64(payload))
+      .build();
+    assert_eq!(dispatcher.on_pre_checkout_query(pre_checkout(b"order:1")).error_message(), "Payments aren't available");
+
+    dispatcher.timeout(Duration::from_millis(100)).shipping(move |query: &ShippingQuery, _deadline: Instant| {
+      match query.shipping_address().country_code().as_str() {
+        "US" => Ok(Verdict::Accept(vec![shipping_option("post", "Post", vec![("Post", Amount::new(500, usd))])])),
+        "XX" => {
+          std::thread::sleep(Duration::from_millis(200));
+          Ok(Verdict::Accept(vec![]))
+        }
+        _ => Ok(Verdict::Reject("We don't ship there".to_string())),
+      }
+    }).pre_checkout(|query: &PreCheckoutQuery, deadline: Instant| {
+      assert!(deadline > Instant::now());
+      if query.payload() != b"order:1" { return Err(RTDError::custom("unknown order".to_string())) }
+      Ok(Verdict::Accept(()))
+    });
+
+    let shipping = |country: &str| UpdateNewShippingQuery::builder()
+      .id(1)
+      .invoice_payload(encode_base64(b"order:1"))
+      .shipping_address(Address::builder().country_code(country).build())
+      .build();
+    assert_eq!(dispatcher.on_shipping_query(shipping("US")).shipping_options()[0].id(), "post");
+    assert_eq!(dispatcher.on_shipping_query(shipping("FR")).error_message(), "We don't ship there");
+    assert_eq!(dispatcher.on_shipping_query(shipping("XX")).error_message(), "The shop didn't respond in time, please try again");
+
+    assert_eq!(dispatcher.on_pre_checkout_query(pre_checkout(b"order:1")).error_message(), "");
+    assert_eq!(
+      dispatcher.on_pre_checkout_query(pre_checkout(b"order:2")).error_message(),
+      "Payment can't be processed right now, please try again later"
+    );
+  }
+}
@@ -0,0 +1,430 @@
+//! Polls and quizzes: validated poll messages, answer tracking and quiz leaderboards.
+//!
+//! `PollTracker` is fed with `updatePoll` and `updatePollAnswer`. Answers of single users are only
+//! sent to bots for non-anonymous polls they created, so leaderboards need non-anonymous quizzes.
+//!
+//! ```rust
+//! use rtdlib::poll::PollMessage;
+//! # use rtdlib::types::FormattedText;
+//!
+//! let content = PollMessage::builder("Which keyword declares a trait?")
+//!   .options(vec!["struct", "trait", "impl"])
+//!   .anonymous(false)
+//!   .quiz(1, FormattedText::builder().text("`trait` declares a trait").build())
+//!   .open_period(60)
+//!   .build()
+//!   .unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Maximum length of a poll question, in characters
+pub const MAX_QUESTION_LENGTH: usize = 300;
+/// Minimum number of poll options
+pub const MIN_OPTIONS: usize = 2;
+/// Maximum number of poll options
+pub const MAX_OPTIONS: usize = 10;
+/// Maximum length of a poll option, in characters
+pub const MAX_OPTION_LENGTH: usize = 100;
+/// Maximum length of a quiz explanation, in characters
+pub const MAX_EXPLANATION_LENGTH: usize = 200;
+/// Maximum number of line feeds in a quiz explanation
+pub const MAX_EXPLANATION_LINE_FEEDS: usize = 2;
+/// Bounds of a poll open period, in seconds
+pub const OPEN_PERIOD_RANGE: (i64, i64) = (5, 600);
+
+/// Builds `InputMessagePoll` content
+pub struct PollMessage;
+
+impl PollMessage {
+  pub fn builder<S: AsRef<str>>(question: S) -> PollMessageBuilder {
+    PollMessageBuilder {
+      question: question.as_ref().to_string(),
+      options: vec![],
+      is_anonymous: true,
+      allow_multiple_answers: false,
+      quiz: None,
+      open_period: 0,
+      close_date: 0,
+      is_closed: false,
+    }
+  }
+}
+
+pub struct PollMessageBuilder {
+  question: String,
+  options: Vec<String>,
+  is_anonymous: bool,
+  allow_multiple_answers: bool,
+  quiz: Option<(i64, FormattedText)>,
+  open_period: i64,
+  close_date: i64,
+  is_closed: bool,
+}
+
+impl PollMessageBuilder {
+  pub fn option<S: AsRef<str>>(&mut self, option: S) -> &mut Self {
+    self.options.push(option.as_ref().to_string());
+    self
+  }
+
+  pub fn options<S: AsRef<str>, I: IntoIterator<Item = S>>(&mut self, options: I) -> &mut Self {
+    self.options.extend(options.into_iter().map(|option| option.as_ref().to_string()));
+    self
+  }
+
+  /// Anonymous polls don't tell who voted, default is true
+  pub fn anonymous(&mut self, is_anonymous: bool) -> &mut Self {
+    self.is_anonymous = is_anonymous;
+    self
+  }
+
+  /// Let users choose several options of a regular poll
+  pub fn multiple_answers(&mut self, allow_multiple_answers: bool) -> &mut Self {
+    self.allow_multiple_answers = allow_multiple_answers;
+    self
+  }
+
+  /// Make the poll a quiz with its correct option, 0-based, and an explanation shown after answering
+  pub fn quiz(&mut self, correct_option_id: i64, explanation: FormattedText) -> &mut Self {
+    self.quiz = Some((correct_option_id, explanation));
+    self
+  }
+
+  /// Close the poll this many seconds after it is sent
+  pub fn open_period(&mut self, open_period: i64) -> &mut Self {
+    self.open_period = open_period;
+    self
+  }
+
+  /// Close the poll at this unix time, 5-600 seconds in the future
+  pub fn close_date(&mut self, close_date: i64) -> &mut Self {
+    self.close_date = close_date;
+    self
+  }
+
+  /// Send the poll already closed
+  pub fn closed(&mut self, is_closed: bool) -> &mut Self {
+    self.is_closed = is_closed;
+    self
+  }
+
+  fn validate(&self) -> RTDResult<()> {
+    let question = self.question.chars().count();
+    if question == 0 || question > MAX_QUESTION_LENGTH {
+      return Err(RTDError::custom(format!("poll question must be 1-{} characters", MAX_QUESTION_LENGTH)));
+    }
+    if self.options.len() < MIN_OPTIONS || self.options.len() > MAX_OPTIONS {
+      return Err(RTDError::custom(format!("poll must have {}-{} options", MIN_OPTIONS, MAX_OPTIONS)));
+    }
+    for (index, option) in self.options.iter().enumerate() {
+      let length = option.chars().count();
+      if length == 0 || length > MAX_OPTION_LENGTH {
+        return Err(RTDError::custom(format!("poll option {} must be 1-{} characters", index, MAX_OPTION_LENGTH)));
+      }
+      if self.options[..index].contains(option) {
+        return Err(RTDError::custom(format!("duplicate poll option {}", option)));
+      }
+    }
+    if let Some((correct_option_id, explanation)) = &self.quiz {
+      if *correct_option_id < 0 || *correct_option_id as usize >= self.options.len() {
+        return Err(RTDError::custom(format!("quiz correct option {} doesn't exist", correct_option_id)));
+      }
+      if self.allow_multiple_answers {
+        return Err(RTDError::custom("quiz can't allow multiple answers".to_string()));
+      }
+      let text = explanation.text();
+      if text.chars().count() > MAX_EXPLANATION_LENGTH {
+        return Err(RTDError::custom(format!("quiz explanation is longer than {} characters", MAX_EXPLANATION_LENGTH)));
+      }
+      if text.matches('\n').count() > MAX_EXPLANATION_LINE_FEEDS {
+        return Err(RTDError::custom(format!("quiz explanation has more than {} line feeds", MAX_EXPLANATION_LINE_FEEDS)));
+      }
+    }
+    if self.open_period != 0 && self.close_date != 0 {
+      return Err(RTDError::custom("poll can't have both open period and close date".to_string()));
+    }
+    if self.open_period != 0 && (self.open_period < OPEN_PERIOD_RANGE.0 || self.open_period > OPEN_PERIOD_RANGE.1) {
+      return Err(RTDError::custom(format!("poll open period must be {}-{} seconds", OPEN_PERIOD_RANGE.0, OPEN_PERIOD_RANGE.1)));
+    }
+    Ok(())
+  }
+
+  pub fn build(&self) -> RTDResult<InputMessageContent> {
+    self.validate()?;
+    let type_ = match &self.quiz {
+      Some((correct_option_id, explanation)) => PollType::quiz(PollTypeQuiz::builder()
+        .correct_option_id(*correct_option_id)
+        .explanation(explanation)
+        .build()),
+      None => PollType::regular(PollTypeRegular::builder().allow_multiple_answers(self.allow_multiple_answers).build()),
+    };
+    let poll = InputMessagePoll::builder()
+      .question(&self.question)
+      .options(self.options.clone())
+      .is_anonymous(self.is_anonymous)
+      .type_(type_)
+      .open_period(self.open_period)
+      .close_date(self.close_date)
+      .is_closed(self.is_closed)
+      .build();
+    Ok(InputMessageContent::input_message_poll(poll))
+  }
+}
+
+/// A poll with the answers of users
+#[derive(Debug, Clone, Default)]
+pub struct TrackedPoll {
+  poll: Option<Poll>,
+  message: Option<(i64, i64)>,
+  answers: HashMap<i64, Vec<i64>>,
+  stop_at: Option<i64>,
+}
+
+impl TrackedPoll {
+  /// Latest poll state, `None` if only answers were received
+  pub fn poll(&self) -> Option<&Poll> { self.poll.as_ref() }
+
+  /// Chat and message identifiers of the message with the poll
+  pub fn message(&self) -> Option<(i64, i64)> { self.message }
+
+  /// Chosen options of every user who answered
+  pub fn answers(&self) -> &HashMap<i64, Vec<i64>> { &self.answers }
+
+  pub fn answer(&self, user_id: i64) -> Option<&Vec<i64>> { self.answers.get(&user_id) }
+
+  /// Correct option of a quiz, known to the quiz creator
+  pub fn correct_option_id(&self) -> Option<i64> {
+    self.poll.as_ref()?.type_().as_quiz().map(|quiz| quiz.correct_option_id()).filter(|id| *id >= 0)
+  }
+
+  pub fn is_closed(&self) -> bool { self.poll.as_ref().is_some_and(|poll| poll.is_closed()) }
+
+  /// Votes per option, counted from the answers of users if known, otherwise from the poll.
+  /// Empty until the poll is known, answers with option ids the poll doesn't have are skipped
+  pub fn tally(&self) -> Vec<i64> {
+    let poll = match &self.poll {
+      Some(poll) => poll,
+      None => return vec![],
+    };
+    if self.answers.is_empty() {
+      return poll.options().iter().map(|option| option.voter_count()).collect();
+    }
+    let mut tally = vec![0; poll.options().len()];
+    for option_id in self.answers.values().flatten() {
+      if let Some(votes) = usize::try_from(*option_id).ok().and_then(|index| tally.get_mut(index)) {
+        *votes += 1;
+      }
+    }
+    tally
+  }
+
+  /// Users who chose an option
+  pub fn voters(&self, option_id: i64) -> Vec<i64> {
+    let mut voters = self.answers.iter()
+      .filter(|(_, option_ids)| option_ids.contains(&option_id))
+      .map(|(user_id, _)| *user_id)
+      .collect::<Vec<_>>();
+    voters.sort_unstable();
+    voters
+  }
+}
+
+/// Quiz results of a user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+  rank: usize,
+  user_id: i64,
+  correct: usize,
+  answered: usize,
+}
+
+impl LeaderboardEntry {
+  /// Rank starting from 1, users with the same results share it
+  pub fn rank(&self) -> usize { self.rank }
+
+  pub fn user_id(&self) -> i64 { self.user_id }
+
+  pub fn correct(&self) -> usize { self.correct }
+
+  pub fn answered(&self) -> usize { self.answered }
+}
+
+/// Tracks polls, their answers and scheduled closing
+#[derive(Debug, Clone, Default)]
+pub struct PollTracker {
+  polls: HashMap<i64, TrackedPoll>,
+}
+
+impl PollTracker {
+  pub fn new() -> Self { Self::default() }
+
+  pub fn poll(&self, poll_id: i64) -> Option<&TrackedPoll> { self.polls.get(&poll_id) }
+
+  /// Track the poll of a sent message, returns its identifier. `stop_at` schedules closing the poll
+  pub fn track_message<M: AsRef<Message>>(&mut self, message: M, stop_at: Option<i64>) -> Option<i64> {
+    let message = message.as_ref();
+    let poll = message.content().as_message_poll()?.poll();
+    let tracked = self.polls.entry(poll.id()).or_default();
+    tracked.poll = Some(poll.clone());
+    tracked.message = Some((message.chat_id(), message.id()));
+    tracked.stop_at = stop_at;
+    Some(poll.id())
+  }
+
+  /// Handle `updatePoll`
+  pub fn on_update_poll<U: AsRef<UpdatePoll>>(&mut self, update: U) {
+    let poll = update.as_ref().poll();
+    let tracked = self.polls.entry(poll.id()).or_default();
+    if poll.is_closed() { tracked.stop_at = None; }
+    tracked.poll = Some(poll.clone());
+  }
+
+  /// Handle `updatePollAnswer`, no options means the user retracted the vote
+  pub fn on_update_poll_answer<U: AsRef<UpdatePollAnswer>>(&mut self, update: U) {
+    let update = update.as_ref();
+    let tracked = self.polls.entry(update.poll_id()).or_default();
+    if update.option_ids().is_empty() {
+      tracked.answers.remove(&update.user_id());
+    } else {
+      tracked.answers.insert(update.user_id(), update.option_ids().clone());
+    }
+  }
+
+  /// Close a tracked poll at a unix time, for periods longer than `open_period` allows
+  pub fn schedule_stop(&mut self, poll_id: i64, stop_at: i64) -> RTDResult<()> {
+    match self.polls.get_mut(&poll_id) {
+      Some(tracked) if tracked.message.is_some() => {
+        tracked.stop_at = Some(stop_at);
+        Ok(())
+      }
+      _ => Err(RTDError::custom(format!("message of poll {} isn't tracked", poll_id))),
+    }
+  }
+
+  /// `stopPoll` requests of polls due to be closed, they are unscheduled
+  pub fn due_stops(&mut self, now: i64) -> Vec<StopPoll> {
+    let mut due = self.polls.values_mut()
+      .filter(|tracked| tracked.stop_at.is_some_and(|stop_at| stop_at <= now))
+      .filter_map(|tracked| {
+        tracked.stop_at = None;
+        tracked.message
+      })
+      .collect::<Vec<_>>();
+    due.sort_unstable();
+    due.into_iter()
+      .map(|(chat_id, message_id)| StopPoll::builder().chat_id(chat_id).message_id(message_id).build())
+      .collect()
+  }
+
+  /// Leaderboard of a quiz series, by correct answers then fewest answered quizzes
+  pub fn leaderboard(&self, poll_ids: &[i64]) -> Vec<LeaderboardEntry> {
+    let mut scores: HashMap<i64, (usize, usize)> = HashMap::new();
+    for tracked in poll_ids.iter().filter_map(|poll_id| self.polls.get(poll_id)) {
+      let correct_option_id = tracked.correct_option_id();
+      for (user_id, option_ids) in &tracked.answers {
+        let score = scores.entry(*user_id).or_default();
+        score.1 += 1;
+        if correct_option_id.is_some() && option_ids.first().copied() == correct_option_id { score.0 += 1; }
+      }
+    }
+    let mut entries = scores.into_iter()
+      .map(|(user_id, (correct, answered))| LeaderboardEntry { rank: 0, user_id, correct, answered })
+      .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.correct.cmp(&a.correct).then(a.answered.cmp(&b.answered)).then(a.user_id.cmp(&b.user_id)));
+    for index in 0..entries.len() {
+      entries[index].rank = match index {
+        0 => 1,
+        _ if (entries[index].correct, entries[index].answered) == (entries[index - 1].correct, entries[index - 1].answered) => entries[index - 1].rank,
+        _ => index + 1,
+      };
+    }
+    entries
+  }
+
+  /// Forget a poll
+  pub fn remove(&mut self, poll_id: i64) -> Option<TrackedPoll> {
+    self.polls.remove(&poll_id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::poll::*;
+
+  fn explanation(text: &str) -> FormattedText {
+    FormattedText::builder().text(text).build()
+  }
+
+  fn quiz(id: i64, correct_option_id: i64) -> Message {
+    let poll = Poll::builder()
+      .id(id)
+      .options(vec![PollOption::builder().text("a").build(), PollOption::builder().text("b").build()])
+      .type_(PollType::quiz(PollTypeQuiz::builder().correct_option_id(correct_option_id).build()))
+      .build();
+    Message::builder()
+      .chat_id(-100)
+      .id(id * 10)
+      .content(MessageContent::message_poll(MessagePoll::builder().poll(poll).build()))
+      .build()
+  }
+
+  fn answer(poll_id: i64, user_id: i64, option_ids: Vec<i64>) -> UpdatePollAnswer {
+    UpdatePollAnswer::builder().poll_id(poll_id).user_id(user_id).option_ids(option_ids).build()
+  }
+
+  #[test]
+  fn test_poll_message() {
+    let content = PollMessage::builder("Best language?").options(vec!["Rust", "C"]).multiple_answers(true).build().unwrap();
+    let poll = content.as_input_message_poll().unwrap();
+    assert!(poll.is_anonymous());
+    assert!(poll.type_().as_regular().unwrap().allow_multiple_answers());
+
+    assert!(PollMessage::builder("?").option("only").build().is_err());
+    assert!(PollMessage::builder("?").options(vec!["a", "a"]).build().is_err());
+    assert!(PollMessage::builder("?").options(vec!["a", &"b".repeat(101)]).build().is_err());
+    assert!(PollMessage::builder("?").options(vec!["a", "b"]).quiz(2, explanation("")).build().is_err());
+    assert!(PollMessage::builder("?").options(vec!["a", "b"]).quiz(1, explanation("1\n2\n3\n4")).build().is_err());
+    assert!(PollMessage::builder("?").options(vec!["a", "b"]).open_period(601).build().is_err());
+    assert!(PollMessage::builder("?").options(vec!["a", "b"]).open_period(60).close_date(100).build().is_err());
+    let quiz = PollMessage::builder("?").options(vec!["a", "b"]).quiz(1, explanation("b")).build().unwrap();
+    assert_eq!(quiz.as_input_message_poll().unwrap().type_().as_quiz().unwrap().correct_option_id(), 1);
+  }
+
+  #[test]
+  fn test_tally_and_leaderboard() {
+    let mut tracker = PollTracker::new();
+    assert_eq!(tracker.track_message(quiz(1, 0), None), Some(1));
+    tracker.track_message(quiz(2, 1), Some(100));
+    tracker.on_update_poll_answer(answer(1, 10, vec![0]));
+    tracker.on_update_poll_answer(answer(1, 20, vec![1]));
+    tracker.on_update_poll_answer(answer(1, 30, vec![0]));
+    tracker.on_update_poll_answer(answer(2, 10, vec![1]));
+    tracker.on_update_poll_answer(answer(2, 20, vec![1]));
+    tracker.on_update_poll_answer(answer(2, 40, vec![1]));
+    tracker.on_update_poll_answer(answer(2, 40, vec![]));
+
+    assert_eq!(tracker.poll(1).unwrap().tally(), vec![2, 1]);
+    tracker.on_update_poll_answer(answer(1, 50, vec![-1, 1, i64::MAX]));
+    assert_eq!(tracker.poll(1).unwrap().tally(), vec![2, 2]);
+    tracker.on_update_poll_answer(answer(1, 50, vec![]));
+    tracker.on_update_poll_answer(answer(3, 10, vec![0]));
+    assert!(tracker.poll(3).unwrap().tally().is_empty());
+    assert_eq!(tracker.poll(1).unwrap().voters(0), vec![10, 30]);
+
+    let leaderboard = tracker.leaderboard(&[1, 2]).iter()
+      .map(|entry| (entry.rank(), entry.user_id(), entry.correct(), entry.answered()))
+      .collect::<Vec<_>>();
+    assert_eq!(leaderboard, vec![(1, 10, 2, 2), (2, 30, 1, 1), (3, 20, 1, 2)]);
+
+    assert!(tracker.due_stops(99).is_empty());
+    let stops = tracker.due_stops(100);
+    assert_eq!((stops[0].chat_id(), stops[0].message_id()), (-100, 20));
+    assert!(tracker.due_stops(200).is_empty());
+    assert!(tracker.schedule_stop(3, 100).is_err());
+  }
+}
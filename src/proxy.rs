@@ -0,0 +1,529 @@
+//! Proxy links, MTProto secrets and a failover proxy pool.
+//!
+//! ```rust
+//! use rtdlib::proxy::ProxyLink;
+//!
+//! let link = ProxyLink::parse("tg://socks?server=1.2.3.4&port=1080&user=alice&pass=secret").unwrap();
+//! assert_eq!(link.to_t_me_link().unwrap(), "https://t.me/socks?server=1.2.3.4&port=1080&user=alice&pass=secret");
+//! let add_proxy = link.add_proxy(true);
+//! ```
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::encoding::*;
+use crate::errors::*;
+use crate::types::*;
+
+fn percent_decode(text: &str) -> RTDResult<String> {
+  let bytes = text.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'%' => {
+        let byte = text.get(i + 1..i + 3)
+          .and_then(decode_hex)
+          .ok_or_else(|| RTDError::custom(format!("invalid percent encoding in {}", text)))?;
+        out.extend(byte);
+        i += 3;
+      }
+      b'+' => { out.push(b' '); i += 1; }
+      b => { out.push(b); i += 1; }
+    }
+  }
+  String::from_utf8(out).map_err(|_| RTDError::custom(format!("invalid utf-8 in {}", text)))
+}
+
+fn percent_encode(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for b in text.bytes() {
+    match b {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+      _ => out.push_str(&format!("%{:02X}", b)),
+    }
+  }
+  out
+}
+
+/// MTProto proxy secret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MtprotoSecret {
+  /// Plain 16 bytes secret
+  Plain(Vec<u8>),
+  /// `dd` prefixed secret, random padding is enabled
+  Secured(Vec<u8>),
+  /// `ee` prefixed secret, connection is disguised as TLS to `domain`
+  FakeTls { key: Vec<u8>, domain: String },
+}
+
+impl MtprotoSecret {
+  /// Parse secret in hexadecimal or base64 (standard or url safe) encoding
+  pub fn parse<S: AsRef<str>>(secret: S) -> RTDResult<Self> {
+    let secret = secret.as_ref().trim();
+    let is_hex = !secret.is_empty() && secret.chars().all(|c| c.is_ascii_hexdigit());
+    let bytes = if is_hex { decode_hex(secret) } else { decode_base64(secret) }
+      .ok_or_else(|| RTDError::custom(format!("invalid mtproto secret encoding: {}", secret)))?;
+    Self::from_bytes(&bytes)
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> RTDResult<Self> {
+    match bytes.first() {
+      _ if bytes.len() == 16 => Ok(MtprotoSecret::Plain(bytes.to_vec())),
+      Some(0xdd) if bytes.len() == 17 => Ok(MtprotoSecret::Secured(bytes[1..].to_vec())),
+      Some(0xee) if bytes.len() > 17 => {
+        let domain = String::from_utf8(bytes[17..].to_vec())
+          .map_err(|_| RTDError::custom("fake tls domain is not valid utf-8".to_string()))?;
+        let valid_domain = domain.split('.').all(|label| {
+          !label.is_empty() && label.len() <= 63
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !label.starts_with('-') && !label.ends_with('-')
+        });
+        if !valid_domain || domain.len() > 253 {
+          return Err(RTDError::custom(format!("invalid fake tls domain: {}", domain)));
+        }
+        Ok(MtprotoSecret::FakeTls { key: bytes[1..17].to_vec(), domain })
+      }
+      _ => Err(RTDError::custom(format!("invalid mtproto secret length: {} bytes", bytes.len()))),
+    }
+  }
+
+  /// The 16 bytes secret key
+  pub fn key(&self) -> &Vec<u8> {
+    match self {
+      MtprotoSecret::Plain(key) => key,
+      MtprotoSecret::Secured(key) => key,
+      MtprotoSecret::FakeTls { key, .. } => key,
+    }
+  }
+
+  /// Domain used for fake tls, only exists on `ee` secret
+  pub fn domain(&self) -> Option<&String> {
+    match self {
+      MtprotoSecret::FakeTls { domain, .. } => Some(domain),
+      _ => None,
+    }
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    match self {
+      MtprotoSecret::Plain(key) => key.clone(),
+      MtprotoSecret::Secured(key) => [&[0xdd][..], key].concat(),
+      MtprotoSecret::FakeTls { key, domain } => [&[0xee][..], key, domain.as_bytes()].concat(),
+    }
+  }
+
+  /// Secret in hexadecimal encoding, the format required by `ProxyTypeMtproto`
+  pub fn to_hex(&self) -> String { encode_hex(&self.to_bytes()) }
+}
+
+/// A proxy server parsed from or convertible to a proxy link
+#[derive(Debug, Clone)]
+pub struct ProxyLink {
+  server: String,
+  port: i64,
+  type_: ProxyType,
+}
+
+impl ProxyLink {
+  pub fn new<S: AsRef<str>, T: AsRef<ProxyType>>(server: S, port: i64, type_: T) -> Self {
+    Self { server: server.as_ref().to_string(), port, type_: type_.as_ref().clone() }
+  }
+
+  /// Parse `tg://proxy`, `tg://socks`, `https://t.me/proxy` or `https://t.me/socks` link
+  pub fn parse<S: AsRef<str>>(link: S) -> RTDResult<Self> {
+    let link = link.as_ref().trim();
+    let (path, query) = match link.find('?') {
+      Some(pos) => (&link[..pos], &link[pos + 1..]),
+      None => return Err(RTDError::custom(format!("proxy link has no parameters: {}", link))),
+    };
+    let path = path.trim_end_matches('/');
+    let path = ["https://", "http://"].iter()
+      .find_map(|scheme| path.strip_prefix(scheme))
+      .unwrap_or(path);
+    let kind = match path {
+      "tg://proxy" | "t.me/proxy" | "telegram.me/proxy" => "proxy",
+      "tg://socks" | "t.me/socks" | "telegram.me/socks" => "socks",
+      _ => return Err(RTDError::custom(format!("unsupported proxy link: {}", link))),
+    };
+
+    let mut params = HashMap::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+      let (key, value) = match pair.find('=') {
+        Some(pos) => (&pair[..pos], &pair[pos + 1..]),
+        None => (pair, ""),
+      };
+      params.insert(percent_decode(key)?, percent_decode(value)?);
+    }
+    let param = |name: &str| params.get(name).cloned().unwrap_or_default();
+
+    let server = param("server");
+    if server.is_empty() {
+      return Err(RTDError::custom(format!("proxy link has no server: {}", link)));
+    }
+    let port = param("port").parse::<i64>()
+      .ok()
+      .filter(|port| *port > 0 && *port <= 65535)
+      .ok_or_else(|| RTDError::custom(format!("proxy link has invalid port: {}", link)))?;
+
+    let type_ = match kind {
+      "proxy" => {
+        let secret = MtprotoSecret::parse(param("secret"))?;
+        ProxyType::mtproto(ProxyTypeMtproto::builder().secret(secret.to_hex()).build())
+      }
+      _ => ProxyType::socks5(ProxyTypeSocks5::builder()
+        .username(param("user"))
+        .password(param("pass"))
+        .build()),
+    };
+    Ok(Self { server, port, type_ })
+  }
+
+  pub fn server(&self) -> &String { &self.server }
+
+  pub fn port(&self) -> i64 { self.port }
+
+  pub fn type_(&self) -> &ProxyType { &self.type_ }
+
+  fn query(&self) -> RTDResult<(&'static str, String)> {
+    let mut query = format!("server={}&port={}", percent_encode(&self.server), self.port);
+    let kind = match &self.type_ {
+      ProxyType::Mtproto(mtproto) => {
+        let secret = MtprotoSecret::parse(mtproto.secret())?;
+        query.push_str(&format!("&secret={}", secret.to_hex()));
+        "proxy"
+      }
+      ProxyType::Socks5(socks5) => {
+        if !socks5.username().is_empty() { query.push_str(&format!("&user={}", percent_encode(socks5.username()))); }
+        if !socks5.password().is_empty() { query.push_str(&format!("&pass={}", percent_encode(socks5.password()))); }
+        "socks"
+      }
+      other => return Err(RTDError::custom(format!("{} proxy can't be shared as a link", other.td_name()))),
+    };
+    Ok((kind, query))
+  }
+
+  /// Link in `tg://proxy` or `tg://socks` format
+  pub fn to_tg_link(&self) -> RTDResult<String> {
+    let (kind, query) = self.query()?;
+    Ok(format!("tg://{}?{}", kind, query))
+  }
+
+  /// Link in `https://t.me/proxy` or `https://t.me/socks` format
+  pub fn to_t_me_link(&self) -> RTDResult<String> {
+    let (kind, query) = self.query()?;
+    Ok(format!("https://t.me/{}?{}", kind, query))
+  }
+
+  pub fn add_proxy(&self, enable: bool) -> AddProxy {
+    AddProxy::builder()
+      .server(&self.server)
+      .port(self.port)
+      .enable(enable)
+      .type_(&self.type_)
+      .build()
+  }
+
+  fn same_as(&self, proxy: &Proxy) -> bool {
+    self.server == *proxy.server() && self.port == proxy.port() && self.type_.td_name() == proxy.type_().td_name()
+  }
+}
+
+impl AsRef<ProxyLink> for ProxyLink {
+  fn as_ref(&self) -> &ProxyLink { self }
+}
+
+/// A proxy managed by `ProxyPool`
+#[derive(Debug, Clone)]
+pub struct PoolEntry {
+  link: ProxyLink,
+  proxy_id: Option<i64>,
+  latency: Option<f32>,
+  failures: u32,
+}
+
+impl PoolEntry {
+  pub fn link(&self) -> &ProxyLink { &self.link }
+
+  /// TDLib proxy identifier, `None` until the proxy is added
+  pub fn proxy_id(&self) -> Option<i64> { self.proxy_id }
+
+  /// Latency in seconds of the last successful ping
+  pub fn latency(&self) -> Option<f32> { self.latency }
+
+  /// Number of failures since the last successful ping
+  pub fn failures(&self) -> u32 { self.failures }
+}
+
+/// Pool of proxies with latency tracking and failover.
+///
+/// The pool doesn't send anything itself. It returns the requests to send, and should be fed with
+/// TDLib responses (matched by `@extra`) and `updateConnectionState` updates.
+#[derive(Debug)]
+pub struct ProxyPool {
+  entries: Vec<PoolEntry>,
+  pending_adds: HashMap<String, usize>,
+  pending_pings: HashMap<String, i64>,
+  active: Option<i64>,
+  max_failures: u32,
+  failover_after: Duration,
+  disconnected_since: Option<Instant>,
+}
+
+impl Default for ProxyPool {
+  fn default() -> Self { ProxyPool::new() }
+}
+
+impl ProxyPool {
+  pub fn new() -> Self {
+    Self {
+      entries: vec![],
+      pending_adds: HashMap::new(),
+      pending_pings: HashMap::new(),
+      active: None,
+      max_failures: 3,
+      failover_after: Duration::from_secs(15),
+      disconnected_since: None,
+    }
+  }
+
+  /// Number of failures after which a proxy isn't used anymore, default is 3
+  pub fn max_failures(&mut self, max_failures: u32) -> &mut Self {
+    self.max_failures = max_failures;
+    self
+  }
+
+  /// How long the connection may stay broken before switching to another proxy, default is 15 seconds
+  pub fn failover_after(&mut self, failover_after: Duration) -> &mut Self {
+    self.failover_after = failover_after;
+    self
+  }
+
+  pub fn entries(&self) -> &Vec<PoolEntry> { &self.entries }
+
+  /// Identifier of the currently enabled proxy
+  pub fn active(&self) -> Option<i64> { self.active }
+
+  /// Add proxy to pool, returns the `AddProxy` request to send
+  pub fn add<L: AsRef<ProxyLink>>(&mut self, link: L) -> AddProxy {
+    let request = link.as_ref().add_proxy(false);
+    self.entries.push(PoolEntry { link: link.as_ref().clone(), proxy_id: None, latency: None, failures: 0 });
+    if let Some(extra) = request.extra() {
+      self.pending_adds.insert(extra, self.entries.len() - 1);
+    }
+    request
+  }
+
+  /// Handle `proxy` returned by `AddProxy`, or any proxy returned by `GetProxies`
+  pub fn on_proxy<P: AsRef<Proxy>>(&mut self, proxy: P) {
+    let proxy = proxy.as_ref();
+    let index = proxy.extra()
+      .and_then(|extra| self.pending_adds.remove(&extra))
+      .or_else(|| self.entries.iter().position(|entry| entry.proxy_id == Some(proxy.id())))
+      .or_else(|| self.entries.iter().position(|entry| entry.proxy_id.is_none() && entry.link.same_as(proxy)));
+    match index {
+      Some(index) => self.entries[index].proxy_id = Some(proxy.id()),
+      None => self.entries.push(PoolEntry {
+        link: ProxyLink::new(proxy.server(), proxy.port(), proxy.type_()),
+        proxy_id: Some(proxy.id()),
+        latency: None,
+        failures: 0,
+      }),
+    }
+    if proxy.is_enabled() {
+      self.active = Some(proxy.id());
+    } else if self.active == Some(proxy.id()) {
+      self.active = None;
+    }
+  }
+
+  /// Handle `proxies` returned by `GetProxies`
+  pub fn on_proxies<P: AsRef<Proxies>>(&mut self, proxies: P) {
+    let proxies = proxies.as_ref().proxies();
+    proxies.iter().for_each(|proxy| self.on_proxy(proxy));
+    // the list is complete, so no enabled proxy means a direct connection
+    if !proxies.iter().any(|proxy| proxy.is_enabled()) { self.active = None; }
+  }
+
+  /// `PingProxy` requests for all added proxies
+  pub fn ping_all(&mut self) -> Vec<PingProxy> {
+    let ids = self.entries.iter().filter_map(|entry| entry.proxy_id).collect::<Vec<i64>>();
+    ids.into_iter()
+      .map(|proxy_id| {
+        let request = PingProxy::builder().proxy_id(proxy_id).build();
+        if let Some(extra) = request.extra() { self.pending_pings.insert(extra, proxy_id); }
+        request
+      })
+      .collect()
+  }
+
+  /// Handle `seconds` returned by `PingProxy`
+  pub fn on_ping<S: AsRef<Seconds>>(&mut self, seconds: S) {
+    let proxy_id = match seconds.as_ref().extra().and_then(|extra| self.pending_pings.remove(&extra)) {
+      Some(proxy_id) => proxy_id,
+      None => return,
+    };
+    if let Some(entry) = self.entry_mut(proxy_id) {
+      entry.latency = Some(seconds.as_ref().seconds());
+      entry.failures = 0;
+    }
+  }
+
+  /// Handle `error` returned by `PingProxy`
+  pub fn on_ping_error<E: AsRef<Error>>(&mut self, error: E) {
+    let proxy_id = match error.as_ref().extra().and_then(|extra| self.pending_pings.remove(&extra)) {
+      Some(proxy_id) => proxy_id,
+      None => return,
+    };
+    if let Some(entry) = self.entry_mut(proxy_id) {
+      entry.latency = None;
+      entry.failures += 1;
+    }
+  }
+
+  /// Usable proxy with the lowest latency, proxies never pinged come last
+  pub fn best(&self) -> Option<&PoolEntry> {
+    self.entries.iter()
+      .filter(|entry| entry.proxy_id.is_some() && entry.failures < self.max_failures)
+      .min_by(|a, b| match (a.latency, b.latency) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.failures.cmp(&b.failures),
+      })
+  }
+
+  /// `EnableProxy` request for the best proxy, if it isn't already enabled
+  pub fn enable_best(&mut self) -> Option<EnableProxy> {
+    let proxy_id = self.best()?.proxy_id?;
+    if self.active == Some(proxy_id) { return None }
+    self.active = Some(proxy_id);
+    self.disconnected_since = None;
+    Some(EnableProxy::builder().proxy_id(proxy_id).build())
+  }
+
+  /// Handle `updateConnectionState`, may return a failover request
+  pub fn on_connection_state<U: AsRef<UpdateConnectionState>>(&mut self, update: U, now: Instant) -> Option<EnableProxy> {
+    match update.as_ref().state() {
+      ConnectionState::Ready(_) | ConnectionState::Updating(_) => {
+        self.disconnected_since = None;
+        None
+      }
+      ConnectionState::WaitingForNetwork(_) => {
+        // no network at all, switching proxy won't help
+        self.disconnected_since = None;
+        None
+      }
+      _ => {
+        if self.disconnected_since.is_none() { self.disconnected_since = Some(now); }
+        self.check(now)
+      }
+    }
+  }
+
+  /// Switch to another proxy if the connection is broken for too long; should be called periodically
+  pub fn check(&mut self, now: Instant) -> Option<EnableProxy> {
+    let since = self.disconnected_since?;
+    if now.duration_since(since) < self.failover_after { return None }
+    let active = self.active?;
+    if let Some(entry) = self.entry_mut(active) {
+      entry.failures += 1;
+      entry.latency = None;
+    }
+    self.disconnected_since = Some(now);
+    let proxy_id = self.entries.iter()
+      .filter(|entry| entry.proxy_id != Some(active))
+      .filter(|entry| entry.proxy_id.is_some() && entry.failures < self.max_failures)
+      .min_by(|a, b| a.latency.unwrap_or(f32::MAX).partial_cmp(&b.latency.unwrap_or(f32::MAX)).unwrap_or(std::cmp::Ordering::Equal))?
+      .proxy_id?;
+    self.active = Some(proxy_id);
+    Some(EnableProxy::builder().proxy_id(proxy_id).build())
+  }
+
+  fn entry_mut(&mut self, proxy_id: i64) -> Option<&mut PoolEntry> {
+    self.entries.iter_mut().find(|entry| entry.proxy_id == Some(proxy_id))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, Instant};
+
+  use crate::proxy::*;
+
+  #[test]
+  fn test_mtproto_secret() {
+    let plain = MtprotoSecret::parse("0123456789abcdef0123456789abcdef").unwrap();
+    assert_eq!(plain.key().len(), 16);
+    let secured = MtprotoSecret::parse("dd0123456789abcdef0123456789abcdef").unwrap();
+    assert_eq!(secured, MtprotoSecret::Secured(plain.key().clone()));
+    let fake_tls = MtprotoSecret::parse("ee0123456789abcdef0123456789abcdef676f6f676c652e636f6d").unwrap();
+    assert_eq!(fake_tls.domain(), Some(&"google.com".to_string()));
+    assert_eq!(fake_tls.to_hex(), "ee0123456789abcdef0123456789abcdef676f6f676c652e636f6d");
+    // same secret in url safe base64
+    assert_eq!(MtprotoSecret::parse("7gEjRWeJq83vASNFZ4mrze9nb29nbGUuY29t").unwrap(), fake_tls);
+
+    assert!(MtprotoSecret::parse("0123").is_err());
+    assert!(MtprotoSecret::parse("ee0123456789abcdef0123456789abcdef").is_err());
+  }
+
+  #[test]
+  fn test_proxy_link() {
+    let link = ProxyLink::parse("https://t.me/proxy?server=proxy.example.com&port=443&secret=dd0123456789abcdef0123456789abcdef").unwrap();
+    assert_eq!(link.server(), "proxy.example.com");
+    assert_eq!(link.port(), 443);
+    assert_eq!(link.type_().as_mtproto().unwrap().secret(), "dd0123456789abcdef0123456789abcdef");
+    assert_eq!(link.to_tg_link().unwrap(), "tg://proxy?server=proxy.example.com&port=443&secret=dd0123456789abcdef0123456789abcdef");
+
+    let socks = ProxyLink::parse("tg://socks?server=1.2.3.4&port=1080&user=al%20ice&pass=p%26ss").unwrap();
+    let socks5 = socks.type_().as_socks5().unwrap();
+    assert_eq!(socks5.username(), "al ice");
+    assert_eq!(socks5.password(), "p&ss");
+    assert_eq!(socks.to_tg_link().unwrap(), "tg://socks?server=1.2.3.4&port=1080&user=al%20ice&pass=p%26ss");
+
+    let http = ProxyLink::new("1.2.3.4", 80, ProxyType::http(ProxyTypeHttp::builder().build()));
+    assert!(http.to_tg_link().is_err());
+    assert!(ProxyLink::parse("tg://socks?server=1.2.3.4&port=70000").is_err());
+    assert!(ProxyLink::parse("tg://resolve?domain=rust").is_err());
+  }
+
+  #[test]
+  fn test_proxy_pool_failover() {
+    let mut pool = ProxyPool::new();
+    pool.failover_after(Duration::from_secs(10));
+    for (i, server) in ["1.1.1.1", "2.2.2.2"].iter().enumerate() {
+      let add = pool.add(ProxyLink::parse(format!("tg://socks?server={}&port=1080", server)).unwrap());
+      pool.on_proxy(Proxy::builder().id(i as i64 + 1).server(add.server()).port(add.port()).type_(add.type_()).build());
+    }
+    let pings = pool.ping_all();
+    assert_eq!(pings.len(), 2);
+    let seconds = |request: &PingProxy, seconds: f32| -> Seconds {
+      serde_json::from_value(serde_json::json!({"@type": "seconds", "@extra": request.extra(), "seconds": seconds})).unwrap()
+    };
+    let slow = seconds(&pings[0], 0.3);
+    let fast = seconds(&pings[1], 0.05);
+    pool.on_ping(fast);
+    pool.on_ping(slow);
+
+    assert_eq!(pool.enable_best().unwrap().proxy_id(), 2);
+    assert!(pool.enable_best().is_none());
+
+    let now = Instant::now();
+    let connecting = UpdateConnectionState::builder()
+      .state(ConnectionState::connecting_to_proxy(ConnectionStateConnectingToProxy::builder().build()))
+      .build();
+    assert!(pool.on_connection_state(&connecting, now).is_none());
+    assert!(pool.check(now + Duration::from_secs(5)).is_none());
+    assert_eq!(pool.check(now + Duration::from_secs(11)).unwrap().proxy_id(), 1);
+    assert_eq!(pool.active(), Some(1));
+
+    // disabling another proxy keeps the active one, disabling the active one clears it
+    pool.on_proxy(Proxy::builder().id(2).server("2.2.2.2").port(1080).build());
+    assert_eq!(pool.active(), Some(1));
+    pool.on_proxy(Proxy::builder().id(1).server("1.1.1.1").port(1080).build());
+    assert_eq!(pool.active(), None);
+    pool.on_proxy(Proxy::builder().id(1).server("1.1.1.1").port(1080).is_enabled(true).build());
+    pool.on_proxies(Proxies::builder().proxies(vec![Proxy::builder().id(2).server("2.2.2.2").port(1080).build()]).build());
+    assert_eq!(pool.active(), None);
+  }
+}
@@ -0,0 +1,468 @@
+//! Scheduled messages.
+//!
+//! `MessageScheduler` turns `sendMessage` requests into scheduled ones, follows them until the
+//! server confirms them, reconciles them with `getChatScheduledMessages`, reschedules them with
+//! `editMessageSchedulingState` and notices when they are published: TDLib then removes the
+//! scheduled message with `updateDeleteMessages` from the cache and adds the sent one with
+//! `updateNewMessage`. Chats where the server can't schedule, like secret chats, are scheduled
+//! locally and returned by `due` when it is time to send.
+//!
+//! ```rust
+//! use rtdlib::scheduled::{MessageScheduler, Schedule};
+//! # use rtdlib::types::*;
+//! # let now = 1_650_000_000;
+//! let mut scheduler = MessageScheduler::new();
+//! let request = SendMessage::builder().chat_id(-1001234567890).build();
+//! let (id, request) = scheduler.schedule(request, Schedule::At(now + 3600), now).unwrap();
+//! // send `request` if the server schedules it, otherwise send `scheduler.due(now)` from time to time
+//! for request in scheduler.due(now) {
+//!   // send `request` to TDLib
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::errors::*;
+use crate::secret_chat::chat_secret_chat_id;
+use crate::types::*;
+
+/// The server schedules messages up to 367 days ahead
+pub const MAX_SCHEDULE_DAYS: i64 = 367;
+
+/// When a scheduled message is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+  /// At a unix time
+  At(i64),
+  /// When the other user comes online, private chats only
+  WhenOnline,
+}
+
+impl Schedule {
+  pub fn of(state: &MessageSchedulingState) -> Option<Schedule> {
+    match state {
+      MessageSchedulingState::SendAtDate(state) => Some(Schedule::At(state.send_date())),
+      MessageSchedulingState::SendWhenOnline(_) => Some(Schedule::WhenOnline),
+      _ => None,
+    }
+  }
+
+  pub fn state(&self) -> MessageSchedulingState {
+    match self {
+      Schedule::At(date) => MessageSchedulingState::send_at_date(MessageSchedulingStateSendAtDate::builder().send_date(*date).build()),
+      Schedule::WhenOnline => MessageSchedulingState::send_when_online(MessageSchedulingStateSendWhenOnline::builder().build()),
+    }
+  }
+
+  fn check(&self, now: i64) -> RTDResult<()> {
+    match self {
+      Schedule::At(date) if *date <= now => Err(RTDError::custom(format!("schedule date {} isn't in the future", date))),
+      Schedule::At(date) if *date > now + MAX_SCHEDULE_DAYS * 86400 => {
+        Err(RTDError::custom(format!("schedule date {} is more than {} days ahead", date, MAX_SCHEDULE_DAYS)))
+      }
+      _ => Ok(()),
+    }
+  }
+}
+
+/// Where a scheduled message is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduledStatus {
+  /// The scheduled message is being sent to the server
+  Sending,
+  /// The server will publish the message
+  Scheduled,
+  /// The message is kept by the scheduler until `due`
+  Local,
+  /// The message was published, `message_id` is known once its `updateNewMessage` arrived
+  Published { date: i64, message_id: Option<i64> },
+  /// The scheduled message was deleted before it was published
+  Deleted,
+  /// The server didn't accept the scheduled message
+  Failed { code: i64, message: String },
+  /// The message disappeared from `getChatScheduledMessages`, it was published or deleted meanwhile
+  Gone,
+}
+
+/// A message scheduled with `MessageScheduler`, or found by `getChatScheduledMessages`
+#[derive(Debug, Clone)]
+pub struct ScheduledMessage {
+  id: u64,
+  chat_id: i64,
+  message_id: Option<i64>,
+  schedule: Schedule,
+  status: ScheduledStatus,
+  /// Request without scheduling state of a local message
+  request: Option<SendMessage>,
+}
+
+impl ScheduledMessage {
+  /// Identifier given by `MessageScheduler::schedule`
+  pub fn id(&self) -> u64 { self.id }
+
+  pub fn chat_id(&self) -> i64 { self.chat_id }
+
+  /// Identifier of the scheduled message on the server
+  pub fn message_id(&self) -> Option<i64> { self.message_id }
+
+  pub fn schedule(&self) -> Schedule { self.schedule }
+
+  pub fn status(&self) -> &ScheduledStatus { &self.status }
+
+  fn is_open(&self) -> bool {
+    matches!(self.status, ScheduledStatus::Sending | ScheduledStatus::Scheduled | ScheduledStatus::Local)
+  }
+}
+
+/// A status change of a scheduled message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledEvent {
+  id: u64,
+  status: ScheduledStatus,
+}
+
+impl ScheduledEvent {
+  pub fn id(&self) -> u64 { self.id }
+
+  pub fn status(&self) -> &ScheduledStatus { &self.status }
+}
+
+/// Schedules messages on the server, or locally where the server can't
+#[derive(Debug, Clone, Default)]
+pub struct MessageScheduler {
+  messages: BTreeMap<u64, ScheduledMessage>,
+  next_id: u64,
+  local_chats: HashSet<i64>,
+  /// Entries waiting for the temporary message returned by `sendMessage`, by `@extra`
+  requests: HashMap<String, u64>,
+  /// Entries waiting for `updateMessageSendSucceeded`, by chat and temporary message identifier
+  temporary: HashMap<(i64, i64), u64>,
+}
+
+impl MessageScheduler {
+  pub fn new() -> Self { Self::default() }
+
+  /// Schedule messages of a chat locally, secret chats always are
+  pub fn local_chat(&mut self, chat_id: i64) -> &mut Self {
+    self.local_chats.insert(chat_id);
+    self
+  }
+
+  fn is_local(&self, chat_id: i64) -> bool {
+    self.local_chats.contains(&chat_id) || chat_secret_chat_id(chat_id).is_some()
+  }
+
+  pub fn message(&self, id: u64) -> Option<&ScheduledMessage> { self.messages.get(&id) }
+
+  /// Messages of a chat waiting to be published, in publishing order
+  pub fn pending(&self, chat_id: i64) -> Vec<&ScheduledMessage> {
+    let mut pending = self.messages.values().filter(|message| message.chat_id == chat_id && message.is_open()).collect::<Vec<_>>();
+    pending.sort_by_key(|message| (match message.schedule { Schedule::At(date) => date, Schedule::WhenOnline => i64::MAX }, message.id));
+    pending
+  }
+
+  fn add(&mut self, chat_id: i64, message_id: Option<i64>, schedule: Schedule, status: ScheduledStatus, request: Option<SendMessage>) -> u64 {
+    self.next_id += 1;
+    let id = self.next_id;
+    self.messages.insert(id, ScheduledMessage { id, chat_id, message_id, schedule, status, request });
+    id
+  }
+
+  fn set_status(&mut self, id: u64, status: ScheduledStatus) -> Option<ScheduledEvent> {
+    let message = self.messages.get_mut(&id)?;
+    if message.status == status { return None }
+    message.status = status.clone();
+    Some(ScheduledEvent { id, status })
+  }
+
+  /// Schedule a message, returning its identifier and the `sendMessage` request to send, or
+  /// `None` if it is kept locally until `due`
+  pub fn schedule(&mut self, request: SendMessage, schedule: Schedule, now: i64) -> RTDResult<(u64, Option<SendMessage>)> {
+    schedule.check(now)?;
+    let chat_id = request.chat_id();
+    if self.is_local(chat_id) {
+      if schedule == Schedule::WhenOnline {
+        return Err(RTDError::custom(format!("can't schedule a message in chat {} until the user is online", chat_id)));
+      }
+      let id = self.add(chat_id, None, schedule, ScheduledStatus::Local, Some(request));
+      return Ok((id, None));
+    }
+
+    let options = request.options();
+    let scheduled = SendMessage::builder()
+      .chat_id(chat_id)
+      .message_thread_id(request.message_thread_id())
+      .reply_to_message_id(request.reply_to_message_id())
+      .options(MessageSendOptions::builder()
+        .disable_notification(options.disable_notification())
+        .from_background(options.from_background())
+        .protect_content(options.protect_content())
+        .scheduling_state(schedule.state())
+        .build())
+      .reply_markup(request.reply_markup())
+      .input_message_content(request.input_message_content())
+      .build();
+    let id = self.add(chat_id, None, schedule, ScheduledStatus::Sending, None);
+    if let Some(extra) = scheduled.extra() { self.requests.insert(extra, id); }
+    Ok((id, Some(scheduled)))
+  }
+
+  /// Handle the temporary `message` returned by `sendMessage`
+  pub fn on_message<M: AsRef<Message>>(&mut self, message: M) {
+    let message = message.as_ref();
+    if let Some(id) = message.extra().and_then(|extra| self.requests.remove(&extra)) {
+      self.temporary.insert((message.chat_id(), message.id()), id);
+    }
+  }
+
+  /// Handle `error` returned by `sendMessage`
+  pub fn on_error<E: AsRef<Error>>(&mut self, error: E) -> Option<ScheduledEvent> {
+    let error = error.as_ref();
+    let id = self.requests.remove(&error.extra()?)?;
+    self.set_status(id, ScheduledStatus::Failed { code: error.code(), message: error.message().clone() })
+  }
+
+  /// Handle `updateMessageSendSucceeded`, the scheduled message got its server identifier
+  pub fn on_send_succeeded<U: AsRef<UpdateMessageSendSucceeded>>(&mut self, update: U) -> Option<ScheduledEvent> {
+    let update = update.as_ref();
+    let message = update.message();
+    let id = self.temporary.remove(&(message.chat_id(), update.old_message_id()))?;
+    self.messages.get_mut(&id)?.message_id = Some(message.id());
+    self.set_status(id, ScheduledStatus::Scheduled)
+  }
+
+  /// Handle `updateMessageSendFailed`
+  pub fn on_send_failed<U: AsRef<UpdateMessageSendFailed>>(&mut self, update: U) -> Option<ScheduledEvent> {
+    let update = update.as_ref();
+    let id = self.temporary.remove(&(update.message().chat_id(), update.old_message_id()))?;
+    self.set_status(id, ScheduledStatus::Failed { code: update.error_code(), message: update.error_message().clone() })
+  }
+
+  fn find(&self, chat_id: i64, message_id: i64) -> Option<u64> {
+    self.messages.values().find(|message| message.chat_id == chat_id && message.message_id == Some(message_id)).map(|message| message.id)
+  }
+
+  /// Handle `updateDeleteMessages`: a scheduled message removed from the cache was published,
+  /// otherwise it was deleted
+  pub fn on_delete_messages<U: AsRef<UpdateDeleteMessages>>(&mut self, update: U, now: i64) -> Vec<ScheduledEvent> {
+    let update = update.as_ref();
+    let status = if update.from_cache() && !update.is_permanent() {
+      ScheduledStatus::Published { date: now, message_id: None }
+    } else {
+      ScheduledStatus::Deleted
+    };
+    update.message_ids().iter()
+      .filter_map(|message_id| self.find(update.chat_id(), *message_id))
+      .filter(|id| self.messages[id].status == ScheduledStatus::Scheduled)
+      .collect::<Vec<_>>()
+      .into_iter()
+      .filter_map(|id| self.set_status(id, status.clone()))
+      .collect()
+  }
+
+  /// Handle `updateNewMessage`, linking an outgoing message to the published one of the chat
+  /// scheduled for the message date. Nothing is linked unless exactly one message matches, so
+  /// messages sent when online keep no `message_id`
+  pub fn on_new_message<U: AsRef<UpdateNewMessage>>(&mut self, update: U) -> Option<ScheduledEvent> {
+    let message = update.as_ref().message();
+    if !message.is_outgoing() || message.scheduling_state().is_some() { return None }
+    let mut candidates = self.messages.values()
+      .filter(|scheduled| scheduled.chat_id == message.chat_id() && scheduled.schedule == Schedule::At(message.date()))
+      .filter_map(|scheduled| match scheduled.status {
+        ScheduledStatus::Published { date, message_id: None } => Some((scheduled.id, date)),
+        _ => None,
+      });
+    let (id, date) = candidates.next()?;
+    if candidates.next().is_some() { return None }
+    self.set_status(id, ScheduledStatus::Published { date, message_id: Some(message.id()) })
+  }
+
+  pub fn reconcile_request(&self, chat_id: i64) -> GetChatScheduledMessages {
+    GetChatScheduledMessages::builder().chat_id(chat_id).build()
+  }
+
+  /// Handle `messages` returned by `getChatScheduledMessages`: unknown messages are added, known
+  /// ones take the server schedule, and scheduled ones missing from the list are `Gone`
+  pub fn on_scheduled_messages<M: AsRef<Messages>>(&mut self, chat_id: i64, messages: M) -> Vec<ScheduledEvent> {
+    let mut events = vec![];
+    let mut listed = HashSet::new();
+    for message in messages.as_ref().messages().iter().flatten() {
+      let schedule = match message.scheduling_state().as_ref().and_then(Schedule::of) {
+        Some(schedule) => schedule,
+        None => continue,
+      };
+      listed.insert(message.id());
+      match self.find(chat_id, message.id()) {
+        Some(id) => {
+          self.messages.get_mut(&id).expect("message exists").schedule = schedule;
+          events.extend(self.set_status(id, ScheduledStatus::Scheduled));
+        }
+        None => {
+          let id = self.add(chat_id, Some(message.id()), schedule, ScheduledStatus::Scheduled, None);
+          events.push(ScheduledEvent { id, status: ScheduledStatus::Scheduled });
+        }
+      }
+    }
+    let gone = self.messages.values()
+      .filter(|message| message.chat_id == chat_id && message.status == ScheduledStatus::Scheduled)
+      .filter(|message| message.message_id.is_some_and(|message_id| !listed.contains(&message_id)))
+      .map(|message| message.id)
+      .collect::<Vec<_>>();
+    events.extend(gone.into_iter().filter_map(|id| self.set_status(id, ScheduledStatus::Gone)));
+    events
+  }
+
+  /// Change when a message is published, returning the request for messages on the server
+  pub fn reschedule(&mut self, id: u64, schedule: Schedule, now: i64) -> RTDResult<Option<EditMessageSchedulingState>> {
+    schedule.check(now)?;
+    let message = self.messages.get_mut(&id).ok_or_else(|| RTDError::custom(format!("unknown scheduled message {}", id)))?;
+    match (&message.status, message.message_id) {
+      (ScheduledStatus::Local, _) if schedule == Schedule::WhenOnline => {
+        Err(RTDError::custom(format!("can't schedule a message in chat {} until the user is online", message.chat_id)))
+      }
+      (ScheduledStatus::Local, _) => {
+        message.schedule = schedule;
+        Ok(None)
+      }
+      (ScheduledStatus::Scheduled, Some(message_id)) => {
+        message.schedule = schedule;
+        Ok(Some(EditMessageSchedulingState::builder()
+          .chat_id(message.chat_id)
+          .message_id(message_id)
+          .scheduling_state(schedule.state())
+          .build()))
+      }
+      (status, _) => Err(RTDError::custom(format!("scheduled message {} can't be rescheduled in status {:?}", id, status))),
+    }
+  }
+
+  /// Drop a local message, or return the request deleting a message scheduled on the server
+  pub fn cancel(&mut self, id: u64) -> RTDResult<Option<DeleteMessages>> {
+    let message = self.messages.get(&id).ok_or_else(|| RTDError::custom(format!("unknown scheduled message {}", id)))?;
+    let request = match (&message.status, message.message_id) {
+      (ScheduledStatus::Local, _) => None,
+      (ScheduledStatus::Scheduled, Some(message_id)) => {
+        Some(DeleteMessages::builder().chat_id(message.chat_id).message_ids(vec![message_id]).revoke(true).build())
+      }
+      (status, _) => return Err(RTDError::custom(format!("scheduled message {} can't be cancelled in status {:?}", id, status))),
+    };
+    self.set_status(id, ScheduledStatus::Deleted);
+    Ok(request)
+  }
+
+  /// Earliest time a local message is due
+  pub fn next_due_at(&self) -> Option<i64> {
+    self.messages.values()
+      .filter(|message| message.status == ScheduledStatus::Local)
+      .filter_map(|message| match message.schedule { Schedule::At(date) => Some(date), Schedule::WhenOnline => None })
+      .min()
+  }
+
+  /// Take the requests of local messages due at `now`, in schedule order
+  pub fn due(&mut self, now: i64) -> Vec<SendMessage> {
+    let mut due = self.messages.values()
+      .filter(|message| message.status == ScheduledStatus::Local && matches!(message.schedule, Schedule::At(date) if date <= now))
+      .map(|message| (message.schedule, message.id))
+      .collect::<Vec<_>>();
+    due.sort_by_key(|(schedule, id)| (match schedule { Schedule::At(date) => *date, Schedule::WhenOnline => i64::MAX }, *id));
+    due.into_iter()
+      .filter_map(|(_, id)| {
+        let message = self.messages.get_mut(&id)?;
+        message.status = ScheduledStatus::Published { date: now, message_id: None };
+        message.request.take()
+      })
+      .collect()
+  }
+
+  /// Forget messages which were published, deleted, failed or gone
+  pub fn remove_finished(&mut self) {
+    self.messages.retain(|_, message| message.is_open());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::scheduled::*;
+  use crate::secret_chat::secret_chat_chat_id;
+
+  const NOW: i64 = 1_650_000_000;
+
+  fn request(chat_id: i64) -> SendMessage {
+    SendMessage::builder().chat_id(chat_id).build()
+  }
+
+  fn scheduled_message(chat_id: i64, id: i64, date: i64) -> Message {
+    Message::builder().chat_id(chat_id).id(id).is_outgoing(true).scheduling_state(Schedule::At(date).state()).build()
+  }
+
+  /// The temporary message TDLib returns for `request`
+  fn temporary(request: &SendMessage, id: i64) -> Message {
+    let message = Message::builder()
+      .chat_id(request.chat_id())
+      .id(id)
+      .sender_id(MessageSender::user(MessageSenderUser::builder().user_id(1).build()))
+      .content(MessageContent::message_text(MessageText::builder().text(FormattedText::builder().text("hi").build()).build()))
+      .build();
+    let mut value = serde_json::to_value(message).unwrap();
+    value["@extra"] = serde_json::json!(request.extra());
+    serde_json::from_value(value).unwrap()
+  }
+
+  #[test]
+  fn test_server_schedule() {
+    let mut scheduler = MessageScheduler::new();
+    assert!(scheduler.schedule(request(1), Schedule::At(NOW), NOW).is_err());
+    assert!(scheduler.schedule(request(1), Schedule::At(NOW + 400 * 86400), NOW).is_err());
+
+    let (id, send) = scheduler.schedule(request(1), Schedule::At(NOW + 3600), NOW).unwrap();
+    let send = send.unwrap();
+    assert_eq!(Schedule::of(send.options().scheduling_state()), Some(Schedule::At(NOW + 3600)));
+    scheduler.on_message(temporary(&send, -5));
+    let succeeded = UpdateMessageSendSucceeded::builder().message(scheduled_message(1, 700, NOW + 3600)).old_message_id(-5).build();
+    assert_eq!(scheduler.on_send_succeeded(&succeeded).map(|event| event.status), Some(ScheduledStatus::Scheduled));
+    assert_eq!(scheduler.message(id).unwrap().message_id(), Some(700));
+
+    let edit = scheduler.reschedule(id, Schedule::At(NOW + 7200), NOW).unwrap().unwrap();
+    assert_eq!((edit.chat_id(), edit.message_id()), (1, 700));
+
+    // a message scheduled from another device shows up when reconciling
+    let listed = Messages::builder().messages(vec![Some(scheduled_message(1, 700, NOW + 7200)), Some(scheduled_message(1, 701, NOW + 9000))]).build();
+    let events = scheduler.on_scheduled_messages(1, &listed);
+    assert_eq!(events.len(), 1);
+    let other = events[0].id();
+    assert_eq!(scheduler.pending(1).iter().map(|message| message.id()).collect::<Vec<_>>(), vec![id, other]);
+
+    let published = UpdateDeleteMessages::builder().chat_id(1).message_ids(vec![700]).from_cache(true).build();
+    assert_eq!(scheduler.on_delete_messages(&published, NOW + 7200)[0].status(), &ScheduledStatus::Published { date: NOW + 7200, message_id: None });
+    let sent = |date: i64| UpdateNewMessage::builder().message(Message::builder().chat_id(1).id(800).is_outgoing(true).date(date).build()).build();
+    // an unrelated outgoing message isn't linked
+    assert!(scheduler.on_new_message(sent(NOW + 7100)).is_none());
+    assert_eq!(scheduler.on_new_message(sent(NOW + 7200)).unwrap().status(), &ScheduledStatus::Published { date: NOW + 7200, message_id: Some(800) });
+
+    let deleted = UpdateDeleteMessages::builder().chat_id(1).message_ids(vec![701]).is_permanent(true).build();
+    assert_eq!(scheduler.on_delete_messages(&deleted, NOW)[0].status(), &ScheduledStatus::Deleted);
+    scheduler.remove_finished();
+    assert!(scheduler.message(id).is_none());
+  }
+
+  #[test]
+  fn test_local_schedule() {
+    let mut scheduler = MessageScheduler::new();
+    let secret = secret_chat_chat_id(7);
+    assert!(scheduler.schedule(request(secret), Schedule::WhenOnline, NOW).is_err());
+    let (late, send) = scheduler.schedule(request(secret), Schedule::At(NOW + 60), NOW).unwrap();
+    assert!(send.is_none());
+    scheduler.local_chat(5);
+    let (early, _) = scheduler.schedule(request(5), Schedule::At(NOW + 30), NOW).unwrap();
+    let (cancelled, _) = scheduler.schedule(request(5), Schedule::At(NOW + 10), NOW).unwrap();
+    assert_eq!(scheduler.cancel(cancelled).unwrap().map(|request| request.chat_id()), None);
+    assert_eq!(scheduler.next_due_at(), Some(NOW + 30));
+    assert!(scheduler.due(NOW + 29).is_empty());
+
+    assert!(scheduler.reschedule(late, Schedule::At(NOW + 20), NOW).unwrap().is_none());
+    let due = scheduler.due(NOW + 30);
+    assert_eq!(due.iter().map(|request| request.chat_id()).collect::<Vec<_>>(), vec![secret, 5]);
+    assert_eq!(scheduler.message(early).unwrap().status(), &ScheduledStatus::Published { date: NOW + 30, message_id: None });
+    assert!(scheduler.due(NOW + 100).is_empty());
+  }
+}
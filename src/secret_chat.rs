@@ -0,0 +1,462 @@
+//! Secret chat lifecycle management.
+//!
+//! `SecretChatManager` is fed with `updateSecretChat` and `updateChatMessageTtl`, tracks the secret
+//! chats of every user, warns about partners with an old layer and about key changes after the
+//! key was verified, and keeps self-destruct deadlines of messages. `KeyFingerprint` renders the
+//! `key_hash` of a chat as the identicon, hex or emoji grid to compare with the chat partner.
+//!
+//! ```rust
+//! use rtdlib::secret_chat::SecretChatManager;
+//! # use rtdlib::types::*;
+//! # let update = UpdateSecretChat::builder().secret_chat(SecretChat::builder().id(1).user_id(10).build()).build();
+//! let mut manager = SecretChatManager::new();
+//! manager.min_layer(101);
+//! for event in manager.on_update_secret_chat(&update) {
+//!   println!("{:?}", event.kind());
+//! }
+//! let create = manager.create_request(10);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::encoding::decode_base64;
+use crate::errors::*;
+use crate::types::*;
+
+/// Chat identifiers of secret chats are offset by this value from secret chat identifiers
+pub const SECRET_CHAT_ID_OFFSET: i64 = -2_000_000_000_000;
+
+/// First layer supporting nested, underline and strikethrough entities
+pub const NESTED_ENTITIES_LAYER: i64 = 101;
+
+/// Chat identifier of a secret chat
+pub fn secret_chat_chat_id(secret_chat_id: i64) -> i64 { SECRET_CHAT_ID_OFFSET + secret_chat_id }
+
+/// Secret chat identifier of a chat, if it is a secret chat
+pub fn chat_secret_chat_id(chat_id: i64) -> Option<i64> {
+  let secret_chat_id = chat_id - SECRET_CHAT_ID_OFFSET;
+  if chat_id < 0 && (1..=i32::MAX as i64).contains(&secret_chat_id) { Some(secret_chat_id) } else { None }
+}
+
+/// Identicon colors of the 2-bit `key_hash` pixel values
+pub const IDENTICON_COLORS: [u32; 4] = [0xffffff, 0xd5e6f3, 0x2d5775, 0x2f99c9];
+
+/// Emoji closest to the identicon colors
+pub const IDENTICON_EMOJI: [&str; 4] = ["⬜", "🔲", "⬛", "🟦"];
+
+/// Key fingerprint of a secret chat decoded from `key_hash`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyFingerprint {
+  bytes: Vec<u8>,
+}
+
+impl KeyFingerprint {
+  /// Decode a base64 `key_hash` of 36 bytes, or 16 bytes for chats created with old layers
+  pub fn from_key_hash<S: AsRef<str>>(key_hash: S) -> RTDResult<Self> {
+    let bytes = decode_base64(key_hash.as_ref()).ok_or_else(|| RTDError::custom("secret chat key hash isn't valid base64".to_string()))?;
+    Self::from_bytes(bytes)
+  }
+
+  pub fn from_bytes(bytes: Vec<u8>) -> RTDResult<Self> {
+    match bytes.len() {
+      16 | 36 => Ok(Self { bytes }),
+      len => Err(RTDError::custom(format!("secret chat key hash must have 16 or 36 bytes, got {}", len))),
+    }
+  }
+
+  pub fn bytes(&self) -> &[u8] { &self.bytes }
+
+  /// Width and height of the identicon, 12 for 36 byte hashes and 8 for 16 byte hashes
+  pub fn size(&self) -> usize { if self.bytes.len() == 36 { 12 } else { 8 } }
+
+  /// Identicon rows of color indices into `IDENTICON_COLORS`, two bits per pixel from the lowest
+  pub fn pixels(&self) -> Vec<Vec<u8>> {
+    let size = self.size();
+    (0..size).map(|row| (0..size).map(|column| {
+      let bit = (row * size + column) * 2;
+      (self.bytes[bit / 8] >> (bit % 8)) & 0b11
+    }).collect()).collect()
+  }
+
+  /// Identicon as an SVG image with `scale` pixels per cell
+  pub fn svg(&self, scale: usize) -> String {
+    let size = self.size() * scale;
+    let mut svg = format!(
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\">",
+      size = size,
+    );
+    for (y, row) in self.pixels().iter().enumerate() {
+      for (x, pixel) in row.iter().enumerate() {
+        svg.push_str(&format!(
+          "<rect x=\"{}\" y=\"{}\" width=\"{scale}\" height=\"{scale}\" fill=\"#{:06x}\"/>",
+          x * scale, y * scale, IDENTICON_COLORS[*pixel as usize], scale = scale,
+        ));
+      }
+    }
+    svg.push_str("</svg>");
+    svg
+  }
+
+  /// Identicon as rows of `IDENTICON_EMOJI`
+  pub fn emoji_grid(&self) -> String {
+    self.pixels().iter()
+      .map(|row| row.iter().map(|pixel| IDENTICON_EMOJI[*pixel as usize]).collect::<String>())
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+
+  /// Hex of the first 32 bytes in 4 lines of two 4-byte groups, as shown by the official apps
+  pub fn hex(&self) -> String {
+    let mut hex = String::new();
+    for (i, byte) in self.bytes.iter().take(32).enumerate() {
+      if i != 0 && i % 8 == 0 { hex.push('\n') } else if i != 0 && i % 4 == 0 { hex.push(' ') }
+      hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+  }
+}
+
+/// What happened to a secret chat
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretChatEventKind {
+  /// A secret chat appeared, `is_outbound` if it was created by the current user
+  Created { user_id: i64, is_outbound: bool },
+  /// The key exchange finished
+  Ready,
+  Closed,
+  /// The key hash changed, `was_verified` if the previous key was verified with the partner
+  KeyChanged { was_verified: bool },
+  /// The partner's layer is below `SecretChatManager::min_layer`
+  LayerTooLow { layer: i64, min_layer: i64 },
+  /// The partner's app was updated to a newer layer
+  LayerUpgraded { from: i64, to: i64 },
+  /// The self-destruct timer changed, 0 if it was disabled
+  TtlChanged(i64),
+}
+
+/// A change of a secret chat reported by `SecretChatManager`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretChatEvent {
+  secret_chat_id: i64,
+  kind: SecretChatEventKind,
+}
+
+impl SecretChatEvent {
+  pub fn secret_chat_id(&self) -> i64 { self.secret_chat_id }
+
+  pub fn chat_id(&self) -> i64 { secret_chat_chat_id(self.secret_chat_id) }
+
+  pub fn kind(&self) -> &SecretChatEventKind { &self.kind }
+}
+
+/// A secret chat known to `SecretChatManager`
+#[derive(Debug, Clone)]
+pub struct TrackedSecretChat {
+  secret_chat: SecretChat,
+  ttl: i64,
+  verified_key_hash: Option<String>,
+}
+
+impl TrackedSecretChat {
+  pub fn secret_chat(&self) -> &SecretChat { &self.secret_chat }
+
+  pub fn chat_id(&self) -> i64 { secret_chat_chat_id(self.secret_chat.id()) }
+
+  pub fn is_ready(&self) -> bool { self.secret_chat.state().is_ready() }
+
+  pub fn is_closed(&self) -> bool { self.secret_chat.state().is_closed() }
+
+  /// Self-destruct timer of new messages in seconds, 0 if disabled
+  pub fn ttl(&self) -> i64 { self.ttl }
+
+  /// True if the current key was verified with `SecretChatManager::mark_verified`
+  pub fn is_verified(&self) -> bool {
+    self.verified_key_hash.as_ref() == Some(self.secret_chat.key_hash())
+  }
+
+  pub fn supports_nested_entities(&self) -> bool { self.secret_chat.layer() >= NESTED_ENTITIES_LAYER }
+
+  pub fn fingerprint(&self) -> RTDResult<KeyFingerprint> {
+    if self.secret_chat.key_hash().is_empty() {
+      return Err(RTDError::custom(format!("secret chat {} has no key yet", self.secret_chat.id())));
+    }
+    KeyFingerprint::from_key_hash(self.secret_chat.key_hash())
+  }
+}
+
+/// Tracks secret chats, their keys, layers and self-destruct timers
+#[derive(Debug, Clone)]
+pub struct SecretChatManager {
+  chats: HashMap<i64, TrackedSecretChat>,
+  min_layer: i64,
+  /// Self-destruct deadlines by chat and message identifier
+  deadlines: HashMap<(i64, i64), i64>,
+}
+
+impl Default for SecretChatManager {
+  fn default() -> Self {
+    Self { chats: HashMap::new(), min_layer: NESTED_ENTITIES_LAYER, deadlines: HashMap::new() }
+  }
+}
+
+impl SecretChatManager {
+  pub fn new() -> Self { Self::default() }
+
+  /// Layer below which `LayerTooLow` is reported, `NESTED_ENTITIES_LAYER` by default
+  pub fn min_layer(&mut self, min_layer: i64) -> &mut Self {
+    self.min_layer = min_layer;
+    self
+  }
+
+  pub fn secret_chat(&self, secret_chat_id: i64) -> Option<&TrackedSecretChat> { self.chats.get(&secret_chat_id) }
+
+  /// Secret chats with a user, newest first
+  pub fn chats_with_user(&self, user_id: i64) -> Vec<&TrackedSecretChat> {
+    let mut chats = self.chats.values().filter(|chat| chat.secret_chat.user_id() == user_id).collect::<Vec<_>>();
+    chats.sort_by_key(|chat| std::cmp::Reverse(chat.secret_chat.id()));
+    chats
+  }
+
+  /// Newest secret chat with a user that isn't closed, ready chats first
+  pub fn active_chat_with_user(&self, user_id: i64) -> Option<&TrackedSecretChat> {
+    let chats = self.chats_with_user(user_id);
+    chats.iter().find(|chat| chat.is_ready()).or_else(|| chats.iter().find(|chat| !chat.is_closed())).copied()
+  }
+
+  /// Handle `updateSecretChat`
+  pub fn on_update_secret_chat<U: AsRef<UpdateSecretChat>>(&mut self, update: U) -> Vec<SecretChatEvent> {
+    let secret_chat = update.as_ref().secret_chat();
+    let secret_chat_id = secret_chat.id();
+    let event = |kind| SecretChatEvent { secret_chat_id, kind };
+    let mut events = vec![];
+
+    let previous = match self.chats.get_mut(&secret_chat_id) {
+      Some(tracked) => Some(std::mem::replace(&mut tracked.secret_chat, secret_chat.clone())),
+      None => {
+        self.chats.insert(secret_chat_id, TrackedSecretChat { secret_chat: secret_chat.clone(), ttl: 0, verified_key_hash: None });
+        events.push(event(SecretChatEventKind::Created { user_id: secret_chat.user_id(), is_outbound: secret_chat.is_outbound() }));
+        None
+      }
+    };
+    let tracked = &self.chats[&secret_chat_id];
+
+    let was_ready = previous.as_ref().is_some_and(|previous| previous.state().is_ready());
+    let was_closed = previous.as_ref().is_some_and(|previous| previous.state().is_closed());
+    if tracked.is_ready() && !was_ready { events.push(event(SecretChatEventKind::Ready)) }
+    if tracked.is_closed() && !was_closed { events.push(event(SecretChatEventKind::Closed)) }
+
+    if let Some(previous) = &previous {
+      if !previous.key_hash().is_empty() && previous.key_hash() != secret_chat.key_hash() {
+        let was_verified = tracked.verified_key_hash.as_ref() == Some(previous.key_hash());
+        events.push(event(SecretChatEventKind::KeyChanged { was_verified }));
+      }
+      if previous.layer() < secret_chat.layer() && previous.layer() != 0 {
+        events.push(event(SecretChatEventKind::LayerUpgraded { from: previous.layer(), to: secret_chat.layer() }));
+      }
+    }
+
+    // the layer is only known once the chat is ready, and is reported once per layer
+    let layer = secret_chat.layer();
+    let layer_changed = previous.as_ref().is_none_or(|previous| previous.layer() != layer || !was_ready);
+    if tracked.is_ready() && layer < self.min_layer && layer_changed {
+      events.push(event(SecretChatEventKind::LayerTooLow { layer, min_layer: self.min_layer }));
+    }
+    events
+  }
+
+  /// Handle `updateChatMessageTtl`, ignored for chats that aren't known secret chats
+  pub fn on_update_message_ttl<U: AsRef<UpdateChatMessageTtl>>(&mut self, update: U) -> Option<SecretChatEvent> {
+    let update = update.as_ref();
+    let secret_chat_id = chat_secret_chat_id(update.chat_id())?;
+    let tracked = self.chats.get_mut(&secret_chat_id)?;
+    if tracked.ttl == update.message_ttl() { return None }
+    tracked.ttl = update.message_ttl();
+    Some(SecretChatEvent { secret_chat_id, kind: SecretChatEventKind::TtlChanged(tracked.ttl) })
+  }
+
+  /// Remember that the current key of a chat was compared with the partner
+  pub fn mark_verified(&mut self, secret_chat_id: i64) -> RTDResult<()> {
+    let tracked = self.chats.get_mut(&secret_chat_id)
+      .ok_or_else(|| RTDError::custom(format!("unknown secret chat {}", secret_chat_id)))?;
+    if tracked.secret_chat.key_hash().is_empty() {
+      return Err(RTDError::custom(format!("secret chat {} has no key yet", secret_chat_id)));
+    }
+    tracked.verified_key_hash = Some(tracked.secret_chat.key_hash().clone());
+    Ok(())
+  }
+
+  pub fn fingerprint(&self, secret_chat_id: i64) -> RTDResult<KeyFingerprint> {
+    self.chats.get(&secret_chat_id)
+      .ok_or_else(|| RTDError::custom(format!("unknown secret chat {}", secret_chat_id)))?
+      .fingerprint()
+  }
+
+  pub fn create_request(&self, user_id: i64) -> CreateNewSecretChat {
+    CreateNewSecretChat::builder().user_id(user_id).build()
+  }
+
+  /// Request to open a known secret chat as a chat
+  pub fn open_request(&self, secret_chat_id: i64) -> RTDResult<CreateSecretChat> {
+    self.tracked(secret_chat_id)?;
+    Ok(CreateSecretChat::builder().secret_chat_id(secret_chat_id).build())
+  }
+
+  pub fn close_request(&self, secret_chat_id: i64) -> RTDResult<CloseSecretChat> {
+    if self.tracked(secret_chat_id)?.is_closed() {
+      return Err(RTDError::custom(format!("secret chat {} is already closed", secret_chat_id)));
+    }
+    Ok(CloseSecretChat::builder().secret_chat_id(secret_chat_id).build())
+  }
+
+  /// Requests closing every open secret chat with a user
+  pub fn close_all_with_user(&self, user_id: i64) -> Vec<CloseSecretChat> {
+    self.chats_with_user(user_id).iter()
+      .filter(|chat| !chat.is_closed())
+      .map(|chat| CloseSecretChat::builder().secret_chat_id(chat.secret_chat.id()).build())
+      .collect()
+  }
+
+  /// Request to change the self-destruct timer, the chat must be ready
+  pub fn set_ttl_request(&self, secret_chat_id: i64, ttl: i64) -> RTDResult<SetChatMessageTtl> {
+    let tracked = self.tracked(secret_chat_id)?;
+    if !tracked.is_ready() {
+      return Err(RTDError::custom(format!("secret chat {} isn't ready", secret_chat_id)));
+    }
+    if !(0..=i32::MAX as i64).contains(&ttl) {
+      return Err(RTDError::custom(format!("invalid self-destruct timer {}", ttl)));
+    }
+    Ok(SetChatMessageTtl::builder().chat_id(tracked.chat_id()).ttl(ttl).build())
+  }
+
+  fn tracked(&self, secret_chat_id: i64) -> RTDResult<&TrackedSecretChat> {
+    self.chats.get(&secret_chat_id).ok_or_else(|| RTDError::custom(format!("unknown secret chat {}", secret_chat_id)))
+  }
+
+  /// Track the self-destruct deadline of a message with a TTL, returning it. Call again when the
+  /// message is opened, the timer of some messages only starts then
+  pub fn track_message(&mut self, message: &Message, now: i64) -> Option<i64> {
+    if message.ttl() <= 0 { return None }
+    let deadline = now + message.ttl_expires_in().ceil() as i64;
+    self.deadlines.insert((message.chat_id(), message.id()), deadline);
+    Some(deadline)
+  }
+
+  pub fn untrack_message(&mut self, chat_id: i64, message_id: i64) {
+    self.deadlines.remove(&(chat_id, message_id));
+  }
+
+  /// Self-destruct deadline of a tracked message
+  pub fn deadline(&self, chat_id: i64, message_id: i64) -> Option<i64> {
+    self.deadlines.get(&(chat_id, message_id)).copied()
+  }
+
+  /// Take the `(chat_id, message_id)` of messages whose deadline passed, in deadline order, so
+  /// their decrypted content can be wiped
+  pub fn take_expired(&mut self, now: i64) -> Vec<(i64, i64)> {
+    let mut expired = self.deadlines.iter()
+      .filter(|(_, deadline)| **deadline <= now)
+      .map(|(id, deadline)| (*deadline, *id))
+      .collect::<Vec<_>>();
+    expired.sort();
+    for (_, id) in &expired { self.deadlines.remove(id); }
+    expired.into_iter().map(|(_, id)| id).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::secret_chat::*;
+
+  fn update(state: SecretChatState, key_hash: &str, layer: i64) -> UpdateSecretChat {
+    UpdateSecretChat::builder()
+      .secret_chat(SecretChat::builder().id(7).user_id(10).is_outbound(true).state(state).key_hash(key_hash).layer(layer).build())
+      .build()
+  }
+
+  fn pending() -> SecretChatState { SecretChatState::pending(SecretChatStatePending::builder().build()) }
+
+  fn ready() -> SecretChatState { SecretChatState::ready(SecretChatStateReady::builder().build()) }
+
+  fn closed() -> SecretChatState { SecretChatState::closed(SecretChatStateClosed::builder().build()) }
+
+  fn kinds(events: Vec<SecretChatEvent>) -> Vec<SecretChatEventKind> {
+    events.into_iter().map(|event| event.kind).collect()
+  }
+
+  #[test]
+  fn test_fingerprint() {
+    let mut bytes = vec![0u8; 36];
+    bytes[0] = 0b1110_0100;
+    bytes[35] = 0b1100_0000;
+    let fingerprint = KeyFingerprint::from_key_hash(crate::encoding::encode_base64(&bytes)).unwrap();
+    let pixels = fingerprint.pixels();
+    assert_eq!((pixels.len(), &pixels[0][..5]), (12, &[0, 1, 2, 3, 0][..]));
+    assert_eq!(pixels[11][11], 3);
+    assert!(fingerprint.emoji_grid().starts_with("⬜🔲⬛🟦⬜"));
+    assert_eq!(fingerprint.emoji_grid().lines().count(), 12);
+    assert!(fingerprint.svg(4).contains("<rect x=\"4\" y=\"0\" width=\"4\" height=\"4\" fill=\"#d5e6f3\"/>"));
+    assert_eq!(fingerprint.hex().lines().next(), Some("e4000000 00000000"));
+    assert_eq!(fingerprint.hex().lines().count(), 4);
+    assert_eq!(KeyFingerprint::from_bytes(vec![0; 16]).unwrap().size(), 8);
+    assert!(KeyFingerprint::from_bytes(vec![0; 20]).is_err());
+    assert!(KeyFingerprint::from_key_hash("not base64!").is_err());
+  }
+
+  #[test]
+  fn test_lifecycle() {
+    let mut manager = SecretChatManager::new();
+    assert_eq!(
+      kinds(manager.on_update_secret_chat(update(pending(), "", 0))),
+      vec![SecretChatEventKind::Created { user_id: 10, is_outbound: true }],
+    );
+    assert!(manager.set_ttl_request(7, 60).is_err());
+    assert!(manager.mark_verified(7).is_err());
+
+    let key = crate::encoding::encode_base64(&[1u8; 36]);
+    assert_eq!(
+      kinds(manager.on_update_secret_chat(update(ready(), &key, 73))),
+      vec![SecretChatEventKind::Ready, SecretChatEventKind::LayerTooLow { layer: 73, min_layer: 101 }],
+    );
+    assert!(manager.on_update_secret_chat(update(ready(), &key, 73)).is_empty());
+    manager.mark_verified(7).unwrap();
+    assert!(manager.secret_chat(7).unwrap().is_verified());
+    assert!(!manager.secret_chat(7).unwrap().supports_nested_entities());
+
+    let new_key = crate::encoding::encode_base64(&[2u8; 36]);
+    assert_eq!(
+      kinds(manager.on_update_secret_chat(update(ready(), &new_key, 143))),
+      vec![SecretChatEventKind::KeyChanged { was_verified: true }, SecretChatEventKind::LayerUpgraded { from: 73, to: 143 }],
+    );
+    assert!(!manager.secret_chat(7).unwrap().is_verified());
+    assert_eq!(manager.fingerprint(7).unwrap().bytes(), &[2u8; 36][..]);
+
+    let chat_id = secret_chat_chat_id(7);
+    assert_eq!(chat_secret_chat_id(chat_id), Some(7));
+    assert_eq!(chat_secret_chat_id(-100123), None);
+    assert_eq!(manager.set_ttl_request(7, 60).unwrap().chat_id(), chat_id);
+    let ttl = UpdateChatMessageTtl::builder().chat_id(chat_id).message_ttl(60).build();
+    assert_eq!(manager.on_update_message_ttl(&ttl).map(|event| event.kind), Some(SecretChatEventKind::TtlChanged(60)));
+    assert!(manager.on_update_message_ttl(&ttl).is_none());
+    assert_eq!(manager.secret_chat(7).unwrap().ttl(), 60);
+
+    assert_eq!(manager.active_chat_with_user(10).map(|chat| chat.chat_id()), Some(chat_id));
+    assert_eq!(manager.close_all_with_user(10).len(), 1);
+    assert_eq!(kinds(manager.on_update_secret_chat(update(closed(), &new_key, 143))), vec![SecretChatEventKind::Closed]);
+    assert!(manager.close_request(7).is_err());
+    assert!(manager.active_chat_with_user(10).is_none());
+  }
+
+  #[test]
+  fn test_message_deadlines() {
+    let mut manager = SecretChatManager::new();
+    let message = |id: i64, ttl: i64, expires_in: f32| Message::builder().id(id).chat_id(secret_chat_chat_id(7)).ttl(ttl).ttl_expires_in(expires_in).build();
+    assert_eq!(manager.track_message(&message(1, 0, 0.0), 1000), None);
+    assert_eq!(manager.track_message(&message(2, 30, 30.0), 1000), Some(1030));
+    assert_eq!(manager.track_message(&message(3, 10, 4.5), 1000), Some(1005));
+    assert_eq!(manager.track_message(&message(4, 10, 10.0), 1000), Some(1010));
+    manager.untrack_message(secret_chat_chat_id(7), 4);
+    assert!(manager.take_expired(1004).is_empty());
+    assert_eq!(manager.take_expired(1100), vec![(secret_chat_chat_id(7), 3), (secret_chat_chat_id(7), 2)]);
+    assert_eq!(manager.deadline(secret_chat_chat_id(7), 2), None);
+  }
+}
@@ -0,0 +1,499 @@
+//! Message sending with delivery tracking.
+//!
+//! `sendMessage` returns a temporary message which is later replaced by
+//! `updateMessageSendSucceeded` or fails with `updateMessageSendFailed`. `SendPipeline` hands out a
+//! `SendFuture` per message resolving to the message sent by the server or a `SendError`,
+//! resends failed messages with `resendMessages` when TDLib allows it, and sends the messages of a
+//! chat one after another, so they arrive in the order they were queued.
+//!
+//! ```rust
+//! use rtdlib::sending::SendPipeline;
+//! # use rtdlib::types::*;
+//! # let now = 1_650_000_000;
+//! let mut pipeline = SendPipeline::new();
+//! pipeline.max_attempts(5);
+//! let sent = pipeline.send(SendMessage::builder().chat_id(1).build());
+//! for request in pipeline.poll_requests(now) {
+//!   // send `request.to_json()` to TDLib, pass responses and updates to the pipeline
+//! }
+//! // `sent.await` in async code, or check it from time to time
+//! assert!(sent.result().is_none());
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Why a message wasn't sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+  /// TDLib rejected the request before creating the message
+  Rejected { code: i64, message: String },
+  /// Sending failed and can't or won't be retried
+  Failed {
+    code: i64,
+    message: String,
+    /// TDLib allows resending, but `SendPipeline::max_attempts` was reached
+    can_retry: bool,
+    /// The message can only be resent on behalf of another sender
+    need_another_sender: bool,
+    attempts: u32,
+  },
+  /// The message was dropped from the pipeline before it was sent
+  Cancelled,
+}
+
+impl fmt::Display for SendError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SendError::Rejected { code, message } => write!(f, "message rejected: {} {}", code, message),
+      SendError::Failed { code, message, attempts, .. } => write!(f, "message not sent after {} attempts: {} {}", attempts, code, message),
+      SendError::Cancelled => write!(f, "message sending cancelled"),
+    }
+  }
+}
+
+impl std::error::Error for SendError {}
+
+#[derive(Debug, Default)]
+struct Slot {
+  result: Option<Result<Message, SendError>>,
+  waker: Option<Waker>,
+}
+
+/// Outcome of sending a message, resolved by `SendPipeline`
+#[derive(Debug, Clone)]
+pub struct SendFuture {
+  slot: Arc<Mutex<Slot>>,
+}
+
+impl SendFuture {
+  /// The outcome if it is already known
+  pub fn result(&self) -> Option<Result<Message, SendError>> {
+    self.slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).result.clone()
+  }
+}
+
+impl Future for SendFuture {
+  type Output = Result<Message, SendError>;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    let mut slot = self.slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match &slot.result {
+      Some(result) => Poll::Ready(result.clone()),
+      None => {
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+      }
+    }
+  }
+}
+
+/// A request produced by `SendPipeline::poll_requests`
+#[derive(Debug, Clone)]
+pub enum OutgoingRequest {
+  Send(Box<SendMessage>),
+  Resend(ResendMessages),
+}
+
+impl OutgoingRequest {
+  pub fn to_json(&self) -> RTDResult<String> {
+    match self {
+      OutgoingRequest::Send(request) => request.to_json(),
+      OutgoingRequest::Resend(request) => request.to_json(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+  Queued,
+  /// `sendMessage` or `resendMessages` was returned with this `@extra`
+  Requested(String),
+  /// TDLib is sending the temporary message
+  Pending(i64),
+  /// The message failed and will be resent after `retry_at`
+  Failed { message_id: i64, retry_at: i64 },
+}
+
+#[derive(Debug)]
+struct Outgoing {
+  request: SendMessage,
+  slot: Arc<Mutex<Slot>>,
+  state: State,
+  attempts: u32,
+}
+
+impl Outgoing {
+  fn resolve(self, result: Result<Message, SendError>) {
+    let mut slot = self.slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    slot.result = Some(result);
+    if let Some(waker) = slot.waker.take() { waker.wake() }
+  }
+}
+
+/// The request with an `@extra` to match its response, builders already set one
+fn tagged<T: RObject + Clone + Serialize + DeserializeOwned>(request: &T) -> RTDResult<(T, String)> {
+  if let Some(extra) = request.extra() {
+    return Ok((request.clone(), extra));
+  }
+  let extra = Uuid::new_v4().to_string();
+  let mut value = serde_json::to_value(request)?;
+  if let Some(object) = value.as_object_mut() {
+    object.insert("@extra".to_string(), serde_json::Value::String(extra.clone()));
+  }
+  Ok((serde_json::from_value(value)?, extra))
+}
+
+/// Sends messages in order per chat and tracks them until the server confirms them
+#[derive(Debug)]
+pub struct SendPipeline {
+  chats: HashMap<i64, VecDeque<Outgoing>>,
+  max_attempts: u32,
+}
+
+impl Default for SendPipeline {
+  fn default() -> Self { Self { chats: HashMap::new(), max_attempts: 3 } }
+}
+
+impl SendPipeline {
+  pub fn new() -> Self { Self::default() }
+
+  /// How many times a message is sent before failing, default is 3
+  pub fn max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+    self.max_attempts = max_attempts.max(1);
+    self
+  }
+
+  /// Queue a message, it is sent after the messages queued before in the same chat
+  pub fn send(&mut self, request: SendMessage) -> SendFuture {
+    let slot = Arc::new(Mutex::new(Slot::default()));
+    self.chats.entry(request.chat_id()).or_default().push_back(Outgoing { request, slot: slot.clone(), state: State::Queued, attempts: 0 });
+    SendFuture { slot }
+  }
+
+  /// Number of messages not resolved yet
+  pub fn len(&self) -> usize { self.chats.values().map(|queue| queue.len()).sum() }
+
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Earliest time a failed message should be resent, to schedule the next `poll_requests`
+  pub fn next_retry_at(&self) -> Option<i64> {
+    self.chats.values()
+      .filter_map(|queue| match queue.front()?.state { State::Failed { retry_at, .. } => Some(retry_at), _ => None })
+      .min()
+  }
+
+  /// Requests to send now: the first queued message of every chat and due resends
+  pub fn poll_requests(&mut self, now: i64) -> Vec<OutgoingRequest> {
+    let mut requests = vec![];
+    let mut chat_ids = self.chats.keys().copied().collect::<Vec<_>>();
+    chat_ids.sort_unstable();
+    for chat_id in chat_ids {
+      let queue = self.chats.get_mut(&chat_id).expect("chat exists");
+      let head = match queue.front_mut() {
+        Some(head) => head,
+        None => continue,
+      };
+      let tagged = match head.state {
+        State::Queued => tagged(&head.request).map(|(request, extra)| (OutgoingRequest::Send(Box::new(request)), extra)),
+        State::Failed { message_id, retry_at } if retry_at <= now => {
+          let request = ResendMessages::builder().chat_id(chat_id).message_ids(vec![message_id]).build();
+          tagged(&request).map(|(request, extra)| (OutgoingRequest::Resend(request), extra))
+        }
+        _ => continue,
+      };
+      match tagged {
+        Ok((request, extra)) => {
+          head.state = State::Requested(extra);
+          head.attempts += 1;
+          requests.push(request);
+        }
+        Err(error) => {
+          let head = queue.pop_front().expect("head exists");
+          head.resolve(Err(SendError::Rejected { code: 400, message: error.to_string() }));
+        }
+      }
+    }
+    requests
+  }
+
+  /// Chat whose first message is in the given state
+  fn head(&self, state: impl Fn(&State) -> bool) -> Option<i64> {
+    self.chats.iter()
+      .find(|(_, queue)| queue.front().is_some_and(|head| state(&head.state)))
+      .map(|(chat_id, _)| *chat_id)
+  }
+
+  fn pop(&mut self, chat_id: i64) -> Outgoing {
+    let queue = self.chats.get_mut(&chat_id).expect("chat exists");
+    let head = queue.pop_front().expect("head exists");
+    if queue.is_empty() { self.chats.remove(&chat_id); }
+    head
+  }
+
+  fn requested(&self, extra: Option<String>) -> Option<i64> {
+    let extra = extra?;
+    self.head(|state| state == &State::Requested(extra.clone()))
+  }
+
+  /// Handle the temporary `message` returned by `sendMessage`, which can already have failed
+  pub fn on_message<M: AsRef<Message>>(&mut self, message: M, now: i64) {
+    let message = message.as_ref();
+    let chat_id = match self.requested(message.extra()) {
+      Some(chat_id) => chat_id,
+      None => return,
+    };
+    match message.sending_state() {
+      Some(MessageSendingState::Pending(_)) => self.chats.get_mut(&chat_id).expect("chat exists")[0].state = State::Pending(message.id()),
+      Some(MessageSendingState::Failed(failed)) => self.fail(chat_id, message.id(), failed, now),
+      // messages which don't need the server, e.g. in some local chats, are sent right away
+      _ => self.pop(chat_id).resolve(Ok(message.clone())),
+    }
+  }
+
+  /// Handle `messages` returned by `resendMessages`
+  pub fn on_messages<M: AsRef<Messages>>(&mut self, messages: M) {
+    let messages = messages.as_ref();
+    let chat_id = match self.requested(messages.extra()) {
+      Some(chat_id) => chat_id,
+      None => return,
+    };
+    match messages.messages().first().and_then(|message| message.as_ref()) {
+      Some(message) => self.chats.get_mut(&chat_id).expect("chat exists")[0].state = State::Pending(message.id()),
+      None => {
+        let head = self.pop(chat_id);
+        let attempts = head.attempts;
+        head.resolve(Err(SendError::Failed {
+          code: 400,
+          message: "message can't be resent".to_string(),
+          can_retry: false,
+          need_another_sender: false,
+          attempts,
+        }));
+      }
+    }
+  }
+
+  /// Handle `error` returned by `sendMessage` or `resendMessages`
+  pub fn on_error<E: AsRef<Error>>(&mut self, error: E) {
+    let error = error.as_ref();
+    let chat_id = match self.requested(error.extra()) {
+      Some(chat_id) => chat_id,
+      None => return,
+    };
+    let head = self.pop(chat_id);
+    let attempts = head.attempts;
+    let (code, message) = (error.code(), error.message().clone());
+    head.resolve(Err(if attempts > 1 {
+      SendError::Failed { code, message, can_retry: false, need_another_sender: false, attempts }
+    } else {
+      SendError::Rejected { code, message }
+    }));
+  }
+
+  /// Handle `updateMessageSendSucceeded`
+  pub fn on_send_succeeded<U: AsRef<UpdateMessageSendSucceeded>>(&mut self, update: U) {
+    let update = update.as_ref();
+    let chat_id = update.message().chat_id();
+    let is_head = self.chats.get(&chat_id)
+      .and_then(|queue| queue.front())
+      .is_some_and(|head| head.state == State::Pending(update.old_message_id()));
+    if is_head {
+      self.pop(chat_id).resolve(Ok(update.message().clone()));
+    }
+  }
+
+  /// Handle `updateMessageSendFailed`, a message which can be retried is resent after `retry_after`
+  pub fn on_send_failed<U: AsRef<UpdateMessageSendFailed>>(&mut self, update: U, now: i64) {
+    let update = update.as_ref();
+    let chat_id = update.message().chat_id();
+    let is_head = self.chats.get(&chat_id)
+      .and_then(|queue| queue.front())
+      .is_some_and(|head| head.state == State::Pending(update.old_message_id()));
+    if !is_head { return }
+    match update.message().sending_state() {
+      Some(MessageSendingState::Failed(failed)) => self.fail(chat_id, update.message().id(), failed, now),
+      _ => {
+        let head = self.pop(chat_id);
+        let attempts = head.attempts;
+        head.resolve(Err(SendError::Failed {
+          code: update.error_code(),
+          message: update.error_message().clone(),
+          can_retry: false,
+          need_another_sender: false,
+          attempts,
+        }));
+      }
+    }
+  }
+
+  fn fail(&mut self, chat_id: i64, message_id: i64, failed: &MessageSendingStateFailed, now: i64) {
+    let max_attempts = self.max_attempts;
+    let head = &mut self.chats.get_mut(&chat_id).expect("chat exists")[0];
+    if failed.can_retry() && head.attempts < max_attempts {
+      head.state = State::Failed { message_id, retry_at: now + failed.retry_after().max(0.0).ceil() as i64 };
+      return;
+    }
+    let head = self.pop(chat_id);
+    let attempts = head.attempts;
+    head.resolve(Err(SendError::Failed {
+      code: failed.error_code(),
+      message: failed.error_message().clone(),
+      can_retry: failed.can_retry(),
+      need_another_sender: failed.need_another_sender(),
+      attempts,
+    }));
+  }
+
+  /// Resolve every message of a chat not sent yet as `Cancelled`
+  pub fn cancel_chat(&mut self, chat_id: i64) {
+    for outgoing in self.chats.remove(&chat_id).unwrap_or_default() {
+      outgoing.resolve(Err(SendError::Cancelled));
+    }
+  }
+}
+
+impl Drop for SendPipeline {
+  fn drop(&mut self) {
+    // don't leave futures waiting forever
+    for (_, queue) in self.chats.drain() {
+      queue.into_iter().for_each(|outgoing| outgoing.resolve(Err(SendError::Cancelled)));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::task::Waker;
+
+  use crate::sending::*;
+
+  /// A copy of `value` as TDLib returns it for the request with `extra`
+  fn with_extra<T: Serialize + DeserializeOwned>(value: T, extra: Option<String>) -> T {
+    let mut value = serde_json::to_value(value).unwrap();
+    value["@extra"] = serde_json::json!(extra);
+    serde_json::from_value(value).unwrap()
+  }
+
+  fn message(chat_id: i64, id: i64) -> RTDMessageBuilder {
+    let mut builder = Message::builder();
+    builder
+      .id(id)
+      .chat_id(chat_id)
+      .sender_id(MessageSender::user(MessageSenderUser::builder().user_id(1).build()))
+      .content(MessageContent::message_text(MessageText::builder().text(FormattedText::builder().text("hi").build()).build()));
+    builder
+  }
+
+  fn pending(chat_id: i64, id: i64, extra: Option<String>) -> Message {
+    let state = MessageSendingState::pending(MessageSendingStatePending::builder().build());
+    with_extra(message(chat_id, id).sending_state(state).build(), extra)
+  }
+
+  fn failed(chat_id: i64, id: i64, old_id: i64, can_retry: bool) -> UpdateMessageSendFailed {
+    let state = MessageSendingStateFailed::builder()
+      .error_code(429)
+      .error_message("Too Many Requests")
+      .can_retry(can_retry)
+      .retry_after(2.5)
+      .build();
+    UpdateMessageSendFailed::builder()
+      .message(message(chat_id, id).sending_state(MessageSendingState::failed(state)).build())
+      .old_message_id(old_id)
+      .error_code(429)
+      .error_message("Too Many Requests")
+      .build()
+  }
+
+  fn succeeded(chat_id: i64, id: i64, old_id: i64) -> UpdateMessageSendSucceeded {
+    UpdateMessageSendSucceeded::builder().message(Message::builder().id(id).chat_id(chat_id).build()).old_message_id(old_id).build()
+  }
+
+  fn extra(request: &OutgoingRequest) -> Option<String> {
+    match request {
+      OutgoingRequest::Send(request) => request.extra(),
+      OutgoingRequest::Resend(request) => request.extra(),
+    }
+  }
+
+  #[test]
+  fn test_ordering_and_success() {
+    let mut pipeline = SendPipeline::new();
+    let first = pipeline.send(SendMessage::builder().chat_id(1).build());
+    let second = pipeline.send(SendMessage::builder().chat_id(1).build());
+    let other = pipeline.send(SendMessage::builder().chat_id(2).build());
+
+    let requests = pipeline.poll_requests(0);
+    assert_eq!(requests.len(), 2);
+    assert!(pipeline.poll_requests(0).is_empty());
+    pipeline.on_message(pending(1, 100, extra(&requests[0])), 0);
+    pipeline.on_error(with_extra(Error::builder().code(400).message("CHAT_WRITE_FORBIDDEN").build(), extra(&requests[1])));
+    assert_eq!(other.result().and_then(Result::err), Some(SendError::Rejected { code: 400, message: "CHAT_WRITE_FORBIDDEN".to_string() }));
+
+    // the second message waits for the first one
+    assert!(pipeline.poll_requests(0).is_empty());
+    let mut future = Box::pin(first.clone());
+    let mut context = Context::from_waker(Waker::noop());
+    assert!(future.as_mut().poll(&mut context).is_pending());
+    pipeline.on_send_succeeded(succeeded(1, 5000, 100));
+    match future.as_mut().poll(&mut context) {
+      Poll::Ready(Ok(message)) => assert_eq!(message.id(), 5000),
+      result => panic!("unexpected {:?}", result),
+    }
+
+    let requests = pipeline.poll_requests(0);
+    assert_eq!(requests.len(), 1);
+    pipeline.on_message(pending(1, 101, extra(&requests[0])), 0);
+    drop(pipeline);
+    assert_eq!(second.result().and_then(Result::err), Some(SendError::Cancelled));
+  }
+
+  #[test]
+  fn test_retries() {
+    let mut pipeline = SendPipeline::new();
+    pipeline.max_attempts(2);
+    let sent = pipeline.send(SendMessage::builder().chat_id(1).build());
+    let requests = pipeline.poll_requests(1000);
+    pipeline.on_message(pending(1, 100, extra(&requests[0])), 1000);
+    pipeline.on_send_failed(failed(1, 200, 100, true), 1000);
+    assert_eq!(pipeline.next_retry_at(), Some(1003));
+    assert!(pipeline.poll_requests(1002).is_empty());
+
+    let requests = pipeline.poll_requests(1003);
+    match &requests[0] {
+      OutgoingRequest::Resend(resend) => assert_eq!((resend.chat_id(), resend.message_ids().clone()), (1, vec![200])),
+      request => panic!("unexpected {:?}", request),
+    }
+    let messages = with_extra(Messages::builder().total_count(1).messages(vec![Some(pending(1, 101, None))]).build(), extra(&requests[0]));
+    pipeline.on_messages(messages);
+    pipeline.on_send_failed(failed(1, 201, 101, true), 1010);
+    assert!(pipeline.is_empty());
+    match sent.result() {
+      Some(Err(SendError::Failed { code: 429, can_retry: true, attempts: 2, .. })) => {}
+      result => panic!("unexpected {:?}", result),
+    }
+
+    // a temporary message can come back already failed
+    let flooded = pipeline.send(SendMessage::builder().chat_id(2).build());
+    let requests = pipeline.poll_requests(2000);
+    pipeline.on_message(with_extra(failed(2, 300, 0, true).message().clone(), extra(&requests[0])), 2000);
+    assert_eq!(pipeline.next_retry_at(), Some(2003));
+    assert!(pipeline.poll_requests(2002).is_empty());
+    assert!(flooded.result().is_none());
+
+    let cancelled = pipeline.send(SendMessage::builder().chat_id(3).build());
+    pipeline.cancel_chat(3);
+    assert_eq!(cancelled.result().and_then(Result::err), Some(SendError::Cancelled));
+  }
+}
@@ -0,0 +1,506 @@
+//! Login monitoring over active sessions and connected websites.
+//!
+//! `SessionAuditor` diffs successive `getActiveSessions` and `getConnectedWebsites` results,
+//! flags logins from new devices, IP addresses and countries, unofficial applications and
+//! sessions waiting for the 2-step verification password, and reports them as serializable
+//! `SessionAuditRecord`s. Logins matching its `TerminationPolicy` are queued for `terminateSession`
+//! and `disconnectWebsite`. The first result of each kind is the baseline of known logins.
+//!
+//! ```rust
+//! use rtdlib::sessions::{SessionAuditor, SessionFlag, TerminationPolicy};
+//! # use rtdlib::types::*;
+//! # let now = 1_650_000_000;
+//! let mut policy = TerminationPolicy::new();
+//! policy.terminate_on(SessionFlag::PasswordPending).terminate_on(SessionFlag::UnofficialApplication);
+//! let mut auditor = SessionAuditor::new(policy);
+//! let request = auditor.sessions_request();
+//! # let sessions = Sessions::builder().build();
+//! for record in auditor.on_sessions(&sessions, now) {
+//!   println!("{}", record.to_json_line().unwrap());
+//! }
+//! let terminations = auditor.take_terminations();
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Why a login looks suspicious
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionFlag {
+  /// The device model and platform weren't seen before
+  NewDevice,
+  /// The IP address wasn't seen before
+  NewIp,
+  /// The country wasn't seen before
+  NewCountry,
+  /// The country isn't one of `TerminationPolicy::allow_country`
+  DisallowedCountry,
+  /// The application isn't official and its api_id isn't allowed by the policy
+  UnofficialApplication,
+  /// The session waits for the 2-step verification password
+  PasswordPending,
+  /// The session or website wasn't used for `TerminationPolicy::max_inactive_days`
+  Inactive,
+}
+
+/// Which logins are terminated automatically
+#[derive(Debug, Clone, Default)]
+pub struct TerminationPolicy {
+  terminate_on: HashSet<SessionFlag>,
+  allowed_api_ids: HashSet<i64>,
+  allowed_countries: HashSet<String>,
+  max_inactive_days: Option<i64>,
+  disconnect_new_websites: bool,
+}
+
+impl TerminationPolicy {
+  /// A policy which only reports, terminating nothing
+  pub fn new() -> Self { Self::default() }
+
+  /// Terminate sessions and disconnect websites having the flag
+  pub fn terminate_on(&mut self, flag: SessionFlag) -> &mut Self {
+    self.terminate_on.insert(flag);
+    self
+  }
+
+  /// Don't flag an unofficial application, e.g. an own client
+  pub fn allow_api_id(&mut self, api_id: i64) -> &mut Self {
+    self.allowed_api_ids.insert(api_id);
+    self
+  }
+
+  /// Flag sessions from countries which weren't allowed, once any country is allowed
+  pub fn allow_country<S: AsRef<str>>(&mut self, country: S) -> &mut Self {
+    self.allowed_countries.insert(country.as_ref().to_uppercase());
+    self
+  }
+
+  pub fn max_inactive_days(&mut self, days: i64) -> &mut Self {
+    self.max_inactive_days = Some(days);
+    self
+  }
+
+  /// Disconnect every website connected after the baseline
+  pub fn disconnect_new_websites(&mut self, disconnect: bool) -> &mut Self {
+    self.disconnect_new_websites = disconnect;
+    self
+  }
+
+  fn terminates(&self, flags: &[SessionFlag]) -> bool {
+    flags.iter().any(|flag| self.terminate_on.contains(flag))
+  }
+
+  fn is_inactive(&self, last_active_date: i64, now: i64) -> bool {
+    self.max_inactive_days.is_some_and(|days| now - last_active_date > days * 86400)
+  }
+}
+
+/// Summary of a session in audit records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+  #[serde(with = "crate::types::int64")]
+  pub id: i64,
+  pub api_id: i64,
+  pub application: String,
+  pub device: String,
+  pub ip: String,
+  pub country: String,
+  pub region: String,
+  pub log_in_date: i64,
+  pub last_active_date: i64,
+}
+
+impl SessionInfo {
+  pub fn of(session: &Session) -> Self {
+    Self {
+      id: session.id(),
+      api_id: session.api_id(),
+      application: format!("{} {}", session.application_name(), session.application_version()).trim().to_string(),
+      device: device_key(session),
+      ip: session.ip().clone(),
+      country: session.country().clone(),
+      region: session.region().clone(),
+      log_in_date: session.log_in_date(),
+      last_active_date: session.last_active_date(),
+    }
+  }
+}
+
+/// Summary of a connected website in audit records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebsiteInfo {
+  #[serde(with = "crate::types::int64")]
+  pub id: i64,
+  pub domain_name: String,
+  pub bot_user_id: i64,
+  pub browser: String,
+  pub ip: String,
+  pub location: String,
+  pub log_in_date: i64,
+  pub last_active_date: i64,
+}
+
+impl WebsiteInfo {
+  pub fn of(website: &ConnectedWebsite) -> Self {
+    Self {
+      id: website.id(),
+      domain_name: website.domain_name().clone(),
+      bot_user_id: website.bot_user_id(),
+      browser: format!("{} on {}", website.browser(), website.platform()),
+      ip: website.ip().clone(),
+      location: website.location().clone(),
+      log_in_date: website.log_in_date(),
+      last_active_date: website.last_active_date(),
+    }
+  }
+}
+
+/// What the auditor noticed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionAuditEvent {
+  /// A session appeared since the previous result
+  SessionAdded { session: SessionInfo, flags: Vec<SessionFlag> },
+  /// A session known before got flags, e.g. in the baseline or after becoming inactive
+  SessionFlagged { session: SessionInfo, flags: Vec<SessionFlag> },
+  /// A session disappeared, it was terminated or logged out
+  SessionRemoved { session: SessionInfo },
+  /// The 2-step verification password was entered in a pending session
+  PasswordEntered { session: SessionInfo },
+  WebsiteAdded { website: WebsiteInfo, flags: Vec<SessionFlag> },
+  WebsiteFlagged { website: WebsiteInfo, flags: Vec<SessionFlag> },
+  WebsiteRemoved { website: WebsiteInfo },
+  /// `terminateSession` was queued by the policy
+  TerminationQueued { session: SessionInfo, flags: Vec<SessionFlag> },
+  /// `disconnectWebsite` was queued by the policy
+  DisconnectionQueued { website: WebsiteInfo, flags: Vec<SessionFlag> },
+}
+
+/// An audit event with the time it was noticed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionAuditRecord {
+  pub date: i64,
+  #[serde(flatten)]
+  pub event: SessionAuditEvent,
+}
+
+impl SessionAuditRecord {
+  pub fn to_json_line(&self) -> RTDResult<String> { Ok(serde_json::to_string(self)?) }
+}
+
+fn device_key(session: &Session) -> String {
+  [session.device_model(), session.platform(), session.system_version()].iter()
+    .filter(|part| !part.is_empty())
+    .map(|part| part.as_str())
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Sessions and connected websites have independent identifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AuditKey {
+  Session(i64),
+  Website(i64),
+}
+
+/// Diffs sessions and connected websites and applies a `TerminationPolicy`
+#[derive(Debug, Clone, Default)]
+pub struct SessionAuditor {
+  policy: TerminationPolicy,
+  sessions: Option<HashMap<i64, Session>>,
+  websites: Option<HashMap<i64, ConnectedWebsite>>,
+  devices: HashSet<String>,
+  ips: HashSet<String>,
+  countries: HashSet<String>,
+  /// Flags already reported for known sessions and websites
+  reported: HashMap<AuditKey, Vec<SessionFlag>>,
+  requested: HashSet<AuditKey>,
+  terminations: Vec<TerminateSession>,
+  disconnections: Vec<DisconnectWebsite>,
+}
+
+impl SessionAuditor {
+  pub fn new(policy: TerminationPolicy) -> Self {
+    Self { policy, ..Default::default() }
+  }
+
+  pub fn policy(&self) -> &TerminationPolicy { &self.policy }
+
+  pub fn sessions_request(&self) -> GetActiveSessions { GetActiveSessions::builder().build() }
+
+  pub fn websites_request(&self) -> GetConnectedWebsites { GetConnectedWebsites::builder().build() }
+
+  /// Current sessions, empty before the first result
+  pub fn sessions(&self) -> Vec<&Session> {
+    self.sessions.iter().flat_map(|sessions| sessions.values()).collect()
+  }
+
+  /// Treat a device, IP address and country as known, e.g. after a user confirmed a login
+  pub fn trust_session(&mut self, session: &Session) {
+    self.devices.insert(device_key(session));
+    self.ips.insert(session.ip().clone());
+    self.countries.insert(session.country().to_uppercase());
+  }
+
+  fn session_flags(&self, session: &Session, now: i64) -> Vec<SessionFlag> {
+    let country = session.country().to_uppercase();
+    let policy = &self.policy;
+    let checks = [
+      (SessionFlag::NewDevice, !self.devices.contains(&device_key(session))),
+      (SessionFlag::NewIp, !session.ip().is_empty() && !self.ips.contains(session.ip())),
+      (SessionFlag::NewCountry, !country.is_empty() && !self.countries.contains(&country)),
+      (SessionFlag::DisallowedCountry, !policy.allowed_countries.is_empty() && !policy.allowed_countries.contains(&country)),
+      (SessionFlag::UnofficialApplication, !session.is_official_application() && !policy.allowed_api_ids.contains(&session.api_id())),
+      (SessionFlag::PasswordPending, session.is_password_pending()),
+      (SessionFlag::Inactive, policy.is_inactive(session.last_active_date(), now)),
+    ];
+    checks.iter().filter(|(_, set)| *set).map(|(flag, _)| *flag).collect()
+  }
+
+  /// Handle a `getActiveSessions` result, returning what changed since the previous one
+  pub fn on_sessions(&mut self, sessions: &Sessions, now: i64) -> Vec<SessionAuditRecord> {
+    let record = |event| SessionAuditRecord { date: now, event };
+    let mut records = vec![];
+    let current = sessions.sessions().iter().map(|session| (session.id(), session.clone())).collect::<HashMap<_, _>>();
+    let previous = self.sessions.take();
+    let is_baseline = previous.is_none();
+    if is_baseline {
+      sessions.sessions().iter().for_each(|session| self.trust_session(session));
+    }
+    let previous = previous.unwrap_or_default();
+
+    let mut ordered = sessions.sessions().iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|session| (session.log_in_date(), session.id()));
+    for session in ordered {
+      let info = SessionInfo::of(session);
+      let flags = self.session_flags(session, now);
+      let known = previous.get(&session.id());
+      if known.is_some_and(|known| known.is_password_pending() && !session.is_password_pending()) {
+        records.push(record(SessionAuditEvent::PasswordEntered { session: info.clone() }));
+      }
+      if known.is_none() && !is_baseline {
+        records.push(record(SessionAuditEvent::SessionAdded { session: info.clone(), flags: flags.clone() }));
+        self.reported.insert(AuditKey::Session(session.id()), flags.clone());
+        // a login the policy terminates stays unknown until `trust_session` is called for it
+        if !self.policy.terminates(&flags) { self.trust_session(session); }
+      } else {
+        // report flags once, when they first show up on a known session
+        let reported = self.reported.entry(AuditKey::Session(session.id())).or_default();
+        let new_flags = flags.iter().filter(|flag| !reported.contains(flag)).copied().collect::<Vec<_>>();
+        *reported = flags.clone();
+        if !new_flags.is_empty() {
+          records.push(record(SessionAuditEvent::SessionFlagged { session: info.clone(), flags: new_flags }));
+        }
+      }
+
+      if !session.is_current() && self.policy.terminates(&flags) && self.requested.insert(AuditKey::Session(session.id())) {
+        self.terminations.push(TerminateSession::builder().session_id(session.id()).build());
+        records.push(record(SessionAuditEvent::TerminationQueued { session: info, flags }));
+      }
+    }
+
+    let mut removed = previous.values().filter(|session| !current.contains_key(&session.id())).collect::<Vec<_>>();
+    removed.sort_by_key(|session| session.id());
+    for session in removed {
+      self.reported.remove(&AuditKey::Session(session.id()));
+      self.requested.remove(&AuditKey::Session(session.id()));
+      records.push(record(SessionAuditEvent::SessionRemoved { session: SessionInfo::of(session) }));
+    }
+    self.sessions = Some(current);
+    records
+  }
+
+  /// Handle a `getConnectedWebsites` result, returning what changed since the previous one
+  pub fn on_websites(&mut self, websites: &ConnectedWebsites, now: i64) -> Vec<SessionAuditRecord> {
+    let record = |event| SessionAuditRecord { date: now, event };
+    let mut records = vec![];
+    let current = websites.websites().iter().map(|website| (website.id(), website.clone())).collect::<HashMap<_, _>>();
+    let previous = self.websites.take();
+    let is_baseline = previous.is_none();
+    if is_baseline {
+      self.ips.extend(websites.websites().iter().map(|website| website.ip().clone()));
+    }
+    let previous = previous.unwrap_or_default();
+
+    let mut ordered = websites.websites().iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|website| (website.log_in_date(), website.id()));
+    for website in ordered {
+      let info = WebsiteInfo::of(website);
+      let mut flags = vec![];
+      if !website.ip().is_empty() && !self.ips.contains(website.ip()) { flags.push(SessionFlag::NewIp) }
+      if self.policy.is_inactive(website.last_active_date(), now) { flags.push(SessionFlag::Inactive) }
+      let is_new = !is_baseline && !previous.contains_key(&website.id());
+      if is_new {
+        records.push(record(SessionAuditEvent::WebsiteAdded { website: info.clone(), flags: flags.clone() }));
+        self.reported.insert(AuditKey::Website(website.id()), flags.clone());
+        if !self.policy.terminates(&flags) { self.ips.insert(website.ip().clone()); }
+      } else {
+        let reported = self.reported.entry(AuditKey::Website(website.id())).or_default();
+        let new_flags = flags.iter().filter(|flag| !reported.contains(flag)).copied().collect::<Vec<_>>();
+        *reported = flags.clone();
+        if !new_flags.is_empty() {
+          records.push(record(SessionAuditEvent::WebsiteFlagged { website: info.clone(), flags: new_flags }));
+        }
+      }
+
+      let disconnect = (is_new && self.policy.disconnect_new_websites) || self.policy.terminates(&flags);
+      if disconnect && self.requested.insert(AuditKey::Website(website.id())) {
+        self.disconnections.push(DisconnectWebsite::builder().website_id(website.id()).build());
+        records.push(record(SessionAuditEvent::DisconnectionQueued { website: info, flags }));
+      }
+    }
+
+    let mut removed = previous.values().filter(|website| !current.contains_key(&website.id())).collect::<Vec<_>>();
+    removed.sort_by_key(|website| website.id());
+    for website in removed {
+      self.reported.remove(&AuditKey::Website(website.id()));
+      self.requested.remove(&AuditKey::Website(website.id()));
+      records.push(record(SessionAuditEvent::WebsiteRemoved { website: WebsiteInfo::of(website) }));
+    }
+    self.websites = Some(current);
+    records
+  }
+
+  /// `terminateSession` requests queued by the policy
+  pub fn take_terminations(&mut self) -> Vec<TerminateSession> { std::mem::take(&mut self.terminations) }
+
+  /// `disconnectWebsite` requests queued by the policy
+  pub fn take_disconnections(&mut self) -> Vec<DisconnectWebsite> { std::mem::take(&mut self.disconnections) }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::sessions::*;
+
+  fn builder(id: i64, device: &str, ip: &str, country: &str) -> RTDSessionBuilder {
+    let mut builder = Session::builder();
+    builder
+      .id(id)
+      .api_id(6)
+      .application_name("Telegram Android")
+      .is_official_application(true)
+      .device_model(device)
+      .platform("Android")
+      .ip(ip)
+      .country(country)
+      .log_in_date(id)
+      .last_active_date(1000);
+    builder
+  }
+
+  fn session(id: i64, device: &str, ip: &str, country: &str) -> Session { builder(id, device, ip, country).build() }
+
+  fn sessions(sessions: Vec<Session>) -> Sessions {
+    Sessions::builder().sessions(sessions).inactive_session_ttl_days(180).build()
+  }
+
+  fn events(records: Vec<SessionAuditRecord>) -> Vec<SessionAuditEvent> {
+    records.into_iter().map(|record| record.event).collect()
+  }
+
+  #[test]
+  fn test_session_diff_and_policy() {
+    let mut policy = TerminationPolicy::new();
+    policy.terminate_on(SessionFlag::UnofficialApplication).terminate_on(SessionFlag::PasswordPending);
+    let mut auditor = SessionAuditor::new(policy);
+
+    let current = builder(1, "Pixel 6", "1.1.1.1", "DE").is_current(true).build();
+    let baseline = sessions(vec![current.clone(), session(2, "iPad", "2.2.2.2", "DE")]);
+    assert!(auditor.on_sessions(&baseline, 1000).is_empty());
+    assert!(auditor.take_terminations().is_empty());
+
+    let unofficial = builder(3, "Pixel 6", "1.1.1.1", "DE").is_official_application(false).build();
+    let foreign = session(4, "ThinkPad", "9.9.9.9", "BR");
+    let records = auditor.on_sessions(&sessions(vec![current.clone(), unofficial.clone(), foreign]), 2000);
+    assert_eq!(events(records.clone()), vec![
+      SessionAuditEvent::SessionAdded { session: SessionInfo::of(&unofficial), flags: vec![SessionFlag::UnofficialApplication] },
+      SessionAuditEvent::TerminationQueued { session: SessionInfo::of(&unofficial), flags: vec![SessionFlag::UnofficialApplication] },
+      SessionAuditEvent::SessionAdded {
+        session: SessionInfo::of(&session(4, "ThinkPad", "9.9.9.9", "BR")),
+        flags: vec![SessionFlag::NewDevice, SessionFlag::NewIp, SessionFlag::NewCountry],
+      },
+      SessionAuditEvent::SessionRemoved { session: SessionInfo::of(&session(2, "iPad", "2.2.2.2", "DE")) },
+    ]);
+    assert!(records[0].to_json_line().unwrap().starts_with("{\"date\":2000,\"type\":\"session_added\",\"session\":{\"id\":3,"));
+    let terminations = auditor.take_terminations();
+    assert_eq!(terminations.iter().map(|request| request.session_id()).collect::<Vec<_>>(), vec![3]);
+
+    // a termination is queued once while the session is still listed
+    let records = auditor.on_sessions(&sessions(vec![current, unofficial]), 3000);
+    assert_eq!(records.len(), 1);
+    assert!(auditor.take_terminations().is_empty());
+  }
+
+  #[test]
+  fn test_terminated_login_stays_unknown() {
+    let mut policy = TerminationPolicy::new();
+    policy.terminate_on(SessionFlag::NewCountry);
+    let mut auditor = SessionAuditor::new(policy);
+    let current = builder(1, "Pixel 6", "1.1.1.1", "DE").is_current(true).build();
+    assert!(auditor.on_sessions(&sessions(vec![current.clone()]), 1000).is_empty());
+
+    let flags = vec![SessionFlag::NewDevice, SessionFlag::NewIp, SessionFlag::NewCountry];
+    let first = session(2, "ThinkPad", "9.9.9.9", "BR");
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![current.clone(), first.clone()]), 2000)), vec![
+      SessionAuditEvent::SessionAdded { session: SessionInfo::of(&first), flags: flags.clone() },
+      SessionAuditEvent::TerminationQueued { session: SessionInfo::of(&first), flags: flags.clone() },
+    ]);
+    assert_eq!(auditor.take_terminations().len(), 1);
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![current.clone()]), 3000)).len(), 1);
+
+    // the next login from the same place is flagged and terminated again
+    let second = session(3, "ThinkPad", "9.9.9.9", "BR");
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![current.clone(), second.clone()]), 4000)), vec![
+      SessionAuditEvent::SessionAdded { session: SessionInfo::of(&second), flags: flags.clone() },
+      SessionAuditEvent::TerminationQueued { session: SessionInfo::of(&second), flags },
+    ]);
+    assert_eq!(auditor.take_terminations().iter().map(|request| request.session_id()).collect::<Vec<_>>(), vec![3]);
+
+    // until the user trusts it
+    auditor.trust_session(&second);
+    let third = session(4, "ThinkPad", "9.9.9.9", "BR");
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![current, third.clone()]), 5000)), vec![
+      SessionAuditEvent::SessionAdded { session: SessionInfo::of(&third), flags: vec![] },
+      SessionAuditEvent::SessionRemoved { session: SessionInfo::of(&second) },
+    ]);
+  }
+
+  #[test]
+  fn test_password_pending_and_websites() {
+    let mut policy = TerminationPolicy::new();
+    policy.allow_country("de").max_inactive_days(30).disconnect_new_websites(true);
+    let mut auditor = SessionAuditor::new(policy);
+    let pending = builder(1, "Pixel 6", "1.1.1.1", "DE").is_password_pending(true).build();
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![pending.clone()]), 1000)), vec![
+      SessionAuditEvent::SessionFlagged { session: SessionInfo::of(&pending), flags: vec![SessionFlag::PasswordPending] },
+    ]);
+    let entered = session(1, "Pixel 6", "1.1.1.1", "DE");
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![entered.clone()]), 1000)), vec![
+      SessionAuditEvent::PasswordEntered { session: SessionInfo::of(&entered) },
+    ]);
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![entered.clone()]), 1000 + 31 * 86400)), vec![
+      SessionAuditEvent::SessionFlagged { session: SessionInfo::of(&entered), flags: vec![SessionFlag::Inactive] },
+    ]);
+
+    let website = |id: i64, ip: &str| ConnectedWebsite::builder().id(id).domain_name("example.com").ip(ip).last_active_date(1000).build();
+    let websites = |list: Vec<ConnectedWebsite>| ConnectedWebsites::builder().websites(list).build();
+    assert!(auditor.on_websites(&websites(vec![website(10, "1.1.1.1")]), 1000).is_empty());
+    let records = events(auditor.on_websites(&websites(vec![website(10, "1.1.1.1"), website(11, "5.5.5.5")]), 1000));
+    assert_eq!(records, vec![
+      SessionAuditEvent::WebsiteAdded { website: WebsiteInfo::of(&website(11, "5.5.5.5")), flags: vec![SessionFlag::NewIp] },
+      SessionAuditEvent::DisconnectionQueued { website: WebsiteInfo::of(&website(11, "5.5.5.5")), flags: vec![SessionFlag::NewIp] },
+    ]);
+    assert_eq!(auditor.take_disconnections().iter().map(|request| request.website_id()).collect::<Vec<_>>(), vec![11]);
+    assert_eq!(events(auditor.on_websites(&websites(vec![]), 1000)).len(), 2);
+
+    // a website and a session with the same id don't share state
+    assert_eq!(events(auditor.on_websites(&websites(vec![website(1, "1.1.1.1")]), 1000)).len(), 2);
+    assert_eq!(auditor.take_disconnections().len(), 1);
+    assert_eq!(events(auditor.on_sessions(&sessions(vec![]), 1000)).len(), 1);
+    assert!(auditor.on_websites(&websites(vec![website(1, "1.1.1.1")]), 1000).is_empty());
+    assert!(auditor.take_disconnections().is_empty());
+  }
+}
@@ -0,0 +1,468 @@
+//! Report generation for channel and supergroup statistics.
+//!
+//! ```rust
+//! use rtdlib::statistics::StatisticsReport;
+//! # use rtdlib::types::*;
+//! # let stats = ChatStatistics::supergroup(ChatStatisticsSupergroup::builder().build());
+//! let report = StatisticsReport::builder()
+//!   .title("Rust Community")
+//!   .top(5)
+//!   .build(&stats)
+//!   .unwrap();
+//! println!("{}", report.to_markdown());
+//! ```
+
+use std::collections::HashMap;
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Growth of a statistical value relative to its previous value, as a percentage.
+///
+/// Returns `None` if the previous value is zero and growth can't be computed.
+pub fn growth_percentage(value: &StatisticalValue) -> Option<f64> {
+  let previous = value.previous_value() as f64;
+  if previous == 0.0 { return None }
+  Some((value.value() as f64 - previous) / previous * 100.0)
+}
+
+/// Format unix timestamp as `YYYY-MM-DD` (UTC)
+pub(crate) fn format_date(timestamp: i64) -> String {
+  // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  let z = timestamp.div_euclid(86400) + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub(crate) fn escape_html<S: AsRef<str>>(text: S) -> String {
+  let mut escaped = String::with_capacity(text.as_ref().len());
+  for c in text.as_ref().chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// A single metric of a statistics report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportMetric {
+  name: String,
+  value: f64,
+  previous_value: f64,
+  growth_percentage: Option<f64>,
+}
+
+impl ReportMetric {
+  fn new<S: AsRef<str>>(name: S, value: &StatisticalValue) -> Self {
+    Self {
+      name: name.as_ref().to_string(),
+      value: value.value() as f64,
+      previous_value: value.previous_value() as f64,
+      growth_percentage: growth_percentage(value),
+    }
+  }
+
+  pub fn name(&self) -> &String { &self.name }
+
+  pub fn value(&self) -> f64 { self.value }
+
+  pub fn previous_value(&self) -> f64 { self.previous_value }
+
+  pub fn growth_percentage(&self) -> Option<f64> { self.growth_percentage }
+
+  fn growth_text(&self) -> String {
+    match self.growth_percentage {
+      Some(growth) => format!("{:+.2}%", growth),
+      None => "n/a".to_string(),
+    }
+  }
+}
+
+/// A ranked user of a statistics report, e.g. top poster, administrator or inviter
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankedUser {
+  rank: usize,
+  user_id: i64,
+  name: Option<String>,
+  score: i64,
+  details: Vec<(String, i64)>,
+}
+
+impl RankedUser {
+  pub fn rank(&self) -> usize { self.rank }
+
+  pub fn user_id(&self) -> i64 { self.user_id }
+
+  pub fn name(&self) -> Option<&String> { self.name.as_ref() }
+
+  /// Value the user was ranked by
+  pub fn score(&self) -> i64 { self.score }
+
+  pub fn details(&self) -> &Vec<(String, i64)> { &self.details }
+
+  fn display_name(&self) -> String {
+    self.name.clone().unwrap_or_else(|| format!("user {}", self.user_id))
+  }
+}
+
+/// A ranked recently sent message of a channel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankedMessage {
+  rank: usize,
+  message_id: i64,
+  view_count: i64,
+  forward_count: i64,
+}
+
+impl RankedMessage {
+  pub fn rank(&self) -> usize { self.rank }
+
+  pub fn message_id(&self) -> i64 { self.message_id }
+
+  pub fn view_count(&self) -> i64 { self.view_count }
+
+  pub fn forward_count(&self) -> i64 { self.forward_count }
+}
+
+/// Human readable report built from `ChatStatistics`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatisticsReport {
+  title: String,
+  kind: String,
+  start_date: i64,
+  end_date: i64,
+  metrics: Vec<ReportMetric>,
+  top_senders: Vec<RankedUser>,
+  top_administrators: Vec<RankedUser>,
+  top_inviters: Vec<RankedUser>,
+  top_messages: Vec<RankedMessage>,
+}
+
+impl StatisticsReport {
+  pub fn builder() -> StatisticsReportBuilder {
+    StatisticsReportBuilder {
+      title: None,
+      top: 10,
+      period: None,
+      user_names: HashMap::new(),
+    }
+  }
+
+  pub fn title(&self) -> &String { &self.title }
+
+  /// Either `channel` or `supergroup`
+  pub fn kind(&self) -> &String { &self.kind }
+
+  pub fn start_date(&self) -> i64 { self.start_date }
+
+  pub fn end_date(&self) -> i64 { self.end_date }
+
+  pub fn metrics(&self) -> &Vec<ReportMetric> { &self.metrics }
+
+  pub fn metric<S: AsRef<str>>(&self, name: S) -> Option<&ReportMetric> {
+    self.metrics.iter().find(|metric| metric.name == name.as_ref())
+  }
+
+  pub fn top_senders(&self) -> &Vec<RankedUser> { &self.top_senders }
+
+  pub fn top_administrators(&self) -> &Vec<RankedUser> { &self.top_administrators }
+
+  pub fn top_inviters(&self) -> &Vec<RankedUser> { &self.top_inviters }
+
+  pub fn top_messages(&self) -> &Vec<RankedMessage> { &self.top_messages }
+
+  fn period_text(&self) -> String {
+    format!("{} – {}", format_date(self.start_date), format_date(self.end_date))
+  }
+
+  /// Render report as markdown
+  pub fn to_markdown(&self) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("# {}\n\n", self.title));
+    out.push_str(&format!("Period: {}\n\n", self.period_text()));
+    out.push_str("| Metric | Value | Previous | Growth |\n");
+    out.push_str("|---|---:|---:|---:|\n");
+    for metric in &self.metrics {
+      out.push_str(&format!("| {} | {} | {} | {} |\n", metric.name, metric.value, metric.previous_value, metric.growth_text()));
+    }
+    let sections = [
+      ("Top senders", &self.top_senders),
+      ("Top administrators", &self.top_administrators),
+      ("Top inviters", &self.top_inviters),
+    ];
+    for (header, users) in sections.iter() {
+      if users.is_empty() { continue }
+      out.push_str(&format!("\n## {}\n\n", header));
+      for user in users.iter() {
+        let details = user.details.iter()
+          .map(|(name, value)| format!("{}: {}", name, value))
+          .collect::<Vec<String>>()
+          .join(", ");
+        out.push_str(&format!("{}. {} ({})\n", user.rank, user.display_name(), details));
+      }
+    }
+    if !self.top_messages.is_empty() {
+      out.push_str("\n## Top messages\n\n");
+      for message in &self.top_messages {
+        out.push_str(&format!("{}. message {} ({} views, {} forwards)\n", message.rank, message.message_id, message.view_count, message.forward_count));
+      }
+    }
+    out
+  }
+
+  /// Render report as html fragment
+  pub fn to_html(&self) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("<h1>{}</h1>\n", escape_html(&self.title)));
+    out.push_str(&format!("<p>Period: {}</p>\n", escape_html(self.period_text())));
+    out.push_str("<table>\n<tr><th>Metric</th><th>Value</th><th>Previous</th><th>Growth</th></tr>\n");
+    for metric in &self.metrics {
+      out.push_str(&format!(
+        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+        escape_html(&metric.name), metric.value, metric.previous_value, metric.growth_text()
+      ));
+    }
+    out.push_str("</table>\n");
+    let sections = [
+      ("Top senders", &self.top_senders),
+      ("Top administrators", &self.top_administrators),
+      ("Top inviters", &self.top_inviters),
+    ];
+    for (header, users) in sections.iter() {
+      if users.is_empty() { continue }
+      out.push_str(&format!("<h2>{}</h2>\n<ol>\n", header));
+      for user in users.iter() {
+        let details = user.details.iter()
+          .map(|(name, value)| format!("{}: {}", name, value))
+          .collect::<Vec<String>>()
+          .join(", ");
+        out.push_str(&format!("<li>{} ({})</li>\n", escape_html(user.display_name()), details));
+      }
+      out.push_str("</ol>\n");
+    }
+    if !self.top_messages.is_empty() {
+      out.push_str("<h2>Top messages</h2>\n<ol>\n");
+      for message in &self.top_messages {
+        out.push_str(&format!("<li>message {} ({} views, {} forwards)</li>\n", message.message_id, message.view_count, message.forward_count));
+      }
+      out.push_str("</ol>\n");
+    }
+    out
+  }
+
+  /// Render report as json
+  pub fn to_json(&self) -> RTDResult<String> { Ok(serde_json::to_string(self)?) }
+}
+
+#[doc(hidden)]
+pub struct StatisticsReportBuilder {
+  title: Option<String>,
+  top: usize,
+  period: Option<(i64, i64)>,
+  user_names: HashMap<i64, String>,
+}
+
+impl StatisticsReportBuilder {
+  /// Report title, default is `Channel statistics` or `Supergroup statistics`
+  pub fn title<T: AsRef<str>>(&mut self, title: T) -> &mut Self {
+    self.title = Some(title.as_ref().to_string());
+    self
+  }
+
+  /// Max number of entries of each ranking, default is 10
+  pub fn top(&mut self, top: usize) -> &mut Self {
+    self.top = top;
+    self
+  }
+
+  /// Period shown in the report, default is the period of the statistics. Only the label
+  /// changes: TDLib computes the values for its own period, so they aren't filtered
+  pub fn label_period(&mut self, start_date: i64, end_date: i64) -> &mut Self {
+    self.period = Some((start_date, end_date));
+    self
+  }
+
+  /// Name used for user in rankings instead of the user identifier
+  pub fn user_name<T: AsRef<str>>(&mut self, user_id: i64, name: T) -> &mut Self {
+    self.user_names.insert(user_id, name.as_ref().to_string());
+    self
+  }
+
+  pub fn build<S: AsRef<ChatStatistics>>(&self, statistics: S) -> RTDResult<StatisticsReport> {
+    match statistics.as_ref() {
+      ChatStatistics::Channel(channel) => Ok(self.build_channel(channel)),
+      ChatStatistics::Supergroup(supergroup) => Ok(self.build_supergroup(supergroup)),
+      other => Err(RTDError::custom(format!("can't build statistics report from {}", other.td_name()))),
+    }
+  }
+
+  fn report(&self, kind: &str, period: &DateRange, metrics: Vec<ReportMetric>) -> StatisticsReport {
+    let (start_date, end_date) = self.period.unwrap_or((period.start_date(), period.end_date()));
+    let title = self.title.clone().unwrap_or_else(|| match kind {
+      "channel" => "Channel statistics".to_string(),
+      _ => "Supergroup statistics".to_string(),
+    });
+    StatisticsReport {
+      title,
+      kind: kind.to_string(),
+      start_date,
+      end_date,
+      metrics,
+      top_senders: vec![],
+      top_administrators: vec![],
+      top_inviters: vec![],
+      top_messages: vec![],
+    }
+  }
+
+  fn build_channel(&self, channel: &ChatStatisticsChannel) -> StatisticsReport {
+    let metrics = vec![
+      ReportMetric::new("Members", channel.member_count()),
+      ReportMetric::new("Mean views", channel.mean_view_count()),
+      ReportMetric::new("Mean shares", channel.mean_share_count()),
+    ];
+    let mut report = self.report("channel", channel.period(), metrics);
+    let mut messages = channel.recent_message_interactions().clone();
+    messages.sort_by(|a, b| b.view_count().cmp(&a.view_count()).then(b.forward_count().cmp(&a.forward_count())));
+    report.top_messages = messages.iter()
+      .take(self.top)
+      .enumerate()
+      .map(|(i, message)| RankedMessage {
+        rank: i + 1,
+        message_id: message.message_id(),
+        view_count: message.view_count(),
+        forward_count: message.forward_count(),
+      })
+      .collect();
+    report
+  }
+
+  fn build_supergroup(&self, supergroup: &ChatStatisticsSupergroup) -> StatisticsReport {
+    let metrics = vec![
+      ReportMetric::new("Members", supergroup.member_count()),
+      ReportMetric::new("Messages", supergroup.message_count()),
+      ReportMetric::new("Viewers", supergroup.viewer_count()),
+      ReportMetric::new("Senders", supergroup.sender_count()),
+    ];
+    let mut report = self.report("supergroup", supergroup.period(), metrics);
+    report.top_senders = self.rank(supergroup.top_senders().iter().map(|sender| (
+      sender.user_id(),
+      sender.sent_message_count(),
+      vec![
+        ("messages".to_string(), sender.sent_message_count()),
+        ("average characters".to_string(), sender.average_character_count()),
+      ],
+    )));
+    report.top_administrators = self.rank(supergroup.top_administrators().iter().map(|admin| (
+      admin.user_id(),
+      admin.deleted_message_count() + admin.banned_user_count() + admin.restricted_user_count(),
+      vec![
+        ("deleted messages".to_string(), admin.deleted_message_count()),
+        ("banned users".to_string(), admin.banned_user_count()),
+        ("restricted users".to_string(), admin.restricted_user_count()),
+      ],
+    )));
+    report.top_inviters = self.rank(supergroup.top_inviters().iter().map(|inviter| (
+      inviter.user_id(),
+      inviter.added_member_count(),
+      vec![("added members".to_string(), inviter.added_member_count())],
+    )));
+    report
+  }
+
+  fn rank<I: Iterator<Item=(i64, i64, Vec<(String, i64)>)>>(&self, entries: I) -> Vec<RankedUser> {
+    let mut entries = entries.collect::<Vec<_>>();
+    // stable sort keeps TDLib order for equal scores
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+    entries.into_iter()
+      .take(self.top)
+      .enumerate()
+      .map(|(i, (user_id, score, details))| RankedUser {
+        rank: i + 1,
+        user_id,
+        name: self.user_names.get(&user_id).cloned(),
+        score,
+        details,
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::statistics::*;
+
+  fn value(value: f32, previous_value: f32) -> StatisticalValue {
+    StatisticalValue::builder().value(value).previous_value(previous_value).build()
+  }
+
+  #[test]
+  fn test_growth_percentage() {
+    assert_eq!(growth_percentage(&value(150.0, 100.0)), Some(50.0));
+    assert_eq!(growth_percentage(&value(50.0, 100.0)), Some(-50.0));
+    assert_eq!(growth_percentage(&value(10.0, 0.0)), None);
+    assert_eq!(format_date(1633046400), "2021-10-01");
+  }
+
+  #[test]
+  fn test_supergroup_report() {
+    let supergroup = ChatStatisticsSupergroup::builder()
+      .period(DateRange::builder().start_date(1633046400).end_date(1633651200).build())
+      .member_count(value(120.0, 100.0))
+      .top_senders(vec![
+        ChatStatisticsMessageSenderInfo::builder().user_id(1).sent_message_count(10).build(),
+        ChatStatisticsMessageSenderInfo::builder().user_id(2).sent_message_count(30).build(),
+        ChatStatisticsMessageSenderInfo::builder().user_id(3).sent_message_count(20).build(),
+      ])
+      .top_administrators(vec![
+        ChatStatisticsAdministratorActionsInfo::builder().user_id(4).deleted_message_count(1).banned_user_count(5).build(),
+        ChatStatisticsAdministratorActionsInfo::builder().user_id(5).restricted_user_count(3).build(),
+      ])
+      .build();
+    let report = StatisticsReport::builder()
+      .title("Weekly <report>")
+      .top(2)
+      .user_name(2, "Alice")
+      .build(ChatStatistics::supergroup(supergroup.clone()))
+      .unwrap();
+
+    assert_eq!(report.metric("Members").unwrap().growth_percentage(), Some(20.0));
+    let senders = report.top_senders().iter().map(|u| u.user_id()).collect::<Vec<i64>>();
+    assert_eq!(senders, vec![2, 3]);
+    assert_eq!(report.top_administrators()[0].user_id(), 4);
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("Period: 2021-10-01 – 2021-10-08"));
+    assert!(markdown.contains("| Members | 120 | 100 | +20.00% |"));
+    assert!(markdown.contains("1. Alice (messages: 30, average characters: 0)"));
+    assert!(report.to_html().contains("<h1>Weekly &lt;report&gt;</h1>"));
+    assert!(report.to_json().is_ok());
+
+    // only the label changes, values stay those of the statistics period
+    let labeled = StatisticsReport::builder()
+      .label_period(1633046400, 1633305600)
+      .build(ChatStatistics::supergroup(supergroup))
+      .unwrap();
+    assert!(labeled.to_markdown().contains("Period: 2021-10-01 – 2021-10-04"));
+    assert_eq!(labeled.metric("Members"), report.metric("Members"));
+  }
+
+  #[test]
+  fn test_report_requires_statistics() {
+    let stats = ChatStatistics::get_chat_statistics(GetChatStatistics::builder().build());
+    assert!(StatisticsReport::builder().build(stats).is_err());
+  }
+}
@@ -0,0 +1,511 @@
+//! Colors, theme and background rendering helpers.
+//!
+//! TDLib represents colors as RGB24 integers. `Color` converts them to hex, CSS and HSL,
+//! `fill_css` renders a `BackgroundFill` as a CSS background for web dashboards, `rasterize_fill`
+//! draws it, including freeform gradients, into an RGB buffer, and `BackgroundLink` parses and
+//! generates the background names used by `internalLinkTypeBackground` and `searchBackground`.
+//!
+//! ```rust
+//! use rtdlib::theme::{BackgroundLink, Color, fill_css};
+//! # use rtdlib::types::*;
+//! let link = BackgroundLink::parse("https://t.me/bg/ffffff-000000?rotation=45").unwrap();
+//! let css = link.fill().map(fill_css);
+//! let search = link.search_request();
+//! assert_eq!(Color::from_rgb24(0x3390ec).css(), "#3390ec");
+//! ```
+
+use std::fmt;
+
+use crate::errors::*;
+use crate::types::*;
+
+/// RGB color
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Color {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+}
+
+impl Color {
+  pub fn new(r: u8, g: u8, b: u8) -> Self { Self { r, g, b } }
+
+  /// Color of an RGB24 integer, upper bits are ignored
+  pub fn from_rgb24(rgb: i64) -> Self {
+    Self::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+  }
+
+  pub fn to_rgb24(self) -> i64 { (self.r as i64) << 16 | (self.g as i64) << 8 | self.b as i64 }
+
+  /// Parse `rrggbb` or `rgb` with an optional `#`
+  pub fn parse_hex<S: AsRef<str>>(hex: S) -> Option<Self> {
+    let hex = hex.as_ref();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !hex.bytes().all(|c| c.is_ascii_hexdigit()) { return None }
+    let rgb = i64::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+      6 => Some(Self::from_rgb24(rgb)),
+      3 => Some(Self::new((rgb >> 8) as u8 * 0x11, (rgb >> 4 & 0xf) as u8 * 0x11, (rgb & 0xf) as u8 * 0x11)),
+      _ => None,
+    }
+  }
+
+  /// `rrggbb`, as used in background names
+  pub fn hex(self) -> String { format!("{:02x}{:02x}{:02x}", self.r, self.g, self.b) }
+
+  /// `#rrggbb`
+  pub fn css(self) -> String { format!("#{}", self.hex()) }
+
+  /// `rgba(r, g, b, alpha)`
+  pub fn css_rgba(self, alpha: f64) -> String {
+    format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, alpha.clamp(0.0, 1.0))
+  }
+
+  /// Hue in degrees, saturation and lightness in `0.0..=1.0`
+  pub fn hsl(self) -> (f64, f64, f64) {
+    let [r, g, b] = [self.r, self.g, self.b].map(|c| c as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 { return (0.0, 0.0, lightness) }
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r { ((g - b) / delta).rem_euclid(6.0) } else if max == g { (b - r) / delta + 2.0 } else { (r - g) / delta + 4.0 };
+    (hue * 60.0, saturation, lightness)
+  }
+
+  pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+    let (saturation, lightness) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+      0 => (chroma, x, 0.0),
+      1 => (x, chroma, 0.0),
+      2 => (0.0, chroma, x),
+      3 => (0.0, x, chroma),
+      4 => (x, 0.0, chroma),
+      _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+    Self::new(channel(r), channel(g), channel(b))
+  }
+
+  /// Relative luminance as defined by WCAG
+  pub fn luminance(self) -> f64 {
+    let linear = |c: u8| {
+      let c = c as f64 / 255.0;
+      if c <= 0.039_28 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+  }
+
+  /// WCAG contrast ratio between `1.0` and `21.0`
+  pub fn contrast_ratio(self, other: Color) -> f64 {
+    let (a, b) = (self.luminance(), other.luminance());
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+  }
+
+  /// True if white text is more readable on the color than black text
+  pub fn is_dark(self) -> bool {
+    self.contrast_ratio(Color::new(255, 255, 255)) > self.contrast_ratio(Color::new(0, 0, 0))
+  }
+
+  /// Linear interpolation, `ratio` 0 is `self` and 1 is `other`
+  pub fn mix(self, other: Color, ratio: f64) -> Self {
+    let ratio = ratio.clamp(0.0, 1.0);
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * ratio).round() as u8;
+    Self::new(channel(self.r, other.r), channel(self.g, other.g), channel(self.b, other.b))
+  }
+}
+
+impl fmt::Display for Color {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str(&self.css()) }
+}
+
+/// Positions of freeform gradient colors, `x` and `y` in `0.0..=1.0` from the top left corner
+pub const FREEFORM_POSITIONS: [(f64, f64); 4] = [(0.80, 0.10), (0.35, 0.25), (0.20, 0.90), (0.65, 0.75)];
+
+fn check_freeform(colors: &[i64]) -> RTDResult<()> {
+  if colors.len() != 3 && colors.len() != 4 {
+    return Err(RTDError::custom(format!("freeform gradient must have 3 or 4 colors, got {}", colors.len())));
+  }
+  Ok(())
+}
+
+/// Color of a freeform gradient at a point, weighted by the distance to each color position
+pub fn freeform_color(colors: &[i64], x: f64, y: f64) -> Color {
+  let mut sum = [0.0; 3];
+  let mut total = 0.0;
+  for (color, (px, py)) in colors.iter().zip(FREEFORM_POSITIONS.iter()) {
+    let distance = ((x - px).powi(2) + (y - py).powi(2)).sqrt();
+    let weight = (0.9 - distance).max(0.0).powi(4);
+    let color = Color::from_rgb24(*color);
+    for (sum, channel) in sum.iter_mut().zip([color.r, color.g, color.b]) {
+      *sum += channel as f64 * weight;
+    }
+    total += weight;
+  }
+  if total == 0.0 { return average_color(colors) }
+  let [r, g, b] = sum.map(|sum| (sum / total).round() as u8);
+  Color::new(r, g, b)
+}
+
+fn average_color(colors: &[i64]) -> Color {
+  if colors.is_empty() { return Color::default() }
+  let channel = |f: fn(Color) -> u8| {
+    (colors.iter().map(|color| f(Color::from_rgb24(*color)) as u32).sum::<u32>() / colors.len() as u32) as u8
+  };
+  Color::new(channel(|c| c.r), channel(|c| c.g), channel(|c| c.b))
+}
+
+/// Average color of a fill, useful for choosing text colors over it
+pub fn fill_average(fill: &BackgroundFill) -> Color {
+  match fill {
+    BackgroundFill::Solid(solid) => Color::from_rgb24(solid.color()),
+    BackgroundFill::Gradient(gradient) => average_color(&[gradient.top_color(), gradient.bottom_color()]),
+    BackgroundFill::FreeformGradient(freeform) => average_color(freeform.colors()),
+    _ => Color::default(),
+  }
+}
+
+/// CSS angle of a gradient, TDLib angles rotate a top to bottom gradient clockwise
+fn css_angle(rotation_angle: i64) -> i64 { (180 + rotation_angle).rem_euclid(360) }
+
+/// CSS `background` value of a fill, freeform gradients are approximated with radial gradients
+pub fn fill_css(fill: &BackgroundFill) -> String {
+  match fill {
+    BackgroundFill::Solid(solid) => Color::from_rgb24(solid.color()).css(),
+    BackgroundFill::Gradient(gradient) => format!(
+      "linear-gradient({}deg, {}, {})",
+      css_angle(gradient.rotation_angle()),
+      Color::from_rgb24(gradient.top_color()),
+      Color::from_rgb24(gradient.bottom_color()),
+    ),
+    BackgroundFill::FreeformGradient(freeform) => {
+      let mut layers = freeform.colors().iter().zip(FREEFORM_POSITIONS.iter())
+        .map(|(color, (x, y))| format!(
+          "radial-gradient(circle at {}% {}%, {} 0%, {} 65%)",
+          x * 100.0, y * 100.0, Color::from_rgb24(*color), Color::from_rgb24(*color).css_rgba(0.0),
+        ))
+        .collect::<Vec<_>>();
+      layers.push(average_color(freeform.colors()).css());
+      layers.join(", ")
+    }
+    _ => String::new(),
+  }
+}
+
+/// Draw a fill into a row-major RGB buffer of `width * height * 3` bytes
+pub fn rasterize_fill(fill: &BackgroundFill, width: usize, height: usize) -> RTDResult<Vec<u8>> {
+  if width == 0 || height == 0 {
+    return Err(RTDError::custom(format!("can't rasterize a fill to {}x{}", width, height)));
+  }
+  if let BackgroundFill::FreeformGradient(freeform) = fill {
+    check_freeform(freeform.colors())?;
+  }
+  let (w, h) = (width as f64, height as f64);
+  let pixel: Box<dyn Fn(f64, f64) -> Color> = match fill {
+    BackgroundFill::Gradient(gradient) => {
+      let (top, bottom) = (Color::from_rgb24(gradient.top_color()), Color::from_rgb24(gradient.bottom_color()));
+      // project onto the CSS gradient line so the corners get the pure colors
+      let angle = (css_angle(gradient.rotation_angle()) as f64).to_radians();
+      let (dx, dy) = (angle.sin(), -angle.cos());
+      let length = (w * dx).abs() + (h * dy).abs();
+      Box::new(move |x, y| top.mix(bottom, ((x * w - w / 2.0) * dx + (y * h - h / 2.0) * dy) / length + 0.5))
+    }
+    BackgroundFill::FreeformGradient(freeform) => {
+      let colors = freeform.colors().clone();
+      Box::new(move |x, y| freeform_color(&colors, x, y))
+    }
+    _ => {
+      let color = fill_average(fill);
+      Box::new(move |_, _| color)
+    }
+  };
+  let mut buffer = Vec::with_capacity(width * height * 3);
+  for row in 0..height {
+    for column in 0..width {
+      let color = pixel((column as f64 + 0.5) / w, (row as f64 + 0.5) / h);
+      buffer.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+  }
+  Ok(buffer)
+}
+
+/// CSS custom property declarations for theme settings
+pub fn theme_css(settings: &ThemeSettings) -> String {
+  let mut css = format!(
+    "--accent-color: {};\n--outgoing-accent-color: {};\n--outgoing-message-background: {};\n",
+    Color::from_rgb24(settings.accent_color()),
+    Color::from_rgb24(settings.outgoing_message_accent_color()),
+    fill_css(settings.outgoing_message_fill()),
+  );
+  let fill = settings.background().as_ref().and_then(|background| match background.type_() {
+    BackgroundType::Fill(fill) => Some(fill.fill()),
+    BackgroundType::Pattern(pattern) => Some(pattern.fill()),
+    _ => None,
+  });
+  if let Some(fill) = fill {
+    css.push_str(&format!("--chat-background: {};\n", fill_css(fill)));
+  }
+  css
+}
+
+/// CSS rule for a chat theme, the dark settings apply with `prefers-color-scheme: dark`
+pub fn chat_theme_css<S: AsRef<str>>(theme: &ChatTheme, selector: S) -> String {
+  let selector = selector.as_ref();
+  let indent = |css: String, prefix: &str| css.lines().map(|line| format!("{}{}\n", prefix, line)).collect::<String>();
+  format!(
+    "{selector} {{\n{}}}\n@media (prefers-color-scheme: dark) {{\n  {selector} {{\n{}  }}\n}}\n",
+    indent(theme_css(theme.light_settings()), "  "),
+    indent(theme_css(theme.dark_settings()), "    "),
+    selector = selector,
+  )
+}
+
+fn parse_color(text: &str) -> RTDResult<i64> {
+  match text.len() {
+    6 => Color::parse_hex(text).map(Color::to_rgb24),
+    _ => None,
+  }.ok_or_else(|| RTDError::custom(format!("invalid background color {:?}", text)))
+}
+
+/// Parse `rrggbb`, `rrggbb-rrggbb` or `rrggbb~rrggbb~rrggbb[~rrggbb]` with a gradient rotation
+fn parse_fill(colors: &str, rotation: i64) -> RTDResult<BackgroundFill> {
+  if colors.contains('~') {
+    let colors = colors.split('~').map(parse_color).collect::<RTDResult<Vec<_>>>()?;
+    check_freeform(&colors)?;
+    return Ok(BackgroundFill::freeform_gradient(BackgroundFillFreeformGradient::builder().colors(colors).build()));
+  }
+  match colors.split_once('-') {
+    Some((top, bottom)) => Ok(BackgroundFill::gradient(BackgroundFillGradient::builder()
+      .top_color(parse_color(top)?)
+      .bottom_color(parse_color(bottom)?)
+      .rotation_angle(rotation)
+      .build())),
+    None => Ok(BackgroundFill::solid(BackgroundFillSolid::builder().color(parse_color(colors)?).build())),
+  }
+}
+
+/// Colors of a fill and the `rotation` parameter for rotated gradients
+fn fill_params(fill: &BackgroundFill) -> (String, Option<i64>) {
+  let hex = |color: i64| Color::from_rgb24(color).hex();
+  match fill {
+    BackgroundFill::Solid(solid) => (hex(solid.color()), None),
+    BackgroundFill::Gradient(gradient) => (
+      format!("{}-{}", hex(gradient.top_color()), hex(gradient.bottom_color())),
+      Some(gradient.rotation_angle()).filter(|angle| *angle != 0),
+    ),
+    BackgroundFill::FreeformGradient(freeform) => (freeform.colors().iter().map(|color| hex(*color)).collect::<Vec<_>>().join("~"), None),
+    _ => (String::new(), None),
+  }
+}
+
+/// A background name, as in `https://t.me/bg/<name>`: colors for fills, or the slug of a pattern or
+/// wallpaper with `intensity`, `bg_color`, `rotation` and `mode` parameters
+#[derive(Debug, Clone)]
+pub struct BackgroundLink {
+  slug: String,
+  background_type: BackgroundType,
+}
+
+impl BackgroundLink {
+  /// Link to an uploaded background, `slug` is the `name` of a `Background`
+  pub fn new<S: AsRef<str>, T: AsRef<BackgroundType>>(slug: S, background_type: T) -> Self {
+    Self { slug: slug.as_ref().to_string(), background_type: background_type.as_ref().clone() }
+  }
+
+  /// Link to a fill, which has no slug
+  pub fn from_fill<T: AsRef<BackgroundFill>>(fill: T) -> Self {
+    Self::new("", BackgroundType::fill(BackgroundTypeFill::builder().fill(fill).build()))
+  }
+
+  pub fn from_background(background: &Background) -> Self {
+    match background.type_() {
+      BackgroundType::Fill(fill) => Self::from_fill(fill.fill()),
+      type_ => Self::new(background.name(), type_),
+    }
+  }
+
+  pub fn from_internal_link(link: &InternalLinkTypeBackground) -> RTDResult<Self> {
+    Self::parse(link.background_name())
+  }
+
+  /// Parse a background name, a `t.me/bg/` link or a `tg://bg` link
+  pub fn parse<S: AsRef<str>>(link: S) -> RTDResult<Self> {
+    let link = link.as_ref().trim();
+    let without_scheme = link.strip_prefix("https://").or_else(|| link.strip_prefix("http://")).unwrap_or(link);
+    let name = ["t.me/bg/", "telegram.me/bg/", "telegram.dog/bg/"].iter()
+      .find_map(|prefix| without_scheme.strip_prefix(prefix))
+      .unwrap_or(without_scheme);
+    let (path, query) = match link.strip_prefix("tg://bg?") {
+      Some(query) => ("", query),
+      None => name.split_once('?').unwrap_or((name, "")),
+    };
+    let params = query.split('&').filter_map(|param| param.split_once('=')).collect::<Vec<_>>();
+    let param = |key: &str| params.iter().find(|(name, _)| *name == key).map(|(_, value)| *value);
+    let number = |key: &str| -> RTDResult<Option<i64>> {
+      param(key).map(|value| value.parse::<i64>().map_err(|_| RTDError::custom(format!("invalid background {} {:?}", key, value)))).transpose()
+    };
+
+    let rotation = number("rotation")?.unwrap_or(0);
+    if !(0..360).contains(&rotation) || rotation % 45 != 0 {
+      return Err(RTDError::custom(format!("background rotation must be a multiple of 45 below 360, got {}", rotation)));
+    }
+    let modes = param("mode").unwrap_or("").split(['+', ' ']).flat_map(|mode| mode.split("%20")).collect::<Vec<_>>();
+    let (is_blurred, is_moving) = (modes.contains(&"blur"), modes.contains(&"motion"));
+    let slug = param("slug").unwrap_or(path);
+
+    if let Some(colors) = param("color").or_else(|| param("gradient")).or_else(|| Some(slug).filter(|slug| is_fill_name(slug))) {
+      return Ok(Self::from_fill(parse_fill(colors, rotation)?));
+    }
+    if slug.is_empty() {
+      return Err(RTDError::custom(format!("no background in {:?}", link)));
+    }
+    let intensity = number("intensity")?;
+    let background_type = match (intensity, param("bg_color")) {
+      (None, None) => BackgroundType::wallpaper(BackgroundTypeWallpaper::builder().is_blurred(is_blurred).is_moving(is_moving).build()),
+      (intensity, colors) => {
+        let intensity = intensity.unwrap_or(50);
+        if !(-100..=100).contains(&intensity) {
+          return Err(RTDError::custom(format!("background intensity must be in -100..=100, got {}", intensity)));
+        }
+        BackgroundType::pattern(BackgroundTypePattern::builder()
+          .fill(parse_fill(colors.unwrap_or("ffffff"), rotation)?)
+          .intensity(intensity.abs())
+          .is_inverted(intensity < 0)
+          .is_moving(is_moving)
+          .build())
+      }
+    };
+    Ok(Self::new(slug, background_type))
+  }
+
+  pub fn slug(&self) -> &str { &self.slug }
+
+  pub fn background_type(&self) -> &BackgroundType { &self.background_type }
+
+  /// Fill of a fill or pattern background
+  pub fn fill(&self) -> Option<&BackgroundFill> {
+    match &self.background_type {
+      BackgroundType::Fill(fill) => Some(fill.fill()),
+      BackgroundType::Pattern(pattern) => Some(pattern.fill()),
+      _ => None,
+    }
+  }
+
+  /// Background name with parameters, as accepted by `searchBackground`
+  pub fn name(&self) -> String {
+    let mut params = vec![];
+    let mut mode = vec![];
+    let path = match &self.background_type {
+      BackgroundType::Fill(fill) => {
+        let (colors, rotation) = fill_params(fill.fill());
+        params.extend(rotation.map(|rotation| format!("rotation={}", rotation)));
+        colors
+      }
+      BackgroundType::Pattern(pattern) => {
+        let (colors, rotation) = fill_params(pattern.fill());
+        let sign = if pattern.is_inverted() { -1 } else { 1 };
+        params.push(format!("intensity={}", sign * pattern.intensity()));
+        params.push(format!("bg_color={}", colors));
+        params.extend(rotation.map(|rotation| format!("rotation={}", rotation)));
+        if pattern.is_moving() { mode.push("motion") }
+        self.slug.clone()
+      }
+      BackgroundType::Wallpaper(wallpaper) => {
+        if wallpaper.is_blurred() { mode.push("blur") }
+        if wallpaper.is_moving() { mode.push("motion") }
+        self.slug.clone()
+      }
+      _ => self.slug.clone(),
+    };
+    if !mode.is_empty() { params.push(format!("mode={}", mode.join("+"))) }
+    if params.is_empty() { path } else { format!("{}?{}", path, params.join("&")) }
+  }
+
+  /// `https://t.me/bg/` link
+  pub fn url(&self) -> String { format!("https://t.me/bg/{}", self.name()) }
+
+  pub fn search_request(&self) -> SearchBackground {
+    SearchBackground::builder().name(self.name()).build()
+  }
+}
+
+fn is_fill_name(name: &str) -> bool {
+  !name.is_empty() && name.split(['-', '~']).all(|color| color.len() == 6 && color.bytes().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::theme::*;
+
+  #[test]
+  fn test_color() {
+    let color = Color::from_rgb24(0x3390ec);
+    assert_eq!(color, Color::new(0x33, 0x90, 0xec));
+    assert_eq!(color.to_rgb24(), 0x3390ec);
+    assert_eq!(Color::parse_hex("#3390EC"), Some(color));
+    assert_eq!(Color::parse_hex("fff"), Some(Color::new(255, 255, 255)));
+    assert_eq!(Color::parse_hex("12345"), None);
+    let (h, s, l) = color.hsl();
+    assert_eq!(Color::from_hsl(h, s, l), color);
+    assert_eq!(Color::from_hsl(120.0, 1.0, 0.5), Color::new(0, 255, 0));
+    assert!(Color::new(0, 0, 0).is_dark());
+    assert!(!Color::new(255, 255, 255).is_dark());
+    assert!((Color::new(0, 0, 0).contrast_ratio(Color::new(255, 255, 255)) - 21.0).abs() < 1e-9);
+    assert_eq!(Color::new(0, 0, 0).mix(Color::new(255, 255, 255), 0.5), Color::new(128, 128, 128));
+  }
+
+  #[test]
+  fn test_fill_css_and_rasterize() {
+    let gradient = BackgroundFill::gradient(BackgroundFillGradient::builder().top_color(0xff0000).bottom_color(0x0000ff).rotation_angle(90).build());
+    assert_eq!(fill_css(&gradient), "linear-gradient(270deg, #ff0000, #0000ff)");
+    // rotated clockwise by 90 degrees the top color is on the right
+    let pixels = rasterize_fill(&gradient, 4, 1).unwrap();
+    assert!(pixels[0] < pixels[9] && pixels[2] > pixels[11]);
+
+    let colors = vec![0xff0000, 0x00ff00, 0x0000ff, 0xffffff];
+    let freeform = BackgroundFill::freeform_gradient(BackgroundFillFreeformGradient::builder().colors(colors.clone()).build());
+    assert!(fill_css(&freeform).starts_with("radial-gradient(circle at 80% 10%, #ff0000 0%, rgba(255, 0, 0, 0) 65%), "));
+    let pixels = rasterize_fill(&freeform, 10, 10).unwrap();
+    assert_eq!(pixels.len(), 300);
+    // the pixel at the red color position is mostly red
+    let red = &pixels[(10 + 8) * 3..(10 + 8) * 3 + 3];
+    assert!(red[0] > 200 && red[1] < 60 && red[2] < 60);
+    assert_eq!(freeform_color(&colors, 0.5, 0.5), freeform_color(&colors, 0.5, 0.5));
+
+    let bad = BackgroundFill::freeform_gradient(BackgroundFillFreeformGradient::builder().colors(vec![1, 2]).build());
+    assert!(rasterize_fill(&bad, 1, 1).is_err());
+    assert!(rasterize_fill(&gradient, 0, 1).is_err());
+  }
+
+  #[test]
+  fn test_background_link() {
+    let link = BackgroundLink::parse("https://t.me/bg/ffffff-000000?rotation=45").unwrap();
+    assert_eq!(link.slug(), "");
+    assert_eq!(link.name(), "ffffff-000000?rotation=45");
+    assert_eq!(link.fill().map(fill_css).unwrap(), "linear-gradient(225deg, #ffffff, #000000)");
+
+    let pattern = BackgroundLink::parse("t.me/bg/Qe7XjBrjSEMAAAAA?intensity=-40&bg_color=aaaaaa~bbbbbb~cccccc&mode=motion").unwrap();
+    let type_ = pattern.background_type().as_pattern().unwrap();
+    assert_eq!((type_.intensity(), type_.is_inverted(), type_.is_moving()), (40, true, true));
+    assert_eq!(pattern.name(), "Qe7XjBrjSEMAAAAA?intensity=-40&bg_color=aaaaaa~bbbbbb~cccccc&mode=motion");
+    assert_eq!(pattern.search_request().name(), &pattern.name());
+
+    let wallpaper = BackgroundLink::parse("Wallpaper?mode=blur+motion").unwrap();
+    assert!(wallpaper.background_type().as_wallpaper().is_some_and(|w| w.is_blurred() && w.is_moving()));
+    assert_eq!(wallpaper.url(), "https://t.me/bg/Wallpaper?mode=blur+motion");
+
+    let solid = BackgroundLink::parse("tg://bg?color=00ff00").unwrap();
+    assert_eq!(solid.name(), "00ff00");
+    let internal = InternalLinkTypeBackground::builder().background_name("Pattern?bg_color=123456").build();
+    assert_eq!(BackgroundLink::from_internal_link(&internal).unwrap().name(), "Pattern?intensity=50&bg_color=123456");
+
+    assert!(BackgroundLink::parse("ffffff-000000?rotation=30").is_err());
+    assert!(BackgroundLink::parse("Pattern?intensity=200").is_err());
+    assert!(BackgroundLink::parse("aaaaaa~bbbbbb").is_err());
+  }
+}
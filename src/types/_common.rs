@@ -118,6 +118,10 @@ pub trait RObject: Debug {
   fn extra(&self) -> Option<String>;
   /// Return td type to json string
   fn to_json(&self) -> RTDResult<String>;
+  /// Return td type to json string with `int64` values written in `format`
+  fn to_json_with(&self, format: Int64Format) -> RTDResult<String> {
+    with_int64_format(format, || self.to_json())
+  }
 }
 
 pub trait RFunction: Debug + RObject {}
@@ -0,0 +1,112 @@
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// How tdlib `int64` values are written to json
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Int64Format {
+  /// Json numbers, the default
+  Number,
+  /// Json strings as tdlib does, numbers above 2^53 lose precision in javascript
+  String,
+}
+
+thread_local! {
+  static INT64_FORMAT: Cell<Int64Format> = Cell::new(Int64Format::Number);
+}
+
+/// Restores the previous format, also when serialization panics
+struct FormatGuard(Int64Format);
+
+impl Drop for FormatGuard {
+  fn drop(&mut self) { INT64_FORMAT.with(|format| format.set(self.0)) }
+}
+
+/// Run `f` with `int64` values serialized in `format` on the current thread, e.g. to serialize a
+/// type with `serde_json::to_value`. Both formats are accepted when deserializing
+pub fn with_int64_format<R, F: FnOnce() -> R>(format: Int64Format, f: F) -> R {
+  let _guard = FormatGuard(INT64_FORMAT.with(|current| current.replace(format)));
+  f()
+}
+
+fn int64_format() -> Int64Format { INT64_FORMAT.with(|format| format.get()) }
+
+/// A tdlib `int64` value, serialized as a number unless `with_int64_format` says otherwise and deserialized from a json number or string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TdInt64(pub i64);
+
+impl From<i64> for TdInt64 {
+  fn from(value: i64) -> Self { TdInt64(value) }
+}
+
+impl From<TdInt64> for i64 {
+  fn from(value: TdInt64) -> Self { value.0 }
+}
+
+impl fmt::Display for TdInt64 {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.0) }
+}
+
+impl Serialize for TdInt64 {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match int64_format() {
+      Int64Format::Number => serializer.serialize_i64(self.0),
+      Int64Format::String => serializer.collect_str(&self.0),
+    }
+  }
+}
+
+struct TdInt64Visitor;
+
+impl<'de> Visitor<'de> for TdInt64Visitor {
+  type Value = TdInt64;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("a 64-bit integer as a number or a string")
+  }
+
+  fn visit_i64<E: de::Error>(self, value: i64) -> Result<TdInt64, E> { Ok(TdInt64(value)) }
+
+  fn visit_u64<E: de::Error>(self, value: u64) -> Result<TdInt64, E> {
+    i64::try_from(value).map(TdInt64).map_err(|_| E::custom(format!("int64 out of range: {}", value)))
+  }
+
+  fn visit_str<E: de::Error>(self, value: &str) -> Result<TdInt64, E> {
+    value.trim().parse().map(TdInt64).map_err(|_| E::custom(format!("invalid int64: {}", value)))
+  }
+}
+
+impl<'de> Deserialize<'de> for TdInt64 {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TdInt64, D::Error> {
+    deserializer.deserialize_any(TdInt64Visitor)
+  }
+}
+
+/// Serde adapter of `int64` fields, `#[serde(with = "crate::types::int64")]`
+pub mod int64 {
+  use super::*;
+
+  pub fn serialize<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    TdInt64(*value).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    TdInt64::deserialize(deserializer).map(|value| value.0)
+  }
+}
+
+/// Serde adapter of `vector<int64>` fields, `#[serde(with = "crate::types::int64_vec")]`
+pub mod int64_vec {
+  use super::*;
+
+  pub fn serialize<S: Serializer>(values: &[i64], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(|value| TdInt64(*value)))
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<i64>, D::Error> {
+    Vec::<TdInt64>::deserialize(deserializer).map(|values| values.into_iter().map(|value| value.0).collect())
+  }
+}
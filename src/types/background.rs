@@ -16,7 +16,7 @@ pub struct Background {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique background identifier
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// True, if this is one of default backgrounds
   is_default: bool,
   /// True, if the background is dark and is recommended to be used with dark theme
@@ -47,7 +47,7 @@ impl Background {
     RTDBackgroundBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn is_default(&self) -> bool { self.is_default }
 
@@ -70,7 +70,7 @@ impl RTDBackgroundBuilder {
   pub fn build(&self) -> Background { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
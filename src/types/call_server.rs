@@ -16,7 +16,7 @@ pub struct CallServer {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Server identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Server IPv4 address
   ip_address: String,
   /// Server IPv6 address
@@ -45,7 +45,7 @@ impl CallServer {
     RTDCallServerBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn ip_address(&self) -> &String { &self.ip_address }
 
@@ -66,7 +66,7 @@ impl RTDCallServerBuilder {
   pub fn build(&self) -> CallServer { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
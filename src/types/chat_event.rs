@@ -16,7 +16,7 @@ pub struct ChatEvent {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Chat event identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Point in time (Unix timestamp) when the event happened
   date: i64,
   /// Identifier of the user or chat who performed the action
@@ -43,7 +43,7 @@ impl ChatEvent {
     RTDChatEventBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn date(&self) -> i64 { self.date }
 
@@ -62,7 +62,7 @@ impl RTDChatEventBuilder {
   pub fn build(&self) -> ChatEvent { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
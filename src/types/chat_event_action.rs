@@ -2321,9 +2321,9 @@ pub struct ChatEventStickerSetChanged {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Previous identifier of the chat sticker set; 0 if none
-  old_sticker_set_id: isize,
+  #[serde(with = "crate::types::int64")] old_sticker_set_id: i64,
   /// New identifier of the chat sticker set; 0 if none
-  new_sticker_set_id: isize,
+  #[serde(with = "crate::types::int64")] new_sticker_set_id: i64,
   
 }
 
@@ -2347,9 +2347,9 @@ impl ChatEventStickerSetChanged {
     RTDChatEventStickerSetChangedBuilder { inner }
   }
 
-  pub fn old_sticker_set_id(&self) -> isize { self.old_sticker_set_id }
+  pub fn old_sticker_set_id(&self) -> i64 { self.old_sticker_set_id }
 
-  pub fn new_sticker_set_id(&self) -> isize { self.new_sticker_set_id }
+  pub fn new_sticker_set_id(&self) -> i64 { self.new_sticker_set_id }
 
 }
 
@@ -2362,13 +2362,13 @@ impl RTDChatEventStickerSetChangedBuilder {
   pub fn build(&self) -> ChatEventStickerSetChanged { self.inner.clone() }
 
    
-  pub fn old_sticker_set_id(&mut self, old_sticker_set_id: isize) -> &mut Self {
+  pub fn old_sticker_set_id(&mut self, old_sticker_set_id: i64) -> &mut Self {
     self.inner.old_sticker_set_id = old_sticker_set_id;
     self
   }
 
    
-  pub fn new_sticker_set_id(&mut self, new_sticker_set_id: isize) -> &mut Self {
+  pub fn new_sticker_set_id(&mut self, new_sticker_set_id: i64) -> &mut Self {
     self.inner.new_sticker_set_id = new_sticker_set_id;
     self
   }
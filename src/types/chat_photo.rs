@@ -16,7 +16,7 @@ pub struct ChatPhoto {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique photo identifier
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Point in time (Unix timestamp) when the photo has been added
   added_date: i64,
   /// Photo minithumbnail; may be null
@@ -45,7 +45,7 @@ impl ChatPhoto {
     RTDChatPhotoBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn added_date(&self) -> i64 { self.added_date }
 
@@ -66,7 +66,7 @@ impl RTDChatPhotoBuilder {
   pub fn build(&self) -> ChatPhoto { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
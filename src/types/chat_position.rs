@@ -18,7 +18,7 @@ pub struct ChatPosition {
   /// The chat list
   list: ChatList,
   /// A parameter used to determine order of the chat in the chat list. Chats must be sorted by the pair (order, chat.id) in descending order
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] order: isize,
+  #[serde(with = "crate::types::int64")] order: i64,
   /// True, if the chat is pinned in the chat list
   is_pinned: bool,
   /// Source of the chat in the chat list; may be null
@@ -45,7 +45,7 @@ impl ChatPosition {
 
   pub fn list(&self) -> &ChatList { &self.list }
 
-  pub fn order(&self) -> isize { self.order }
+  pub fn order(&self) -> i64 { self.order }
 
   pub fn is_pinned(&self) -> bool { self.is_pinned }
 
@@ -68,7 +68,7 @@ impl RTDChatPositionBuilder {
   }
 
    
-  pub fn order(&mut self, order: isize) -> &mut Self {
+  pub fn order(&mut self, order: i64) -> &mut Self {
     self.inner.order = order;
     self
   }
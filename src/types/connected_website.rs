@@ -16,7 +16,7 @@ pub struct ConnectedWebsite {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Website identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// The domain name of the website
   domain_name: String,
   /// User identifier of a bot linked with the website
@@ -53,7 +53,7 @@ impl ConnectedWebsite {
     RTDConnectedWebsiteBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn domain_name(&self) -> &String { &self.domain_name }
 
@@ -82,7 +82,7 @@ impl RTDConnectedWebsiteBuilder {
   pub fn build(&self) -> ConnectedWebsite { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
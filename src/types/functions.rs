@@ -1274,7 +1274,7 @@ pub struct AnswerCallbackQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the callback query
-  callback_query_id: isize,
+  #[serde(with = "crate::types::int64")] callback_query_id: i64,
   /// Text of the answer
   text: String,
   /// If true, an alert must be shown to the user instead of a toast notification
@@ -1306,7 +1306,7 @@ impl AnswerCallbackQuery {
     RTDAnswerCallbackQueryBuilder { inner }
   }
 
-  pub fn callback_query_id(&self) -> isize { self.callback_query_id }
+  pub fn callback_query_id(&self) -> i64 { self.callback_query_id }
 
   pub fn text(&self) -> &String { &self.text }
 
@@ -1327,7 +1327,7 @@ impl RTDAnswerCallbackQueryBuilder {
   pub fn build(&self) -> AnswerCallbackQuery { self.inner.clone() }
 
    
-  pub fn callback_query_id(&mut self, callback_query_id: isize) -> &mut Self {
+  pub fn callback_query_id(&mut self, callback_query_id: i64) -> &mut Self {
     self.inner.callback_query_id = callback_query_id;
     self
   }
@@ -1382,7 +1382,7 @@ pub struct AnswerCustomQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of a custom query
-  custom_query_id: isize,
+  #[serde(with = "crate::types::int64")] custom_query_id: i64,
   /// JSON-serialized answer to the query
   data: String,
   
@@ -1408,7 +1408,7 @@ impl AnswerCustomQuery {
     RTDAnswerCustomQueryBuilder { inner }
   }
 
-  pub fn custom_query_id(&self) -> isize { self.custom_query_id }
+  pub fn custom_query_id(&self) -> i64 { self.custom_query_id }
 
   pub fn data(&self) -> &String { &self.data }
 
@@ -1423,7 +1423,7 @@ impl RTDAnswerCustomQueryBuilder {
   pub fn build(&self) -> AnswerCustomQuery { self.inner.clone() }
 
    
-  pub fn custom_query_id(&mut self, custom_query_id: isize) -> &mut Self {
+  pub fn custom_query_id(&mut self, custom_query_id: i64) -> &mut Self {
     self.inner.custom_query_id = custom_query_id;
     self
   }
@@ -1460,7 +1460,7 @@ pub struct AnswerInlineQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the inline query
-  inline_query_id: isize,
+  #[serde(with = "crate::types::int64")] inline_query_id: i64,
   /// True, if the result of the query can be cached for the specified user
   is_personal: bool,
   /// The results of the query
@@ -1496,7 +1496,7 @@ impl AnswerInlineQuery {
     RTDAnswerInlineQueryBuilder { inner }
   }
 
-  pub fn inline_query_id(&self) -> isize { self.inline_query_id }
+  pub fn inline_query_id(&self) -> i64 { self.inline_query_id }
 
   pub fn is_personal(&self) -> bool { self.is_personal }
 
@@ -1521,7 +1521,7 @@ impl RTDAnswerInlineQueryBuilder {
   pub fn build(&self) -> AnswerInlineQuery { self.inner.clone() }
 
    
-  pub fn inline_query_id(&mut self, inline_query_id: isize) -> &mut Self {
+  pub fn inline_query_id(&mut self, inline_query_id: i64) -> &mut Self {
     self.inner.inline_query_id = inline_query_id;
     self
   }
@@ -1588,7 +1588,7 @@ pub struct AnswerPreCheckoutQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the pre-checkout query
-  pre_checkout_query_id: isize,
+  #[serde(with = "crate::types::int64")] pre_checkout_query_id: i64,
   /// An error message, empty on success
   error_message: String,
   
@@ -1614,7 +1614,7 @@ impl AnswerPreCheckoutQuery {
     RTDAnswerPreCheckoutQueryBuilder { inner }
   }
 
-  pub fn pre_checkout_query_id(&self) -> isize { self.pre_checkout_query_id }
+  pub fn pre_checkout_query_id(&self) -> i64 { self.pre_checkout_query_id }
 
   pub fn error_message(&self) -> &String { &self.error_message }
 
@@ -1629,7 +1629,7 @@ impl RTDAnswerPreCheckoutQueryBuilder {
   pub fn build(&self) -> AnswerPreCheckoutQuery { self.inner.clone() }
 
    
-  pub fn pre_checkout_query_id(&mut self, pre_checkout_query_id: isize) -> &mut Self {
+  pub fn pre_checkout_query_id(&mut self, pre_checkout_query_id: i64) -> &mut Self {
     self.inner.pre_checkout_query_id = pre_checkout_query_id;
     self
   }
@@ -1666,7 +1666,7 @@ pub struct AnswerShippingQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the shipping query
-  shipping_query_id: isize,
+  #[serde(with = "crate::types::int64")] shipping_query_id: i64,
   /// Available shipping options
   shipping_options: Vec<ShippingOption>,
   /// An error message, empty on success
@@ -1694,7 +1694,7 @@ impl AnswerShippingQuery {
     RTDAnswerShippingQueryBuilder { inner }
   }
 
-  pub fn shipping_query_id(&self) -> isize { self.shipping_query_id }
+  pub fn shipping_query_id(&self) -> i64 { self.shipping_query_id }
 
   pub fn shipping_options(&self) -> &Vec<ShippingOption> { &self.shipping_options }
 
@@ -1711,7 +1711,7 @@ impl RTDAnswerShippingQueryBuilder {
   pub fn build(&self) -> AnswerShippingQuery { self.inner.clone() }
 
    
-  pub fn shipping_query_id(&mut self, shipping_query_id: isize) -> &mut Self {
+  pub fn shipping_query_id(&mut self, shipping_query_id: i64) -> &mut Self {
     self.inner.shipping_query_id = shipping_query_id;
     self
   }
@@ -2358,7 +2358,7 @@ pub struct ChangeStickerSet {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the sticker set
-  set_id: isize,
+  #[serde(with = "crate::types::int64")] set_id: i64,
   /// The new value of is_installed
   is_installed: bool,
   /// The new value of is_archived. A sticker set can't be installed and archived simultaneously
@@ -2386,7 +2386,7 @@ impl ChangeStickerSet {
     RTDChangeStickerSetBuilder { inner }
   }
 
-  pub fn set_id(&self) -> isize { self.set_id }
+  pub fn set_id(&self) -> i64 { self.set_id }
 
   pub fn is_installed(&self) -> bool { self.is_installed }
 
@@ -2403,7 +2403,7 @@ impl RTDChangeStickerSetBuilder {
   pub fn build(&self) -> ChangeStickerSet { self.inner.clone() }
 
    
-  pub fn set_id(&mut self, set_id: isize) -> &mut Self {
+  pub fn set_id(&mut self, set_id: i64) -> &mut Self {
     self.inner.set_id = set_id;
     self
   }
@@ -6302,7 +6302,7 @@ pub struct DeleteProfilePhoto {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the profile photo to delete
-  profile_photo_id: isize,
+  #[serde(with = "crate::types::int64")] profile_photo_id: i64,
   
 }
 
@@ -6326,7 +6326,7 @@ impl DeleteProfilePhoto {
     RTDDeleteProfilePhotoBuilder { inner }
   }
 
-  pub fn profile_photo_id(&self) -> isize { self.profile_photo_id }
+  pub fn profile_photo_id(&self) -> i64 { self.profile_photo_id }
 
 }
 
@@ -6339,7 +6339,7 @@ impl RTDDeleteProfilePhotoBuilder {
   pub fn build(&self) -> DeleteProfilePhoto { self.inner.clone() }
 
    
-  pub fn profile_photo_id(&mut self, profile_photo_id: isize) -> &mut Self {
+  pub fn profile_photo_id(&mut self, profile_photo_id: i64) -> &mut Self {
     self.inner.profile_photo_id = profile_photo_id;
     self
   }
@@ -6688,7 +6688,7 @@ pub struct DiscardCall {
   /// True, if the call was a video call
   is_video: bool,
   /// Identifier of the connection used during the call
-  connection_id: isize,
+  #[serde(with = "crate::types::int64")] connection_id: i64,
   
 }
 
@@ -6720,7 +6720,7 @@ impl DiscardCall {
 
   pub fn is_video(&self) -> bool { self.is_video }
 
-  pub fn connection_id(&self) -> isize { self.connection_id }
+  pub fn connection_id(&self) -> i64 { self.connection_id }
 
 }
 
@@ -6757,7 +6757,7 @@ impl RTDDiscardCallBuilder {
   }
 
    
-  pub fn connection_id(&mut self, connection_id: isize) -> &mut Self {
+  pub fn connection_id(&mut self, connection_id: i64) -> &mut Self {
     self.inner.connection_id = connection_id;
     self
   }
@@ -6846,7 +6846,7 @@ pub struct DisconnectWebsite {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Website identifier
-  website_id: isize,
+  #[serde(with = "crate::types::int64")] website_id: i64,
   
 }
 
@@ -6870,7 +6870,7 @@ impl DisconnectWebsite {
     RTDDisconnectWebsiteBuilder { inner }
   }
 
-  pub fn website_id(&self) -> isize { self.website_id }
+  pub fn website_id(&self) -> i64 { self.website_id }
 
 }
 
@@ -6883,7 +6883,7 @@ impl RTDDisconnectWebsiteBuilder {
   pub fn build(&self) -> DisconnectWebsite { self.inner.clone() }
 
    
-  pub fn website_id(&mut self, website_id: isize) -> &mut Self {
+  pub fn website_id(&mut self, website_id: i64) -> &mut Self {
     self.inner.website_id = website_id;
     self
   }
@@ -8704,7 +8704,7 @@ pub struct FinishFileGeneration {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The identifier of the generation process
-  generation_id: isize,
+  #[serde(with = "crate::types::int64")] generation_id: i64,
   /// If passed, the file generation has failed and must be terminated; pass null if the file generation succeeded
   error: Error,
   
@@ -8730,7 +8730,7 @@ impl FinishFileGeneration {
     RTDFinishFileGenerationBuilder { inner }
   }
 
-  pub fn generation_id(&self) -> isize { self.generation_id }
+  pub fn generation_id(&self) -> i64 { self.generation_id }
 
   pub fn error(&self) -> &Error { &self.error }
 
@@ -8745,7 +8745,7 @@ impl RTDFinishFileGenerationBuilder {
   pub fn build(&self) -> FinishFileGeneration { self.inner.clone() }
 
    
-  pub fn generation_id(&mut self, generation_id: isize) -> &mut Self {
+  pub fn generation_id(&mut self, generation_id: i64) -> &mut Self {
     self.inner.generation_id = generation_id;
     self
   }
@@ -9338,7 +9338,7 @@ pub struct GetArchivedStickerSets {
   /// Pass true to return mask stickers sets; pass false to return ordinary sticker sets
   is_masks: bool,
   /// Identifier of the sticker set from which to return the result
-  offset_sticker_set_id: isize,
+  #[serde(with = "crate::types::int64")] offset_sticker_set_id: i64,
   /// The maximum number of sticker sets to return; up to 100
   limit: i64,
   
@@ -9366,7 +9366,7 @@ impl GetArchivedStickerSets {
 
   pub fn is_masks(&self) -> bool { self.is_masks }
 
-  pub fn offset_sticker_set_id(&self) -> isize { self.offset_sticker_set_id }
+  pub fn offset_sticker_set_id(&self) -> i64 { self.offset_sticker_set_id }
 
   pub fn limit(&self) -> i64 { self.limit }
 
@@ -9387,7 +9387,7 @@ impl RTDGetArchivedStickerSetsBuilder {
   }
 
    
-  pub fn offset_sticker_set_id(&mut self, offset_sticker_set_id: isize) -> &mut Self {
+  pub fn offset_sticker_set_id(&mut self, offset_sticker_set_id: i64) -> &mut Self {
     self.inner.offset_sticker_set_id = offset_sticker_set_id;
     self
   }
@@ -10128,7 +10128,7 @@ pub struct GetCallbackQueryMessage {
   /// Message identifier
   message_id: i64,
   /// Identifier of the callback query
-  callback_query_id: isize,
+  #[serde(with = "crate::types::int64")] callback_query_id: i64,
   
 }
 
@@ -10156,7 +10156,7 @@ impl GetCallbackQueryMessage {
 
   pub fn message_id(&self) -> i64 { self.message_id }
 
-  pub fn callback_query_id(&self) -> isize { self.callback_query_id }
+  pub fn callback_query_id(&self) -> i64 { self.callback_query_id }
 
 }
 
@@ -10181,7 +10181,7 @@ impl RTDGetCallbackQueryMessageBuilder {
   }
 
    
-  pub fn callback_query_id(&mut self, callback_query_id: isize) -> &mut Self {
+  pub fn callback_query_id(&mut self, callback_query_id: i64) -> &mut Self {
     self.inner.callback_query_id = callback_query_id;
     self
   }
@@ -10420,7 +10420,7 @@ pub struct GetChatEventLog {
   /// Search query by which to filter events
   query: String,
   /// Identifier of an event from which to return results. Use 0 to get results from the latest events
-  from_event_id: isize,
+  #[serde(with = "crate::types::int64")] from_event_id: i64,
   /// The maximum number of events to return; up to 100
   limit: i64,
   /// The types of events to return; pass null to get chat events of all types
@@ -10454,7 +10454,7 @@ impl GetChatEventLog {
 
   pub fn query(&self) -> &String { &self.query }
 
-  pub fn from_event_id(&self) -> isize { self.from_event_id }
+  pub fn from_event_id(&self) -> i64 { self.from_event_id }
 
   pub fn limit(&self) -> i64 { self.limit }
 
@@ -10485,7 +10485,7 @@ impl RTDGetChatEventLogBuilder {
   }
 
    
-  pub fn from_event_id(&mut self, from_event_id: isize) -> &mut Self {
+  pub fn from_event_id(&mut self, from_event_id: i64) -> &mut Self {
     self.inner.from_event_id = from_event_id;
     self
   }
@@ -18454,7 +18454,7 @@ pub struct GetStickerSet {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the sticker set
-  set_id: isize,
+  #[serde(with = "crate::types::int64")] set_id: i64,
   
 }
 
@@ -18478,7 +18478,7 @@ impl GetStickerSet {
     RTDGetStickerSetBuilder { inner }
   }
 
-  pub fn set_id(&self) -> isize { self.set_id }
+  pub fn set_id(&self) -> i64 { self.set_id }
 
 }
 
@@ -18491,7 +18491,7 @@ impl RTDGetStickerSetBuilder {
   pub fn build(&self) -> GetStickerSet { self.inner.clone() }
 
    
-  pub fn set_id(&mut self, set_id: isize) -> &mut Self {
+  pub fn set_id(&mut self, set_id: i64) -> &mut Self {
     self.inner.set_id = set_id;
     self
   }
@@ -22264,7 +22264,7 @@ pub struct RemoveBackground {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The background identifier
-  background_id: isize,
+  #[serde(with = "crate::types::int64")] background_id: i64,
   
 }
 
@@ -22288,7 +22288,7 @@ impl RemoveBackground {
     RTDRemoveBackgroundBuilder { inner }
   }
 
-  pub fn background_id(&self) -> isize { self.background_id }
+  pub fn background_id(&self) -> i64 { self.background_id }
 
 }
 
@@ -22301,7 +22301,7 @@ impl RTDRemoveBackgroundBuilder {
   pub fn build(&self) -> RemoveBackground { self.inner.clone() }
 
    
-  pub fn background_id(&mut self, background_id: isize) -> &mut Self {
+  pub fn background_id(&mut self, background_id: i64) -> &mut Self {
     self.inner.background_id = background_id;
     self
   }
@@ -23258,7 +23258,7 @@ pub struct ReorderInstalledStickerSets {
   /// Pass true to change the order of mask sticker sets; pass false to change the order of ordinary sticker sets
   is_masks: bool,
   /// Identifiers of installed sticker sets in the new correct order
-  sticker_set_ids: Vec<isize>,
+  #[serde(with = "crate::types::int64_vec")] sticker_set_ids: Vec<i64>,
   
 }
 
@@ -23284,7 +23284,7 @@ impl ReorderInstalledStickerSets {
 
   pub fn is_masks(&self) -> bool { self.is_masks }
 
-  pub fn sticker_set_ids(&self) -> &Vec<isize> { &self.sticker_set_ids }
+  pub fn sticker_set_ids(&self) -> &Vec<i64> { &self.sticker_set_ids }
 
 }
 
@@ -23303,7 +23303,7 @@ impl RTDReorderInstalledStickerSetsBuilder {
   }
 
    
-  pub fn sticker_set_ids(&mut self, sticker_set_ids: Vec<isize>) -> &mut Self {
+  pub fn sticker_set_ids(&mut self, sticker_set_ids: Vec<i64>) -> &mut Self {
     self.inner.sticker_set_ids = sticker_set_ids;
     self
   }
@@ -27036,7 +27036,7 @@ pub struct SendInlineQueryResultMessage {
   /// Options to be used to send the message; pass null to use default options
   options: MessageSendOptions,
   /// Identifier of the inline query
-  query_id: isize,
+  #[serde(with = "crate::types::int64")] query_id: i64,
   /// Identifier of the inline result
   result_id: String,
   /// If true, there will be no mention of a bot, via which the message is sent. Can be used only for bots GetOption("animation_search_bot_username"), GetOption("photo_search_bot_username") and GetOption("venue_search_bot_username")
@@ -27072,7 +27072,7 @@ impl SendInlineQueryResultMessage {
 
   pub fn options(&self) -> &MessageSendOptions { &self.options }
 
-  pub fn query_id(&self) -> isize { self.query_id }
+  pub fn query_id(&self) -> i64 { self.query_id }
 
   pub fn result_id(&self) -> &String { &self.result_id }
 
@@ -27113,7 +27113,7 @@ impl RTDSendInlineQueryResultMessageBuilder {
   }
 
    
-  pub fn query_id(&mut self, query_id: isize) -> &mut Self {
+  pub fn query_id(&mut self, query_id: i64) -> &mut Self {
     self.inner.query_id = query_id;
     self
   }
@@ -27464,7 +27464,7 @@ pub struct SendPaymentForm {
   /// Message identifier
   message_id: i64,
   /// Payment form identifier returned by getPaymentForm
-  payment_form_id: isize,
+  #[serde(with = "crate::types::int64")] payment_form_id: i64,
   /// Identifier returned by validateOrderInfo, or an empty string
   order_info_id: String,
   /// Identifier of a chosen shipping option, if applicable
@@ -27500,7 +27500,7 @@ impl SendPaymentForm {
 
   pub fn message_id(&self) -> i64 { self.message_id }
 
-  pub fn payment_form_id(&self) -> isize { self.payment_form_id }
+  pub fn payment_form_id(&self) -> i64 { self.payment_form_id }
 
   pub fn order_info_id(&self) -> &String { &self.order_info_id }
 
@@ -27533,7 +27533,7 @@ impl RTDSendPaymentFormBuilder {
   }
 
    
-  pub fn payment_form_id(&mut self, payment_form_id: isize) -> &mut Self {
+  pub fn payment_form_id(&mut self, payment_form_id: i64) -> &mut Self {
     self.inner.payment_form_id = payment_form_id;
     self
   }
@@ -29704,7 +29704,7 @@ pub struct SetFileGenerationProgress {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The identifier of the generation process
-  generation_id: isize,
+  #[serde(with = "crate::types::int64")] generation_id: i64,
   /// Expected size of the generated file, in bytes; 0 if unknown
   expected_size: i64,
   /// The number of bytes already generated
@@ -29732,7 +29732,7 @@ impl SetFileGenerationProgress {
     RTDSetFileGenerationProgressBuilder { inner }
   }
 
-  pub fn generation_id(&self) -> isize { self.generation_id }
+  pub fn generation_id(&self) -> i64 { self.generation_id }
 
   pub fn expected_size(&self) -> i64 { self.expected_size }
 
@@ -29749,7 +29749,7 @@ impl RTDSetFileGenerationProgressBuilder {
   pub fn build(&self) -> SetFileGenerationProgress { self.inner.clone() }
 
    
-  pub fn generation_id(&mut self, generation_id: isize) -> &mut Self {
+  pub fn generation_id(&mut self, generation_id: i64) -> &mut Self {
     self.inner.generation_id = generation_id;
     self
   }
@@ -31668,7 +31668,7 @@ pub struct SetSupergroupStickerSet {
   /// Identifier of the supergroup
   supergroup_id: i64,
   /// New value of the supergroup sticker set identifier. Use 0 to remove the supergroup sticker set
-  sticker_set_id: isize,
+  #[serde(with = "crate::types::int64")] sticker_set_id: i64,
   
 }
 
@@ -31694,7 +31694,7 @@ impl SetSupergroupStickerSet {
 
   pub fn supergroup_id(&self) -> i64 { self.supergroup_id }
 
-  pub fn sticker_set_id(&self) -> isize { self.sticker_set_id }
+  pub fn sticker_set_id(&self) -> i64 { self.sticker_set_id }
 
 }
 
@@ -31713,7 +31713,7 @@ impl RTDSetSupergroupStickerSetBuilder {
   }
 
    
-  pub fn sticker_set_id(&mut self, sticker_set_id: isize) -> &mut Self {
+  pub fn sticker_set_id(&mut self, sticker_set_id: i64) -> &mut Self {
     self.inner.sticker_set_id = sticker_set_id;
     self
   }
@@ -32650,7 +32650,7 @@ pub struct TerminateSession {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Session identifier
-  session_id: isize,
+  #[serde(with = "crate::types::int64")] session_id: i64,
   
 }
 
@@ -32674,7 +32674,7 @@ impl TerminateSession {
     RTDTerminateSessionBuilder { inner }
   }
 
-  pub fn session_id(&self) -> isize { self.session_id }
+  pub fn session_id(&self) -> i64 { self.session_id }
 
 }
 
@@ -32687,7 +32687,7 @@ impl RTDTerminateSessionBuilder {
   pub fn build(&self) -> TerminateSession { self.inner.clone() }
 
    
-  pub fn session_id(&mut self, session_id: isize) -> &mut Self {
+  pub fn session_id(&mut self, session_id: i64) -> &mut Self {
     self.inner.session_id = session_id;
     self
   }
@@ -34568,7 +34568,7 @@ pub struct ToggleSessionCanAcceptCalls {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Session identifier
-  session_id: isize,
+  #[serde(with = "crate::types::int64")] session_id: i64,
   /// True, if incoming calls can be accepted by the session
   can_accept_calls: bool,
   
@@ -34594,7 +34594,7 @@ impl ToggleSessionCanAcceptCalls {
     RTDToggleSessionCanAcceptCallsBuilder { inner }
   }
 
-  pub fn session_id(&self) -> isize { self.session_id }
+  pub fn session_id(&self) -> i64 { self.session_id }
 
   pub fn can_accept_calls(&self) -> bool { self.can_accept_calls }
 
@@ -34609,7 +34609,7 @@ impl RTDToggleSessionCanAcceptCallsBuilder {
   pub fn build(&self) -> ToggleSessionCanAcceptCalls { self.inner.clone() }
 
    
-  pub fn session_id(&mut self, session_id: isize) -> &mut Self {
+  pub fn session_id(&mut self, session_id: i64) -> &mut Self {
     self.inner.session_id = session_id;
     self
   }
@@ -34646,7 +34646,7 @@ pub struct ToggleSessionCanAcceptSecretChats {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Session identifier
-  session_id: isize,
+  #[serde(with = "crate::types::int64")] session_id: i64,
   /// True, if incoming secret chats can be accepted by the session
   can_accept_secret_chats: bool,
   
@@ -34672,7 +34672,7 @@ impl ToggleSessionCanAcceptSecretChats {
     RTDToggleSessionCanAcceptSecretChatsBuilder { inner }
   }
 
-  pub fn session_id(&self) -> isize { self.session_id }
+  pub fn session_id(&self) -> i64 { self.session_id }
 
   pub fn can_accept_secret_chats(&self) -> bool { self.can_accept_secret_chats }
 
@@ -34687,7 +34687,7 @@ impl RTDToggleSessionCanAcceptSecretChatsBuilder {
   pub fn build(&self) -> ToggleSessionCanAcceptSecretChats { self.inner.clone() }
 
    
-  pub fn session_id(&mut self, session_id: isize) -> &mut Self {
+  pub fn session_id(&mut self, session_id: i64) -> &mut Self {
     self.inner.session_id = session_id;
     self
   }
@@ -35612,7 +35612,7 @@ pub struct ViewTrendingStickerSets {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifiers of viewed trending sticker sets
-  sticker_set_ids: Vec<isize>,
+  #[serde(with = "crate::types::int64_vec")] sticker_set_ids: Vec<i64>,
   
 }
 
@@ -35636,7 +35636,7 @@ impl ViewTrendingStickerSets {
     RTDViewTrendingStickerSetsBuilder { inner }
   }
 
-  pub fn sticker_set_ids(&self) -> &Vec<isize> { &self.sticker_set_ids }
+  pub fn sticker_set_ids(&self) -> &Vec<i64> { &self.sticker_set_ids }
 
 }
 
@@ -35649,7 +35649,7 @@ impl RTDViewTrendingStickerSetsBuilder {
   pub fn build(&self) -> ViewTrendingStickerSets { self.inner.clone() }
 
    
-  pub fn sticker_set_ids(&mut self, sticker_set_ids: Vec<isize>) -> &mut Self {
+  pub fn sticker_set_ids(&mut self, sticker_set_ids: Vec<i64>) -> &mut Self {
     self.inner.sticker_set_ids = sticker_set_ids;
     self
   }
@@ -35680,7 +35680,7 @@ pub struct WriteGeneratedFilePart {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The identifier of the generation process
-  generation_id: isize,
+  #[serde(with = "crate::types::int64")] generation_id: i64,
   /// The offset from which to write the data to the file
   offset: i64,
   /// The data to write
@@ -35708,7 +35708,7 @@ impl WriteGeneratedFilePart {
     RTDWriteGeneratedFilePartBuilder { inner }
   }
 
-  pub fn generation_id(&self) -> isize { self.generation_id }
+  pub fn generation_id(&self) -> i64 { self.generation_id }
 
   pub fn offset(&self) -> i64 { self.offset }
 
@@ -35725,7 +35725,7 @@ impl RTDWriteGeneratedFilePartBuilder {
   pub fn build(&self) -> WriteGeneratedFilePart { self.inner.clone() }
 
    
-  pub fn generation_id(&mut self, generation_id: isize) -> &mut Self {
+  pub fn generation_id(&mut self, generation_id: i64) -> &mut Self {
     self.inner.generation_id = generation_id;
     self
   }
@@ -16,7 +16,7 @@ pub struct Game {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Game ID
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Game short name. To share a game use the URL https://t.me/{bot_username}?game={game_short_name}
   short_name: String,
   /// Game title
@@ -49,7 +49,7 @@ impl Game {
     RTDGameBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn short_name(&self) -> &String { &self.short_name }
 
@@ -74,7 +74,7 @@ impl RTDGameBuilder {
   pub fn build(&self) -> Game { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
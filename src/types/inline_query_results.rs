@@ -16,7 +16,7 @@ pub struct InlineQueryResults {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique identifier of the inline query
-  inline_query_id: isize,
+  #[serde(with = "crate::types::int64")] inline_query_id: i64,
   /// The offset for the next request. If empty, there are no more results
   next_offset: String,
   /// Results of the query
@@ -45,7 +45,7 @@ impl InlineQueryResults {
     RTDInlineQueryResultsBuilder { inner }
   }
 
-  pub fn inline_query_id(&self) -> isize { self.inline_query_id }
+  pub fn inline_query_id(&self) -> i64 { self.inline_query_id }
 
   pub fn next_offset(&self) -> &String { &self.next_offset }
 
@@ -66,7 +66,7 @@ impl RTDInlineQueryResultsBuilder {
   pub fn build(&self) -> InlineQueryResults { self.inner.clone() }
 
    
-  pub fn inline_query_id(&mut self, inline_query_id: isize) -> &mut Self {
+  pub fn inline_query_id(&mut self, inline_query_id: i64) -> &mut Self {
     self.inner.inline_query_id = inline_query_id;
     self
   }
@@ -171,7 +171,7 @@ pub struct InputBackgroundRemote {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The background identifier
-  background_id: isize,
+  #[serde(with = "crate::types::int64")] background_id: i64,
   
 }
 
@@ -195,7 +195,7 @@ impl InputBackgroundRemote {
     RTDInputBackgroundRemoteBuilder { inner }
   }
 
-  pub fn background_id(&self) -> isize { self.background_id }
+  pub fn background_id(&self) -> i64 { self.background_id }
 
 }
 
@@ -208,7 +208,7 @@ impl RTDInputBackgroundRemoteBuilder {
   pub fn build(&self) -> InputBackgroundRemote { self.inner.clone() }
 
    
-  pub fn background_id(&mut self, background_id: isize) -> &mut Self {
+  pub fn background_id(&mut self, background_id: i64) -> &mut Self {
     self.inner.background_id = background_id;
     self
   }
@@ -191,7 +191,7 @@ pub struct InputChatPhotoPrevious {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the current user's profile photo to reuse
-  chat_photo_id: isize,
+  #[serde(with = "crate::types::int64")] chat_photo_id: i64,
   
 }
 
@@ -215,7 +215,7 @@ impl InputChatPhotoPrevious {
     RTDInputChatPhotoPreviousBuilder { inner }
   }
 
-  pub fn chat_photo_id(&self) -> isize { self.chat_photo_id }
+  pub fn chat_photo_id(&self) -> i64 { self.chat_photo_id }
 
 }
 
@@ -228,7 +228,7 @@ impl RTDInputChatPhotoPreviousBuilder {
   pub fn build(&self) -> InputChatPhotoPrevious { self.inner.clone() }
 
    
-  pub fn chat_photo_id(&mut self, chat_photo_id: isize) -> &mut Self {
+  pub fn chat_photo_id(&mut self, chat_photo_id: i64) -> &mut Self {
     self.inner.chat_photo_id = chat_photo_id;
     self
   }
@@ -76,7 +76,7 @@ pub struct Message {
   /// For channel posts and anonymous group messages, optional author signature
   author_signature: String,
   /// Unique identifier of an album this message belongs to. Only audios, documents, photos and videos can be grouped together in albums
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] media_album_id: isize,
+  #[serde(with = "crate::types::int64")] media_album_id: i64,
   /// If non-empty, contains a human-readable description of the reason why access to this message must be restricted
   restriction_reason: String,
   /// Content of the message
@@ -163,7 +163,7 @@ impl Message {
 
   pub fn author_signature(&self) -> &String { &self.author_signature }
 
-  pub fn media_album_id(&self) -> isize { self.media_album_id }
+  pub fn media_album_id(&self) -> i64 { self.media_album_id }
 
   pub fn restriction_reason(&self) -> &String { &self.restriction_reason }
 
@@ -362,7 +362,7 @@ impl RTDMessageBuilder {
   }
 
    
-  pub fn media_album_id(&mut self, media_album_id: isize) -> &mut Self {
+  pub fn media_album_id(&mut self, media_album_id: i64) -> &mut Self {
     self.inner.media_album_id = media_album_id;
     self
   }
@@ -7,8 +7,16 @@ pub use self::_common::{
   from_json,
   TdType,
 };
+pub use self::_int64::{
+  Int64Format,
+  TdInt64,
+  int64,
+  int64_vec,
+  with_int64_format,
+};
 
 #[macro_use] mod _common;
+mod _int64;
 
 pub use self::account_ttl::*;
 pub use self::address::*;
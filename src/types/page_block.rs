@@ -966,8 +966,8 @@ impl RTDPageBlockCoverBuilder {
   pub fn build(&self) -> PageBlockCover { self.inner.clone() }
 
    
-  pub fn cover<T: AsRef<Box<PageBlock>>>(&mut self, cover: T) -> &mut Self {
-    self.inner.cover = cover.as_ref().clone();
+  pub fn cover<T: AsRef<PageBlock>>(&mut self, cover: T) -> &mut Self {
+    *self.inner.cover = cover.as_ref().clone();
     self
   }
 
@@ -16,7 +16,7 @@ pub struct PaymentForm {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The payment form identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Full information of the invoice
   invoice: Invoice,
   /// Payment form URL
@@ -55,7 +55,7 @@ impl PaymentForm {
     RTDPaymentFormBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn invoice(&self) -> &Invoice { &self.invoice }
 
@@ -86,7 +86,7 @@ impl RTDPaymentFormBuilder {
   pub fn build(&self) -> PaymentForm { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
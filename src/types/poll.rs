@@ -16,7 +16,7 @@ pub struct Poll {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique poll identifier
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Poll question; 1-300 characters
   question: String,
   /// List of poll answer options
@@ -55,7 +55,7 @@ impl Poll {
     RTDPollBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn question(&self) -> &String { &self.question }
 
@@ -86,7 +86,7 @@ impl RTDPollBuilder {
   pub fn build(&self) -> Poll { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -16,7 +16,7 @@ pub struct ProfilePhoto {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Photo identifier; 0 for an empty photo. Can be used to find a photo in a list of user profile photos
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// A small (160x160) user profile photo. The file can be downloaded only before the photo is changed
   small: File,
   /// A big (640x640) user profile photo. The file can be downloaded only before the photo is changed
@@ -45,7 +45,7 @@ impl ProfilePhoto {
     RTDProfilePhotoBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn small(&self) -> &File { &self.small }
 
@@ -66,7 +66,7 @@ impl RTDProfilePhotoBuilder {
   pub fn build(&self) -> ProfilePhoto { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -16,7 +16,7 @@ pub struct PushReceiverId {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The globally unique identifier of push notification subscription
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   
 }
 
@@ -37,7 +37,7 @@ impl PushReceiverId {
     RTDPushReceiverIdBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
 }
 
@@ -50,7 +50,7 @@ impl RTDPushReceiverIdBuilder {
   pub fn build(&self) -> PushReceiverId { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -366,8 +366,8 @@ impl RTDRichTextAnchorLinkBuilder {
   pub fn build(&self) -> RichTextAnchorLink { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -446,8 +446,8 @@ impl RTDRichTextBoldBuilder {
   pub fn build(&self) -> RichTextBold { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -518,8 +518,8 @@ impl RTDRichTextEmailAddressBuilder {
   pub fn build(&self) -> RichTextEmailAddress { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -592,8 +592,8 @@ impl RTDRichTextFixedBuilder {
   pub fn build(&self) -> RichTextFixed { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -748,8 +748,8 @@ impl RTDRichTextItalicBuilder {
   pub fn build(&self) -> RichTextItalic { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -816,8 +816,8 @@ impl RTDRichTextMarkedBuilder {
   pub fn build(&self) -> RichTextMarked { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -888,8 +888,8 @@ impl RTDRichTextPhoneNumberBuilder {
   pub fn build(&self) -> RichTextPhoneNumber { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -925,7 +925,7 @@ pub struct RichTextPlain {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Text
-  text: Box<RichText>,
+  text: String,
   
 }
 
@@ -949,7 +949,7 @@ impl RichTextPlain {
     RTDRichTextPlainBuilder { inner }
   }
 
-  pub fn text(&self) -> &Box<RichText> { &self.text }
+  pub fn text(&self) -> &String { &self.text }
 
 }
 
@@ -962,8 +962,8 @@ impl RTDRichTextPlainBuilder {
   pub fn build(&self) -> RichTextPlain { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<str>>(&mut self, text: T) -> &mut Self {
+    self.inner.text = text.as_ref().to_string();
     self
   }
 
@@ -1038,8 +1038,8 @@ impl RTDRichTextReferenceBuilder {
   pub fn build(&self) -> RichTextReference { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -1118,8 +1118,8 @@ impl RTDRichTextStrikethroughBuilder {
   pub fn build(&self) -> RichTextStrikethrough { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -1186,8 +1186,8 @@ impl RTDRichTextSubscriptBuilder {
   pub fn build(&self) -> RichTextSubscript { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -1254,8 +1254,8 @@ impl RTDRichTextSuperscriptBuilder {
   pub fn build(&self) -> RichTextSuperscript { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -1322,8 +1322,8 @@ impl RTDRichTextUnderlineBuilder {
   pub fn build(&self) -> RichTextUnderline { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -1398,8 +1398,8 @@ impl RTDRichTextUrlBuilder {
   pub fn build(&self) -> RichTextUrl { self.inner.clone() }
 
    
-  pub fn text<T: AsRef<Box<RichText>>>(&mut self, text: T) -> &mut Self {
-    self.inner.text = text.as_ref().clone();
+  pub fn text<T: AsRef<RichText>>(&mut self, text: T) -> &mut Self {
+    *self.inner.text = text.as_ref().clone();
     self
   }
 
@@ -16,7 +16,7 @@ pub struct Session {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Session identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// True, if this session is the current session
   is_current: bool,
   /// True, if a password is needed to complete authorization of the session
@@ -69,7 +69,7 @@ impl Session {
     RTDSessionBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn is_current(&self) -> bool { self.is_current }
 
@@ -114,7 +114,7 @@ impl RTDSessionBuilder {
   pub fn build(&self) -> Session { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
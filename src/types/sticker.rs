@@ -16,7 +16,7 @@ pub struct Sticker {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The identifier of the sticker set to which the sticker belongs; 0 if none
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] set_id: isize,
+  #[serde(with = "crate::types::int64")] set_id: i64,
   /// Sticker width; as defined by the sender
   width: i64,
   /// Sticker height; as defined by the sender
@@ -55,7 +55,7 @@ impl Sticker {
     RTDStickerBuilder { inner }
   }
 
-  pub fn set_id(&self) -> isize { self.set_id }
+  pub fn set_id(&self) -> i64 { self.set_id }
 
   pub fn width(&self) -> i64 { self.width }
 
@@ -86,7 +86,7 @@ impl RTDStickerBuilder {
   pub fn build(&self) -> Sticker { self.inner.clone() }
 
    
-  pub fn set_id(&mut self, set_id: isize) -> &mut Self {
+  pub fn set_id(&mut self, set_id: i64) -> &mut Self {
     self.inner.set_id = set_id;
     self
   }
@@ -16,7 +16,7 @@ pub struct StickerSet {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the sticker set
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Title of the sticker set
   title: String,
   /// Name of the sticker set
@@ -61,7 +61,7 @@ impl StickerSet {
     RTDStickerSetBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn title(&self) -> &String { &self.title }
 
@@ -98,7 +98,7 @@ impl RTDStickerSetBuilder {
   pub fn build(&self) -> StickerSet { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -16,7 +16,7 @@ pub struct StickerSetInfo {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the sticker set
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Title of the sticker set
   title: String,
   /// Name of the sticker set
@@ -61,7 +61,7 @@ impl StickerSetInfo {
     RTDStickerSetInfoBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn title(&self) -> &String { &self.title }
 
@@ -98,7 +98,7 @@ impl RTDStickerSetInfoBuilder {
   pub fn build(&self) -> StickerSetInfo { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
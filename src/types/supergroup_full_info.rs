@@ -46,7 +46,7 @@ pub struct SupergroupFullInfo {
   /// True, if new chat members will have access to old messages. In public or discussion groups and both public and private channels, old messages are always available, so this option affects only private supergroups without a linked chat. The value of this field is only available for chat administrators
   is_all_history_available: bool,
   /// Identifier of the supergroup sticker set; 0 if none
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] sticker_set_id: isize,
+  #[serde(with = "crate::types::int64")] sticker_set_id: i64,
   /// Location to which the supergroup is connected; may be null
   location: Option<ChatLocation>,
   /// Primary invite link for this chat; may be null. For chat administrators with can_invite_users right only
@@ -107,7 +107,7 @@ impl SupergroupFullInfo {
 
   pub fn is_all_history_available(&self) -> bool { self.is_all_history_available }
 
-  pub fn sticker_set_id(&self) -> isize { self.sticker_set_id }
+  pub fn sticker_set_id(&self) -> i64 { self.sticker_set_id }
 
   pub fn location(&self) -> &Option<ChatLocation> { &self.location }
 
@@ -220,7 +220,7 @@ impl RTDSupergroupFullInfoBuilder {
   }
 
    
-  pub fn sticker_set_id(&mut self, sticker_set_id: isize) -> &mut Self {
+  pub fn sticker_set_id(&mut self, sticker_set_id: i64) -> &mut Self {
     self.inner.sticker_set_id = sticker_set_id;
     self
   }
@@ -191,7 +191,7 @@ pub struct TMeUrlTypeStickerSet {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the sticker set
-  sticker_set_id: isize,
+  #[serde(with = "crate::types::int64")] sticker_set_id: i64,
   
 }
 
@@ -215,7 +215,7 @@ impl TMeUrlTypeStickerSet {
     RTDTMeUrlTypeStickerSetBuilder { inner }
   }
 
-  pub fn sticker_set_id(&self) -> isize { self.sticker_set_id }
+  pub fn sticker_set_id(&self) -> i64 { self.sticker_set_id }
 
 }
 
@@ -228,7 +228,7 @@ impl RTDTMeUrlTypeStickerSetBuilder {
   pub fn build(&self) -> TMeUrlTypeStickerSet { self.inner.clone() }
 
    
-  pub fn sticker_set_id(&mut self, sticker_set_id: isize) -> &mut Self {
+  pub fn sticker_set_id(&mut self, sticker_set_id: i64) -> &mut Self {
     self.inner.sticker_set_id = sticker_set_id;
     self
   }
@@ -4075,7 +4075,7 @@ pub struct UpdateFileGenerationStart {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique identifier for the generation process
-  generation_id: isize,
+  #[serde(with = "crate::types::int64")] generation_id: i64,
   /// The path to a file from which a new file is generated; may be empty
   original_path: String,
   /// The path to a file that must be created and where the new file is generated
@@ -4105,7 +4105,7 @@ impl UpdateFileGenerationStart {
     RTDUpdateFileGenerationStartBuilder { inner }
   }
 
-  pub fn generation_id(&self) -> isize { self.generation_id }
+  pub fn generation_id(&self) -> i64 { self.generation_id }
 
   pub fn original_path(&self) -> &String { &self.original_path }
 
@@ -4124,7 +4124,7 @@ impl RTDUpdateFileGenerationStartBuilder {
   pub fn build(&self) -> UpdateFileGenerationStart { self.inner.clone() }
 
    
-  pub fn generation_id(&mut self, generation_id: isize) -> &mut Self {
+  pub fn generation_id(&mut self, generation_id: i64) -> &mut Self {
     self.inner.generation_id = generation_id;
     self
   }
@@ -4173,7 +4173,7 @@ pub struct UpdateFileGenerationStop {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique identifier for the generation process
-  generation_id: isize,
+  #[serde(with = "crate::types::int64")] generation_id: i64,
   
 }
 
@@ -4197,7 +4197,7 @@ impl UpdateFileGenerationStop {
     RTDUpdateFileGenerationStopBuilder { inner }
   }
 
-  pub fn generation_id(&self) -> isize { self.generation_id }
+  pub fn generation_id(&self) -> i64 { self.generation_id }
 
 }
 
@@ -4210,7 +4210,7 @@ impl RTDUpdateFileGenerationStopBuilder {
   pub fn build(&self) -> UpdateFileGenerationStop { self.inner.clone() }
 
    
-  pub fn generation_id(&mut self, generation_id: isize) -> &mut Self {
+  pub fn generation_id(&mut self, generation_id: i64) -> &mut Self {
     self.inner.generation_id = generation_id;
     self
   }
@@ -4467,7 +4467,7 @@ pub struct UpdateInstalledStickerSets {
   /// True, if the list of installed mask sticker sets was updated
   is_masks: bool,
   /// The new list of installed ordinary sticker sets
-  sticker_set_ids: Vec<isize>,
+  #[serde(with = "crate::types::int64_vec")] sticker_set_ids: Vec<i64>,
   
 }
 
@@ -4493,7 +4493,7 @@ impl UpdateInstalledStickerSets {
 
   pub fn is_masks(&self) -> bool { self.is_masks }
 
-  pub fn sticker_set_ids(&self) -> &Vec<isize> { &self.sticker_set_ids }
+  pub fn sticker_set_ids(&self) -> &Vec<i64> { &self.sticker_set_ids }
 
 }
 
@@ -4512,7 +4512,7 @@ impl RTDUpdateInstalledStickerSetsBuilder {
   }
 
    
-  pub fn sticker_set_ids(&mut self, sticker_set_ids: Vec<isize>) -> &mut Self {
+  pub fn sticker_set_ids(&mut self, sticker_set_ids: Vec<i64>) -> &mut Self {
     self.inner.sticker_set_ids = sticker_set_ids;
     self
   }
@@ -5569,7 +5569,7 @@ pub struct UpdateNewCallbackQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique query identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Identifier of the user who sent the query
   sender_user_id: i64,
   /// Identifier of the chat where the query was sent
@@ -5577,7 +5577,7 @@ pub struct UpdateNewCallbackQuery {
   /// Identifier of the message, from which the query originated
   message_id: i64,
   /// Identifier that uniquely corresponds to the chat to which the message was sent
-  chat_instance: isize,
+  #[serde(with = "crate::types::int64")] chat_instance: i64,
   /// Query payload
   payload: CallbackQueryPayload,
   
@@ -5603,7 +5603,7 @@ impl UpdateNewCallbackQuery {
     RTDUpdateNewCallbackQueryBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
 
@@ -5611,7 +5611,7 @@ impl UpdateNewCallbackQuery {
 
   pub fn message_id(&self) -> i64 { self.message_id }
 
-  pub fn chat_instance(&self) -> isize { self.chat_instance }
+  pub fn chat_instance(&self) -> i64 { self.chat_instance }
 
   pub fn payload(&self) -> &CallbackQueryPayload { &self.payload }
 
@@ -5626,7 +5626,7 @@ impl RTDUpdateNewCallbackQueryBuilder {
   pub fn build(&self) -> UpdateNewCallbackQuery { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -5650,7 +5650,7 @@ impl RTDUpdateNewCallbackQueryBuilder {
   }
 
    
-  pub fn chat_instance(&mut self, chat_instance: isize) -> &mut Self {
+  pub fn chat_instance(&mut self, chat_instance: i64) -> &mut Self {
     self.inner.chat_instance = chat_instance;
     self
   }
@@ -6019,7 +6019,7 @@ pub struct UpdateNewCustomQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// The query identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// JSON-serialized query data
   data: String,
   /// Query timeout
@@ -6047,7 +6047,7 @@ impl UpdateNewCustomQuery {
     RTDUpdateNewCustomQueryBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn data(&self) -> &String { &self.data }
 
@@ -6064,7 +6064,7 @@ impl RTDUpdateNewCustomQueryBuilder {
   pub fn build(&self) -> UpdateNewCustomQuery { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -6107,13 +6107,13 @@ pub struct UpdateNewInlineCallbackQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique query identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Identifier of the user who sent the query
   sender_user_id: i64,
   /// Identifier of the inline message, from which the query originated
   inline_message_id: String,
   /// An identifier uniquely corresponding to the chat a message was sent to
-  chat_instance: isize,
+  #[serde(with = "crate::types::int64")] chat_instance: i64,
   /// Query payload
   payload: CallbackQueryPayload,
   
@@ -6139,13 +6139,13 @@ impl UpdateNewInlineCallbackQuery {
     RTDUpdateNewInlineCallbackQueryBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
 
   pub fn inline_message_id(&self) -> &String { &self.inline_message_id }
 
-  pub fn chat_instance(&self) -> isize { self.chat_instance }
+  pub fn chat_instance(&self) -> i64 { self.chat_instance }
 
   pub fn payload(&self) -> &CallbackQueryPayload { &self.payload }
 
@@ -6160,7 +6160,7 @@ impl RTDUpdateNewInlineCallbackQueryBuilder {
   pub fn build(&self) -> UpdateNewInlineCallbackQuery { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -6178,7 +6178,7 @@ impl RTDUpdateNewInlineCallbackQueryBuilder {
   }
 
    
-  pub fn chat_instance(&mut self, chat_instance: isize) -> &mut Self {
+  pub fn chat_instance(&mut self, chat_instance: i64) -> &mut Self {
     self.inner.chat_instance = chat_instance;
     self
   }
@@ -6215,7 +6215,7 @@ pub struct UpdateNewInlineQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique query identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Identifier of the user who sent the query
   sender_user_id: i64,
   /// User location; may be null
@@ -6249,7 +6249,7 @@ impl UpdateNewInlineQuery {
     RTDUpdateNewInlineQueryBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
 
@@ -6272,7 +6272,7 @@ impl RTDUpdateNewInlineQueryBuilder {
   pub fn build(&self) -> UpdateNewInlineQuery { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -6401,7 +6401,7 @@ pub struct UpdateNewPreCheckoutQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique query identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Identifier of the user who sent the query
   sender_user_id: i64,
   /// Currency for the product price
@@ -6437,7 +6437,7 @@ impl UpdateNewPreCheckoutQuery {
     RTDUpdateNewPreCheckoutQueryBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
 
@@ -6462,7 +6462,7 @@ impl RTDUpdateNewPreCheckoutQueryBuilder {
   pub fn build(&self) -> UpdateNewPreCheckoutQuery { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -6529,7 +6529,7 @@ pub struct UpdateNewShippingQuery {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique query identifier
-  id: isize,
+  #[serde(with = "crate::types::int64")] id: i64,
   /// Identifier of the user who sent the query
   sender_user_id: i64,
   /// Invoice payload
@@ -6559,7 +6559,7 @@ impl UpdateNewShippingQuery {
     RTDUpdateNewShippingQueryBuilder { inner }
   }
 
-  pub fn id(&self) -> isize { self.id }
+  pub fn id(&self) -> i64 { self.id }
 
   pub fn sender_user_id(&self) -> i64 { self.sender_user_id }
 
@@ -6578,7 +6578,7 @@ impl RTDUpdateNewShippingQueryBuilder {
   pub fn build(&self) -> UpdateNewShippingQuery { self.inner.clone() }
 
    
-  pub fn id(&mut self, id: isize) -> &mut Self {
+  pub fn id(&mut self, id: i64) -> &mut Self {
     self.inner.id = id;
     self
   }
@@ -6989,7 +6989,7 @@ pub struct UpdatePollAnswer {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Unique poll identifier
-  poll_id: isize,
+  #[serde(with = "crate::types::int64")] poll_id: i64,
   /// The user, who changed the answer to the poll
   user_id: i64,
   /// 0-based identifiers of answer options, chosen by the user
@@ -7017,7 +7017,7 @@ impl UpdatePollAnswer {
     RTDUpdatePollAnswerBuilder { inner }
   }
 
-  pub fn poll_id(&self) -> isize { self.poll_id }
+  pub fn poll_id(&self) -> i64 { self.poll_id }
 
   pub fn user_id(&self) -> i64 { self.user_id }
 
@@ -7034,7 +7034,7 @@ impl RTDUpdatePollAnswerBuilder {
   pub fn build(&self) -> UpdatePollAnswer { self.inner.clone() }
 
    
-  pub fn poll_id(&mut self, poll_id: isize) -> &mut Self {
+  pub fn poll_id(&mut self, poll_id: i64) -> &mut Self {
     self.inner.poll_id = poll_id;
     self
   }
@@ -7739,7 +7739,7 @@ pub struct UpdateSupergroupFullInfo {
   #[serde(rename(serialize = "@extra", deserialize = "@extra"))]
   extra: Option<String>,
   /// Identifier of the supergroup or channel
-  #[serde(deserialize_with = "serde_aux::field_attributes::deserialize_number_from_string")] supergroup_id: i64,
+  #[serde(with = "crate::types::int64")] supergroup_id: i64,
   /// New full information about the supergroup
   supergroup_full_info: SupergroupFullInfo,
   
@@ -0,0 +1,519 @@
+//! Network and storage usage accounting.
+//!
+//! `NetworkUsage` and `StorageUsage` flatten the nested lists returned by `getNetworkStatistics` and
+//! `getStorageStatistics` into totals, and `CleanupPlanner` turns storage statistics into an
+//! `OptimizeStorage` request.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Add, AddAssign, Sub};
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Amount of sent and received bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Traffic {
+  sent_bytes: i64,
+  received_bytes: i64,
+}
+
+impl Traffic {
+  pub fn new(sent_bytes: i64, received_bytes: i64) -> Self { Self { sent_bytes, received_bytes } }
+
+  pub fn sent_bytes(&self) -> i64 { self.sent_bytes }
+
+  pub fn received_bytes(&self) -> i64 { self.received_bytes }
+
+  pub fn total_bytes(&self) -> i64 { self.sent_bytes + self.received_bytes }
+}
+
+impl Add for Traffic {
+  type Output = Traffic;
+  fn add(self, other: Traffic) -> Traffic {
+    Traffic::new(self.sent_bytes + other.sent_bytes, self.received_bytes + other.received_bytes)
+  }
+}
+
+impl AddAssign for Traffic {
+  fn add_assign(&mut self, other: Traffic) { *self = *self + other; }
+}
+
+impl Sub for Traffic {
+  type Output = Traffic;
+  fn sub(self, other: Traffic) -> Traffic {
+    Traffic::new(self.sent_bytes - other.sent_bytes, self.received_bytes - other.received_bytes)
+  }
+}
+
+fn sub_maps<K: Ord + Clone, V: Copy + Default + Sub<Output=V>>(current: &BTreeMap<K, V>, previous: &BTreeMap<K, V>) -> BTreeMap<K, V> {
+  let mut delta = BTreeMap::new();
+  for key in current.keys().chain(previous.keys()) {
+    let value = current.get(key).copied().unwrap_or_default() - previous.get(key).copied().unwrap_or_default();
+    delta.insert(key.clone(), value);
+  }
+  delta
+}
+
+/// Network usage aggregated from `NetworkStatistics`.
+///
+/// Network and file types are keyed by their TDLib names, e.g. `networkTypeWiFi` or `fileTypePhoto`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkUsage {
+  since_date: i64,
+  by_network_type: BTreeMap<String, Traffic>,
+  by_file_type: BTreeMap<String, Traffic>,
+  calls: Traffic,
+  call_duration: f64,
+}
+
+impl NetworkUsage {
+  pub fn new<N: AsRef<NetworkStatistics>>(statistics: N) -> Self {
+    let statistics = statistics.as_ref();
+    let mut usage = NetworkUsage { since_date: statistics.since_date(), ..Default::default() };
+    for entry in statistics.entries() {
+      match entry {
+        NetworkStatisticsEntry::File(file) => {
+          let traffic = Traffic::new(file.sent_bytes(), file.received_bytes());
+          *usage.by_network_type.entry(file.network_type().td_name().to_string()).or_default() += traffic;
+          *usage.by_file_type.entry(file.file_type().td_name().to_string()).or_default() += traffic;
+        }
+        NetworkStatisticsEntry::Call(call) => {
+          let traffic = Traffic::new(call.sent_bytes(), call.received_bytes());
+          *usage.by_network_type.entry(call.network_type().td_name().to_string()).or_default() += traffic;
+          usage.calls += traffic;
+          usage.call_duration += call.duration() as f64;
+        }
+        _ => {}
+      }
+    }
+    usage
+  }
+
+  /// Point in time (Unix timestamp) from which the statistics are collected
+  pub fn since_date(&self) -> i64 { self.since_date }
+
+  pub fn by_network_type(&self) -> &BTreeMap<String, Traffic> { &self.by_network_type }
+
+  /// Traffic of files, calls aren't included
+  pub fn by_file_type(&self) -> &BTreeMap<String, Traffic> { &self.by_file_type }
+
+  pub fn network_type<N: AsRef<NetworkType>>(&self, network_type: N) -> Traffic {
+    self.by_network_type.get(network_type.as_ref().td_name()).copied().unwrap_or_default()
+  }
+
+  pub fn file_type<F: AsRef<FileType>>(&self, file_type: F) -> Traffic {
+    self.by_file_type.get(file_type.as_ref().td_name()).copied().unwrap_or_default()
+  }
+
+  pub fn calls(&self) -> Traffic { self.calls }
+
+  /// Total duration of calls, in seconds
+  pub fn call_duration(&self) -> f64 { self.call_duration }
+
+  pub fn total(&self) -> Traffic {
+    self.by_network_type.values().fold(Traffic::default(), |total, traffic| total + *traffic)
+  }
+
+  /// Usage between `previous` and this snapshot.
+  ///
+  /// Statistics reset by `resetNetworkStatistics` between the snapshots produce negative values.
+  pub fn delta(&self, previous: &NetworkUsage) -> NetworkUsage {
+    NetworkUsage {
+      since_date: previous.since_date,
+      by_network_type: sub_maps(&self.by_network_type, &previous.by_network_type),
+      by_file_type: sub_maps(&self.by_file_type, &previous.by_file_type),
+      calls: self.calls - previous.calls,
+      call_duration: self.call_duration - previous.call_duration,
+    }
+  }
+}
+
+/// Size and count of stored files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Storage {
+  size: i64,
+  count: i64,
+}
+
+impl Storage {
+  pub fn new(size: i64, count: i64) -> Self { Self { size, count } }
+
+  /// Total size of files, in bytes
+  pub fn size(&self) -> i64 { self.size }
+
+  pub fn count(&self) -> i64 { self.count }
+}
+
+impl Add for Storage {
+  type Output = Storage;
+  fn add(self, other: Storage) -> Storage { Storage::new(self.size + other.size, self.count + other.count) }
+}
+
+impl AddAssign for Storage {
+  fn add_assign(&mut self, other: Storage) { *self = *self + other; }
+}
+
+impl Sub for Storage {
+  type Output = Storage;
+  fn sub(self, other: Storage) -> Storage { Storage::new(self.size - other.size, self.count - other.count) }
+}
+
+/// Storage usage aggregated from `StorageStatistics`.
+///
+/// Chat identifier 0 holds files not belonging to any chat.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageUsage {
+  total: Storage,
+  by_chat: BTreeMap<i64, Storage>,
+  by_file_type: BTreeMap<String, Storage>,
+}
+
+impl StorageUsage {
+  pub fn new<S: AsRef<StorageStatistics>>(statistics: S) -> Self {
+    let statistics = statistics.as_ref();
+    let mut usage = StorageUsage { total: Storage::new(statistics.size(), statistics.count()), ..Default::default() };
+    for chat in statistics.by_chat() {
+      *usage.by_chat.entry(chat.chat_id()).or_default() += Storage::new(chat.size(), chat.count());
+      for file_type in chat.by_file_type() {
+        *usage.by_file_type.entry(file_type.file_type().td_name().to_string()).or_default() += Storage::new(file_type.size(), file_type.count());
+      }
+    }
+    usage
+  }
+
+  pub fn total(&self) -> Storage { self.total }
+
+  pub fn by_chat(&self) -> &BTreeMap<i64, Storage> { &self.by_chat }
+
+  pub fn by_file_type(&self) -> &BTreeMap<String, Storage> { &self.by_file_type }
+
+  pub fn chat(&self, chat_id: i64) -> Storage { self.by_chat.get(&chat_id).copied().unwrap_or_default() }
+
+  pub fn file_type<F: AsRef<FileType>>(&self, file_type: F) -> Storage {
+    self.by_file_type.get(file_type.as_ref().td_name()).copied().unwrap_or_default()
+  }
+
+  /// Chats ordered by used size, largest first
+  pub fn largest_chats(&self) -> Vec<(i64, Storage)> {
+    let mut chats = self.by_chat.iter().map(|(chat_id, storage)| (*chat_id, *storage)).collect::<Vec<_>>();
+    chats.sort_by_key(|(_, storage)| std::cmp::Reverse(storage.size));
+    chats
+  }
+
+  /// Change of storage usage between `previous` and this snapshot, negative values mean freed space
+  pub fn delta(&self, previous: &StorageUsage) -> StorageUsage {
+    StorageUsage {
+      total: self.total - previous.total,
+      by_chat: sub_maps(&self.by_chat, &previous.by_chat),
+      by_file_type: sub_maps(&self.by_file_type, &previous.by_file_type),
+    }
+  }
+}
+
+/// Cleanup policy of a chat or file type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupPolicy {
+  /// Never delete files
+  Keep,
+  /// Always delete files, even if the target size is already reached
+  Clean,
+  /// Delete files only if it's needed to reach the target size
+  Auto,
+}
+
+/// Storage cleanup computed by `CleanupPlanner`
+#[derive(Debug, Clone)]
+pub struct CleanupPlan {
+  requests: Vec<OptimizeStorage>,
+  current_size: i64,
+  clean_size: i64,
+  freeable_size: i64,
+  target_size: i64,
+}
+
+impl CleanupPlan {
+  /// The requests to send in order, empty if there is nothing to delete
+  pub fn requests(&self) -> &Vec<OptimizeStorage> { &self.requests }
+
+  /// Whether the policies leave nothing to delete
+  pub fn is_empty(&self) -> bool { self.requests.is_empty() }
+
+  pub fn current_size(&self) -> i64 { self.current_size }
+
+  /// Size of files allowed to be deleted by the requests
+  pub fn freeable_size(&self) -> i64 { self.freeable_size }
+
+  /// Expected storage size after the cleanup
+  pub fn expected_size(&self) -> i64 {
+    (self.current_size - self.freeable_size).max(self.target_size.min(self.current_size - self.clean_size))
+  }
+
+  /// Whether the policies allow to reach the target size
+  pub fn meets_target(&self) -> bool { self.current_size - self.freeable_size <= self.target_size }
+}
+
+/// Plans `OptimizeStorage` requests reaching a target storage size.
+///
+/// Files of chats and file types with the `Clean` policy are deleted by requests without a size
+/// limit, then the largest `Auto` chats are cleaned until the target size is reached. TDLib treats
+/// empty `chat_ids` and `file_types` as "all", so no request is made when nothing may be deleted.
+/// Like TDLib, thumbnails, profile photos, stickers and wallpapers are kept unless another policy
+/// is set for them.
+///
+/// ```rust
+/// use rtdlib::usage::{CleanupPlanner, CleanupPolicy};
+/// # use rtdlib::types::*;
+/// # let statistics = StorageStatistics::builder().build();
+/// let plan = CleanupPlanner::new(512 * 1024 * 1024)
+///   .chat(-1001234567890, CleanupPolicy::Keep)
+///   .file_type(FileType::video(FileTypeVideo::builder().build()), CleanupPolicy::Clean)
+///   .plan(&statistics)
+///   .unwrap();
+/// for request in plan.requests() {
+///   // send `request` to TDLib
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CleanupPlanner {
+  target_size: i64,
+  ttl: i64,
+  immunity_delay: i64,
+  chats: HashMap<i64, CleanupPolicy>,
+  file_types: HashMap<String, CleanupPolicy>,
+}
+
+impl CleanupPlanner {
+  /// `target_size` is the wanted total size of files, in bytes
+  pub fn new(target_size: i64) -> Self {
+    let mut file_types = HashMap::new();
+    for name in ["fileTypeThumbnail", "fileTypeProfilePhoto", "fileTypeSticker", "fileTypeWallpaper"].iter() {
+      file_types.insert(name.to_string(), CleanupPolicy::Keep);
+    }
+    Self { target_size, ttl: -1, immunity_delay: -1, chats: HashMap::new(), file_types }
+  }
+
+  /// Passed as `ttl` of `OptimizeStorage`, default is -1 (TDLib default)
+  pub fn ttl(&mut self, ttl: i64) -> &mut Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// Passed as `immunity_delay` of `OptimizeStorage`, default is -1 (TDLib default)
+  pub fn immunity_delay(&mut self, immunity_delay: i64) -> &mut Self {
+    self.immunity_delay = immunity_delay;
+    self
+  }
+
+  pub fn chat(&mut self, chat_id: i64, policy: CleanupPolicy) -> &mut Self {
+    self.chats.insert(chat_id, policy);
+    self
+  }
+
+  pub fn file_type<F: AsRef<FileType>>(&mut self, file_type: F, policy: CleanupPolicy) -> &mut Self {
+    self.file_types.insert(file_type.as_ref().td_name().to_string(), policy);
+    self
+  }
+
+  fn chat_policy(&self, chat_id: i64) -> CleanupPolicy {
+    self.chats.get(&chat_id).copied().unwrap_or(CleanupPolicy::Auto)
+  }
+
+  fn file_type_policy(&self, file_type: &FileType) -> CleanupPolicy {
+    self.file_types.get(file_type.td_name()).copied().unwrap_or(CleanupPolicy::Auto)
+  }
+
+  fn request(&self, size: i64, chat_ids: Vec<i64>, file_types: Vec<FileType>) -> Option<OptimizeStorage> {
+    if chat_ids.is_empty() || file_types.is_empty() { return None }
+    Some(OptimizeStorage::builder()
+      .size(size)
+      .ttl(self.ttl)
+      .count(-1)
+      .immunity_delay(self.immunity_delay)
+      .file_types(file_types)
+      .chat_ids(chat_ids)
+      .return_deleted_file_statistics(true)
+      .chat_limit(-1)
+      .build())
+  }
+
+  pub fn plan<S: AsRef<StorageStatistics>>(&self, statistics: S) -> RTDResult<CleanupPlan> {
+    let statistics = statistics.as_ref();
+    if self.target_size < 0 {
+      return Err(RTDError::custom(format!("invalid cleanup target size {}", self.target_size)));
+    }
+
+    let mut all_types: Vec<FileType> = vec![];
+    let mut clean_types: Vec<FileType> = vec![];
+    let mut auto_types: Vec<FileType> = vec![];
+    let mut clean_chat_ids = vec![];
+    let mut clean_type_chat_ids = vec![];
+    let mut candidates = vec![];
+    let mut clean_size = 0;
+    for chat in statistics.by_chat() {
+      let chat_policy = self.chat_policy(chat.chat_id());
+      if chat_policy == CleanupPolicy::Keep { continue }
+      let (mut cleaned, mut freeable) = (0, 0);
+      for by_file_type in chat.by_file_type() {
+        let file_type = by_file_type.file_type();
+        let type_policy = self.file_type_policy(file_type);
+        if type_policy == CleanupPolicy::Keep { continue }
+        for types in [&mut all_types, if type_policy == CleanupPolicy::Clean { &mut clean_types } else { &mut auto_types }] {
+          if !types.iter().any(|t| t.td_name() == file_type.td_name()) { types.push(file_type.clone()); }
+        }
+        if chat_policy == CleanupPolicy::Clean || type_policy == CleanupPolicy::Clean {
+          cleaned += by_file_type.size();
+        } else {
+          freeable += by_file_type.size();
+        }
+      }
+      if chat_policy == CleanupPolicy::Clean {
+        clean_chat_ids.push(chat.chat_id());
+      } else {
+        if cleaned > 0 { clean_type_chat_ids.push(chat.chat_id()); }
+        if freeable > 0 { candidates.push((chat.chat_id(), freeable)); }
+      }
+      clean_size += cleaned;
+    }
+
+    // the largest `Auto` chats until the target is reached
+    candidates.sort_by_key(|(_, freeable)| std::cmp::Reverse(*freeable));
+    let mut chat_ids = vec![];
+    let mut freeable_size = clean_size;
+    for (chat_id, freeable) in candidates {
+      if statistics.size() - freeable_size <= self.target_size { break }
+      chat_ids.push(chat_id);
+      freeable_size += freeable;
+    }
+
+    let requests = vec![
+      self.request(0, clean_chat_ids, all_types),
+      self.request(0, clean_type_chat_ids, clean_types),
+      self.request(self.target_size, chat_ids, auto_types),
+    ];
+    Ok(CleanupPlan {
+      requests: requests.into_iter().flatten().collect(),
+      current_size: statistics.size(),
+      clean_size,
+      freeable_size,
+      target_size: self.target_size,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::types::*;
+  use crate::usage::*;
+
+  fn by_file_type(file_type: FileType, size: i64) -> StorageStatisticsByFileType {
+    StorageStatisticsByFileType::builder().file_type(file_type).size(size).count(1).build()
+  }
+
+  fn storage_statistics() -> StorageStatistics {
+    let photo = || FileType::photo(FileTypePhoto::builder().build());
+    let video = || FileType::video(FileTypeVideo::builder().build());
+    let sticker = || FileType::sticker(FileTypeSticker::builder().build());
+    let chat = |chat_id: i64, files: Vec<StorageStatisticsByFileType>| StorageStatisticsByChat::builder()
+      .chat_id(chat_id)
+      .size(files.iter().map(|f| f.size()).sum())
+      .count(files.len() as i64)
+      .by_file_type(files)
+      .build();
+    StorageStatistics::builder()
+      .size(1000)
+      .count(6)
+      .by_chat(vec![
+        chat(1, vec![by_file_type(photo(), 100), by_file_type(video(), 400)]),
+        chat(2, vec![by_file_type(photo(), 300), by_file_type(sticker(), 50)]),
+        chat(3, vec![by_file_type(video(), 150)]),
+      ])
+      .build()
+  }
+
+  fn names(request: &OptimizeStorage) -> Vec<&str> {
+    request.file_types().iter().map(|t| t.td_name()).collect()
+  }
+
+  #[test]
+  fn test_network_usage() {
+    let wifi = || NetworkType::wi_fi(NetworkTypeWiFi::builder().build());
+    let mobile = || NetworkType::mobile(NetworkTypeMobile::builder().build());
+    let file = |network_type: NetworkType, sent: i64, received: i64| NetworkStatisticsEntry::file(NetworkStatisticsEntryFile::builder()
+      .file_type(FileType::photo(FileTypePhoto::builder().build()))
+      .network_type(network_type)
+      .sent_bytes(sent)
+      .received_bytes(received)
+      .build());
+    let call = NetworkStatisticsEntry::call(NetworkStatisticsEntryCall::builder()
+      .network_type(mobile())
+      .sent_bytes(10)
+      .received_bytes(20)
+      .duration(60.0)
+      .build());
+    let before = NetworkUsage::new(NetworkStatistics::builder().entries(vec![file(wifi(), 5, 100)]).build());
+    let after = NetworkUsage::new(NetworkStatistics::builder().entries(vec![file(wifi(), 5, 300), file(mobile(), 1, 2), call]).build());
+
+    assert_eq!(after.network_type(wifi()), Traffic::new(5, 300));
+    assert_eq!(after.network_type(mobile()), Traffic::new(11, 22));
+    assert_eq!(after.file_type(FileType::photo(FileTypePhoto::builder().build())), Traffic::new(6, 302));
+    assert_eq!(after.calls(), Traffic::new(10, 20));
+    assert_eq!(after.total().total_bytes(), 338);
+    let delta = after.delta(&before);
+    assert_eq!(delta.network_type(wifi()), Traffic::new(0, 200));
+    assert_eq!(delta.total(), Traffic::new(11, 222));
+  }
+
+  #[test]
+  fn test_storage_usage() {
+    let usage = StorageUsage::new(storage_statistics());
+    assert_eq!(usage.chat(1), Storage::new(500, 2));
+    assert_eq!(usage.file_type(FileType::video(FileTypeVideo::builder().build())), Storage::new(550, 2));
+    assert_eq!(usage.largest_chats()[0].0, 1);
+  }
+
+  #[test]
+  fn test_cleanup_plan() {
+    let statistics = storage_statistics();
+    let plan = CleanupPlanner::new(600).plan(&statistics).unwrap();
+    assert_eq!(plan.requests().len(), 1);
+    assert_eq!(plan.requests()[0].chat_ids(), &vec![1]);
+    assert_eq!(plan.requests()[0].size(), 600);
+    assert!(plan.meets_target());
+    assert_eq!(plan.expected_size(), 600);
+    assert_eq!(names(&plan.requests()[0]), vec!["fileTypePhoto", "fileTypeVideo"]);
+
+    let plan = CleanupPlanner::new(600)
+      .chat(1, CleanupPolicy::Keep)
+      .chat(3, CleanupPolicy::Clean)
+      .plan(&statistics)
+      .unwrap();
+    assert_eq!(plan.requests().len(), 2);
+    assert_eq!((plan.requests()[0].chat_ids(), plan.requests()[0].size()), (&vec![3], 0));
+    assert_eq!(plan.requests()[1].chat_ids(), &vec![2]);
+    assert_eq!(plan.freeable_size(), 450);
+    assert!(plan.meets_target());
+
+    let plan = CleanupPlanner::new(100).chat(1, CleanupPolicy::Keep).plan(&statistics).unwrap();
+    assert!(!plan.meets_target());
+    assert_eq!(plan.expected_size(), 550);
+  }
+
+  #[test]
+  fn test_cleanup_nothing_to_do() {
+    let statistics = storage_statistics();
+    assert!(CleanupPlanner::new(1000).plan(&statistics).unwrap().is_empty());
+    let plan = CleanupPlanner::new(0).chat(1, CleanupPolicy::Keep).chat(2, CleanupPolicy::Keep).chat(3, CleanupPolicy::Keep).plan(&statistics).unwrap();
+    assert!(plan.is_empty());
+    assert_eq!(plan.expected_size(), 1000);
+    let photo = FileType::photo(FileTypePhoto::builder().build());
+    let video = FileType::video(FileTypeVideo::builder().build());
+    assert!(CleanupPlanner::new(0).file_type(photo, CleanupPolicy::Keep).file_type(video, CleanupPolicy::Keep).plan(&statistics).unwrap().is_empty());
+
+    // clean file types are deleted even when the target is met, only in chats having them
+    let plan = CleanupPlanner::new(1000).file_type(FileType::video(FileTypeVideo::builder().build()), CleanupPolicy::Clean).plan(&statistics).unwrap();
+    assert_eq!(plan.requests().len(), 1);
+    assert_eq!(plan.requests()[0].chat_ids(), &vec![1, 3]);
+    assert_eq!(names(&plan.requests()[0]), vec!["fileTypeVideo"]);
+    assert_eq!(plan.expected_size(), 450);
+  }
+}
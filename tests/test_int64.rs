@@ -0,0 +1,42 @@
+use rtdlib::types::*;
+
+const UPDATES: [&str; 3] = [
+  r#"{"@type":"updateNewCallbackQuery","@extra":null,"id":"9223372036854775807","sender_user_id":5,"chat_id":-100,"message_id":1048576,"chat_instance":"-4611686018427387904","payload":{"@type":"callbackQueryPayloadData","@extra":null,"data":"YQ=="}}"#,
+  r#"{"@type":"updateChatPosition","@extra":null,"chat_id":10,"position":{"@type":"chatPosition","@extra":null,"list":{"@type":"chatListMain","@extra":null},"order":"6998514803516342273","is_pinned":false,"source":null}}"#,
+  r#"{"@type":"updateInstalledStickerSets","@extra":null,"is_masks":false,"sticker_set_ids":["1258816259751983","-2","9007199254740993"]}"#,
+];
+
+fn as_json(json: &str) -> serde_json::Value {
+  serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_int64_round_trip() {
+  // both formats are accepted
+  let number: UpdateNewCallbackQuery = from_json(&UPDATES[0].replace(r#""9223372036854775807""#, "9223372036854775807")).unwrap();
+  let string: UpdateNewCallbackQuery = from_json(UPDATES[0]).unwrap();
+  assert_eq!(number.id(), i64::MAX);
+  assert_eq!(string.id(), i64::MAX);
+  assert_eq!(string.chat_instance(), -4611686018427387904);
+  assert!(from_json::<UpdateNewCallbackQuery>(&UPDATES[0].replace("9223372036854775807", "9223372036854775808")).is_err());
+  assert!(from_json::<UpdateNewCallbackQuery>(&UPDATES[0].replace(r#""9223372036854775807""#, r#""id""#)).is_err());
+
+  for json in UPDATES.iter() {
+    let td_type: TdType = from_json(json).unwrap();
+    assert_eq!(as_json(&td_type.to_json_with(Int64Format::String).unwrap()), as_json(json));
+  }
+
+  // the format only applies to the call
+  let td_type: TdType = from_json(UPDATES[2]).unwrap();
+  let json = as_json(&td_type.to_json().unwrap());
+  assert_eq!(json["sticker_set_ids"], serde_json::json!([1258816259751983i64, -2, 9007199254740993i64]));
+  let update: UpdateInstalledStickerSets = from_json(UPDATES[2]).unwrap();
+  let value = with_int64_format(Int64Format::String, || serde_json::to_value(&update).unwrap());
+  assert_eq!(value["sticker_set_ids"], serde_json::json!(["1258816259751983", "-2", "9007199254740993"]));
+  assert_eq!(as_json(&td_type.to_json().unwrap()), json);
+  let td_type: TdType = from_json(&json.to_string()).unwrap();
+  match td_type {
+    TdType::UpdateInstalledStickerSets(update) => assert_eq!(update.sticker_set_ids(), &vec![1258816259751983, -2, 9007199254740993]),
+    _ => panic!("unexpected type"),
+  }
+}